@@ -1,3 +1,5 @@
+use async_trait::async_trait;
+
 /// The Aggregate trait is responsible for validating commands, mapping commands to events, and applying
 /// events onto the state.
 ///
@@ -7,7 +9,7 @@
 ///
 /// This trait is purposefully _synchronous_. If you are implementing this trait, your aggregate
 /// should not have any side effects. If you need additional information to handle commands correctly, then
-/// consider looking up that information and placing it in the command.
+/// consider looking up that information and placing it in the command, or implement [`AsyncAggregate`].
 pub trait Aggregate {
     /// The `NAME` const is responsible for naming an aggregate type.
     /// Each aggregate type should have a name that is unique among all the aggregate types in your application.
@@ -46,3 +48,28 @@ pub trait Aggregate {
     /// If this is not the case, this function is allowed to panic.
     fn apply_event(state: Self::State, payload: Self::Event) -> Self::State;
 }
+
+/// Extension of the [`Aggregate`] handling its commands asynchronously, with access to external
+/// services, e.g. to check the uniqueness of a value or to price an order.
+///
+/// The services are passed in by the caller handling the command (see
+/// `AggregateManager::handle_async_command`). Only the handling of the commands is asynchronous: the events are still applied onto the state
+/// through [`Aggregate::apply_event`], which should never consult the services, so that the state
+/// can always be derived from the events alone.
+#[async_trait]
+pub trait AsyncAggregate: Aggregate {
+    /// The external services the commands are handled with.
+    type Services: Sync;
+
+    /// Handles, validate a command consulting the services and emits events.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the command is denied. As for [`Aggregate::handle_command`], every error
+    /// here should be a "domain error": services failures have to be mapped to domain errors too.
+    async fn handle_command_async(
+        state: &Self::State,
+        command: Self::Command,
+        services: &Self::Services,
+    ) -> Result<Vec<Self::Event>, Self::Error>;
+}
@@ -19,9 +19,26 @@ where
     /// All the errors should be handled from within the [`EventHandler`] and shouldn't panic.
     async fn handle(&self, event: &StoreEvent<A::Event>);
 
+    /// Handle an event as [`EventHandler::handle`] does, returning the error instead of handling it,
+    /// so that the store can report it to its [`ErrorObserver`]s.
+    ///
+    /// By default, this calls [`EventHandler::handle`] and never fails: event handlers able to detect
+    /// failures should override it.
+    async fn try_handle(&self, event: &StoreEvent<A::Event>) -> Result<(), Box<dyn std::error::Error + Send + Sync>>
+    where
+        A::Event: Sync,
+    {
+        self.handle(event).await;
+        Ok(())
+    }
+
     /// Perform a deletion of a resource using the given aggregate_id.
     async fn delete(&self, _aggregate_id: Uuid) {}
 
+    /// Migrate the resources of the aggregate instance `from` to the aggregate instance `to`, after
+    /// its events have been moved or copied to the latter (see `PgStore::rekey`).
+    async fn rekey(&self, _from: Uuid, _to: Uuid) {}
+
     /// The name of the event handler. By default, this is the type name of the event handler,
     /// but it can be overridden to provide a custom name. This name is used as
     /// part of tracing spans, to identify the event handler being run.
@@ -43,17 +60,63 @@ where
         self.deref().handle(event).await;
     }
 
+    /// Deref call to [`EventHandler::try_handle`].
+    async fn try_handle(&self, event: &StoreEvent<A::Event>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.deref().try_handle(event).await
+    }
+
     /// Deref call to [`EventHandler::handle`].
     async fn delete(&self, aggregate_id: Uuid) {
         self.deref().delete(aggregate_id).await;
     }
 
+    /// Deref call to [`EventHandler::rekey`].
+    async fn rekey(&self, from: Uuid, to: Uuid) {
+        self.deref().rekey(from, to).await;
+    }
+
     /// Deref call to [`EventHandler::handle`].
     fn name(&self) -> &'static str {
         self.deref().name()
     }
 }
 
+/// Observer of the failures of the [`EventHandler`]s, as reported by [`EventHandler::try_handle`],
+/// e.g. to raise alerts or to forward the failed events to a dead letter queue.
+///
+/// Any `Fn(&StoreEvent<A::Event>, &str, &(dyn Error + Send + Sync))` closure, taking the event, the
+/// name of the event handler and the error, is an [`ErrorObserver`].
+#[async_trait]
+pub trait ErrorObserver<A>: Sync
+where
+    A: Aggregate,
+{
+    /// Observe the failure of the event handler with the given name while handling the given event.
+    async fn observe(
+        &self,
+        event: &StoreEvent<A::Event>,
+        event_handler_name: &'static str,
+        error: &(dyn std::error::Error + Send + Sync),
+    );
+}
+
+#[async_trait]
+impl<A, F> ErrorObserver<A> for F
+where
+    A: Aggregate,
+    A::Event: Sync,
+    F: Fn(&StoreEvent<A::Event>, &str, &(dyn std::error::Error + Send + Sync)) + Sync,
+{
+    async fn observe(
+        &self,
+        event: &StoreEvent<A::Event>,
+        event_handler_name: &'static str,
+        error: &(dyn std::error::Error + Send + Sync),
+    ) {
+        self(event, event_handler_name, error)
+    }
+}
+
 /// This trait is used to implement a [`TransactionalEventHandler`]. A transactional event handler is
 /// intended to be an entity which can create, update and delete a read side. No side effects must be
 /// performed inside of this kind on handler.
@@ -75,6 +138,12 @@ where
         Ok(())
     }
 
+    /// Migrate the read side projection of the aggregate instance `from` to the aggregate instance
+    /// `to`, in the same transaction moving or copying its events (see `PgStore::rekey`).
+    async fn rekey(&self, _from: Uuid, _to: Uuid, _executor: &mut Ex) -> Result<(), Er> {
+        Ok(())
+    }
+
     /// The name of the event handler. By default, this is the type name of the event handler,
     /// but it can be overridden to provide a custom name. This name is used as
     /// part of tracing spans, to identify the event handler being run.
@@ -102,6 +171,11 @@ where
         self.deref().delete(aggregate_id, executor).await
     }
 
+    /// Deref call to [`TransactionalEventHandler::rekey`].
+    async fn rekey(&self, from: Uuid, to: Uuid, executor: &mut Ex) -> Result<(), Er> {
+        self.deref().rekey(from, to, executor).await
+    }
+
     /// Deref call to [`TransactionalEventHandler::name`].
     fn name(&self) -> &'static str {
         self.deref().name()
@@ -119,9 +193,17 @@ where
 /// the database should be marked as replayable.
 ///
 /// Another use case could be if there's the need to implement a retry logic for this event handler.
+///
+/// While rebuilding, the read side of a single aggregate instance is cleared calling
+/// [`EventHandler::delete`], while the whole read side is cleared calling
+/// [`ReplayableEventHandler::truncate`], before the events get replayed.
+#[async_trait]
 pub trait ReplayableEventHandler<A>: Sync
 where
     Self: EventHandler<A>,
     A: Aggregate,
 {
+    /// Perform a deletion of the whole read side handled by this event handler. By default, this is
+    /// a no-op.
+    async fn truncate(&self) {}
 }
@@ -0,0 +1,68 @@
+use std::ops::Deref;
+
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::store::StoreEvent;
+use crate::Aggregate;
+
+/// This trait is used to implement a [`PersistInterceptor`]. A persist interceptor is intended to be
+/// an entity hooking into the persistence of the events of any aggregate instance of a store, to
+/// implement cross-cutting concerns like enrichment, validation and cache invalidation.
+#[async_trait]
+pub trait PersistInterceptor<A, Er, Ex>: Sync
+where
+    A: Aggregate,
+{
+    /// Called inside of the persisting transaction, before the events are inserted. The events can
+    /// be mutated, and the persistence can be vetoed returning an error, aborting the transaction.
+    async fn before_persist(
+        &self,
+        _aggregate_id: Uuid,
+        _events: &mut Vec<A::Event>,
+        _executor: &mut Ex,
+    ) -> Result<(), Er> {
+        Ok(())
+    }
+
+    /// Called after the persisting transaction is committed, with the persisted events. All the
+    /// errors should be handled from within the [`PersistInterceptor`] and shouldn't panic.
+    async fn after_commit(&self, _store_events: &[StoreEvent<A::Event>]) {}
+
+    /// The name of the persist interceptor. By default, this is the type name of the persist
+    /// interceptor, but it can be overridden to provide a custom name. This name is used as part of
+    /// tracing spans, to identify the persist interceptor being run.
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+}
+
+#[async_trait]
+impl<A, Er, Ex, Q, T> PersistInterceptor<A, Er, Ex> for T
+where
+    A: Aggregate,
+    A::Event: Send + Sync,
+    Ex: Send,
+    Q: PersistInterceptor<A, Er, Ex>,
+    T: Deref<Target = Q> + Send + Sync,
+{
+    /// Deref call to [`PersistInterceptor::before_persist`].
+    async fn before_persist(
+        &self,
+        aggregate_id: Uuid,
+        events: &mut Vec<A::Event>,
+        executor: &mut Ex,
+    ) -> Result<(), Er> {
+        self.deref().before_persist(aggregate_id, events, executor).await
+    }
+
+    /// Deref call to [`PersistInterceptor::after_commit`].
+    async fn after_commit(&self, store_events: &[StoreEvent<A::Event>]) {
+        self.deref().after_commit(store_events).await
+    }
+
+    /// Deref call to [`PersistInterceptor::name`].
+    fn name(&self) -> &'static str {
+        self.deref().name()
+    }
+}
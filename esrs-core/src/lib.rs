@@ -0,0 +1,21 @@
+//! The core abstractions of [esrs](https://docs.rs/esrs): the [`Aggregate`], its
+//! [`AggregateState`], the [`store::EventStore`] and the event handlers.
+//!
+//! This crate doesn't depend on any database driver nor async runtime, so that the crates defining
+//! the domain (commands, events, aggregates), including the ones compiled to wasm, can depend on it
+//! alone.
+
+pub use aggregate::{Aggregate, AsyncAggregate};
+pub use state::{AggregateState, SharedAggregateState};
+
+mod aggregate;
+mod state;
+
+pub mod handler;
+pub mod interceptor;
+pub mod store;
+
+pub mod types {
+    //! Provides custom types.
+    pub type SequenceNumber = i32;
+}
@@ -1,8 +1,13 @@
 use uuid::Uuid;
 
+pub use shared::SharedAggregateState;
+
 use crate::store::EventStoreLockGuard;
-use crate::store::StoreEvent;
+use crate::store::{Snapshot, StoreEvent};
 use crate::types::SequenceNumber;
+use crate::Aggregate;
+
+mod shared;
 
 /// The internal state for an Aggregate.
 /// It contains:
@@ -62,6 +67,16 @@ impl<S: Default> AggregateState<S> {
         }
     }
 
+    /// Creates a new instance of an [`AggregateState`] from the given snapshot, without any lock.
+    pub fn from_snapshot(snapshot: Snapshot<S>) -> Self {
+        Self {
+            id: snapshot.aggregate_id,
+            inner: snapshot.state,
+            sequence_number: snapshot.sequence_number,
+            lock: None,
+        }
+    }
+
     /// Consumes the aggregate state and generates a new one with the events applied to it,
     /// as dictated by `apply_event`.
     pub fn apply_store_events<T, F>(self, store_events: Vec<StoreEvent<T>>, apply_event: F) -> Self
@@ -80,6 +95,28 @@ impl<S: Default> AggregateState<S> {
         })
     }
 
+    /// Consumes the aggregate state and generates a new one with the given events applied to it, in
+    /// order, through [`Aggregate::apply_event`].
+    ///
+    /// This is the exact replay logic of the [`crate::store::EventStore`] loaders, exposed so that
+    /// custom loaders, migrations and debugging tools can reuse it. A slice of events can be
+    /// replayed with `aggregate_state.replay::<MyAggregate>(store_events.iter().cloned())`.
+    pub fn replay<A>(self, store_events: impl IntoIterator<Item = StoreEvent<A::Event>>) -> Self
+    where
+        A: Aggregate<State = S>,
+    {
+        store_events.into_iter().fold(self, |state, store_event| {
+            let sequence_number = *store_event.sequence_number();
+            let inner = A::apply_event(state.inner, store_event.payload);
+
+            Self {
+                sequence_number,
+                inner,
+                ..state
+            }
+        })
+    }
+
     /// Returns an Uuid representing the aggregate id.
     pub const fn id(&self) -> &Uuid {
         &self.id
@@ -116,3 +153,15 @@ impl<S: Default> AggregateState<S> {
         self.lock.take()
     }
 }
+
+impl<S: Clone> AggregateState<S> {
+    /// Clones the aggregate state, without the lock: the returned copy never holds one.
+    pub fn to_unlocked(&self) -> Self {
+        Self {
+            id: self.id,
+            sequence_number: self.sequence_number,
+            lock: None,
+            inner: self.inner.clone(),
+        }
+    }
+}
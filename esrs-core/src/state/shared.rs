@@ -0,0 +1,175 @@
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use crate::store::StoreEvent;
+use crate::types::SequenceNumber;
+use crate::{Aggregate, AggregateState};
+
+/// A cheaply clonable version of [`AggregateState`], holding the internal state behind an [`Arc`].
+///
+/// Cloning a [`SharedAggregateState`] never deep-copies the internal state, making it suitable to be
+/// passed around handlers and caches. Mutations are copy-on-write: the internal state gets cloned only
+/// if it is currently shared with other instances.
+///
+/// Differently from [`AggregateState`], it never holds a lock. Converting an [`AggregateState`] into a
+/// [`SharedAggregateState`] releases its lock, if any.
+#[derive(Debug)]
+pub struct SharedAggregateState<S> {
+    id: Uuid,
+    sequence_number: SequenceNumber,
+    inner: Arc<S>,
+}
+
+impl<S> Clone for SharedAggregateState<S> {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id,
+            sequence_number: self.sequence_number,
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl<S> SharedAggregateState<S> {
+    /// Returns an Uuid representing the aggregate id.
+    pub const fn id(&self) -> &Uuid {
+        &self.id
+    }
+
+    /// Returns the internal state.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Consumes self and extracts the shared internal state.
+    pub fn into_inner(self) -> Arc<S> {
+        self.inner
+    }
+
+    /// Returns the internal sequence number.
+    pub const fn sequence_number(&self) -> &SequenceNumber {
+        &self.sequence_number
+    }
+}
+
+impl<S: Clone> SharedAggregateState<S> {
+    /// Returns a mutable reference to the internal state, cloning it if it is shared with other
+    /// instances.
+    pub fn make_mut(&mut self) -> &mut S {
+        Arc::make_mut(&mut self.inner)
+    }
+
+    /// Consumes the shared aggregate state and generates a new one with the events applied to it,
+    /// as dictated by `apply_event`.
+    ///
+    /// The internal state is cloned only if it is shared with other instances.
+    pub fn apply_store_events<T, F>(self, store_events: Vec<StoreEvent<T>>, apply_event: F) -> Self
+    where
+        F: Fn(S, T) -> S,
+    {
+        let mut sequence_number = self.sequence_number;
+        let inner = store_events
+            .into_iter()
+            .fold(Arc::unwrap_or_clone(self.inner), |inner, store_event| {
+                sequence_number = store_event.sequence_number;
+                apply_event(inner, store_event.payload)
+            });
+
+        Self {
+            id: self.id,
+            sequence_number,
+            inner: Arc::new(inner),
+        }
+    }
+
+    /// Consumes the shared aggregate state and generates a new one with the given events applied to
+    /// it, in order, through [`Aggregate::apply_event`]. See [`AggregateState::replay`].
+    ///
+    /// The internal state is cloned only if it is shared with other instances.
+    pub fn replay<A>(self, store_events: impl IntoIterator<Item = StoreEvent<A::Event>>) -> Self
+    where
+        A: Aggregate<State = S>,
+    {
+        let mut sequence_number = self.sequence_number;
+        let inner = store_events
+            .into_iter()
+            .fold(Arc::unwrap_or_clone(self.inner), |inner, store_event| {
+                sequence_number = store_event.sequence_number;
+                A::apply_event(inner, store_event.payload)
+            });
+
+        Self {
+            id: self.id,
+            sequence_number,
+            inner: Arc::new(inner),
+        }
+    }
+
+    /// Converts self into an owned [`AggregateState`], without any lock.
+    ///
+    /// The internal state is cloned only if it is shared with other instances.
+    pub fn into_aggregate_state(self) -> AggregateState<S> {
+        AggregateState {
+            id: self.id,
+            sequence_number: self.sequence_number,
+            lock: None,
+            inner: Arc::unwrap_or_clone(self.inner),
+        }
+    }
+}
+
+impl<S> From<AggregateState<S>> for SharedAggregateState<S> {
+    fn from(aggregate_state: AggregateState<S>) -> Self {
+        Self {
+            id: aggregate_state.id,
+            sequence_number: aggregate_state.sequence_number,
+            inner: Arc::new(aggregate_state.inner),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{AggregateState, SharedAggregateState};
+
+    #[derive(Clone, Debug, Default, PartialEq)]
+    struct TestState {
+        count: u64,
+    }
+
+    #[test]
+    fn clones_share_the_inner_state_test() {
+        let shared: SharedAggregateState<TestState> = AggregateState::<TestState>::new().into();
+        let clone: SharedAggregateState<TestState> = shared.clone();
+
+        assert_eq!(shared.id(), clone.id());
+        assert_eq!(shared.sequence_number(), clone.sequence_number());
+        assert!(Arc::ptr_eq(&shared.into_inner(), &clone.into_inner()));
+    }
+
+    #[test]
+    fn make_mut_copies_shared_inner_state_test() {
+        let shared: SharedAggregateState<TestState> = AggregateState::<TestState>::new().into();
+        let mut clone: SharedAggregateState<TestState> = shared.clone();
+
+        clone.make_mut().count = 1;
+
+        assert_eq!(shared.inner(), &TestState { count: 0 });
+        assert_eq!(clone.inner(), &TestState { count: 1 });
+        assert!(!Arc::ptr_eq(&shared.into_inner(), &clone.into_inner()));
+    }
+
+    #[test]
+    fn make_mut_does_not_copy_unique_inner_state_test() {
+        let mut shared: SharedAggregateState<TestState> = AggregateState::<TestState>::new().into();
+        let before: *const TestState = shared.inner();
+
+        shared.make_mut().count = 1;
+
+        assert_eq!(shared.inner(), &TestState { count: 1 });
+        assert!(std::ptr::eq(before, shared.inner()));
+    }
+}
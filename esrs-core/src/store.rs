@@ -0,0 +1,487 @@
+use std::ops::Deref;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use uuid::Uuid;
+
+use crate::state::AggregateState;
+use crate::types::SequenceNumber;
+
+/// Marker trait for every [`EventStoreLockGuard`].
+///
+/// Implementors should unlock concurrent access to the guarded resource, when dropped.
+pub trait UnlockOnDrop: Send + Sync + 'static {}
+
+/// Lock guard preventing concurrent access to a resource.
+///
+/// The lock is released when this guard is dropped.
+#[allow(dead_code)]
+pub struct EventStoreLockGuard(Box<dyn UnlockOnDrop>);
+
+impl EventStoreLockGuard {
+    /// Creates a new instance from any [`UnlockOnDrop`].
+    #[must_use]
+    pub fn new(lock: impl UnlockOnDrop) -> Self {
+        Self(Box::new(lock))
+    }
+}
+
+/// An EventStore is responsible for persisting events that an aggregate emits into a database, and loading the events
+/// that represent an aggregate's history from the database.
+#[async_trait]
+pub trait EventStore {
+    type Aggregate: crate::Aggregate;
+    type Error: std::error::Error;
+
+    /// Acquires a lock for the given aggregate, or waits for outstanding guards to be released.
+    ///
+    /// Used to prevent concurrent access to the aggregate state.
+    /// Note that any process which does *not* `lock` will get immediate (possibly shared!) access.
+    /// ALL accesses (regardless of this guard) are subject to the usual optimistic locking strategy on write.
+    async fn lock(&self, aggregate_id: Uuid) -> Result<EventStoreLockGuard, Self::Error>;
+
+    /// Loads the events that an aggregate instance has emitted in the past.
+    async fn by_aggregate_id(
+        &self,
+        aggregate_id: Uuid,
+    ) -> Result<Vec<StoreEvent<<Self::Aggregate as crate::Aggregate>::Event>>, Self::Error>;
+
+    /// Loads the events that an aggregate instance has emitted after the given sequence number, e.g.
+    /// to bring a [`Snapshot`] up to date. By default, this loads all its events: implementors
+    /// should override it with a cheaper query.
+    async fn by_aggregate_id_after(
+        &self,
+        aggregate_id: Uuid,
+        sequence_number: SequenceNumber,
+    ) -> Result<Vec<StoreEvent<<Self::Aggregate as crate::Aggregate>::Event>>, Self::Error> {
+        Ok(self
+            .by_aggregate_id(aggregate_id)
+            .await?
+            .into_iter()
+            .filter(|store_event| store_event.sequence_number > sequence_number)
+            .collect())
+    }
+
+    /// Loads a page of at most `limit` events of an aggregate instance, starting from the given
+    /// sequence number included, e.g. to expose its history through an API. By default, this loads
+    /// all its events: implementors should override it with a cheaper query.
+    async fn by_aggregate_id_paginated(
+        &self,
+        aggregate_id: Uuid,
+        from_sequence_number: SequenceNumber,
+        limit: usize,
+    ) -> Result<Vec<StoreEvent<<Self::Aggregate as crate::Aggregate>::Event>>, Self::Error> {
+        Ok(self
+            .by_aggregate_id(aggregate_id)
+            .await?
+            .into_iter()
+            .filter(|store_event| store_event.sequence_number >= from_sequence_number)
+            .take(limit)
+            .collect())
+    }
+
+    /// Loads at most `limit` events of all the aggregate instances from the given position, in a stable
+    /// order defined by the store (e.g. by `occurred_on` timestamp, then aggregate id and sequence
+    /// number), so that a consumer can catch up incrementally, passing the id of the last event it
+    /// handled. The events of an aggregate instance are always loaded in sequence number order.
+    ///
+    /// By default, no event is loaded, since the aggregate instances of a store can't be listed
+    /// through this trait: implementors able to read all their events should override it.
+    async fn events_since(
+        &self,
+        _since: Since,
+        _limit: usize,
+    ) -> Result<Vec<StoreEvent<<Self::Aggregate as crate::Aggregate>::Event>>, Self::Error> {
+        Ok(vec![])
+    }
+
+    /// Checks whether the given aggregate instance has emitted any event. By default, this loads all
+    /// its events: implementors should override it with a cheaper check.
+    async fn exists(&self, aggregate_id: Uuid) -> Result<bool, Self::Error> {
+        Ok(!self.by_aggregate_id(aggregate_id).await?.is_empty())
+    }
+
+    /// Persists multiple events into the database. This should be done in a single transaction - either
+    /// all the events are persisted correctly, or none are.
+    ///
+    /// Persisting events may additionally trigger configured event handlers (transactional and non-transactional).
+    ///
+    /// The persisted events are moved into the returned [`StoreEvent`]s payloads, so there's no need to
+    /// clone them beforehand if they are needed afterwards.
+    async fn persist(
+        &self,
+        aggregate_state: &mut AggregateState<<Self::Aggregate as crate::Aggregate>::State>,
+        events: Vec<<Self::Aggregate as crate::Aggregate>::Event>,
+    ) -> Result<Vec<StoreEvent<<Self::Aggregate as crate::Aggregate>::Event>>, Self::Error>;
+
+    /// Same as [`EventStore::persist`], attaching the given [`Metadata`] to every persisted event, so
+    /// that event handlers and buses receive it along with the events.
+    ///
+    /// There is no default implementation, so that the metadata is never silently dropped:
+    /// implementors must persist it along with the events.
+    async fn persist_with_metadata(
+        &self,
+        aggregate_state: &mut AggregateState<<Self::Aggregate as crate::Aggregate>::State>,
+        events: Vec<<Self::Aggregate as crate::Aggregate>::Event>,
+        metadata: Metadata,
+    ) -> Result<Vec<StoreEvent<<Self::Aggregate as crate::Aggregate>::Event>>, Self::Error>;
+
+    /// Publish multiple events on the configured events buses.
+    async fn publish(&self, store_events: &[StoreEvent<<Self::Aggregate as crate::Aggregate>::Event>]);
+
+    /// Delete all events from events store related to given `aggregate_id`.
+    ///
+    /// Moreover it should delete all the read side projections triggered by event handlers.
+    async fn delete(&self, aggregate_id: Uuid) -> Result<(), Self::Error>;
+}
+
+/// Blanket implementation making an [`EventStore`] every (smart) pointer to an [`EventStore`],
+/// e.g. `&Store`, `Box<Store>`, `Arc<Store>`.
+/// This is particularly useful when there's the need in your codebase to have a generic [`EventStore`].
+#[async_trait]
+impl<A, E, T, S> EventStore for T
+where
+    A: crate::Aggregate,
+    A::Event: Send + Sync,
+    A::State: Send,
+    E: std::error::Error,
+    S: EventStore<Aggregate = A, Error = E> + Sync + ?Sized,
+    T: Deref<Target = S> + Sync,
+    for<'a> A::Event: 'a,
+{
+    type Aggregate = A;
+    type Error = E;
+
+    /// Deref call to [`EventStore::lock`].
+    async fn lock(&self, aggregate_id: Uuid) -> Result<EventStoreLockGuard, Self::Error> {
+        self.deref().lock(aggregate_id).await
+    }
+
+    /// Deref call to [`EventStore::by_aggregate_id`].
+    async fn by_aggregate_id(
+        &self,
+        aggregate_id: Uuid,
+    ) -> Result<Vec<StoreEvent<<Self::Aggregate as crate::Aggregate>::Event>>, Self::Error> {
+        self.deref().by_aggregate_id(aggregate_id).await
+    }
+
+    /// Deref call to [`EventStore::by_aggregate_id_after`].
+    async fn by_aggregate_id_after(
+        &self,
+        aggregate_id: Uuid,
+        sequence_number: SequenceNumber,
+    ) -> Result<Vec<StoreEvent<<Self::Aggregate as crate::Aggregate>::Event>>, Self::Error> {
+        self.deref().by_aggregate_id_after(aggregate_id, sequence_number).await
+    }
+
+    /// Deref call to [`EventStore::by_aggregate_id_paginated`].
+    async fn by_aggregate_id_paginated(
+        &self,
+        aggregate_id: Uuid,
+        from_sequence_number: SequenceNumber,
+        limit: usize,
+    ) -> Result<Vec<StoreEvent<<Self::Aggregate as crate::Aggregate>::Event>>, Self::Error> {
+        self.deref()
+            .by_aggregate_id_paginated(aggregate_id, from_sequence_number, limit)
+            .await
+    }
+
+    /// Deref call to [`EventStore::events_since`].
+    async fn events_since(
+        &self,
+        since: Since,
+        limit: usize,
+    ) -> Result<Vec<StoreEvent<<Self::Aggregate as crate::Aggregate>::Event>>, Self::Error> {
+        self.deref().events_since(since, limit).await
+    }
+
+    /// Deref call to [`EventStore::exists`].
+    async fn exists(&self, aggregate_id: Uuid) -> Result<bool, Self::Error> {
+        self.deref().exists(aggregate_id).await
+    }
+
+    /// Deref call to [`EventStore::persist`].
+    async fn persist(
+        &self,
+        aggregate_state: &mut AggregateState<<Self::Aggregate as crate::Aggregate>::State>,
+        events: Vec<<Self::Aggregate as crate::Aggregate>::Event>,
+    ) -> Result<Vec<StoreEvent<<Self::Aggregate as crate::Aggregate>::Event>>, Self::Error> {
+        self.deref().persist(aggregate_state, events).await
+    }
+
+    /// Deref call to [`EventStore::persist_with_metadata`].
+    async fn persist_with_metadata(
+        &self,
+        aggregate_state: &mut AggregateState<<Self::Aggregate as crate::Aggregate>::State>,
+        events: Vec<<Self::Aggregate as crate::Aggregate>::Event>,
+        metadata: Metadata,
+    ) -> Result<Vec<StoreEvent<<Self::Aggregate as crate::Aggregate>::Event>>, Self::Error> {
+        self.deref()
+            .persist_with_metadata(aggregate_state, events, metadata)
+            .await
+    }
+
+    /// Deref call to [`EventStore::publish`].
+    async fn publish(&self, events: &[StoreEvent<<Self::Aggregate as crate::Aggregate>::Event>]) {
+        self.deref().publish(events).await
+    }
+
+    /// Deref call to [`EventStore::delete`].
+    async fn delete(&self, aggregate_id: Uuid) -> Result<(), Self::Error> {
+        self.deref().delete(aggregate_id).await
+    }
+}
+
+/// An `IdempotentEventStore` is an [`EventStore`] able to record an idempotency key along with the
+/// persisted events, so that a command handled twice with the same key (e.g. redelivered to an
+/// at-least-once consumer) has its events written only once.
+#[async_trait]
+pub trait IdempotentEventStore: EventStore {
+    /// Loads the events persisted with the given idempotency key for the given aggregate instance,
+    /// ordered by sequence number. Returns an empty list if no events were persisted with that key.
+    async fn by_idempotency_key(
+        &self,
+        aggregate_id: Uuid,
+        idempotency_key: Uuid,
+    ) -> Result<Vec<StoreEvent<<Self::Aggregate as crate::Aggregate>::Event>>, Self::Error>;
+
+    /// Persists the given events like [`EventStore::persist`], recording the given idempotency key
+    /// along with them. If events have already been persisted with the same key for the same
+    /// aggregate instance, nothing is persisted and they are returned instead.
+    async fn persist_idempotent(
+        &self,
+        aggregate_state: &mut AggregateState<<Self::Aggregate as crate::Aggregate>::State>,
+        events: Vec<<Self::Aggregate as crate::Aggregate>::Event>,
+        idempotency_key: Uuid,
+    ) -> Result<Vec<StoreEvent<<Self::Aggregate as crate::Aggregate>::Event>>, Self::Error>;
+}
+
+/// Blanket implementation making an [`IdempotentEventStore`] every (smart) pointer to an
+/// [`IdempotentEventStore`], e.g. `&Store`, `Box<Store>`, `Arc<Store>`.
+#[async_trait]
+impl<A, E, T, S> IdempotentEventStore for T
+where
+    A: crate::Aggregate,
+    A::Event: Send + Sync,
+    A::State: Send,
+    E: std::error::Error,
+    S: IdempotentEventStore<Aggregate = A, Error = E> + Sync + ?Sized,
+    T: Deref<Target = S> + Sync,
+    for<'a> A::Event: 'a,
+{
+    /// Deref call to [`IdempotentEventStore::by_idempotency_key`].
+    async fn by_idempotency_key(
+        &self,
+        aggregate_id: Uuid,
+        idempotency_key: Uuid,
+    ) -> Result<Vec<StoreEvent<<Self::Aggregate as crate::Aggregate>::Event>>, Self::Error> {
+        self.deref().by_idempotency_key(aggregate_id, idempotency_key).await
+    }
+
+    /// Deref call to [`IdempotentEventStore::persist_idempotent`].
+    async fn persist_idempotent(
+        &self,
+        aggregate_state: &mut AggregateState<<Self::Aggregate as crate::Aggregate>::State>,
+        events: Vec<<Self::Aggregate as crate::Aggregate>::Event>,
+        idempotency_key: Uuid,
+    ) -> Result<Vec<StoreEvent<<Self::Aggregate as crate::Aggregate>::Event>>, Self::Error> {
+        self.deref()
+            .persist_idempotent(aggregate_state, events, idempotency_key)
+            .await
+    }
+}
+
+/// The position from which [`EventStore::events_since`] loads the events:
+///
+/// - `OccurredOn`: The events occurred at or after the given timestamp.
+/// - `EventId`: The events following the one with the given id. No event is loaded if there is no
+///   such event.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Since {
+    OccurredOn(DateTime<Utc>),
+    EventId(Uuid),
+}
+
+/// A `StoreEvent` contains the payload (the original event) alongside the event's metadata.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StoreEvent<Event> {
+    /// Uniquely identifies an event among all events emitted from all aggregates.
+    pub id: Uuid,
+    /// The aggregate instance that emitted the event.
+    pub aggregate_id: Uuid,
+    /// The original, emitted, event.
+    pub payload: Event,
+    /// The timestamp of when the event is persisted.
+    pub occurred_on: DateTime<Utc>,
+    /// The sequence number of the event, within its specific aggregate instance.
+    pub sequence_number: SequenceNumber,
+    /// The version of the event.
+    pub version: Option<i32>,
+    /// The metadata attached to the event when persisted, e.g. its correlation and causation ids.
+    #[serde(default)]
+    pub metadata: Metadata,
+    /// The payload as serialized in the event store (using the store schema, if any), when the
+    /// event has been persisted or loaded by a store. See [`StoreEvent::raw_payload`].
+    #[serde(skip)]
+    pub raw_payload: Option<Box<RawValue>>,
+}
+
+impl<Event> StoreEvent<Event> {
+    /// Creates a new `StoreEvent`, with empty metadata and no serialized payload.
+    pub fn new(
+        id: Uuid,
+        aggregate_id: Uuid,
+        payload: Event,
+        occurred_on: DateTime<Utc>,
+        sequence_number: SequenceNumber,
+        version: Option<i32>,
+    ) -> Self {
+        Self {
+            id,
+            aggregate_id,
+            payload,
+            occurred_on,
+            sequence_number,
+            version,
+            metadata: Metadata::default(),
+            raw_payload: None,
+        }
+    }
+
+    /// Sets the metadata attached to the event.
+    #[must_use]
+    pub fn with_metadata(mut self, metadata: Metadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Sets the payload as serialized in the event store. Meant to be used by event store
+    /// implementations.
+    #[must_use]
+    pub fn with_raw_payload(mut self, raw_payload: Box<RawValue>) -> Self {
+        self.raw_payload = Some(raw_payload);
+        self
+    }
+
+    /// Returns the payload as serialized in the event store (using the store schema, if any), when
+    /// the event has been persisted or loaded by a store. Payloads encrypted at rest are returned in
+    /// clear.
+    ///
+    /// Event buses configured to publish the stored payloads publish it as is, instead of serializing
+    /// the payload again: when using a custom schema, the published payload is then the serialized
+    /// schema rather than the serialized event.
+    pub fn raw_payload(&self) -> Option<&RawValue> {
+        self.raw_payload.as_deref()
+    }
+
+    /// Returns the sequence number of the event, within its specific aggregate instance.
+    pub const fn sequence_number(&self) -> &SequenceNumber {
+        &self.sequence_number
+    }
+
+    /// Returns the original, emitted, event.
+    pub const fn payload(&self) -> &Event {
+        &self.payload
+    }
+}
+
+/// Structured metadata attached to persisted events, such as the ids used to trace the flow of
+/// commands and events across aggregates and sagas.
+///
+/// The correlation id is shared by all the events resulting from the same initial command, while
+/// the causation id is the id of the event (or command) directly causing them. See
+/// [`Metadata::caused_by`].
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Metadata {
+    /// Shared by all the events resulting from the same initial command.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<Uuid>,
+    /// The id of the event or command directly causing the event.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub causation_id: Option<Uuid>,
+    /// The user on whose behalf the command has been handled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+    /// Any additional application specific field.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Metadata {
+    /// Returns the metadata of the events caused by the given event, e.g. in a saga: the causation
+    /// id is the id of the event, and the correlation id is the one of the event, or its id if none.
+    /// The user id is propagated too.
+    pub fn caused_by<E>(store_event: &StoreEvent<E>) -> Self {
+        Self {
+            correlation_id: Some(store_event.metadata.correlation_id.unwrap_or(store_event.id)),
+            causation_id: Some(store_event.id),
+            user_id: store_event.metadata.user_id.clone(),
+            extra: serde_json::Map::new(),
+        }
+    }
+
+    /// Sets the correlation id.
+    pub fn with_correlation_id(mut self, correlation_id: Uuid) -> Self {
+        self.correlation_id = Some(correlation_id);
+        self
+    }
+
+    /// Sets the causation id.
+    pub fn with_causation_id(mut self, causation_id: Uuid) -> Self {
+        self.causation_id = Some(causation_id);
+        self
+    }
+
+    /// Sets the user id.
+    pub fn with_user_id(mut self, user_id: impl Into<String>) -> Self {
+        self.user_id = Some(user_id.into());
+        self
+    }
+
+    /// Sets an additional application specific field.
+    pub fn with_extra(mut self, key: impl Into<String>, value: serde_json::Value) -> Self {
+        self.extra.insert(key.into(), value);
+        self
+    }
+
+    /// Returns `true` if no field is set.
+    pub fn is_empty(&self) -> bool {
+        self.correlation_id.is_none() && self.causation_id.is_none() && self.user_id.is_none() && self.extra.is_empty()
+    }
+}
+
+/// A snapshot of the state of an aggregate instance, as it was after applying the events up to the
+/// given sequence number.
+#[derive(Debug, Clone)]
+pub struct Snapshot<S> {
+    /// The aggregate instance the snapshot belongs to.
+    pub aggregate_id: Uuid,
+    /// The sequence number of the last event applied to the state.
+    pub sequence_number: SequenceNumber,
+    /// The version of the state, snapshots of a different version being ignored when loading.
+    pub version: Option<i32>,
+    /// The state of the aggregate instance.
+    pub state: S,
+    /// The timestamp of when the snapshot has been taken.
+    pub taken_on: DateTime<Utc>,
+}
+
+/// A `SnapshotStore` is responsible for persisting and loading the latest [`Snapshot`] of the
+/// aggregate instances, so that they can be loaded replaying only the events following it.
+///
+/// Snapshots are an optimization: the errors are logged, and the aggregate instances are loaded
+/// replaying all their events instead.
+#[async_trait]
+pub trait SnapshotStore<S>: Sync {
+    /// Loads the latest snapshot of the given aggregate instance, if any.
+    async fn latest(&self, aggregate_id: Uuid)
+        -> Result<Option<Snapshot<S>>, Box<dyn std::error::Error + Send + Sync>>;
+
+    /// Persists the given snapshot, replacing the previous ones of the aggregate instance.
+    async fn save(&self, snapshot: &Snapshot<S>) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
+
+    /// Deletes the snapshots of the given aggregate instance.
+    async fn delete(&self, aggregate_id: Uuid) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
+}
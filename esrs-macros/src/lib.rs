@@ -0,0 +1,225 @@
+//! Procedural macros for `esrs`. This crate is not meant to be used directly: enable the `macros`
+//! feature of `esrs` and use the re-exported macros instead.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, DeriveInput, Ident, LitStr, Path};
+
+mod upcaster;
+mod view;
+
+/// Derives an `esrs::handler::EventHandler` upserting the selected event fields into a view table.
+///
+/// The struct-level `view` attribute sets the aggregate, the view table and, optionally, the name
+/// of the struct field holding the `Pool<Postgres>` (`pool` by default). Every other `view`
+/// attribute maps the fields of an event (or event variant) to the columns of the view, optionally
+/// renaming them with `column = field`. Rows are keyed by the aggregate id, in the `id` column.
+///
+/// ```ignore
+/// #[derive(ViewEventHandler)]
+/// #[view(aggregate = OrderAggregate, table = "orders")]
+/// #[view(on = OrderEvent::Placed, columns(customer_id, total_amount = total))]
+/// #[view(on = OrderEvent::Shipped, columns(shipped_at))]
+/// pub struct OrdersView {
+///     pool: Pool<Postgres>,
+/// }
+/// ```
+///
+/// Field values are cloned and converted into `esrs::store::postgres::ColumnValue`s, so their types
+/// must implement `Clone` and `Into<ColumnValue>`. Only events with named fields are supported.
+#[proc_macro_derive(ViewEventHandler, attributes(view))]
+pub fn derive_view_event_handler(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    view_event_handler(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Derives the scaffolding of a Postgres view out of the struct representing its rows: the
+/// `CREATE TABLE` statement, along with `create_table`, `by_id`, `upsert` and `delete` functions.
+///
+/// The struct-level `view` attribute sets the view table, and optionally generates an
+/// `esrs::handler::EventHandler` with the given name, keeping the rows of the aggregate instances
+/// up to date through the given `apply` function. The row of an aggregate instance, if any, is passed
+/// to `apply` along with each event, and the returned row is upserted; returning `None` leaves the
+/// view untouched.
+///
+/// ```ignore
+/// #[derive(View, sqlx::FromRow)]
+/// #[view(table = "orders")]
+/// #[view(event_handler(name = OrdersView, aggregate = OrderAggregate, apply = apply_order))]
+/// pub struct OrderRow {
+///     id: Uuid,
+///     customer_id: Uuid,
+///     #[view(sql_type = "NUMERIC")]
+///     total: Decimal,
+///     shipped_at: Option<DateTime<Utc>>,
+/// }
+///
+/// fn apply_order(row: Option<OrderRow>, event: &StoreEvent<OrderEvent>) -> Option<OrderRow> { ... }
+/// ```
+///
+/// Rows are keyed by the `id` field, or the one marked with `#[view(id)]`, holding the aggregate id
+/// in the generated event handler. The column types are inferred from the common field types
+/// (`Uuid`, `String`, integers, floats, `bool`, `DateTime`, `NaiveDate` and `serde_json::Value`),
+/// `Option`s being nullable; other types need a `#[view(sql_type = "...")]` attribute. The struct is
+/// expected to derive `sqlx::FromRow` as well, to be loaded through `by_id`.
+#[proc_macro_derive(View, attributes(view))]
+pub fn derive_view(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    view::view(input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+/// Derives an `esrs::event::Upcaster` chaining the migrations of the serialized event from its
+/// older versions to the current one.
+///
+/// The `upcast` attribute maps every older version to the function migrating the payload from that
+/// version to the following one, through a
+/// `fn(serde_json::Value) -> Result<serde_json::Value, serde_json::Error>`. The versions must be
+/// consecutive, and the current version of the event is the one following the last of them.
+///
+/// ```ignore
+/// #[derive(Serialize, Deserialize, Upcaster)]
+/// #[upcast(1 => "migrate_v1", 2 => "migrate_v2")]
+/// pub enum OrderEvent { ... }
+///
+/// fn migrate_v1(value: serde_json::Value) -> Result<serde_json::Value, serde_json::Error> { ... }
+/// ```
+///
+/// An event at version 1 is migrated by `migrate_v1` and then by `migrate_v2`, one at version 2 by
+/// `migrate_v2` only, while one at version 3 (the current one) is deserialized as it is. Events
+/// persisted without a version are considered to be at the first version. Without the `upcast`
+/// attribute, the derived implementation deserializes the payload as it is.
+#[proc_macro_derive(Upcaster, attributes(upcast))]
+pub fn derive_upcaster(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    upcaster::upcaster(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+struct Mapping {
+    event: Path,
+    columns: Vec<(Ident, Ident)>,
+}
+
+fn view_event_handler(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let mut aggregate: Option<Path> = None;
+    let mut table: Option<LitStr> = None;
+    let mut pool: Ident = format_ident!("pool");
+    let mut mappings: Vec<Mapping> = vec![];
+
+    for attribute in input.attrs.iter().filter(|attribute| attribute.path().is_ident("view")) {
+        let mut event: Option<Path> = None;
+        let mut columns: Option<Vec<(Ident, Ident)>> = None;
+
+        attribute.parse_nested_meta(|meta| {
+            if meta.path.is_ident("aggregate") {
+                aggregate = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("table") {
+                table = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("pool") {
+                pool = meta.value()?.parse()?;
+            } else if meta.path.is_ident("on") {
+                event = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("columns") {
+                let mut mapped: Vec<(Ident, Ident)> = vec![];
+                meta.parse_nested_meta(|column| {
+                    let name: Ident = column.path.require_ident()?.clone();
+                    let field: Ident = if column.input.peek(syn::Token![=]) {
+                        column.value()?.parse()?
+                    } else {
+                        name.clone()
+                    };
+                    mapped.push((name, field));
+                    Ok(())
+                })?;
+                columns = Some(mapped);
+            } else {
+                return Err(meta.error("unsupported view attribute"));
+            }
+            Ok(())
+        })?;
+
+        match (event, columns) {
+            (Some(event), Some(columns)) => mappings.push(Mapping { event, columns }),
+            (None, None) => {}
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    attribute,
+                    "`on` and `columns` must be set together",
+                ))
+            }
+        }
+    }
+
+    let aggregate: Path = aggregate
+        .ok_or_else(|| syn::Error::new_spanned(&input.ident, "missing `#[view(aggregate = ...)]` attribute"))?;
+    let table: LitStr =
+        table.ok_or_else(|| syn::Error::new_spanned(&input.ident, "missing `#[view(table = \"...\")]` attribute"))?;
+
+    let arms = mappings.iter().map(|Mapping { event, columns }| {
+        let bindings: Vec<Ident> = (0..columns.len()).map(|i| format_ident!("__field_{}", i)).collect();
+        let fields = columns.iter().map(|(_, field)| field);
+        let names = columns.iter().map(|(name, _)| name.to_string());
+
+        quote! {
+            #event { #(#fields: #bindings,)* .. } => vec![
+                #((#names, ::esrs::store::postgres::ColumnValue::from(::std::clone::Clone::clone(#bindings))),)*
+            ],
+        }
+    });
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        #[::esrs::__private::async_trait]
+        impl #impl_generics ::esrs::handler::EventHandler<#aggregate> for #ident #ty_generics #where_clause {
+            async fn handle(&self, event: &::esrs::store::StoreEvent<<#aggregate as ::esrs::Aggregate>::Event>) {
+                #[allow(unreachable_patterns)]
+                let columns: Vec<(&str, ::esrs::store::postgres::ColumnValue)> = match &event.payload {
+                    #(#arms)*
+                    _ => return,
+                };
+
+                let result = match self.#pool.acquire().await {
+                    Ok(mut connection) => {
+                        ::esrs::store::postgres::projection::upsert_by_id(&mut *connection, #table, event.aggregate_id, columns)
+                            .await
+                    }
+                    Err(error) => Err(error),
+                };
+
+                if let Err(error) = result {
+                    ::esrs::__private::tracing::error!({
+                        view = #table,
+                        aggregate_id = %event.aggregate_id,
+                        error = ?error,
+                    }, "failed to upsert view row");
+                }
+            }
+
+            async fn delete(&self, aggregate_id: ::esrs::__private::Uuid) {
+                let result = match self.#pool.acquire().await {
+                    Ok(mut connection) => {
+                        ::esrs::store::postgres::projection::delete_by_id(&mut *connection, #table, aggregate_id).await
+                    }
+                    Err(error) => Err(error),
+                };
+
+                if let Err(error) = result {
+                    ::esrs::__private::tracing::error!({
+                        view = #table,
+                        aggregate_id = %aggregate_id,
+                        error = ?error,
+                    }, "failed to delete view row");
+                }
+            }
+        }
+    })
+}
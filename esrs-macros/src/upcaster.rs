@@ -0,0 +1,97 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{DeriveInput, LitInt, LitStr, Path, Token};
+
+/// A `version => "function"` entry of the `upcast` attribute.
+struct Migration {
+    from: i32,
+    with: Path,
+}
+
+impl Parse for Migration {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let from: i32 = input.parse::<LitInt>()?.base10_parse()?;
+        let _: Token![=>] = input.parse()?;
+        let with: Path = input.parse::<LitStr>()?.parse()?;
+
+        Ok(Self { from, with })
+    }
+}
+
+pub(crate) fn upcaster(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let mut attributes = input
+        .attrs
+        .iter()
+        .filter(|attribute| attribute.path().is_ident("upcast"));
+
+    let mut migrations: Vec<Migration> = match attributes.next() {
+        Some(attribute) => attribute
+            .parse_args_with(Punctuated::<Migration, Token![,]>::parse_terminated)?
+            .into_iter()
+            .collect(),
+        None => vec![],
+    };
+
+    if let Some(attribute) = attributes.next() {
+        return Err(syn::Error::new_spanned(
+            attribute,
+            "the migrations must be listed in a single `upcast` attribute",
+        ));
+    }
+
+    migrations.sort_by_key(|migration| migration.from);
+
+    for pair in migrations.windows(2) {
+        if pair[1].from != pair[0].from + 1 {
+            return Err(syn::Error::new_spanned(
+                &pair[1].with,
+                "upcast versions must be consecutive, without duplicates",
+            ));
+        }
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    // Without migrations the default implementation, deserializing the payload as it is, is kept.
+    let (Some(first), Some(last)) = (migrations.first(), migrations.last()) else {
+        return Ok(quote! {
+            impl #impl_generics ::esrs::event::Upcaster for #ident #ty_generics #where_clause {}
+        });
+    };
+
+    let first_version: i32 = first.from;
+    let current_version: i32 = last.from + 1;
+    let arms = migrations.iter().map(|Migration { from, with }| {
+        quote! { #from => #with(value)?, }
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::esrs::event::Upcaster for #ident #ty_generics #where_clause {
+            fn upcast(
+                value: ::esrs::__private::serde_json::Value,
+                version: ::std::option::Option<i32>,
+            ) -> ::std::result::Result<Self, ::esrs::__private::serde_json::Error>
+            where
+                Self: ::esrs::__private::serde::de::DeserializeOwned,
+            {
+                let mut value: ::esrs::__private::serde_json::Value = value;
+                let mut version: i32 = version.unwrap_or(#first_version);
+
+                loop {
+                    value = match version {
+                        #(#arms)*
+                        _ => return ::esrs::__private::serde_json::from_value(value),
+                    };
+                    version += 1;
+                }
+            }
+
+            fn current_version() -> ::std::option::Option<i32> {
+                ::std::option::Option::Some(#current_version)
+            }
+        }
+    })
+}
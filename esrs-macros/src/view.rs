@@ -0,0 +1,332 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, GenericArgument, Ident, LitStr, Path, PathArguments, Type};
+
+struct Column {
+    field: Ident,
+    sql_type: String,
+    nullable: bool,
+    id: bool,
+}
+
+struct EventHandler {
+    name: Ident,
+    aggregate: Path,
+    apply: Path,
+}
+
+pub(crate) fn view(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let mut table: Option<LitStr> = None;
+    let mut event_handler: Option<EventHandler> = None;
+
+    for attribute in input.attrs.iter().filter(|attribute| attribute.path().is_ident("view")) {
+        attribute.parse_nested_meta(|meta| {
+            if meta.path.is_ident("table") {
+                table = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("event_handler") {
+                let mut name: Option<Ident> = None;
+                let mut aggregate: Option<Path> = None;
+                let mut apply: Option<Path> = None;
+
+                meta.parse_nested_meta(|nested| {
+                    if nested.path.is_ident("name") {
+                        name = Some(nested.value()?.parse()?);
+                    } else if nested.path.is_ident("aggregate") {
+                        aggregate = Some(nested.value()?.parse()?);
+                    } else if nested.path.is_ident("apply") {
+                        apply = Some(nested.value()?.parse()?);
+                    } else {
+                        return Err(nested.error("unsupported event_handler attribute"));
+                    }
+                    Ok(())
+                })?;
+
+                match (name, aggregate, apply) {
+                    (Some(name), Some(aggregate), Some(apply)) => {
+                        event_handler = Some(EventHandler { name, aggregate, apply })
+                    }
+                    _ => return Err(meta.error("`name`, `aggregate` and `apply` must be set together")),
+                }
+            } else {
+                return Err(meta.error("unsupported view attribute"));
+            }
+            Ok(())
+        })?;
+    }
+
+    let table: LitStr =
+        table.ok_or_else(|| syn::Error::new_spanned(&input.ident, "missing `#[view(table = \"...\")]` attribute"))?;
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "only structs with named fields are supported",
+                ))
+            }
+        },
+        _ => return Err(syn::Error::new_spanned(&input.ident, "only structs are supported")),
+    };
+
+    let mut columns: Vec<Column> = vec![];
+    for field in fields {
+        let ident: Ident = field.ident.clone().expect("named fields have an ident");
+        let mut sql_type: Option<LitStr> = None;
+        let mut id: bool = false;
+
+        for attribute in field.attrs.iter().filter(|attribute| attribute.path().is_ident("view")) {
+            attribute.parse_nested_meta(|meta| {
+                if meta.path.is_ident("id") {
+                    id = true;
+                } else if meta.path.is_ident("sql_type") {
+                    sql_type = Some(meta.value()?.parse()?);
+                } else {
+                    return Err(meta.error("unsupported view field attribute"));
+                }
+                Ok(())
+            })?;
+        }
+
+        let (inner_type, nullable) = match option_inner_type(&field.ty) {
+            Some(inner_type) => (inner_type, true),
+            None => (&field.ty, false),
+        };
+
+        let sql_type: String = match sql_type {
+            Some(sql_type) => sql_type.value(),
+            None => infer_sql_type(inner_type).ok_or_else(|| {
+                syn::Error::new_spanned(&field.ty, "unsupported type: set it with `#[view(sql_type = \"...\")]`")
+            })?,
+        };
+
+        columns.push(Column {
+            field: ident,
+            sql_type,
+            nullable,
+            id,
+        });
+    }
+
+    let id_index: usize = match columns.iter().filter(|column| column.id).count() {
+        0 => columns
+            .iter()
+            .position(|column| column.field == "id")
+            .ok_or_else(|| syn::Error::new_spanned(&input.ident, "missing `id` field or `#[view(id)]` attribute"))?,
+        1 => columns.iter().position(|column| column.id).unwrap_or_default(),
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "only one field can be marked `#[view(id)]`",
+            ))
+        }
+    };
+    let id_field: &Ident = &columns[id_index].field;
+    let id_column: String = id_field.to_string();
+
+    let definitions: Vec<String> = columns
+        .iter()
+        .enumerate()
+        .map(|(index, column)| {
+            let constraint: &str = if index == id_index {
+                " PRIMARY KEY NOT NULL"
+            } else if column.nullable {
+                ""
+            } else {
+                " NOT NULL"
+            };
+            format!("{} {}{}", column.field, column.sql_type, constraint)
+        })
+        .collect();
+    let names: Vec<String> = columns.iter().map(|column| column.field.to_string()).collect();
+    let placeholders: Vec<String> = (1..=columns.len()).map(|i| format!("${}", i)).collect();
+    let assignments: Vec<String> = names
+        .iter()
+        .filter(|name| **name != id_column)
+        .map(|name| format!("{0} = EXCLUDED.{0}", name))
+        .collect();
+    let conflict_action: String = if assignments.is_empty() {
+        "NOTHING".to_string()
+    } else {
+        format!("UPDATE SET {}", assignments.join(", "))
+    };
+
+    let table_name: String = table.value();
+    let create_table: String = format!("CREATE TABLE IF NOT EXISTS {} ({})", table_name, definitions.join(", "));
+    let select_by_id: String = format!(
+        "SELECT {} FROM {} WHERE {} = $1",
+        names.join(", "),
+        table_name,
+        id_column
+    );
+    let upsert: String = format!(
+        "INSERT INTO {} ({}) VALUES ({}) ON CONFLICT ({}) DO {}",
+        table_name,
+        names.join(", "),
+        placeholders.join(", "),
+        id_column,
+        conflict_action
+    );
+    let delete_by_id: String = format!("DELETE FROM {} WHERE {} = $1", table_name, id_column);
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let id_type = &fields
+        .iter()
+        .find(|field| field.ident.as_ref() == Some(id_field))
+        .expect("the id column is a field")
+        .ty;
+    let bound_fields = columns.iter().map(|column| &column.field);
+
+    let scaffolding = quote! {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// The name of the view table.
+            pub const TABLE_NAME: &'static str = #table;
+
+            /// The statement creating the view table, if it doesn't exist.
+            pub const CREATE_TABLE: &'static str = #create_table;
+
+            /// Creates the view table, if it doesn't exist.
+            pub async fn create_table<'e>(
+                executor: impl ::esrs::__private::sqlx::Executor<'e, Database = ::esrs::__private::sqlx::Postgres>,
+            ) -> ::std::result::Result<(), ::esrs::__private::sqlx::Error> {
+                ::esrs::__private::sqlx::query(#create_table).execute(executor).await.map(|_| ())
+            }
+
+            /// Loads the row with the given id, if any.
+            pub async fn by_id<'e>(
+                id: #id_type,
+                executor: impl ::esrs::__private::sqlx::Executor<'e, Database = ::esrs::__private::sqlx::Postgres>,
+            ) -> ::std::result::Result<::std::option::Option<Self>, ::esrs::__private::sqlx::Error>
+            where
+                Self: for<'r> ::esrs::__private::sqlx::FromRow<'r, ::esrs::__private::sqlx::postgres::PgRow>
+                    + ::std::marker::Send
+                    + ::std::marker::Unpin,
+            {
+                ::esrs::__private::sqlx::query_as::<_, Self>(#select_by_id)
+                    .bind(id)
+                    .fetch_optional(executor)
+                    .await
+            }
+
+            /// Inserts the row, overwriting the one with the same id, if any.
+            pub async fn upsert<'e>(
+                &self,
+                executor: impl ::esrs::__private::sqlx::Executor<'e, Database = ::esrs::__private::sqlx::Postgres>,
+            ) -> ::std::result::Result<(), ::esrs::__private::sqlx::Error> {
+                ::esrs::__private::sqlx::query(#upsert)
+                    #(.bind(&self.#bound_fields))*
+                    .execute(executor)
+                    .await
+                    .map(|_| ())
+            }
+
+            /// Deletes the row with the given id, if any.
+            pub async fn delete<'e>(
+                id: #id_type,
+                executor: impl ::esrs::__private::sqlx::Executor<'e, Database = ::esrs::__private::sqlx::Postgres>,
+            ) -> ::std::result::Result<(), ::esrs::__private::sqlx::Error> {
+                ::esrs::__private::sqlx::query(#delete_by_id)
+                    .bind(id)
+                    .execute(executor)
+                    .await
+                    .map(|_| ())
+            }
+        }
+    };
+
+    let event_handler = event_handler.map(|EventHandler { name, aggregate, apply }| {
+        quote! {
+            /// Event handler keeping the view up to date, generated by `#[derive(View)]`.
+            pub struct #name {
+                pool: ::esrs::__private::sqlx::Pool<::esrs::__private::sqlx::Postgres>,
+            }
+
+            impl #name {
+                /// Creates the event handler, working on the given pool.
+                pub fn new(pool: ::esrs::__private::sqlx::Pool<::esrs::__private::sqlx::Postgres>) -> Self {
+                    Self { pool }
+                }
+            }
+
+            #[::esrs::__private::async_trait]
+            impl ::esrs::handler::EventHandler<#aggregate> for #name {
+                async fn handle(&self, event: &::esrs::store::StoreEvent<<#aggregate as ::esrs::Aggregate>::Event>) {
+                    let result: ::std::result::Result<(), ::esrs::__private::sqlx::Error> = async {
+                        let mut connection = self.pool.acquire().await?;
+                        let row = #ident::by_id(event.aggregate_id, &mut *connection).await?;
+
+                        match #apply(row, event) {
+                            ::std::option::Option::Some(row) => row.upsert(&mut *connection).await,
+                            ::std::option::Option::None => Ok(()),
+                        }
+                    }
+                    .await;
+
+                    if let Err(error) = result {
+                        ::esrs::__private::tracing::error!({
+                            view = #table,
+                            aggregate_id = %event.aggregate_id,
+                            error = ?error,
+                        }, "failed to update view row");
+                    }
+                }
+
+                async fn delete(&self, aggregate_id: ::esrs::__private::Uuid) {
+                    if let Err(error) = #ident::delete(aggregate_id, &self.pool).await {
+                        ::esrs::__private::tracing::error!({
+                            view = #table,
+                            aggregate_id = %aggregate_id,
+                            error = ?error,
+                        }, "failed to delete view row");
+                    }
+                }
+            }
+        }
+    });
+
+    Ok(quote! {
+        #scaffolding
+        #event_handler
+    })
+}
+
+/// Returns `T` if the given type is `Option<T>`.
+fn option_inner_type(ty: &Type) -> Option<&Type> {
+    let Type::Path(path) = ty else { return None };
+    let segment = path.path.segments.last()?;
+
+    if segment.ident != "Option" {
+        return None;
+    }
+
+    match &segment.arguments {
+        PathArguments::AngleBracketed(arguments) => match arguments.args.first()? {
+            GenericArgument::Type(inner_type) => Some(inner_type),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Infers the Postgres type of the column from the most common Rust types.
+fn infer_sql_type(ty: &Type) -> Option<String> {
+    let Type::Path(path) = ty else { return None };
+    let sql_type: &str = match path.path.segments.last()?.ident.to_string().as_str() {
+        "Uuid" => "UUID",
+        "String" => "VARCHAR",
+        "bool" => "BOOLEAN",
+        "i16" => "SMALLINT",
+        "i32" => "INTEGER",
+        "i64" => "BIGINT",
+        "f32" => "REAL",
+        "f64" => "DOUBLE PRECISION",
+        "DateTime" => "TIMESTAMPTZ",
+        "NaiveDate" => "DATE",
+        "Value" | "Json" => "JSONB",
+        _ => return None,
+    };
+
+    Some(sql_type.to_string())
+}
@@ -76,4 +76,11 @@ impl BasicView {
     pub fn table_name(&self) -> &str {
         &self.table_name
     }
+
+    /// Returns the same view, backed by the given table, e.g. a shadow copy of its table.
+    pub fn on_table(&self, table_name: &str) -> Self {
+        Self {
+            table_name: table_name.to_string(),
+        }
+    }
 }
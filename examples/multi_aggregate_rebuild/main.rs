@@ -1,28 +1,20 @@
 //! This example serves as a demonstration of creating a custom script to rebuild two separate
 //! [`Aggregate`]s that share the same view.
 //!
-//! The script streams events from two different event stores, opens a transaction, and truncates
-//! both tables.
-//!
-//! It's important to note that the view handled by the `SharedEventHandler` is truncated without
-//! using the transaction. This is because the view is eventually consistent and its deletion is
-//! unlikely to cause an outage.
-//!
-//! After truncation, each event is passed to its respective handler in chronological order.
-//! Finally, the transaction is committed.
+//! The script truncates both views, and then uses a [`MergedPgRebuilder`] to stream the events
+//! from the two different event stores, passing each event to its respective handlers in
+//! chronological order, within a single transaction.
 
-use futures::StreamExt;
-use sqlx::{PgConnection, Pool, Postgres, Transaction};
+use sqlx::{Pool, Postgres};
 use uuid::Uuid;
 
-use esrs::handler::{ReplayableEventHandler, TransactionalEventHandler};
 use esrs::manager::AggregateManager;
-use esrs::store::postgres::{PgStore, PgStoreBuilder, PgStoreError};
-use esrs::store::StoreEvent;
+use esrs::rebuilder::{MergedPgRebuilder, PgRebuilder};
+use esrs::store::postgres::{PgStore, PgStoreBuilder};
 use esrs::AggregateState;
 
-use crate::common::a::{AggregateA, CommandA, EventA};
-use crate::common::b::{AggregateB, CommandB, EventB};
+use crate::common::a::{AggregateA, CommandA};
+use crate::common::b::{AggregateB, CommandB};
 use crate::common::shared::event_handler::SharedEventHandler;
 use crate::common::shared::view::SharedView;
 use crate::common::util::new_pool;
@@ -64,23 +56,13 @@ async fn rebuild_multi_aggregate(
     });
 
     // It is important to have `ReplayableEventHandler`s only.
-    let event_handlers_a: Vec<Box<dyn ReplayableEventHandler<AggregateA>>> = vec![event_handler.clone()];
-    let event_handlers_b: Vec<Box<dyn ReplayableEventHandler<AggregateB>>> = vec![event_handler.clone()];
-
-    let transactional_event_handlers_a: Vec<
-        Box<dyn TransactionalEventHandler<AggregateA, PgStoreError, PgConnection>>,
-    > = vec![transactional_event_handler.clone()];
-
-    let transactional_event_handlers_b: Vec<
-        Box<dyn TransactionalEventHandler<AggregateB, PgStoreError, PgConnection>>,
-    > = vec![transactional_event_handler.clone()];
+    let rebuilder_a: PgRebuilder<AggregateA> = PgRebuilder::new()
+        .with_event_handlers(vec![event_handler.clone()])
+        .with_transactional_event_handlers(vec![transactional_event_handler.clone()]);
 
-    let mut events_a = store_a.stream_events(&pool);
-    let mut events_b = store_b.stream_events(&pool);
-
-    // Fetch first element of both the tables
-    let mut event_a_opt: Option<Result<StoreEvent<EventA>, PgStoreError>> = events_a.next().await;
-    let mut event_b_opt: Option<Result<StoreEvent<EventB>, PgStoreError>> = events_b.next().await;
+    let rebuilder_b: PgRebuilder<AggregateB> = PgRebuilder::new()
+        .with_event_handlers(vec![event_handler])
+        .with_transactional_event_handlers(vec![transactional_event_handler]);
 
     // There are 3 choices here:
     // - Truncate all the tables where the event handlers and transactional event handlers insist on.
@@ -90,66 +72,19 @@ async fn rebuild_multi_aggregate(
     //
     // In this example we truncate the tables
 
-    let query: String = format!("TRUNCATE TABLE {}", view.table_name());
+    let query: String = format!(
+        "TRUNCATE TABLE {}, {}",
+        view.table_name(),
+        transactional_view.table_name()
+    );
     let _ = sqlx::query(query.as_str()).execute(&pool).await.unwrap();
 
-    // At this point it's possible to open a transaction
-    let mut transaction: Transaction<Postgres> = pool.begin().await.unwrap();
-
-    let query: String = format!("TRUNCATE TABLE {}", transactional_view.table_name());
-    let _ = sqlx::query(query.as_str()).execute(&mut *transaction).await.unwrap();
-
-    loop {
-        let a_opt: Option<&StoreEvent<EventA>> = event_a_opt.as_ref().map(|v| v.as_ref().unwrap());
-        let b_opt: Option<&StoreEvent<EventB>> = event_b_opt.as_ref().map(|v| v.as_ref().unwrap());
-
-        match (a_opt, b_opt) {
-            // If both the streams returned a value we check what's the oldest. If the oldest is `a`
-            // we proceed to run the transactional event handlers from AggregateA.
-            (Some(a), Some(b)) if a.occurred_on <= b.occurred_on => {
-                for transactional_event_handler in &transactional_event_handlers_a {
-                    transactional_event_handler.handle(a, &mut transaction).await.unwrap();
-                }
-                for event_handler in &event_handlers_a {
-                    event_handler.handle(a).await;
-                }
-
-                // Get next value from AggregateA events stream
-                event_a_opt = events_a.next().await;
-            }
-            // If only the stream on AggregateA events contains values we proceed to run the projectors
-            // from AggregateA.
-            (Some(a), None) => {
-                for transactional_event_handler in &transactional_event_handlers_a {
-                    transactional_event_handler.handle(a, &mut transaction).await.unwrap();
-                }
-                for event_handler in &event_handlers_a {
-                    event_handler.handle(a).await;
-                }
-
-                // Get next value from AggregateA events stream
-                event_a_opt = events_a.next().await;
-            }
-            // If both the streams returned a value and AggregateB event is older or if only the stream
-            // on AggregateB events contains values we proceed to run the projectors from AggregateB.
-            (Some(_), Some(b)) | (None, Some(b)) => {
-                for transactional_event_handler in &transactional_event_handlers_b {
-                    transactional_event_handler.handle(b, &mut transaction).await.unwrap();
-                }
-                for event_handler in &event_handlers_b {
-                    event_handler.handle(b).await;
-                }
-
-                // Get next value from AggregateB events stream
-                event_b_opt = events_b.next().await;
-            }
-            // If both the streams are empty then we break the loop.
-            (None, None) => break,
-        };
-    }
-
-    // Finally commit the transaction
-    transaction.commit().await.unwrap();
+    // The events of both the stores are replayed in chronological order, within a single transaction
+    // for the transactional event handlers.
+    let _ = MergedPgRebuilder::for_stores(vec![(&store_a, rebuilder_a).into(), (&store_b, rebuilder_b).into()])
+        .rebuild(pool.clone())
+        .await
+        .unwrap();
 
     // This fixed the amount that were stored as a negative value
     assert_eq!(view.by_id(shared_id, &pool).await.unwrap().unwrap().sum, 17);
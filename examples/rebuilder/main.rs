@@ -9,6 +9,10 @@
 //!   In this strategy, a transaction is opened to truncate the entire table, removing all existing
 //!   content. Subsequently, all events retrieved at the time the transaction is initiated are rebuilt.
 //!
+//! - Rebuilding into shadow tables:
+//!   In this strategy, the events are projected into a copy of the table, while the live one keeps
+//!   being served. Once caught up, the copy atomically replaces the live table.
+//!
 //! Please note that rebuilding using non-replayable event handlers is not possible in this context.
 //!
 //! This will not compile:
@@ -70,6 +74,14 @@ async fn main() {
     let aggregate_id: Uuid = Uuid::new_v4();
     setup(aggregate_id, view.clone(), transactional_view.clone(), pool.clone()).await;
     rebuild_all_at_once(aggregate_id, view.clone(), transactional_view.clone(), pool.clone()).await;
+
+    // PgRebuilder::into_shadow_tables rebuilding
+    let view: BasicView = BasicView::new("view_v3", &pool).await;
+    let transactional_view: BasicView = BasicView::new("transactional_view_v3", &pool).await;
+
+    let aggregate_id: Uuid = Uuid::new_v4();
+    setup(aggregate_id, view.clone(), transactional_view.clone(), pool.clone()).await;
+    rebuild_into_shadow_tables(aggregate_id, transactional_view.clone(), pool.clone()).await;
 }
 
 async fn setup(aggregate_id: Uuid, view: BasicView, transactional_view: BasicView, pool: Pool<Postgres>) {
@@ -182,3 +194,24 @@ async fn rebuild_all_at_once(aggregate_id: Uuid, view: BasicView, transactional_
         "basic_command.v2"
     );
 }
+
+async fn rebuild_into_shadow_tables(aggregate_id: Uuid, transactional_view: BasicView, pool: Pool<Postgres>) {
+    let transactional_event_handler_v2 = BasicTransactionalEventHandlerV2 {
+        view: transactional_view.clone(),
+    };
+
+    let rebuilder: PgRebuilder<BasicAggregate> =
+        PgRebuilder::new().with_shadow_event_handlers(vec![Box::new(transactional_event_handler_v2)]);
+
+    rebuilder.into_shadow_tables(pool.clone()).await.unwrap();
+
+    assert_eq!(
+        transactional_view
+            .by_id(aggregate_id, &pool)
+            .await
+            .unwrap()
+            .unwrap()
+            .content,
+        "basic_command.v2"
+    );
+}
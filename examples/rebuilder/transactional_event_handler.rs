@@ -1,7 +1,9 @@
 use async_trait::async_trait;
 use sqlx::PgConnection;
+use uuid::Uuid;
 
 use esrs::handler::TransactionalEventHandler;
+use esrs::rebuilder::ShadowEventHandler;
 use esrs::store::postgres::PgStoreError;
 use esrs::store::StoreEvent;
 
@@ -48,3 +50,36 @@ impl TransactionalEventHandler<BasicAggregate, PgStoreError, PgConnection> for B
             .await?)
     }
 }
+
+#[async_trait]
+impl ShadowEventHandler<BasicAggregate> for BasicTransactionalEventHandlerV2 {
+    fn table_name(&self) -> &str {
+        self.view.table_name()
+    }
+
+    async fn handle(
+        &self,
+        event: &StoreEvent<BasicEvent>,
+        table_name: &str,
+        transaction: &mut PgConnection,
+    ) -> Result<(), PgStoreError> {
+        Ok(self
+            .view
+            .on_table(table_name)
+            .upsert(
+                event.aggregate_id,
+                format!("{}.v2", &event.payload.content),
+                transaction,
+            )
+            .await?)
+    }
+
+    async fn delete(
+        &self,
+        aggregate_id: Uuid,
+        table_name: &str,
+        transaction: &mut PgConnection,
+    ) -> Result<(), PgStoreError> {
+        Ok(self.view.on_table(table_name).delete(aggregate_id, transaction).await?)
+    }
+}
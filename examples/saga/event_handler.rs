@@ -1,44 +1,54 @@
-use std::sync::Arc;
-
 use async_trait::async_trait;
-use futures::lock::Mutex;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
-use esrs::handler::EventHandler;
 use esrs::manager::AggregateManager;
+use esrs::process::{ProcessEventHandler, ProcessManager};
 use esrs::store::postgres::PgStore;
 use esrs::store::StoreEvent;
 
 use crate::aggregate::{SagaAggregate, SagaCommand, SagaEvent};
 
-#[derive(Clone)]
-pub struct SagaEventHandler {
-    pub store: PgStore<SagaAggregate>,
-    pub side_effect_mutex: Arc<Mutex<bool>>,
+#[derive(Default, Serialize, Deserialize)]
+pub struct SagaState {
+    pub registered: bool,
+}
+
+pub struct SagaProcessManager {
+    pub manager: AggregateManager<PgStore<SagaAggregate>>,
+}
+
+impl ProcessManager for SagaProcessManager {
+    type State = SagaState;
+
+    fn is_completed(&self, state: &Self::State) -> bool {
+        state.registered
+    }
 }
 
 #[async_trait]
-impl EventHandler<SagaAggregate> for SagaEventHandler {
-    async fn handle(&self, event: &StoreEvent<SagaEvent>) {
-        let manager = AggregateManager::new(self.store.clone());
-
-        if event.payload == SagaEvent::MutationRequested {
-            match manager.load(event.aggregate_id).await {
-                Ok(Some(state)) => {
-                    let mut guard = self.side_effect_mutex.lock().await;
-                    *guard = true;
-                    match manager.handle_command(state, SagaCommand::RegisterMutation).await {
-                        Err(err) => eprintln!("Operational error while handling register mutation command: {:?}", err),
-                        Ok(Err(err)) => eprintln!("Register mutation command denied: {:?}", err),
-                        Ok(Ok(_)) => {}
-                    };
-                }
-                Ok(None) => {
-                    eprintln!("Something went wrong getting aggregate state")
-                }
-                Err(err) => {
-                    eprintln!("Failed to perform side effect: {:?}", err)
-                }
-            }
-        }
+impl ProcessEventHandler<SagaAggregate> for SagaProcessManager {
+    fn process_id(&self, event: &StoreEvent<SagaEvent>) -> Option<Uuid> {
+        (event.payload == SagaEvent::MutationRequested).then_some(event.aggregate_id)
+    }
+
+    async fn handle(
+        &self,
+        process_id: Uuid,
+        state: &mut Self::State,
+        _event: &StoreEvent<SagaEvent>,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let aggregate_state = self
+            .manager
+            .load(process_id)
+            .await?
+            .ok_or("Something went wrong getting aggregate state")?;
+
+        self.manager
+            .handle_command(aggregate_state, SagaCommand::RegisterMutation)
+            .await??;
+
+        state.registered = true;
+        Ok(())
     }
 }
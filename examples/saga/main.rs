@@ -1,53 +1,44 @@
-//! This basic example showcases the usage of the saga pattern approach. It is worth noting that in
-//! this particular scenario, an aggregate is employing a saga over itself, creating a form of
-//! circular dependency.
+//! This basic example showcases the usage of a [`ProcessManager`] to implement the saga pattern. It
+//! is worth noting that in this particular scenario, an aggregate is employing a saga over itself,
+//! creating a form of circular dependency.
 //!
-//! However, this circular dependency is effectively handled by encapsulating the [`PgStore`] within
-//! an [`Arc`], ensuring atomicity and preventing issues.
+//! The [`ProcessRunner`] stores the state of each saga in its own table, and records the handled
+//! events, so that each of them is handled once even if redelivered.
 
-use std::sync::Arc;
-
-use futures::lock::Mutex;
 use sqlx::{Pool, Postgres};
 use uuid::Uuid;
 
 use esrs::manager::AggregateManager;
-use esrs::store::postgres::{PgStore, PgStoreBuilder, PgStoreError};
+use esrs::process::ProcessRunner;
+use esrs::store::postgres::{PgStore, PgStoreBuilder};
 use esrs::store::EventStore;
 use esrs::AggregateState;
 
 use crate::aggregate::{SagaAggregate, SagaCommand, SagaEvent};
 use crate::common::util::new_pool;
-use crate::common::CommonError;
-use crate::event_handler::SagaEventHandler;
+use crate::event_handler::SagaProcessManager;
 
 mod aggregate;
 #[path = "../common/lib.rs"]
 mod common;
 mod event_handler;
 
-#[derive(Debug, thiserror::Error)]
-pub enum SagaError {
-    #[error(transparent)]
-    Aggregate(#[from] CommonError),
-    #[error(transparent)]
-    Store(#[from] PgStoreError),
-}
-
 #[tokio::main]
 async fn main() {
     let pool: Pool<Postgres> = new_pool().await;
 
-    let side_effect_mutex: Arc<Mutex<bool>> = Arc::new(Mutex::new(false));
-
     let store: PgStore<SagaAggregate> = PgStoreBuilder::new(pool.clone()).try_build().await.unwrap();
 
-    let saga_event_handler: SagaEventHandler = SagaEventHandler {
-        store: store.clone(),
-        side_effect_mutex: side_effect_mutex.clone(),
-    };
+    let runner: ProcessRunner<SagaProcessManager> = ProcessRunner::new(
+        pool.clone(),
+        "saga",
+        SagaProcessManager {
+            manager: AggregateManager::new(store.clone()),
+        },
+    );
+    runner.setup().await.unwrap();
 
-    store.add_event_handler(saga_event_handler).await;
+    store.add_event_handler(runner.clone()).await;
 
     let manager: AggregateManager<PgStore<SagaAggregate>> = AggregateManager::new(store.clone());
 
@@ -67,6 +58,6 @@ async fn main() {
     assert!(payloads.contains(&SagaEvent::MutationRequested));
     assert!(payloads.contains(&SagaEvent::MutationRegistered));
 
-    let guard = side_effect_mutex.lock().await;
-    assert!(*guard);
+    let (_, completed) = runner.load(id).await.unwrap().unwrap();
+    assert!(completed);
 }
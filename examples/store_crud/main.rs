@@ -103,14 +103,7 @@ async fn main() {
     let new_payload: BasicEvent = BasicEvent {
         content: "updated content".to_string(),
     };
-    let query: String = format!("UPDATE {} SET payload = $2 WHERE id = $1", store.table_name());
-
-    let _ = sqlx::query(query.as_str())
-        .bind(event_id)
-        .bind(Json(new_payload.clone()))
-        .execute(&pool)
-        .await
-        .unwrap();
+    assert!(store.update_event_payload(event_id, new_payload.clone()).await.unwrap());
 
     assert_eq!(
         get_event_by_event_id(event_id, store.table_name(), &pool)
@@ -122,9 +115,7 @@ async fn main() {
     );
 
     // Delete event by event id
-    let query: String = format!("DELETE FROM {} WHERE id = $1", store.table_name());
-
-    let _ = sqlx::query(query.as_str()).bind(event_id).execute(&pool).await.unwrap();
+    assert!(store.delete_event(event_id).await.unwrap());
 
     assert!(get_event_by_event_id(event_id, store.table_name(), &pool)
         .await
@@ -2,6 +2,7 @@ use rdkafka::ClientConfig;
 use typed_builder::TypedBuilder;
 
 use crate::bus::kafka::error::KafkaEventBusError;
+use crate::bus::TopicStrategy;
 
 #[derive(TypedBuilder)]
 pub struct KafkaEventBusConfig<'a> {
@@ -10,7 +11,11 @@ pub struct KafkaEventBusConfig<'a> {
     pub(crate) broker_url_list: &'a str,
     /// The default topic to use for publishing events. If not specified, events need to be
     /// explicitly published to a specific topic.
+    #[builder(default)]
     pub(crate) topic: &'a str,
+    /// An optional strategy naming the topic after the aggregate, taking precedence over `topic`.
+    #[builder(default, setter(strip_option))]
+    pub(crate) topic_strategy: Option<Box<dyn TopicStrategy>>,
     /// An optional configuration to enable SASL security for authorizing an event bus to publish
     /// events to a specific topic.
     #[builder(default, setter(strip_option))]
@@ -22,6 +27,11 @@ pub struct KafkaEventBusConfig<'a> {
     /// Additional Kafka client configuration.
     #[builder(default, setter(strip_option))]
     pub(crate) client_config: Option<ClientConfig>,
+    /// Whether to publish the payloads as serialized in the event store (see
+    /// [`crate::store::StoreEvent::raw_payload`]) instead of serializing the events again. With a
+    /// custom schema, the published payload is then the serialized schema. Defaults to false.
+    #[builder(default)]
+    pub(crate) stored_payload: bool,
     /// A boxed anonymous function utilized to provide a form of error handling, commonly used for
     /// reporting purposes.
     #[builder(default = Box::new(| _ | ()))]
@@ -10,7 +10,7 @@ use uuid::Bytes;
 pub use config::KafkaEventBusConfig;
 pub use error::KafkaEventBusError;
 
-use crate::bus::EventBus;
+use crate::bus::{serialize_store_event, EventBus};
 use crate::store::StoreEvent;
 use crate::Aggregate;
 
@@ -23,11 +23,15 @@ pub struct KafkaEventBus<A> {
     producer: FutureProducer,
     topic: String,
     request_timeout: Duration,
+    stored_payload: bool,
     error_handler: Box<dyn Fn(KafkaEventBusError) + Send + Sync>,
     _phantom: PhantomData<A>,
 }
 
-impl<A> KafkaEventBus<A> {
+impl<A> KafkaEventBus<A>
+where
+    A: Aggregate,
+{
     pub async fn new(config: KafkaEventBusConfig<'_>) -> Result<KafkaEventBus<A>, KafkaEventBusError> {
         let mut client_config: ClientConfig = config.client_config.unwrap_or_default();
         client_config
@@ -44,8 +48,12 @@ impl<A> KafkaEventBus<A> {
 
         Ok(Self {
             producer: client_config.create()?,
-            topic: config.topic.to_string(),
+            topic: match config.topic_strategy {
+                Some(topic_strategy) => topic_strategy.topic(A::NAME),
+                None => config.topic.to_string(),
+            },
             request_timeout: Duration::from_millis(config.request_timeout),
+            stored_payload: config.stored_payload,
             error_handler: config.error_handler,
             _phantom: Default::default(),
         })
@@ -65,6 +73,13 @@ where
             Err(err) => (self.error_handler)(err),
         }
     }
+
+    async fn try_publish(
+        &self,
+        store_event: &StoreEvent<A::Event>,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Ok(publish(self, store_event).await?)
+    }
 }
 
 async fn publish<A>(event_bus: &KafkaEventBus<A>, store_event: &StoreEvent<A::Event>) -> Result<(), KafkaEventBusError>
@@ -72,7 +87,7 @@ where
     A: Aggregate + Send + Sync,
     A::Event: Serialize,
 {
-    let bytes: Vec<u8> = serde_json::to_vec(store_event)?;
+    let bytes: Vec<u8> = serialize_store_event(store_event, event_bus.stored_payload)?;
     let key_bytes: &Bytes = store_event.aggregate_id.as_bytes();
 
     let _ = event_bus
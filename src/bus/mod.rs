@@ -1,10 +1,23 @@
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::value::RawValue;
+use uuid::Uuid;
 
-use crate::store::StoreEvent;
+use crate::store::{Metadata, StoreEvent};
+use crate::types::SequenceNumber;
 use crate::Aggregate;
 
+#[cfg(any(feature = "runtime-tokio", feature = "runtime-async-std"))]
+pub use policy::{DeadLetterHandler, PublishPolicy, RetryingEventBus};
+
+#[cfg(any(feature = "runtime-tokio", feature = "runtime-async-std"))]
+mod policy;
+
 #[cfg(feature = "kafka")]
 pub mod kafka;
+#[cfg(feature = "pubsub")]
+pub mod pubsub;
 #[cfg(feature = "rabbit")]
 pub mod rabbit;
 
@@ -18,4 +31,108 @@ where
     ///
     /// All the errors should be handled from within the [`EventBus`] and shouldn't panic.
     async fn publish(&self, store_event: &StoreEvent<A::Event>);
+
+    /// Publish an [`Aggregate`] event on an [`EventBus`], returning the error instead of handling it,
+    /// so that the caller can retry the publishing, e.g. the `OutboxRelay` of the `postgres` store.
+    ///
+    /// By default, this calls [`EventBus::publish`] and never fails: buses able to detect failures
+    /// should override it.
+    async fn try_publish(
+        &self,
+        store_event: &StoreEvent<A::Event>,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>>
+    where
+        A::Event: Sync,
+    {
+        self.publish(store_event).await;
+        Ok(())
+    }
+}
+
+/// Names the Kafka or Pub/Sub topic, or the RabbitMQ exchange, the events of an [`Aggregate`] are
+/// published to, so that a single bus configuration can be shared among many aggregates.
+///
+/// Any `Fn(&str) -> String` closure, taking the [`Aggregate::NAME`], is a [`TopicStrategy`].
+pub trait TopicStrategy: Send + Sync {
+    /// Returns the name of the topic for the aggregate with the given [`Aggregate::NAME`].
+    fn topic(&self, aggregate_name: &str) -> String;
+}
+
+impl<F> TopicStrategy for F
+where
+    F: Fn(&str) -> String + Send + Sync,
+{
+    fn topic(&self, aggregate_name: &str) -> String {
+        self(aggregate_name)
+    }
+}
+
+/// A [`TopicStrategy`] using the same name for every aggregate.
+pub struct FixedTopic(String);
+
+impl FixedTopic {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self(name.into())
+    }
+}
+
+impl TopicStrategy for FixedTopic {
+    fn topic(&self, _aggregate_name: &str) -> String {
+        self.0.clone()
+    }
+}
+
+/// A [`TopicStrategy`] replacing the `{aggregate_name}` placeholder of the given template with the
+/// [`Aggregate::NAME`], e.g. `{aggregate_name}-events`.
+pub struct TemplateTopic(String);
+
+impl TemplateTopic {
+    pub fn new(template: impl Into<String>) -> Self {
+        Self(template.into())
+    }
+}
+
+impl TopicStrategy for TemplateTopic {
+    fn topic(&self, aggregate_name: &str) -> String {
+        self.0.replace("{aggregate_name}", aggregate_name)
+    }
+}
+
+/// Serializes a [`StoreEvent`] to be published on an [`EventBus`].
+///
+/// When `stored_payload` is true, its [`StoreEvent::raw_payload`], if any, is reused instead of
+/// serializing the payload again: when using a custom schema, the published payload is then the
+/// serialized schema rather than the serialized event.
+///
+/// # Errors
+///
+/// Will return an `Err` if the serialization fails.
+pub fn serialize_store_event<E>(store_event: &StoreEvent<E>, stored_payload: bool) -> Result<Vec<u8>, serde_json::Error>
+where
+    E: Serialize,
+{
+    match store_event.raw_payload().filter(|_| stored_payload) {
+        None => serde_json::to_vec(store_event),
+        Some(raw_payload) => serde_json::to_vec(&RawStoreEventRef {
+            id: &store_event.id,
+            aggregate_id: &store_event.aggregate_id,
+            payload: raw_payload,
+            occurred_on: &store_event.occurred_on,
+            sequence_number: &store_event.sequence_number,
+            version: &store_event.version,
+            metadata: &store_event.metadata,
+        }),
+    }
+}
+
+/// Same layout of a serialized [`StoreEvent`], with an already serialized payload.
+#[derive(Serialize)]
+struct RawStoreEventRef<'a> {
+    id: &'a Uuid,
+    aggregate_id: &'a Uuid,
+    payload: &'a RawValue,
+    occurred_on: &'a DateTime<Utc>,
+    sequence_number: &'a SequenceNumber,
+    version: &'a Option<i32>,
+    metadata: &'a Metadata,
 }
@@ -0,0 +1,169 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::bus::EventBus;
+use crate::store::StoreEvent;
+use crate::Aggregate;
+
+/// This trait is used to implement a [`DeadLetterHandler`], receiving the events an [`EventBus`]
+/// failed to publish once the retries of its [`PublishPolicy`] are exhausted, e.g. to store them
+/// for a later republishing or to raise an alert.
+#[async_trait]
+pub trait DeadLetterHandler<A>: Sync
+where
+    A: Aggregate,
+{
+    /// Handle an event that couldn't be published, along with the last publishing error.
+    async fn handle(&self, store_event: &StoreEvent<A::Event>, error: &(dyn std::error::Error + Send + Sync));
+}
+
+/// The policy applied when publishing to an [`EventBus`] through a [`RetryingEventBus`]: failed
+/// publishings, as reported by [`EventBus::try_publish`], are retried up to the maximum number of
+/// retries, waiting an exponential backoff between attempts. The events still failing are then
+/// handed to the [`DeadLetterHandler`], if any.
+pub struct PublishPolicy<A>
+where
+    A: Aggregate,
+{
+    max_retries: u32,
+    base_backoff: Duration,
+    max_backoff: Duration,
+    dead_letter_handler: Option<Box<dyn DeadLetterHandler<A> + Send>>,
+}
+
+impl<A> Default for PublishPolicy<A>
+where
+    A: Aggregate,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A> PublishPolicy<A>
+where
+    A: Aggregate,
+{
+    /// Creates a new [`PublishPolicy`], retrying 3 times after 100 milliseconds, doubling the delay
+    /// at every attempt up to 10 seconds, without a [`DeadLetterHandler`].
+    pub fn new() -> Self {
+        Self {
+            max_retries: 3,
+            base_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+            dead_letter_handler: None,
+        }
+    }
+
+    /// Set the maximum number of retries after the first failed attempt.
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Set the delay before the first retry, doubled at every further retry up to the given maximum.
+    pub fn with_backoff(mut self, base_backoff: Duration, max_backoff: Duration) -> Self {
+        self.base_backoff = base_backoff;
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Set the handler of the events still failing once the retries are exhausted.
+    pub fn with_dead_letter_handler(mut self, dead_letter_handler: impl DeadLetterHandler<A> + Send + 'static) -> Self {
+        self.dead_letter_handler = Some(Box::new(dead_letter_handler));
+        self
+    }
+
+    /// The delay before the given retry, starting from zero.
+    fn backoff(&self, retry: u32) -> Duration {
+        self.base_backoff
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_backoff)
+    }
+}
+
+/// [`EventBus`] wrapping another one, publishing to it according to a [`PublishPolicy`].
+///
+/// Note that the retries happen while dispatching the persisted events, delaying the return of the
+/// persisting call: a store outbox fits long outages better.
+pub struct RetryingEventBus<A, B>
+where
+    A: Aggregate,
+{
+    event_bus: B,
+    policy: PublishPolicy<A>,
+}
+
+impl<A, B> RetryingEventBus<A, B>
+where
+    A: Aggregate,
+    B: EventBus<A>,
+{
+    /// Creates a new [`RetryingEventBus`] publishing to the given event bus according to the given
+    /// policy.
+    pub fn new(event_bus: B, policy: PublishPolicy<A>) -> Self {
+        Self { event_bus, policy }
+    }
+
+    /// Tries to publish the event until it succeeds or the retries are exhausted, returning the last
+    /// error in the latter case.
+    async fn publish_with_retries(
+        &self,
+        store_event: &StoreEvent<A::Event>,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>>
+    where
+        A::Event: Sync,
+    {
+        let mut retry: u32 = 0;
+
+        loop {
+            match self.event_bus.try_publish(store_event).await {
+                Ok(()) => return Ok(()),
+                Err(error) if retry >= self.policy.max_retries => return Err(error),
+                Err(error) => {
+                    tracing::warn!({
+                        event_id = %store_event.id,
+                        aggregate_id = %store_event.aggregate_id,
+                        retry = retry + 1,
+                        error = ?error,
+                    }, "event bus failed to publish event, retrying");
+
+                    crate::runtime::sleep(self.policy.backoff(retry)).await;
+                    retry += 1;
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<A, B> EventBus<A> for RetryingEventBus<A, B>
+where
+    A: Aggregate + Sync,
+    A::Event: Sync,
+    B: EventBus<A> + Send,
+{
+    async fn publish(&self, store_event: &StoreEvent<A::Event>) {
+        if let Err(error) = self.publish_with_retries(store_event).await {
+            tracing::error!({
+                event_id = %store_event.id,
+                aggregate_id = %store_event.aggregate_id,
+                error = ?error,
+            }, "event bus failed to publish event, retries exhausted");
+
+            if let Some(dead_letter_handler) = self.policy.dead_letter_handler.as_ref() {
+                dead_letter_handler.handle(store_event, error.as_ref()).await;
+            }
+        }
+    }
+
+    /// Retries as [`EventBus::publish`] does, but returns the last error to the caller instead of
+    /// handing the event to the [`DeadLetterHandler`].
+    async fn try_publish(
+        &self,
+        store_event: &StoreEvent<A::Event>,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.publish_with_retries(store_event).await
+    }
+}
@@ -0,0 +1,34 @@
+use google_cloud_pubsub::client::ClientConfig;
+use typed_builder::TypedBuilder;
+
+use crate::bus::pubsub::error::PubSubEventBusError;
+use crate::bus::TopicStrategy;
+
+#[derive(TypedBuilder)]
+pub struct PubSubEventBusConfig<'a> {
+    /// The name of the Pub/Sub topic to use for publishing events. The topic must already exist.
+    #[builder(default)]
+    pub(crate) topic: &'a str,
+    /// An optional strategy naming the topic after the aggregate, taking precedence over `topic`.
+    #[builder(default, setter(strip_option))]
+    pub(crate) topic_strategy: Option<Box<dyn TopicStrategy>>,
+    /// Whether to set the aggregate id as the ordering key of the messages, so that the events of an
+    /// aggregate instance are delivered in order to the subscriptions with message ordering enabled.
+    /// Defaults to true.
+    #[builder(default = true)]
+    pub(crate) ordering: bool,
+    /// The Pub/Sub client configuration. If not specified, the default configuration is used,
+    /// authenticated through the application default credentials, or pointing to the emulator if
+    /// the `PUBSUB_EMULATOR_HOST` environment variable is set.
+    #[builder(default, setter(strip_option))]
+    pub(crate) client_config: Option<ClientConfig>,
+    /// Whether to publish the payloads as serialized in the event store (see
+    /// [`crate::store::StoreEvent::raw_payload`]) instead of serializing the events again. With a
+    /// custom schema, the published payload is then the serialized schema. Defaults to false.
+    #[builder(default)]
+    pub(crate) stored_payload: bool,
+    /// A boxed anonymous function utilized to provide a form of error handling, commonly used for
+    /// reporting purposes.
+    #[builder(default = Box::new(| _ | ()))]
+    pub(crate) error_handler: Box<dyn Fn(PubSubEventBusError) + Send + Sync>,
+}
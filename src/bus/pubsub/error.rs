@@ -0,0 +1,17 @@
+/// The `PubSubEventBusError` enum defines the following error types:
+///
+/// - `Json`: Indicates a failure in serializing/deserializing the event payload.
+/// - `Auth`: Indicates a failure in loading the credentials of the Pub/Sub client.
+/// - `Connection`: Indicates an error occurred while establishing a connection with Pub/Sub.
+/// - `PubSub`: Indicates an error encountered during the event publishing process.
+#[derive(thiserror::Error, Debug)]
+pub enum PubSubEventBusError {
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    Auth(Box<dyn std::error::Error + Send + Sync>),
+    #[error(transparent)]
+    Connection(#[from] google_cloud_gax::conn::Error),
+    #[error(transparent)]
+    PubSub(#[from] google_cloud_gax::grpc::Status),
+}
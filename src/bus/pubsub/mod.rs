@@ -0,0 +1,106 @@
+use std::marker::PhantomData;
+
+use async_trait::async_trait;
+use google_cloud_googleapis::pubsub::v1::PubsubMessage;
+use google_cloud_pubsub::client::{Client, ClientConfig};
+use google_cloud_pubsub::publisher::Publisher;
+use serde::Serialize;
+
+pub use config::PubSubEventBusConfig;
+pub use error::PubSubEventBusError;
+
+use crate::bus::{serialize_store_event, EventBus};
+use crate::store::StoreEvent;
+use crate::Aggregate;
+
+mod config;
+mod error;
+
+/// The [`PubSubEventBus`] provides an implementation of the `EventBus` trait for publishing events
+/// using Google Cloud Pub/Sub as the underlying messaging system.
+///
+/// By default the messages are keyed by aggregate id through their ordering key, so that the events
+/// of an aggregate instance are delivered in order to the subscriptions with message ordering
+/// enabled.
+pub struct PubSubEventBus<A> {
+    publisher: Publisher,
+    ordering: bool,
+    stored_payload: bool,
+    error_handler: Box<dyn Fn(PubSubEventBusError) + Send + Sync>,
+    _phantom: PhantomData<A>,
+}
+
+impl<A> PubSubEventBus<A>
+where
+    A: Aggregate,
+{
+    pub async fn new(config: PubSubEventBusConfig<'_>) -> Result<PubSubEventBus<A>, PubSubEventBusError> {
+        let client_config: ClientConfig = match config.client_config {
+            Some(client_config) => client_config,
+            None => ClientConfig::default()
+                .with_auth()
+                .await
+                .map_err(|error| PubSubEventBusError::Auth(Box::new(error)))?,
+        };
+
+        let topic: String = match config.topic_strategy {
+            Some(topic_strategy) => topic_strategy.topic(A::NAME),
+            None => config.topic.to_string(),
+        };
+
+        let client: Client = Client::new(client_config).await?;
+
+        Ok(Self {
+            publisher: client.topic(topic.as_str()).new_publisher(None),
+            ordering: config.ordering,
+            stored_payload: config.stored_payload,
+            error_handler: config.error_handler,
+            _phantom: Default::default(),
+        })
+    }
+}
+
+#[async_trait]
+impl<A> EventBus<A> for PubSubEventBus<A>
+where
+    Self: Send,
+    A: Aggregate + Send + Sync,
+    A::Event: Serialize + Sync,
+{
+    async fn publish(&self, store_event: &StoreEvent<A::Event>) {
+        match publish(self, store_event).await {
+            Ok(_) => (),
+            Err(err) => (self.error_handler)(err),
+        }
+    }
+
+    async fn try_publish(
+        &self,
+        store_event: &StoreEvent<A::Event>,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Ok(publish(self, store_event).await?)
+    }
+}
+
+async fn publish<A>(
+    event_bus: &PubSubEventBus<A>,
+    store_event: &StoreEvent<A::Event>,
+) -> Result<(), PubSubEventBusError>
+where
+    A: Aggregate + Send + Sync,
+    A::Event: Serialize,
+{
+    let message: PubsubMessage = PubsubMessage {
+        data: serialize_store_event(store_event, event_bus.stored_payload)?,
+        ordering_key: if event_bus.ordering {
+            store_event.aggregate_id.to_string()
+        } else {
+            String::new()
+        },
+        ..Default::default()
+    };
+
+    let _ = event_bus.publisher.publish(message).await.get().await?;
+
+    Ok(())
+}
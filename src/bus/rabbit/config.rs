@@ -1,9 +1,12 @@
-use lapin::options::{BasicPublishOptions, ExchangeDeclareOptions};
+use std::time::Duration;
+
+use lapin::options::{BasicPublishOptions, ExchangeDeclareOptions, QueueDeclareOptions};
 use lapin::types::FieldTable;
 use lapin::{BasicProperties, ConnectionProperties, ExchangeKind};
 use typed_builder::TypedBuilder;
 
 use crate::bus::rabbit::error::RabbitEventBusError;
+use crate::bus::TopicStrategy;
 
 #[derive(TypedBuilder)]
 pub struct RabbitEventBusConfig<'a> {
@@ -12,7 +15,12 @@ pub struct RabbitEventBusConfig<'a> {
     pub(crate) url: &'a str,
     /// The name of the RabbitMQ exchange to use for publishing events. If not specified, events will
     /// be published to the default exchange.
+    #[builder(default)]
     pub(crate) exchange: &'a str,
+    /// An optional strategy naming the exchange after the aggregate, taking precedence over
+    /// `exchange`.
+    #[builder(default, setter(strip_option))]
+    pub(crate) exchange_strategy: Option<Box<dyn TopicStrategy>>,
     /// Additional connection properties.
     #[builder(default)]
     pub(crate) connection_properties: ConnectionProperties,
@@ -36,8 +44,42 @@ pub struct RabbitEventBusConfig<'a> {
     /// Additional publish properties.
     #[builder(default)]
     pub(crate) publish_properties: BasicProperties,
+    /// Queues to declare at startup and to bind to the exchange, so that the bus is usable without
+    /// separately provisioning the topology.
+    #[builder(default)]
+    pub(crate) queues: Vec<RabbitQueueConfig<'a>>,
+    /// Optional dead letter exchange, declared at startup as a durable fanout exchange, along with a
+    /// durable queue with the same name bound to it. The messages rejected or expired from the
+    /// declared queues are dead-lettered to it.
+    #[builder(default)]
+    pub(crate) dead_letter_exchange: Option<&'a str>,
+    /// Whether to publish the payloads as serialized in the event store (see
+    /// [`crate::store::StoreEvent::raw_payload`]) instead of serializing the events again. With a
+    /// custom schema, the published payload is then the serialized schema. Defaults to false.
+    #[builder(default)]
+    pub(crate) stored_payload: bool,
     /// A boxed anonymous function utilized to provide a form of error handling, commonly used for
     /// reporting purposes.
     #[builder(default = Box::new(| _ | ()))]
     pub(crate) error_handler: Box<dyn Fn(RabbitEventBusError) + Send + Sync>,
 }
+
+/// A queue declared by the [`crate::bus::rabbit::RabbitEventBus`] at startup, and bound to its
+/// exchange.
+#[derive(TypedBuilder)]
+pub struct RabbitQueueConfig<'a> {
+    /// The name of the queue.
+    pub(crate) name: &'a str,
+    /// The key binding the queue to the exchange. Defaults to an empty key.
+    #[builder(default)]
+    pub(crate) binding_key: &'a str,
+    /// Optional time to live of the messages in the queue, after which they expire.
+    #[builder(default)]
+    pub(crate) message_ttl: Option<Duration>,
+    /// Additional queue options.
+    #[builder(default)]
+    pub(crate) options: QueueDeclareOptions,
+    /// Additional queue arguments.
+    #[builder(default)]
+    pub(crate) arguments: FieldTable,
+}
@@ -1,18 +1,19 @@
+use std::convert::TryFrom;
 use std::marker::PhantomData;
 use std::time::Duration;
 
 use async_trait::async_trait;
 use bb8::ManageConnection;
-use lapin::options::{BasicPublishOptions, ExchangeDeclareOptions};
+use lapin::options::{BasicPublishOptions, ExchangeDeclareOptions, QueueBindOptions, QueueDeclareOptions};
 use lapin::publisher_confirm::Confirmation;
-use lapin::types::FieldTable;
+use lapin::types::{AMQPValue, FieldTable};
 use lapin::{BasicProperties, Channel, Connection, ConnectionProperties, ExchangeKind};
 use serde::Serialize;
 
-pub use config::RabbitEventBusConfig;
+pub use config::{RabbitEventBusConfig, RabbitQueueConfig};
 pub use error::RabbitEventBusError;
 
-use crate::bus::EventBus;
+use crate::bus::{serialize_store_event, EventBus};
 use crate::store::StoreEvent;
 use crate::Aggregate;
 
@@ -98,6 +99,7 @@ pub struct RabbitEventBus<A> {
     publish_routing_key: Option<String>,
     publish_options: BasicPublishOptions,
     publish_properties: BasicProperties,
+    stored_payload: bool,
     error_handler: Box<dyn Fn(RabbitEventBusError) + Send + Sync>,
     _phantom: PhantomData<A>,
 }
@@ -107,6 +109,11 @@ where
     A: Aggregate,
 {
     pub async fn new(config: RabbitEventBusConfig<'_>) -> Result<RabbitEventBus<A>, RabbitEventBusError> {
+        let exchange: String = match config.exchange_strategy {
+            Some(exchange_strategy) => exchange_strategy.topic(A::NAME),
+            None => config.exchange.to_string(),
+        };
+
         let connection_manager = RabbitConnectionManager {
             url: config.url.to_string(),
             connection_properties: config.connection_properties,
@@ -122,7 +129,7 @@ where
 
         let channel_manager = RabbitChannelManager {
             connection_pool,
-            exchange: config.exchange.to_string(),
+            exchange: exchange.clone(),
             exchange_kind: config.exchange_kind,
             exchange_options: config.exchange_options,
             exchange_arguments: config.exchange_arguments,
@@ -136,18 +143,104 @@ where
             .build(channel_manager)
             .await?;
 
+        declare_topology(
+            &channel_pool,
+            exchange.as_str(),
+            &config.queues,
+            config.dead_letter_exchange,
+        )
+        .await?;
+
         Ok(Self {
             channel_pool,
-            exchange: config.exchange.to_string(),
+            exchange,
             publish_routing_key: config.publish_routing_key,
             publish_options: config.publish_options,
             publish_properties: config.publish_properties,
+            stored_payload: config.stored_payload,
             error_handler: config.error_handler,
             _phantom: PhantomData,
         })
     }
 }
 
+/// Declares the dead letter exchange and the queues, bound to the exchange, configured in the
+/// [`RabbitEventBusConfig`].
+async fn declare_topology(
+    channel_pool: &bb8::Pool<RabbitChannelManager>,
+    exchange: &str,
+    queues: &[RabbitQueueConfig<'_>],
+    dead_letter_exchange: Option<&str>,
+) -> Result<(), RabbitEventBusError> {
+    if queues.is_empty() && dead_letter_exchange.is_none() {
+        return Ok(());
+    }
+
+    let channel = channel_pool.get().await?;
+
+    if let Some(dead_letter_exchange) = dead_letter_exchange {
+        channel
+            .exchange_declare(
+                dead_letter_exchange,
+                ExchangeKind::Fanout,
+                ExchangeDeclareOptions {
+                    durable: true,
+                    ..ExchangeDeclareOptions::default()
+                },
+                FieldTable::default(),
+            )
+            .await?;
+        let _ = channel
+            .queue_declare(
+                dead_letter_exchange,
+                QueueDeclareOptions {
+                    durable: true,
+                    ..QueueDeclareOptions::default()
+                },
+                FieldTable::default(),
+            )
+            .await?;
+        channel
+            .queue_bind(
+                dead_letter_exchange,
+                dead_letter_exchange,
+                "",
+                QueueBindOptions::default(),
+                FieldTable::default(),
+            )
+            .await?;
+    }
+
+    for queue in queues {
+        let mut arguments: FieldTable = queue.arguments.clone();
+
+        if let Some(message_ttl) = queue.message_ttl {
+            let milliseconds: i64 = i64::try_from(message_ttl.as_millis()).unwrap_or(i64::MAX);
+            arguments.insert("x-message-ttl".into(), AMQPValue::LongLongInt(milliseconds));
+        }
+
+        if let Some(dead_letter_exchange) = dead_letter_exchange {
+            arguments.insert(
+                "x-dead-letter-exchange".into(),
+                AMQPValue::LongString(dead_letter_exchange.into()),
+            );
+        }
+
+        let _ = channel.queue_declare(queue.name, queue.options, arguments).await?;
+        channel
+            .queue_bind(
+                queue.name,
+                exchange,
+                queue.binding_key,
+                QueueBindOptions::default(),
+                FieldTable::default(),
+            )
+            .await?;
+    }
+
+    Ok(())
+}
+
 #[async_trait]
 impl<A> EventBus<A> for RabbitEventBus<A>
 where
@@ -160,6 +253,13 @@ where
             (self.error_handler)(error)
         }
     }
+
+    async fn try_publish(
+        &self,
+        store_event: &StoreEvent<A::Event>,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Ok(publish(self, store_event).await?)
+    }
 }
 
 async fn publish<A>(reb: &RabbitEventBus<A>, store_event: &StoreEvent<A::Event>) -> Result<(), RabbitEventBusError>
@@ -167,7 +267,7 @@ where
     A: Aggregate + Send + Sync,
     A::Event: Serialize,
 {
-    let bytes: Vec<u8> = serde_json::to_vec(store_event)?;
+    let bytes: Vec<u8> = serialize_store_event(store_event, reb.stored_payload)?;
     let routing_key: String = reb.publish_routing_key.clone().unwrap_or_default();
 
     let channel = reb.channel_pool.get().await?;
@@ -1,5 +1,8 @@
 use serde::de::DeserializeOwned;
 
+#[cfg(feature = "macros")]
+pub use esrs_macros::Upcaster;
+
 pub trait Upcaster
 where
     Self: Sized,
@@ -15,3 +18,19 @@ where
         None
     }
 }
+
+/// Hook rewriting the serialized events before they are written, so that they can be read by the
+/// instances still running the previous version of the code, e.g. during a rolling deployment.
+///
+/// When set in the `PgStoreBuilder`, the payloads are written in the form returned by
+/// [`Downcaster::downcast`], tagged with [`Downcaster::version`] instead of
+/// [`Upcaster::current_version`]. Once all the instances are updated, the downcaster can be removed
+/// and the events previously written are upcast as usual.
+pub trait Downcaster: Send + Sync {
+    /// The version the events are written at.
+    fn version(&self) -> Option<i32>;
+
+    /// Transforms the serialized payload of an event, in its current version, into the one of
+    /// [`Downcaster::version`].
+    fn downcast(&self, value: serde_json::Value) -> serde_json::Value;
+}
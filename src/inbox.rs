@@ -0,0 +1,200 @@
+//! Inbox pattern for the consumers of the events published on the buses, giving exactly-once
+//! processing semantics over the at-least-once delivery of Kafka and RabbitMQ, and the
+//! [`DeduplicatingConsumer`] running the event handlers of the consumers through it.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use sqlx::postgres::PgQueryResult;
+use sqlx::{PgConnection, Pool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::handler::EventHandler;
+use crate::store::StoreEvent;
+use crate::Aggregate;
+
+/// Postgres backed inbox of the processed events, stored in the `{name}_inbox` table.
+///
+/// Each consumer processes an event through [`Inbox::process`], recording the pair of consumer and
+/// event id in the same transaction as the side effects of the processing. Events already recorded
+/// for the consumer are skipped, so that redelivered events are processed exactly once.
+pub struct Inbox {
+    pool: Pool<Postgres>,
+    table_name: String,
+}
+
+impl Inbox {
+    /// Creates a new instance of an [`Inbox`].
+    pub fn new(pool: Pool<Postgres>, name: &str) -> Self {
+        Self {
+            pool,
+            table_name: format!("{}_inbox", name),
+        }
+    }
+
+    /// Returns the name of the inbox table.
+    pub fn table_name(&self) -> &str {
+        &self.table_name
+    }
+
+    /// Creates the inbox table, if it doesn't exist yet.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if there's an error running the migration.
+    pub async fn setup(&self) -> Result<(), sqlx::Error> {
+        let migration: String = format!(
+            include_str!("sql/postgres/migrations/create_inbox_table.sql"),
+            self.table_name
+        );
+
+        let _: PgQueryResult = sqlx::query(migration.as_str()).execute(&self.pool).await?;
+        Ok(())
+    }
+
+    /// Processes the event with the given id for the given consumer, running `process` in a
+    /// transaction in which the event is recorded as processed. Returns `Ok(None)` without running
+    /// `process` if the event has already been processed by the consumer.
+    ///
+    /// If `process` fails the transaction is rolled back, so that the event can be processed again
+    /// on redelivery. A redelivery running concurrently waits for the first processing to complete,
+    /// then gets skipped if it succeeded.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if `process` fails, or recording the event fails.
+    pub async fn process<T, E, F>(&self, consumer: &str, event_id: Uuid, process: F) -> Result<Option<T>, E>
+    where
+        E: From<sqlx::Error>,
+        F: for<'c> FnOnce(&'c mut PgConnection) -> BoxFuture<'c, Result<T, E>>,
+    {
+        let Some(mut transaction) = self.record(consumer, event_id).await? else {
+            return Ok(None);
+        };
+
+        let output: T = process(&mut transaction).await?;
+        transaction.commit().await?;
+
+        Ok(Some(output))
+    }
+
+    /// Records the event with the given id as processed by the given consumer in a new transaction,
+    /// returned to be committed once the event is processed. Returns `None` if the event has
+    /// already been processed by the consumer.
+    async fn record(
+        &self,
+        consumer: &str,
+        event_id: Uuid,
+    ) -> Result<Option<Transaction<'static, Postgres>>, sqlx::Error> {
+        let mut transaction: Transaction<Postgres> = self.pool.begin().await?;
+
+        let recorded: PgQueryResult = sqlx::query(
+            format!(
+                include_str!("sql/postgres/statements/insert_inbox.sql"),
+                self.table_name
+            )
+            .as_str(),
+        )
+        .bind(consumer)
+        .bind(event_id)
+        .execute(&mut *transaction)
+        .await?;
+
+        if recorded.rows_affected() == 0 {
+            tracing::debug!({
+                consumer = consumer,
+                event_id = %event_id,
+            }, "skipping already processed event");
+
+            return Ok(None);
+        }
+
+        Ok(Some(transaction))
+    }
+
+    /// Deletes the records of the events processed before the given timestamp, returning their
+    /// number. Events redelivered after their records are deleted are processed again, so the
+    /// timestamp should be well behind the maximum redelivery delay of the bus.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the delete fails.
+    pub async fn prune(&self, processed_before: DateTime<Utc>) -> Result<u64, sqlx::Error> {
+        let result: PgQueryResult = sqlx::query(
+            format!(
+                include_str!("sql/postgres/statements/delete_inbox_processed_before.sql"),
+                self.table_name
+            )
+            .as_str(),
+        )
+        .bind(processed_before)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+/// Wrapper of an [`EventHandler`] run by a bus consumer, deduplicating the events by id through an
+/// [`Inbox`], so that the events delivered more than once by the bus (e.g. replayed after a consumer
+/// group rebalance) are handled once.
+///
+/// Each event is recorded in the inbox, under the name of the wrapped event handler, in a
+/// transaction committed once [`EventHandler::try_handle`] succeeds. A failed event is left
+/// unrecorded, so that it is handled again on redelivery. Since the event handler side effects are
+/// not part of that transaction, an event can still be handled twice if the commit fails.
+pub struct DeduplicatingConsumer<H> {
+    inbox: Inbox,
+    event_handler: H,
+}
+
+impl<H> DeduplicatingConsumer<H> {
+    /// Creates a new instance of a [`DeduplicatingConsumer`] of the given event handler, recording the
+    /// handled events in the given inbox. The inbox table must have been set up through
+    /// [`Inbox::setup`].
+    pub fn new(inbox: Inbox, event_handler: H) -> Self {
+        Self { inbox, event_handler }
+    }
+}
+
+#[async_trait]
+impl<A, H> EventHandler<A> for DeduplicatingConsumer<H>
+where
+    A: Aggregate,
+    A::Event: Sync,
+    H: EventHandler<A> + Send,
+{
+    async fn handle(&self, event: &StoreEvent<A::Event>) {
+        if let Err(error) = EventHandler::<A>::try_handle(self, event).await {
+            tracing::error!({
+                event_id = %event.id,
+                aggregate_id = %event.aggregate_id,
+                event_handler = EventHandler::<A>::name(&self.event_handler),
+                error = ?error,
+            }, "deduplicating consumer failed to handle event");
+        }
+    }
+
+    async fn try_handle(&self, event: &StoreEvent<A::Event>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let Some(transaction) = self
+            .inbox
+            .record(EventHandler::<A>::name(&self.event_handler), event.id)
+            .await?
+        else {
+            return Ok(());
+        };
+
+        self.event_handler.try_handle(event).await?;
+        transaction.commit().await?;
+
+        Ok(())
+    }
+
+    async fn delete(&self, aggregate_id: Uuid) {
+        self.event_handler.delete(aggregate_id).await
+    }
+
+    fn name(&self) -> &'static str {
+        EventHandler::<A>::name(&self.event_handler)
+    }
+}
@@ -0,0 +1,88 @@
+//! Leader election for components that must run as a cluster-wide singleton, like the consumers of
+//! the event store that are deployed on every replica.
+
+use std::future::Future;
+use std::time::Duration;
+
+use futures::future::Either;
+use sqlx::postgres::PgAdvisoryLock;
+use sqlx::{PgConnection, Pool, Postgres};
+
+/// Postgres advisory lock based leader election.
+///
+/// Every replica creates a [`LeaderElector`] with the same name: only one of them at a time is the
+/// leader, holding the advisory lock on a dedicated connection. The lease is renewed checking the
+/// connection at every renew interval: if the connection is lost, so is the leadership, and the
+/// work performed as leader is stopped.
+pub struct LeaderElector {
+    pool: Pool<Postgres>,
+    name: String,
+    renew_interval: Duration,
+}
+
+impl LeaderElector {
+    /// Creates a new instance of a [`LeaderElector`] for the given name, with a renew interval of
+    /// 5 seconds.
+    pub fn new(pool: Pool<Postgres>, name: &str) -> Self {
+        Self {
+            pool,
+            name: name.to_string(),
+            renew_interval: Duration::from_secs(5),
+        }
+    }
+
+    /// Set the interval used both to retry the acquisition of the leadership and to renew it.
+    pub fn with_renew_interval(mut self, renew_interval: Duration) -> Self {
+        self.renew_interval = renew_interval;
+        self
+    }
+
+    /// Waits to become the leader, then runs the given future until completion, releasing the
+    /// leadership afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the leadership can't be acquired, or it is lost while running the
+    /// future. In the latter case the future is dropped before completion.
+    pub async fn run_when_leader<F>(&self, future: F) -> Result<F::Output, sqlx::Error>
+    where
+        F: Future,
+    {
+        let lock: PgAdvisoryLock = PgAdvisoryLock::new(self.name.as_str());
+        let mut connection = self.pool.acquire().await?;
+
+        let mut guard = loop {
+            match lock.try_acquire(connection).await? {
+                sqlx::Either::Left(guard) => break guard,
+                sqlx::Either::Right(not_acquired) => {
+                    connection = not_acquired;
+                    crate::runtime::sleep(self.renew_interval).await;
+                }
+            }
+        };
+
+        let result = {
+            let renewal = renew(&mut guard, self.renew_interval);
+
+            match futures::future::select(Box::pin(future), Box::pin(renewal)).await {
+                Either::Left((output, _)) => Ok(output),
+                Either::Right((error, _)) => Err(error),
+            }
+        };
+
+        let _ = guard.release_now().await;
+
+        result
+    }
+}
+
+/// Checks the connection holding the leadership at every interval, returning when it is lost.
+async fn renew(connection: &mut PgConnection, renew_interval: Duration) -> sqlx::Error {
+    loop {
+        crate::runtime::sleep(renew_interval).await;
+
+        if let Err(error) = sqlx::query("SELECT 1").execute(&mut *connection).await {
+            return error;
+        }
+    }
+}
@@ -9,26 +9,59 @@
 //! This means that everytime an aggregate state is needed the state should be loaded So, for example
 //! while using `postgres` event store, everytime a state load is required a database query is
 //! performed over the event store table.
+//!
+//! The crate runs on tokio by default. Applications running on async-std, or smol, can disable the
+//! default features and enable `runtime-async-std` instead. Without any runtime feature only the
+//! traits are available, along with an [`manager::AggregateManager`] without timeouts.
+
+#[cfg(all(
+    feature = "postgres",
+    not(any(feature = "runtime-tokio", feature = "runtime-async-std"))
+))]
+compile_error!("the `postgres` feature requires either the `runtime-tokio` or the `runtime-async-std` feature");
 
-pub use aggregate::Aggregate;
-pub use state::AggregateState;
+pub use esrs_core::{handler, interceptor};
+pub use esrs_core::{Aggregate, AggregateState, AsyncAggregate, SharedAggregateState};
 
-mod aggregate;
-mod state;
+#[cfg(any(feature = "runtime-tokio", feature = "runtime-async-std"))]
+mod runtime;
+mod state_machine;
 
 pub mod bus;
 #[cfg(feature = "upcasting")]
 pub mod event;
-pub mod handler;
 pub mod manager;
+pub mod query;
 pub mod store;
 
+#[cfg(feature = "postgres")]
+pub mod inbox;
+#[cfg(feature = "postgres")]
+pub mod leader;
+#[cfg(feature = "postgres")]
+pub mod process;
 #[cfg(feature = "rebuilder")]
 pub mod rebuilder;
 #[cfg(feature = "postgres")]
+pub mod scheduler;
+#[cfg(feature = "postgres")]
 pub mod sql;
+#[cfg(feature = "test-utils")]
+pub mod test;
 
-pub mod types {
-    //! Provides custom types.
-    pub type SequenceNumber = i32;
+#[cfg(feature = "macros")]
+pub use esrs_macros::{View, ViewEventHandler};
+
+#[cfg(feature = "macros")]
+#[doc(hidden)]
+pub mod __private {
+    //! Re-exports used by the code generated by the macros. Not part of the public API.
+    pub use async_trait::async_trait;
+    pub use serde;
+    pub use serde_json;
+    pub use sqlx;
+    pub use tracing;
+    pub use uuid::Uuid;
 }
+
+pub use esrs_core::types;
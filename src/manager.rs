@@ -1,33 +1,162 @@
+mod command_bus;
 mod locked_load;
+mod middleware;
+mod retry;
 
+pub use command_bus::{BusCommand, CommandBus, CommandBusError, CommandBusMiddleware};
 pub use locked_load::LockedLoad;
+pub use middleware::CommandMiddleware;
+pub use retry::{ConflictError, RetryPolicy};
 
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use tokio::sync::watch;
 use uuid::Uuid;
 
-use crate::store::{EventStore, StoreEvent};
-use crate::{Aggregate, AggregateState};
+use crate::store::{EventStore, IdempotentEventStore, Metadata, Snapshot, SnapshotStore, StoreEvent};
+use crate::types::SequenceNumber;
+use crate::{Aggregate, AggregateState, AsyncAggregate, SharedAggregateState};
 
 /// The AggregateManager is responsible for coupling the Aggregate with a Store, so that the events
 /// can be persisted when handled, and the state can be reconstructed by loading and apply events sequentially.
 ///
 /// The basic APIs are:
-/// 1. handle_command
+/// 1. handle_command (and handle_commands, handle_async_command)
 /// 2. load
 /// 3. lock_and_load
+/// 4. load_shared (and upgrade)
+/// 5. load_at
+/// 6. watch
 pub struct AggregateManager<E>
 where
     E: EventStore,
 {
     event_store: E,
+    timeout: Option<Timeout<E::Error>>,
+    watchers: Watchers<<E::Aggregate as Aggregate>::State>,
+    snapshots: Option<Snapshots<<E::Aggregate as Aggregate>::State>>,
+    middlewares: Vec<Box<dyn CommandMiddleware<E::Aggregate> + Send>>,
+}
+
+/// The snapshotting configuration set through [`AggregateManager::with_snapshots`].
+struct Snapshots<S> {
+    store: Box<dyn SnapshotStore<S> + Send>,
+    every_n_events: i32,
+    version: Option<i32>,
+    clone_state: fn(&S) -> S,
+}
+
+/// Sends a copy of the given state to a watch channel, returning `false` once all its receivers are
+/// dropped.
+type Watcher<S> = Box<dyn Fn(&AggregateState<S>) -> bool + Send + Sync>;
+
+/// The watchers of each aggregate instance, registered through [`AggregateManager::watch`].
+type Watchers<S> = Mutex<HashMap<Uuid, Vec<Watcher<S>>>>;
+
+/// The timeout set through [`AggregateManager::with_timeout`], along with the conversion of the
+/// [`CommandTimeout`] into the error of the store.
+type Timeout<E> = (Duration, fn(CommandTimeout) -> E);
+
+/// Error returned by [`AggregateManager::handle_command`] when the command isn't handled within the
+/// timeout set through [`AggregateManager::with_timeout`].
+#[derive(thiserror::Error, Debug)]
+#[error("command not handled within {0:?}")]
+pub struct CommandTimeout(pub Duration);
+
+/// The point in the history of an aggregate instance up to which [`AggregateManager::load_at`]
+/// reconstructs its state.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PointInTime {
+    /// Up to the event with the given sequence number, included.
+    SequenceNumber(SequenceNumber),
+    /// Up to the last event occurred on or before the given timestamp.
+    OccurredOn(DateTime<Utc>),
 }
 
 impl<E> AggregateManager<E>
 where
-    E: EventStore,
+    E: EventStore + Sync,
 {
     /// Creates a new instance of an [`AggregateManager`].
     pub fn new(event_store: E) -> Self {
-        Self { event_store }
+        Self {
+            event_store,
+            timeout: None,
+            watchers: Mutex::new(HashMap::new()),
+            snapshots: None,
+            middlewares: vec![],
+        }
+    }
+
+    /// Takes a snapshot of the state of the aggregate instances every `every_n_events` events,
+    /// persisting it in the given [`SnapshotStore`]. [`AggregateManager::load`] then starts from the
+    /// latest snapshot, replaying only the events following it.
+    ///
+    /// Snapshots are taken after the events are persisted, outside of their transaction: failing to
+    /// load or save a snapshot is logged, and falls back to replaying all the events.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `every_n_events` is not positive.
+    pub fn with_snapshots(
+        mut self,
+        snapshot_store: impl SnapshotStore<<E::Aggregate as Aggregate>::State> + Send + 'static,
+        every_n_events: i32,
+    ) -> Self
+    where
+        <E::Aggregate as Aggregate>::State: Clone,
+    {
+        assert!(
+            every_n_events > 0,
+            "snapshots must be taken every positive number of events"
+        );
+
+        self.snapshots = Some(Snapshots {
+            store: Box::new(snapshot_store),
+            every_n_events,
+            version: self.snapshots.and_then(|snapshots| snapshots.version),
+            clone_state: <<E::Aggregate as Aggregate>::State as Clone>::clone,
+        });
+        self
+    }
+
+    /// Sets the version of the state recorded in the snapshots. Snapshots of a different version are
+    /// ignored when loading, so bumping it whenever the shape or the meaning of the state changes
+    /// invalidates the existing snapshots.
+    ///
+    /// Has no effect unless snapshots are enabled through [`AggregateManager::with_snapshots`].
+    pub fn with_snapshot_version(mut self, version: i32) -> Self {
+        if let Some(snapshots) = self.snapshots.as_mut() {
+            snapshots.version = Some(version);
+        }
+        self
+    }
+
+    /// Add a single [`CommandMiddleware`], run around every command handled by this manager.
+    /// Middlewares are run in the order they are added, and the first one rejecting a command stops
+    /// its handling.
+    pub fn with_middleware(mut self, middleware: impl CommandMiddleware<E::Aggregate> + Send + 'static) -> Self {
+        self.middlewares.push(Box::new(middleware));
+        self
+    }
+
+    /// Set an overall timeout on [`AggregateManager::handle_command`]. When it elapses, the command
+    /// handling is abandoned and a [`CommandTimeout`] error is returned.
+    ///
+    /// What has been done up to then depends on the event store. The
+    /// [`crate::store::postgres::PgStore`] either rolls the events back, or, if they were being
+    /// committed, completes both the commit and the dispatching to the event handlers and buses. In
+    /// both cases the lock on the aggregate instance is released.
+    #[cfg(any(feature = "runtime-tokio", feature = "runtime-async-std"))]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self
+    where
+        E::Error: From<CommandTimeout>,
+    {
+        self.timeout = Some((timeout, <E::Error as From<CommandTimeout>>::from));
+        self
     }
 
     /// Validates and handles the command onto the given state, and then passes the events to the store.
@@ -39,30 +168,482 @@ where
     /// Returns two layers of errors:
     /// - `Err(_)` if the aggregate handled the command but the outcome failed to be recorded;
     /// - `Ok(Err(_))` if the aggregate denied the command.
+    ///
+    /// See [`AggregateManager::with_timeout`] to bound the time spent handling the command, and
+    /// [`AggregateManager::with_middleware`] to intercept it.
     pub async fn handle_command(
         &self,
-        mut aggregate_state: AggregateState<<E::Aggregate as Aggregate>::State>,
+        aggregate_state: AggregateState<<E::Aggregate as Aggregate>::State>,
         command: <E::Aggregate as Aggregate>::Command,
     ) -> Result<Result<<E::Aggregate as Aggregate>::State, <E::Aggregate as Aggregate>::Error>, E::Error> {
+        self.handle_command_with_metadata(aggregate_state, command, Metadata::default())
+            .await
+    }
+
+    /// Same as [`AggregateManager::handle_command`], attaching the given [`Metadata`] (e.g. the
+    /// correlation and causation ids) to the persisted events. Event handlers and buses receive it
+    /// along with the events, as [`StoreEvent::metadata`].
+    ///
+    /// The metadata is persisted along with the events through
+    /// [`EventStore::persist_with_metadata`], that every event store implements.
+    pub async fn handle_command_with_metadata(
+        &self,
+        aggregate_state: AggregateState<<E::Aggregate as Aggregate>::State>,
+        command: <E::Aggregate as Aggregate>::Command,
+        metadata: Metadata,
+    ) -> Result<Result<<E::Aggregate as Aggregate>::State, <E::Aggregate as Aggregate>::Error>, E::Error> {
+        match self.timeout {
+            #[cfg(any(feature = "runtime-tokio", feature = "runtime-async-std"))]
+            Some((timeout, into_error)) => {
+                crate::runtime::timeout(timeout, self.handle_command_untimed(aggregate_state, command, metadata))
+                    .await
+                    .unwrap_or_else(|| Err(into_error(CommandTimeout(timeout))))
+            }
+            _ => self.handle_command_untimed(aggregate_state, command, metadata).await,
+        }
+    }
+
+    async fn handle_command_untimed(
+        &self,
+        aggregate_state: AggregateState<<E::Aggregate as Aggregate>::State>,
+        command: <E::Aggregate as Aggregate>::Command,
+        metadata: Metadata,
+    ) -> Result<Result<<E::Aggregate as Aggregate>::State, <E::Aggregate as Aggregate>::Error>, E::Error> {
+        if let Err(middleware_error) = self.before_command(*aggregate_state.id(), aggregate_state.inner(), &command) {
+            return Ok(Err(middleware_error));
+        }
+
         match <E::Aggregate as Aggregate>::handle_command(aggregate_state.inner(), command) {
             Err(domain_error) => Ok(Err(domain_error)),
-            Ok(events) => match self.event_store.persist(&mut aggregate_state, events).await {
-                Ok(store_events) => Ok(Ok(aggregate_state
-                    .apply_store_events(store_events, <E::Aggregate as Aggregate>::apply_event)
-                    .into_inner())),
-                Err(operational_error) => Err(operational_error),
-            },
+            Ok(events) => self.persist_and_apply(aggregate_state, events, metadata).await.map(Ok),
+        }
+    }
+
+    /// Same as [`AggregateManager::handle_command`], recording the given idempotency key along with
+    /// the persisted events, e.g. the id of the message the command comes from.
+    ///
+    /// If a command has already been handled with the same key for the same aggregate instance, the
+    /// command is neither validated nor handled again: the aggregate state is reloaded and returned
+    /// instead, as the given one might be stale. This makes it safe for at-least-once consumers to
+    /// handle redelivered commands.
+    pub async fn handle_command_idempotent(
+        &self,
+        aggregate_state: AggregateState<<E::Aggregate as Aggregate>::State>,
+        command: <E::Aggregate as Aggregate>::Command,
+        idempotency_key: Uuid,
+    ) -> Result<Result<<E::Aggregate as Aggregate>::State, <E::Aggregate as Aggregate>::Error>, E::Error>
+    where
+        E: IdempotentEventStore,
+    {
+        match self.timeout {
+            #[cfg(any(feature = "runtime-tokio", feature = "runtime-async-std"))]
+            Some((timeout, into_error)) => crate::runtime::timeout(
+                timeout,
+                self.handle_command_idempotent_untimed(aggregate_state, command, idempotency_key),
+            )
+            .await
+            .unwrap_or_else(|| Err(into_error(CommandTimeout(timeout)))),
+            _ => {
+                self.handle_command_idempotent_untimed(aggregate_state, command, idempotency_key)
+                    .await
+            }
         }
     }
 
+    async fn handle_command_idempotent_untimed(
+        &self,
+        mut aggregate_state: AggregateState<<E::Aggregate as Aggregate>::State>,
+        command: <E::Aggregate as Aggregate>::Command,
+        idempotency_key: Uuid,
+    ) -> Result<Result<<E::Aggregate as Aggregate>::State, <E::Aggregate as Aggregate>::Error>, E::Error>
+    where
+        E: IdempotentEventStore,
+    {
+        let previous_sequence_number = *aggregate_state.sequence_number();
+        let store_events = self
+            .event_store
+            .by_idempotency_key(*aggregate_state.id(), idempotency_key)
+            .await?;
+
+        if !store_events.is_empty() {
+            tracing::debug!({
+                aggregate_name = <E::Aggregate as Aggregate>::NAME,
+                aggregate_id = %aggregate_state.id(),
+                idempotency_key = %idempotency_key,
+            }, "command already handled, skipping");
+
+            return self.reload(aggregate_state).await.map(Ok);
+        }
+
+        if let Err(middleware_error) = self.before_command(*aggregate_state.id(), aggregate_state.inner(), &command) {
+            return Ok(Err(middleware_error));
+        }
+
+        match <E::Aggregate as Aggregate>::handle_command(aggregate_state.inner(), command) {
+            Err(domain_error) => Ok(Err(domain_error)),
+            Ok(events) => {
+                let store_events = self
+                    .event_store
+                    .persist_idempotent(&mut aggregate_state, events, idempotency_key)
+                    .await?;
+
+                // When a concurrent attempt with the same key won the race, its events are returned
+                // instead: they might not follow the given state, which is then reloaded.
+                let follows: bool = store_events.first().map_or(true, |store_event| {
+                    store_event.sequence_number == previous_sequence_number + 1
+                });
+
+                if !follows {
+                    return self.reload(aggregate_state).await.map(Ok);
+                }
+
+                Ok(Ok(self
+                    .apply_persisted(aggregate_state, store_events, previous_sequence_number)
+                    .await))
+            }
+        }
+    }
+
+    /// Locks and loads the given aggregate instance (creating it if it doesn't exist), and handles the
+    /// command onto it as [`AggregateManager::handle_command`] does.
+    ///
+    /// When the events fail to be persisted because of an optimistic locking conflict, i.e. another
+    /// writer advanced the aggregate instance in the meantime, the aggregate state is reloaded and the
+    /// whole command handled again, up to the maximum number of attempts of the given policy. Once the
+    /// attempts are exhausted the conflict error is returned.
+    ///
+    /// Returns the same two layers of errors of [`AggregateManager::handle_command`].
+    pub async fn handle_command_with_retry(
+        &self,
+        aggregate_id: impl Into<Uuid> + Send,
+        command: <E::Aggregate as Aggregate>::Command,
+        retry_policy: RetryPolicy,
+    ) -> Result<Result<<E::Aggregate as Aggregate>::State, <E::Aggregate as Aggregate>::Error>, E::Error>
+    where
+        <E::Aggregate as Aggregate>::Command: Clone,
+        E::Error: ConflictError,
+    {
+        let aggregate_id: Uuid = aggregate_id.into();
+        let mut attempt: u32 = 1;
+
+        loop {
+            let aggregate_state = self.lock_and_load(aggregate_id).await?.unwrap_or_default();
+
+            match self.handle_command(aggregate_state, command.clone()).await {
+                Err(error) if error.is_conflict() && attempt < retry_policy.max_attempts() => {
+                    tracing::warn!({
+                        aggregate_name = <E::Aggregate as Aggregate>::NAME,
+                        aggregate_id = %aggregate_id,
+                        attempt = attempt,
+                    }, "optimistic locking conflict while handling command, retrying");
+
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Same as [`AggregateManager::handle_command`], handling the command through
+    /// [`AsyncAggregate::handle_command_async`] with the given services.
+    ///
+    /// The aggregate state is held, along with its lock, while the services are consulted: slow
+    /// services delay the other commands addressed to the same aggregate instance.
+    pub async fn handle_async_command(
+        &self,
+        aggregate_state: AggregateState<<E::Aggregate as Aggregate>::State>,
+        command: <E::Aggregate as Aggregate>::Command,
+        services: &<E::Aggregate as AsyncAggregate>::Services,
+    ) -> Result<Result<<E::Aggregate as Aggregate>::State, <E::Aggregate as Aggregate>::Error>, E::Error>
+    where
+        E::Aggregate: AsyncAggregate,
+    {
+        match self.timeout {
+            #[cfg(any(feature = "runtime-tokio", feature = "runtime-async-std"))]
+            Some((timeout, into_error)) => crate::runtime::timeout(
+                timeout,
+                self.handle_async_command_untimed(aggregate_state, command, services),
+            )
+            .await
+            .unwrap_or_else(|| Err(into_error(CommandTimeout(timeout)))),
+            _ => {
+                self.handle_async_command_untimed(aggregate_state, command, services)
+                    .await
+            }
+        }
+    }
+
+    async fn handle_async_command_untimed(
+        &self,
+        aggregate_state: AggregateState<<E::Aggregate as Aggregate>::State>,
+        command: <E::Aggregate as Aggregate>::Command,
+        services: &<E::Aggregate as AsyncAggregate>::Services,
+    ) -> Result<Result<<E::Aggregate as Aggregate>::State, <E::Aggregate as Aggregate>::Error>, E::Error>
+    where
+        E::Aggregate: AsyncAggregate,
+    {
+        if let Err(middleware_error) = self.before_command(*aggregate_state.id(), aggregate_state.inner(), &command) {
+            return Ok(Err(middleware_error));
+        }
+
+        match <E::Aggregate as AsyncAggregate>::handle_command_async(aggregate_state.inner(), command, services).await {
+            Err(domain_error) => Ok(Err(domain_error)),
+            Ok(events) => self
+                .persist_and_apply(aggregate_state, events, Metadata::default())
+                .await
+                .map(Ok),
+        }
+    }
+
+    /// Validates and handles the commands one after the other, each onto the state resulting from
+    /// the events of the previous ones, and then passes all the events to the store at once.
+    ///
+    /// All the events are persisted in a single transaction, with a single lock cycle, greatly
+    /// reducing the overhead of bulk imports and batch jobs compared to handling the commands one by
+    /// one. If any command is denied by the aggregate, no event is persisted.
+    ///
+    /// Returns the same two layers of errors of [`AggregateManager::handle_command`], whose timeout,
+    /// if any, bounds the handling of the whole batch.
+    pub async fn handle_commands(
+        &self,
+        aggregate_state: AggregateState<<E::Aggregate as Aggregate>::State>,
+        commands: Vec<<E::Aggregate as Aggregate>::Command>,
+    ) -> Result<Result<<E::Aggregate as Aggregate>::State, <E::Aggregate as Aggregate>::Error>, E::Error>
+    where
+        <E::Aggregate as Aggregate>::State: Clone,
+        <E::Aggregate as Aggregate>::Event: Clone,
+    {
+        match self.timeout {
+            #[cfg(any(feature = "runtime-tokio", feature = "runtime-async-std"))]
+            Some((timeout, into_error)) => {
+                crate::runtime::timeout(timeout, self.handle_commands_untimed(aggregate_state, commands))
+                    .await
+                    .unwrap_or_else(|| Err(into_error(CommandTimeout(timeout))))
+            }
+            _ => self.handle_commands_untimed(aggregate_state, commands).await,
+        }
+    }
+
+    async fn handle_commands_untimed(
+        &self,
+        aggregate_state: AggregateState<<E::Aggregate as Aggregate>::State>,
+        commands: Vec<<E::Aggregate as Aggregate>::Command>,
+    ) -> Result<Result<<E::Aggregate as Aggregate>::State, <E::Aggregate as Aggregate>::Error>, E::Error>
+    where
+        <E::Aggregate as Aggregate>::State: Clone,
+        <E::Aggregate as Aggregate>::Event: Clone,
+    {
+        let mut state = aggregate_state.inner().clone();
+        let mut events = vec![];
+
+        for command in commands {
+            if let Err(middleware_error) = self.before_command(*aggregate_state.id(), &state, &command) {
+                return Ok(Err(middleware_error));
+            }
+
+            match <E::Aggregate as Aggregate>::handle_command(&state, command) {
+                Err(domain_error) => return Ok(Err(domain_error)),
+                Ok(command_events) => {
+                    state = command_events
+                        .iter()
+                        .cloned()
+                        .fold(state, <E::Aggregate as Aggregate>::apply_event);
+                    events.extend(command_events);
+                }
+            }
+        }
+
+        self.persist_and_apply(aggregate_state, events, Metadata::default())
+            .await
+            .map(Ok)
+    }
+
+    /// Persists the given events with the given metadata, then applies them onto the given state,
+    /// notifying the watchers.
+    async fn persist_and_apply(
+        &self,
+        mut aggregate_state: AggregateState<<E::Aggregate as Aggregate>::State>,
+        events: Vec<<E::Aggregate as Aggregate>::Event>,
+        metadata: Metadata,
+    ) -> Result<<E::Aggregate as Aggregate>::State, E::Error> {
+        let previous_sequence_number = *aggregate_state.sequence_number();
+        let store_events = self
+            .event_store
+            .persist_with_metadata(&mut aggregate_state, events, metadata)
+            .await?;
+
+        Ok(self
+            .apply_persisted(aggregate_state, store_events, previous_sequence_number)
+            .await)
+    }
+
+    /// Reloads the given aggregate instance, falling back to the given state if it has no events.
+    async fn reload(
+        &self,
+        aggregate_state: AggregateState<<E::Aggregate as Aggregate>::State>,
+    ) -> Result<<E::Aggregate as Aggregate>::State, E::Error> {
+        Ok(match self.load(*aggregate_state.id()).await? {
+            Some(loaded) => loaded.into_inner(),
+            None => aggregate_state.into_inner(),
+        })
+    }
+
+    /// Applies the events just persisted onto the given state, running the `after` hook of the
+    /// middlewares, notifying the watchers and taking a snapshot if needed.
+    async fn apply_persisted(
+        &self,
+        aggregate_state: AggregateState<<E::Aggregate as Aggregate>::State>,
+        store_events: Vec<StoreEvent<<E::Aggregate as Aggregate>::Event>>,
+        previous_sequence_number: SequenceNumber,
+    ) -> <E::Aggregate as Aggregate>::State {
+        for middleware in &self.middlewares {
+            middleware.after(*aggregate_state.id(), &store_events);
+        }
+
+        let aggregate_state = aggregate_state.replay::<E::Aggregate>(store_events);
+        self.notify_watchers(&aggregate_state);
+        self.take_snapshot(&aggregate_state, previous_sequence_number).await;
+
+        aggregate_state.into_inner()
+    }
+
+    /// Runs the `before` hook of all the middlewares, stopping at the first one rejecting the command.
+    fn before_command(
+        &self,
+        aggregate_id: Uuid,
+        state: &<E::Aggregate as Aggregate>::State,
+        command: &<E::Aggregate as Aggregate>::Command,
+    ) -> Result<(), <E::Aggregate as Aggregate>::Error> {
+        self.middlewares
+            .iter()
+            .try_for_each(|middleware| middleware.before(aggregate_id, state, command))
+    }
+
+    /// Saves a snapshot of the given state if snapshots are enabled, and the events just applied
+    /// onto it crossed a multiple of the snapshot frequency.
+    async fn take_snapshot(
+        &self,
+        aggregate_state: &AggregateState<<E::Aggregate as Aggregate>::State>,
+        previous_sequence_number: SequenceNumber,
+    ) {
+        let Some(snapshots) = self.snapshots.as_ref() else {
+            return;
+        };
+
+        let sequence_number = *aggregate_state.sequence_number();
+        if sequence_number / snapshots.every_n_events == previous_sequence_number / snapshots.every_n_events {
+            return;
+        }
+
+        let snapshot = Snapshot {
+            aggregate_id: *aggregate_state.id(),
+            sequence_number,
+            version: snapshots.version,
+            state: (snapshots.clone_state)(aggregate_state.inner()),
+            taken_on: chrono::Utc::now(),
+        };
+
+        if let Err(error) = snapshots.store.save(&snapshot).await {
+            tracing::error!({
+                aggregate_name = <E::Aggregate as Aggregate>::NAME,
+                aggregate_id = %snapshot.aggregate_id,
+                sequence_number = sequence_number,
+                error = ?error,
+            }, "failed to save snapshot");
+        }
+    }
+
+    /// Loads the latest snapshot of the given aggregate instance, if snapshots are enabled and it
+    /// matches the current snapshot version.
+    async fn latest_snapshot(&self, aggregate_id: Uuid) -> Option<Snapshot<<E::Aggregate as Aggregate>::State>> {
+        let snapshots = self.snapshots.as_ref()?;
+
+        match snapshots.store.latest(aggregate_id).await {
+            Ok(snapshot) => snapshot.filter(|snapshot| snapshot.version == snapshots.version),
+            Err(error) => {
+                tracing::error!({
+                    aggregate_name = <E::Aggregate as Aggregate>::NAME,
+                    aggregate_id = %aggregate_id,
+                    error = ?error,
+                }, "failed to load snapshot");
+
+                None
+            }
+        }
+    }
+
+    /// Sends the given state to the watchers of its aggregate instance, dropping the ones whose
+    /// receivers are all gone.
+    fn notify_watchers(&self, aggregate_state: &AggregateState<<E::Aggregate as Aggregate>::State>) {
+        let mut watchers = self.watchers.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        if let Some(aggregate_watchers) = watchers.get_mut(aggregate_state.id()) {
+            aggregate_watchers.retain(|watcher| watcher(aggregate_state));
+
+            if aggregate_watchers.is_empty() {
+                watchers.remove(aggregate_state.id());
+            }
+        }
+    }
+
+    /// Subscribes to the state of the given aggregate instance, returning a receiver holding its
+    /// current state and updated after each command successfully handled by this manager on it.
+    ///
+    /// Only the commands handled by this [`AggregateManager`] instance are observed: the events
+    /// persisted through other managers, or by other processes, are not. The states sent to the
+    /// receiver never hold the lock on the aggregate instance.
+    ///
+    /// If the aggregate instance doesn't exist yet, the receiver starts from the default state. A
+    /// command completing while the current state is being loaded might not be observed until the
+    /// next one is handled.
+    pub async fn watch(
+        &self,
+        aggregate_id: impl Into<Uuid> + Send,
+    ) -> Result<watch::Receiver<AggregateState<<E::Aggregate as Aggregate>::State>>, E::Error>
+    where
+        <E::Aggregate as Aggregate>::State: Clone + Send + Sync + 'static,
+    {
+        let aggregate_id: Uuid = aggregate_id.into();
+        let aggregate_state = self
+            .load(aggregate_id)
+            .await?
+            .unwrap_or_else(|| AggregateState::with_id(aggregate_id));
+        let (sender, receiver) = watch::channel(aggregate_state);
+
+        self.watchers
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .entry(aggregate_id)
+            .or_default()
+            .push(Box::new(move |aggregate_state| {
+                sender.send(aggregate_state.to_unlocked()).is_ok()
+            }));
+
+        Ok(receiver)
+    }
+
     /// Loads an aggregate instance from the event store, by applying previously persisted events onto
     /// the aggregate state by order of their sequence number.
+    ///
+    /// If snapshots are enabled through [`AggregateManager::with_snapshots`], the events are applied
+    /// onto the latest snapshot of the aggregate instance instead, if any.
     pub async fn load(
         &self,
         aggregate_id: impl Into<Uuid> + Send,
     ) -> Result<Option<AggregateState<<E::Aggregate as Aggregate>::State>>, E::Error> {
         let aggregate_id: Uuid = aggregate_id.into();
 
+        if let Some(snapshot) = self.latest_snapshot(aggregate_id).await {
+            let store_events: Vec<StoreEvent<<E::Aggregate as Aggregate>::Event>> = self
+                .event_store
+                .by_aggregate_id_after(aggregate_id, snapshot.sequence_number)
+                .await?;
+
+            return Ok(Some(
+                AggregateState::from_snapshot(snapshot).replay::<E::Aggregate>(store_events),
+            ));
+        }
+
         let store_events: Vec<StoreEvent<<E::Aggregate as Aggregate>::Event>> = self
             .event_store
             .by_aggregate_id(aggregate_id)
@@ -74,7 +655,46 @@ where
             None
         } else {
             let aggregate_state = AggregateState::with_id(aggregate_id);
-            Some(aggregate_state.apply_store_events(store_events, <E::Aggregate as Aggregate>::apply_event))
+            Some(aggregate_state.replay::<E::Aggregate>(store_events))
+        })
+    }
+
+    /// Loads an aggregate instance as it was at the given point in time, by applying only the events
+    /// persisted up to that point, e.g. to inspect the state an aggregate instance had when a bug
+    /// happened. Returns `None` if the aggregate instance had no events yet at that point.
+    ///
+    /// Snapshots are not used, since they only hold the latest state of the aggregate instance.
+    pub async fn load_at(
+        &self,
+        aggregate_id: impl Into<Uuid> + Send,
+        point: PointInTime,
+    ) -> Result<Option<AggregateState<<E::Aggregate as Aggregate>::State>>, E::Error> {
+        let aggregate_id: Uuid = aggregate_id.into();
+
+        let store_events: Vec<StoreEvent<<E::Aggregate as Aggregate>::Event>> = match point {
+            // Sequence numbers may have gaps (e.g. after a compaction), so the events are filtered by
+            // their sequence number rather than counted.
+            PointInTime::SequenceNumber(sequence_number) => self
+                .event_store
+                .by_aggregate_id(aggregate_id)
+                .await?
+                .into_iter()
+                .take_while(|store_event| store_event.sequence_number <= sequence_number)
+                .collect(),
+            PointInTime::OccurredOn(occurred_on) => self
+                .event_store
+                .by_aggregate_id(aggregate_id)
+                .await?
+                .into_iter()
+                .take_while(|store_event| store_event.occurred_on <= occurred_on)
+                .collect(),
+        };
+
+        Ok(if store_events.is_empty() {
+            None
+        } else {
+            let aggregate_state = AggregateState::with_id(aggregate_id);
+            Some(aggregate_state.replay::<E::Aggregate>(store_events))
         })
     }
 
@@ -99,9 +719,70 @@ where
         })
     }
 
+    /// Loads an aggregate instance from the event store without acquiring any lock, returning a cheaply
+    /// clonable [`SharedAggregateState`].
+    ///
+    /// This is meant for read-mostly paths: the returned state can be freely shared, and upgraded to an
+    /// exclusively owned and locked [`AggregateState`] through [`AggregateManager::upgrade`] when a
+    /// command needs to be handled.
+    pub async fn load_shared(
+        &self,
+        aggregate_id: impl Into<Uuid> + Send,
+    ) -> Result<Option<SharedAggregateState<<E::Aggregate as Aggregate>::State>>, E::Error> {
+        Ok(self.load(aggregate_id).await?.map(SharedAggregateState::from))
+    }
+
+    /// Upgrades a [`SharedAggregateState`] to an [`AggregateState`] holding the lock on the aggregate
+    /// instance, ready to be used to handle commands.
+    ///
+    /// The lock is acquired first, then the events persisted after the shared state was loaded are
+    /// applied onto it. The internal state is cloned only if it is still shared with other instances.
+    pub async fn upgrade(
+        &self,
+        shared_aggregate_state: SharedAggregateState<<E::Aggregate as Aggregate>::State>,
+    ) -> Result<AggregateState<<E::Aggregate as Aggregate>::State>, E::Error>
+    where
+        <E::Aggregate as Aggregate>::State: Clone,
+    {
+        let id = *shared_aggregate_state.id();
+        let sequence_number = *shared_aggregate_state.sequence_number();
+        let guard = self.event_store.lock(id).await?;
+
+        let store_events: Vec<StoreEvent<<E::Aggregate as Aggregate>::Event>> =
+            self.event_store.by_aggregate_id_after(id, sequence_number).await?;
+
+        let mut aggregate_state = shared_aggregate_state
+            .replay::<E::Aggregate>(store_events)
+            .into_aggregate_state();
+        aggregate_state.set_lock(guard);
+
+        Ok(aggregate_state)
+    }
+
+    /// Checks whether the given aggregate instance exists, without loading its events.
+    pub async fn exists(&self, aggregate_id: impl Into<Uuid> + Send) -> Result<bool, E::Error> {
+        self.event_store.exists(aggregate_id.into()).await
+    }
+
     /// `delete` should either complete the aggregate instance, along with all its associated events
     /// and transactional read side projections, or fail.
+    ///
+    /// If snapshots are enabled, the snapshots of the aggregate instance are deleted first. Failing
+    /// to do so is logged: the stale snapshot would then be loaded by [`AggregateManager::load`]
+    /// until deleted through the [`SnapshotStore`].
     pub async fn delete(&self, aggregate_id: impl Into<Uuid> + Send) -> Result<(), E::Error> {
-        self.event_store.delete(aggregate_id.into()).await
+        let aggregate_id: Uuid = aggregate_id.into();
+
+        if let Some(snapshots) = self.snapshots.as_ref() {
+            if let Err(error) = snapshots.store.delete(aggregate_id).await {
+                tracing::error!({
+                    aggregate_name = <E::Aggregate as Aggregate>::NAME,
+                    aggregate_id = %aggregate_id,
+                    error = ?error,
+                }, "failed to delete snapshots");
+            }
+        }
+
+        self.event_store.delete(aggregate_id).await
     }
 }
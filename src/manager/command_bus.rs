@@ -0,0 +1,170 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::manager::AggregateManager;
+use crate::store::EventStore;
+use crate::Aggregate;
+
+/// A command that can be dispatched through a [`CommandBus`], knowing which aggregate instance it
+/// is addressed to.
+pub trait BusCommand: Send + Sync + 'static {
+    /// Returns the id of the aggregate instance that should handle this command.
+    fn aggregate_id(&self) -> Uuid;
+}
+
+/// Error returned by [`CommandBus::send`].
+#[derive(thiserror::Error, Debug)]
+pub enum CommandBusError {
+    /// No [`AggregateManager`] has been registered for the command type.
+    #[error("No aggregate manager registered for command `{0}`")]
+    NotRegistered(&'static str),
+    /// The aggregate denied the command.
+    #[error(transparent)]
+    Domain(Box<dyn std::error::Error + Send + Sync>),
+    /// The aggregate handled the command but the outcome failed to be recorded.
+    #[error(transparent)]
+    Store(Box<dyn std::error::Error + Send + Sync>),
+    /// A [`CommandBusMiddleware`] rejected the command.
+    #[error(transparent)]
+    Middleware(Box<dyn std::error::Error + Send + Sync>),
+}
+
+/// This trait is used to implement a [`CommandBusMiddleware`]. A command bus middleware is intended
+/// to be an entity which intercepts every command dispatched through a [`CommandBus`], e.g. for
+/// validation, authorization, logging or metrics.
+#[async_trait]
+pub trait CommandBusMiddleware: Sync {
+    /// Called before the command is handled. Returning an error rejects the command. The command can
+    /// be inspected downcasting it to its concrete type.
+    async fn before(&self, _aggregate_id: Uuid, _command: &(dyn Any + Send + Sync)) -> Result<(), CommandBusError> {
+        Ok(())
+    }
+
+    /// Called after the command is handled, with the outcome of the handling.
+    async fn after(&self, _aggregate_id: Uuid, _result: &Result<(), CommandBusError>) {}
+}
+
+/// Application-level bus dispatching commands to the [`AggregateManager`] registered for their type,
+/// so that callers don't need to know which manager handles them.
+///
+/// Commands are handled locking and loading the addressed aggregate instance (or creating a new one
+/// if it doesn't exist), and then handling the command onto it.
+pub struct CommandBus {
+    handlers: HashMap<TypeId, Box<dyn ErasedCommandHandler + Send>>,
+    middlewares: Vec<Box<dyn CommandBusMiddleware + Send>>,
+}
+
+impl CommandBus {
+    /// Creates a new instance of an empty [`CommandBus`].
+    pub fn new() -> Self {
+        Self {
+            handlers: HashMap::new(),
+            middlewares: vec![],
+        }
+    }
+
+    /// Registers the given [`AggregateManager`] as the handler of its aggregate command type,
+    /// replacing any previously registered one.
+    pub fn register<E>(mut self, manager: AggregateManager<E>) -> Self
+    where
+        E: EventStore + Send + Sync + 'static,
+        E::Aggregate: 'static,
+        E::Error: Send + Sync + 'static,
+        <E::Aggregate as Aggregate>::Command: BusCommand,
+        <E::Aggregate as Aggregate>::State: Send + Sync,
+        <E::Aggregate as Aggregate>::Event: Send,
+        <E::Aggregate as Aggregate>::Error: Send + Sync + 'static,
+    {
+        let _ = self.handlers.insert(
+            TypeId::of::<<E::Aggregate as Aggregate>::Command>(),
+            Box::new(ManagerCommandHandler(manager)),
+        );
+        self
+    }
+
+    /// Add a single middleware. Middlewares are run in the order they are added.
+    pub fn add_middleware(mut self, middleware: impl CommandBusMiddleware + Send + 'static) -> Self {
+        self.middlewares.push(Box::new(middleware));
+        self
+    }
+
+    /// Dispatches the command to the [`AggregateManager`] registered for its type, running all the
+    /// middlewares around it.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if no manager is registered for the command type, a middleware rejects
+    /// the command, the aggregate denies it or the outcome fails to be recorded.
+    pub async fn send<C>(&self, command: C) -> Result<(), CommandBusError>
+    where
+        C: BusCommand,
+    {
+        let handler = self
+            .handlers
+            .get(&TypeId::of::<C>())
+            .ok_or(CommandBusError::NotRegistered(std::any::type_name::<C>()))?;
+
+        let aggregate_id: Uuid = command.aggregate_id();
+
+        for middleware in &self.middlewares {
+            middleware.before(aggregate_id, &command).await?;
+        }
+
+        let result = handler.handle(Box::new(command)).await;
+
+        for middleware in &self.middlewares {
+            middleware.after(aggregate_id, &result).await;
+        }
+
+        result
+    }
+}
+
+impl Default for CommandBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Type-erased command handler, stored in the [`CommandBus`].
+#[async_trait]
+trait ErasedCommandHandler: Sync {
+    async fn handle(&self, command: Box<dyn Any + Send>) -> Result<(), CommandBusError>;
+}
+
+struct ManagerCommandHandler<E>(AggregateManager<E>)
+where
+    E: EventStore;
+
+#[async_trait]
+impl<E> ErasedCommandHandler for ManagerCommandHandler<E>
+where
+    E: EventStore + Send + Sync,
+    E::Error: Send + Sync + 'static,
+    <E::Aggregate as Aggregate>::Command: BusCommand,
+    <E::Aggregate as Aggregate>::State: Send + Sync,
+    <E::Aggregate as Aggregate>::Event: Send,
+    <E::Aggregate as Aggregate>::Error: Send + Sync + 'static,
+{
+    async fn handle(&self, command: Box<dyn Any + Send>) -> Result<(), CommandBusError> {
+        let command: <E::Aggregate as Aggregate>::Command = *command
+            .downcast()
+            .expect("Commands are dispatched by their type id, hence they always match the handler command type");
+
+        let aggregate_state = self
+            .0
+            .lock_and_load(command.aggregate_id())
+            .await
+            .map_err(|error| CommandBusError::Store(Box::new(error)))?
+            .unwrap_or_default();
+
+        match self.0.handle_command(aggregate_state, command).await {
+            Ok(Ok(_)) => Ok(()),
+            Ok(Err(domain_error)) => Err(CommandBusError::Domain(Box::new(domain_error))),
+            Err(operational_error) => Err(CommandBusError::Store(Box::new(operational_error))),
+        }
+    }
+}
@@ -0,0 +1,26 @@
+use uuid::Uuid;
+
+use crate::store::StoreEvent;
+use crate::Aggregate;
+
+/// This trait is used to implement a [`CommandMiddleware`]. A command middleware is intended to be an
+/// entity which intercepts every command handled by an [`super::AggregateManager`], e.g. for
+/// validation, authorization, logging or metrics, and inspects the events it produced.
+///
+/// Unlike a [`super::CommandBusMiddleware`], it is typed after the aggregate, and it runs whatever
+/// way the command reaches the manager.
+pub trait CommandMiddleware<A>: Sync
+where
+    A: Aggregate,
+{
+    /// Called before the command is handled by [`Aggregate::handle_command`], with the state it is
+    /// going to be handled onto. Returning an error rejects the command, as if the aggregate denied
+    /// it.
+    fn before(&self, _aggregate_id: Uuid, _state: &A::State, _command: &A::Command) -> Result<(), A::Error> {
+        Ok(())
+    }
+
+    /// Called after the events produced by the command are persisted, before they are applied onto
+    /// the aggregate state.
+    fn after(&self, _aggregate_id: Uuid, _store_events: &[StoreEvent<A::Event>]) {}
+}
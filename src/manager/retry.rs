@@ -0,0 +1,45 @@
+/// Error of an [`crate::store::EventStore`] able to tell whether the events failed to be persisted
+/// because another writer persisted events for the same aggregate instance in the meantime, i.e.
+/// because of an optimistic locking conflict.
+pub trait ConflictError {
+    /// Returns `true` if the error is an optimistic locking conflict, after which the command can be
+    /// handled again onto the reloaded aggregate state.
+    fn is_conflict(&self) -> bool;
+}
+
+/// The policy applied by [`super::AggregateManager::handle_command_with_retry`] to commands failing
+/// because of an optimistic locking conflict.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RetryPolicy {
+    /// Creates a new [`RetryPolicy`], handling the command at most 3 times.
+    pub fn new() -> Self {
+        Self { max_attempts: 3 }
+    }
+
+    /// Set the maximum number of times the command is handled, the first attempt included.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `max_attempts` is zero.
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        assert!(max_attempts > 0, "commands must be handled at least once");
+
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// Returns the maximum number of times the command is handled, the first attempt included.
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+}
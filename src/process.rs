@@ -0,0 +1,320 @@
+//! Process managers (also known as sagas), coordinating long running business processes spanning
+//! many aggregate instances, or many aggregates, by reacting to their events with commands.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sqlx::postgres::PgQueryResult;
+use sqlx::types::Json;
+use sqlx::{Pool, Postgres, Transaction};
+use tracing::Instrument;
+use uuid::Uuid;
+
+use crate::handler::EventHandler;
+use crate::store::StoreEvent;
+use crate::Aggregate;
+
+/// This trait is used to implement a [`ProcessManager`]. A process manager holds the state of each
+/// of its process instances, persisted by the [`ProcessRunner`], and reacts to the events of one or
+/// more aggregates through its [`ProcessEventHandler`] implementations.
+pub trait ProcessManager: Sync {
+    /// The state of a process instance. A new process instance starts from the default state.
+    type State: Default + Serialize + DeserializeOwned + Send + Sync;
+
+    /// Returns whether the process instance in the given state is completed. The events of completed
+    /// process instances are skipped. By default, process instances are never completed.
+    fn is_completed(&self, _state: &Self::State) -> bool {
+        false
+    }
+
+    /// The name of the process manager. By default, this is the type name of the process manager,
+    /// but it can be overridden to provide a custom name. This name is used as part of tracing
+    /// spans, to identify the process manager being run.
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+}
+
+/// This trait is used to make a [`ProcessManager`] react to the events of the aggregate `A`. It is
+/// meant to be implemented once per aggregate the process manager listens to.
+#[async_trait]
+pub trait ProcessEventHandler<A>: ProcessManager
+where
+    A: Aggregate,
+{
+    /// Returns the id of the process instance the given event belongs to, usually found in its
+    /// payload, or `None` if the event is not relevant to the process manager.
+    fn process_id(&self, event: &StoreEvent<A::Event>) -> Option<Uuid>;
+
+    /// Handles the given event for the process instance with the given id, updating its state and
+    /// sending commands to the involved aggregates, e.g. through an
+    /// [`crate::manager::AggregateManager`] held by the process manager.
+    ///
+    /// Returning an error discards the changes to the state, so that the event is handled again on
+    /// redelivery. Since the commands sent before the error are not rolled back, they are sent
+    /// again then: they should be idempotent.
+    async fn handle(
+        &self,
+        process_id: Uuid,
+        state: &mut Self::State,
+        event: &StoreEvent<A::Event>,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
+}
+
+/// Error returned by the [`ProcessRunner`].
+#[derive(thiserror::Error, Debug)]
+pub enum ProcessManagerError {
+    /// Loading or saving the state of the process instance failed.
+    #[error(transparent)]
+    Sql(#[from] sqlx::Error),
+    /// The state of the process instance couldn't be serialized or deserialized.
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    /// The process manager failed to handle the event.
+    #[error(transparent)]
+    Handler(Box<dyn std::error::Error + Send + Sync>),
+}
+
+#[derive(sqlx::FromRow)]
+struct DbProcess {
+    state: Json<serde_json::Value>,
+    completed_at: Option<DateTime<Utc>>,
+}
+
+/// Postgres backed runner of a [`ProcessManager`], storing the state of the process instances in
+/// the `{name}_processes` table, and the events they handled in the `{name}_process_events` table.
+///
+/// Each event is handled in a transaction locking its process instance, in which the updated state
+/// is saved and the event recorded, so that redelivered events are skipped and concurrent events of
+/// the same process instance are handled one at a time. The runner is an [`EventHandler`] of each
+/// aggregate the process manager listens to: running it through a
+/// [`crate::store::postgres::Subscription`] lets the process manager resume from where it left off
+/// after a restart, without missing any event.
+pub struct ProcessRunner<P> {
+    pool: Pool<Postgres>,
+    process_manager: Arc<P>,
+    table_name: String,
+    events_table_name: String,
+}
+
+impl<P> Clone for ProcessRunner<P> {
+    fn clone(&self) -> Self {
+        Self {
+            pool: self.pool.clone(),
+            process_manager: self.process_manager.clone(),
+            table_name: self.table_name.clone(),
+            events_table_name: self.events_table_name.clone(),
+        }
+    }
+}
+
+impl<P> ProcessRunner<P>
+where
+    P: ProcessManager,
+{
+    /// Creates a new instance of a [`ProcessRunner`] of the given process manager.
+    pub fn new(pool: Pool<Postgres>, name: &str, process_manager: P) -> Self {
+        Self {
+            pool,
+            process_manager: Arc::new(process_manager),
+            table_name: format!("{}_processes", name),
+            events_table_name: format!("{}_process_events", name),
+        }
+    }
+
+    /// Returns the name of the table of the process instances.
+    pub fn table_name(&self) -> &str {
+        &self.table_name
+    }
+
+    /// Returns the process manager being run.
+    pub fn process_manager(&self) -> &P {
+        &self.process_manager
+    }
+
+    /// Atomically creates the tables of the process manager, if they don't exist yet.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if there's an error running the migrations.
+    pub async fn setup(&self) -> Result<(), sqlx::Error> {
+        let mut transaction: Transaction<Postgres> = self.pool.begin().await?;
+
+        let migrations: Vec<String> = vec![
+            format!(
+                include_str!("sql/postgres/migrations/create_processes_table.sql"),
+                self.table_name
+            ),
+            format!(
+                include_str!("sql/postgres/migrations/create_process_events_table.sql"),
+                self.events_table_name
+            ),
+        ];
+
+        for migration in migrations {
+            let _: PgQueryResult = sqlx::query(migration.as_str()).execute(&mut *transaction).await?;
+        }
+
+        transaction.commit().await
+    }
+
+    /// Loads the state of the process instance with the given id, along with whether it is
+    /// completed. Returns `None` if the process instance didn't handle any event yet.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the query fails, or the state can't be deserialized.
+    pub async fn load(&self, process_id: Uuid) -> Result<Option<(P::State, bool)>, ProcessManagerError> {
+        let process: Option<DbProcess> = sqlx::query_as::<_, DbProcess>(
+            format!(
+                include_str!("sql/postgres/statements/select_process.sql"),
+                self.table_name
+            )
+            .as_str(),
+        )
+        .bind(process_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match process {
+            Some(process) => Ok(Some((
+                serde_json::from_value(process.state.0)?,
+                process.completed_at.is_some(),
+            ))),
+            None => Ok(None),
+        }
+    }
+
+    /// Handles the given event with the process manager, if relevant, saving the updated state of
+    /// its process instance. Returns `false` if the event has been skipped, being irrelevant,
+    /// already handled, or belonging to a completed process instance.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if any of the queries fails, the state can't be serialized or
+    /// deserialized, or the process manager fails to handle the event.
+    pub async fn handle<A>(&self, event: &StoreEvent<A::Event>) -> Result<bool, ProcessManagerError>
+    where
+        A: Aggregate,
+        A::Event: Sync,
+        P: ProcessEventHandler<A>,
+    {
+        let Some(process_id) = self.process_manager.process_id(event) else {
+            return Ok(false);
+        };
+
+        let mut transaction: Transaction<Postgres> = self.pool.begin().await?;
+
+        let _ = sqlx::query(
+            format!(
+                include_str!("sql/postgres/statements/insert_process.sql"),
+                self.table_name
+            )
+            .as_str(),
+        )
+        .bind(process_id)
+        .bind(Json(serde_json::to_value(P::State::default())?))
+        .execute(&mut *transaction)
+        .await?;
+
+        let process: DbProcess = sqlx::query_as::<_, DbProcess>(
+            format!(
+                include_str!("sql/postgres/statements/select_process_for_update.sql"),
+                self.table_name
+            )
+            .as_str(),
+        )
+        .bind(process_id)
+        .fetch_one(&mut *transaction)
+        .await?;
+
+        let recorded: PgQueryResult = sqlx::query(
+            format!(
+                include_str!("sql/postgres/statements/insert_process_event.sql"),
+                self.events_table_name
+            )
+            .as_str(),
+        )
+        .bind(event.id)
+        .bind(process_id)
+        .execute(&mut *transaction)
+        .await?;
+
+        if recorded.rows_affected() == 0 || process.completed_at.is_some() {
+            tracing::debug!({
+                process_manager = self.process_manager.name(),
+                process_id = %process_id,
+                event_id = %event.id,
+            }, "skipping already handled event or completed process");
+
+            transaction.commit().await?;
+            return Ok(false);
+        }
+
+        let mut state: P::State = serde_json::from_value(process.state.0)?;
+
+        let span = tracing::trace_span!(
+            "esrs.process_manager",
+            event_id = %event.id,
+            aggregate_id = %event.aggregate_id,
+            process_id = %process_id,
+            process_manager = self.process_manager.name()
+        );
+
+        self.process_manager
+            .handle(process_id, &mut state, event)
+            .instrument(span)
+            .await
+            .map_err(ProcessManagerError::Handler)?;
+
+        let completed_at: Option<DateTime<Utc>> = self.process_manager.is_completed(&state).then(Utc::now);
+
+        let _ = sqlx::query(
+            format!(
+                include_str!("sql/postgres/statements/update_process.sql"),
+                self.table_name
+            )
+            .as_str(),
+        )
+        .bind(process_id)
+        .bind(Json(serde_json::to_value(&state)?))
+        .bind(completed_at)
+        .execute(&mut *transaction)
+        .await?;
+
+        transaction.commit().await?;
+
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl<A, P> EventHandler<A> for ProcessRunner<P>
+where
+    A: Aggregate + 'static,
+    A::Event: Send + Sync,
+    P: ProcessEventHandler<A> + Send,
+{
+    async fn handle(&self, event: &StoreEvent<A::Event>) {
+        if let Err(error) = ProcessRunner::handle::<A>(self, event).await {
+            tracing::error!({
+                event_id = %event.id,
+                aggregate_id = %event.aggregate_id,
+                process_manager = self.process_manager.name(),
+                error = ?error,
+            }, "process manager failed to handle event");
+        }
+    }
+
+    async fn try_handle(&self, event: &StoreEvent<A::Event>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let _ = ProcessRunner::handle::<A>(self, event).await?;
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        self.process_manager.name()
+    }
+}
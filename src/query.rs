@@ -0,0 +1,109 @@
+//! Read side abstractions, pairing the views built by the event handlers with the typed queries
+//! they answer.
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::ops::Deref;
+
+use async_trait::async_trait;
+
+/// A typed query over the read side of the application. The query holds its parameters, while its
+/// output and error types describe what the [`ReadModel`] answering it returns.
+pub trait Query: Send + 'static {
+    /// The result of the query.
+    type Output;
+    /// The error the query can fail with.
+    type Error;
+}
+
+/// This trait is used to implement a [`ReadModel`]. A read model is intended to be an entity (e.g.
+/// a view updated by an [`crate::handler::EventHandler`]) answering the queries of a given type.
+///
+/// A read model can answer many query types, implementing this trait once per query type.
+#[async_trait]
+pub trait ReadModel<Q>: Sync
+where
+    Q: Query,
+{
+    /// Answers the given query.
+    async fn query(&self, query: Q) -> Result<Q::Output, Q::Error>;
+}
+
+#[async_trait]
+impl<Q, R, T> ReadModel<Q> for T
+where
+    Q: Query,
+    R: ReadModel<Q>,
+    T: Deref<Target = R> + Send + Sync,
+{
+    /// Deref call to [`ReadModel::query`].
+    async fn query(&self, query: Q) -> Result<Q::Output, Q::Error> {
+        self.deref().query(query).await
+    }
+}
+
+/// Error returned by [`QueryRegistry::query`].
+#[derive(thiserror::Error, Debug)]
+pub enum QueryError<E> {
+    /// No [`ReadModel`] has been registered for the query type.
+    #[error("No read model registered for query `{0}`")]
+    NotRegistered(&'static str),
+    /// The [`ReadModel`] failed to answer the query.
+    #[error(transparent)]
+    ReadModel(E),
+}
+
+/// Application-level registry dispatching queries to the [`ReadModel`] registered for their type,
+/// so that callers don't need to know which read model answers them.
+///
+/// To register the same read model for many query types, wrap it in an [`std::sync::Arc`] and
+/// register a clone for every query type.
+pub struct QueryRegistry {
+    read_models: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+}
+
+impl QueryRegistry {
+    /// Creates a new instance of an empty [`QueryRegistry`].
+    pub fn new() -> Self {
+        Self {
+            read_models: HashMap::new(),
+        }
+    }
+
+    /// Registers the given [`ReadModel`] as the one answering the queries of type `Q`, replacing any
+    /// previously registered one.
+    pub fn register<Q, R>(mut self, read_model: R) -> Self
+    where
+        Q: Query,
+        R: ReadModel<Q> + Send + 'static,
+    {
+        let read_model: Box<dyn ReadModel<Q> + Send> = Box::new(read_model);
+        let _ = self.read_models.insert(TypeId::of::<Q>(), Box::new(read_model));
+        self
+    }
+
+    /// Dispatches the query to the [`ReadModel`] registered for its type.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if no read model is registered for the query type, or the read model
+    /// fails to answer the query.
+    pub async fn query<Q>(&self, query: Q) -> Result<Q::Output, QueryError<Q::Error>>
+    where
+        Q: Query,
+    {
+        let read_model = self
+            .read_models
+            .get(&TypeId::of::<Q>())
+            .and_then(|read_model| read_model.downcast_ref::<Box<dyn ReadModel<Q> + Send>>())
+            .ok_or(QueryError::NotRegistered(std::any::type_name::<Q>()))?;
+
+        read_model.query(query).await.map_err(QueryError::ReadModel)
+    }
+}
+
+impl Default for QueryRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
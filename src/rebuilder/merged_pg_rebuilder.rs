@@ -0,0 +1,199 @@
+use std::collections::HashSet;
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use sqlx::{Pool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::rebuilder::{PgRebuilder, RebuildReport};
+use crate::store::postgres::persistable::Persistable;
+use crate::store::postgres::{GlobalEvent, GlobalEventStream, PgStore, PgStoreError, Schema};
+use crate::store::StoreEvent;
+use crate::Aggregate;
+
+/// Rebuilds the views shared by multiple [`Aggregate`]s, replaying the events of all their stores
+/// merged in chronological order.
+///
+/// Each store comes with the [`PgRebuilder`] holding the handlers and buses of its aggregate:
+///
+/// ```ignore
+/// let report = MergedPgRebuilder::for_stores(vec![
+///     (&store_a, PgRebuilder::new().with_event_handlers(handlers_a)).into(),
+///     (&store_b, PgRebuilder::new().with_event_handlers(handlers_b)).into(),
+/// ])
+/// .rebuild(pool)
+/// .await?;
+/// ```
+///
+/// Every [`crate::handler::ReplayableEventHandler`] is truncated before the rebuild starts. Then a
+/// single transaction is opened, and the events of all the stores are read through a single
+/// [`GlobalEventStream`], ordered by `occurred_on`, then by aggregate type, aggregate id and
+/// sequence number. Stores sharing a table (see
+/// [`crate::store::postgres::PgStoreBuilder::with_shared_table`]) are read through their views. The
+/// event handlers, transactional event handlers and buses receive the events as in
+/// [`PgRebuilder::all_at_once`].
+///
+/// The events are streamed on their own connection, so the pool needs at least two connections.
+pub struct MergedPgRebuilder<'a> {
+    sources: Vec<RebuildSource<'a>>,
+}
+
+impl<'a> MergedPgRebuilder<'a> {
+    pub fn for_stores(sources: Vec<RebuildSource<'a>>) -> Self {
+        Self { sources }
+    }
+
+    /// Rebuilds the views of all the stores.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if many stores belong to the same aggregate, or if the rebuild fails.
+    pub async fn rebuild(&self, pool: Pool<Postgres>) -> Result<RebuildReport, PgStoreError> {
+        let mut aggregate_types: HashSet<&str> = HashSet::new();
+        for source in self.sources.iter() {
+            if !aggregate_types.insert(source.0.aggregate_type()) {
+                return Err(PgStoreError::Custom(
+                    format!("many stores of the `{}` aggregate", source.0.aggregate_type()).into(),
+                ));
+            }
+        }
+
+        let mut report: RebuildReport = RebuildReport::default();
+
+        for source in self.sources.iter() {
+            source.0.setup(&pool).await?;
+        }
+
+        let global_event_stream: GlobalEventStream = self
+            .sources
+            .iter()
+            .fold(GlobalEventStream::new(), |global_event_stream, source| {
+                source.0.add_to(global_event_stream)
+            });
+
+        // The aggregate instances whose first event has been replayed, per source.
+        let mut replayed_aggregate_ids: Vec<HashSet<Uuid>> = vec![HashSet::new(); self.sources.len()];
+        let mut events = global_event_stream.stream(&pool);
+        let mut transaction: Transaction<Postgres> = pool.begin().await?;
+
+        while let Some(event) = events.next().await {
+            let event: GlobalEvent = event?;
+
+            let Some(index) = self
+                .sources
+                .iter()
+                .position(|source| source.0.aggregate_type() == event.aggregate_type)
+            else {
+                continue;
+            };
+
+            self.sources[index]
+                .0
+                .replay(
+                    event,
+                    &mut replayed_aggregate_ids[index],
+                    &mut transaction,
+                    &pool,
+                    &mut report,
+                )
+                .await?;
+        }
+
+        transaction.commit().await?;
+
+        Ok(report)
+    }
+}
+
+/// A store, along with the [`PgRebuilder`] to replay its events, taking part in a
+/// [`MergedPgRebuilder`].
+pub struct RebuildSource<'a>(Box<dyn Source + 'a>);
+
+impl<'a, A, S> From<(&'a PgStore<A, S>, PgRebuilder<A, S>)> for RebuildSource<'a>
+where
+    A: Aggregate + 'static,
+    A::State: Send,
+    A::Event: Send + Sync + 'static,
+    S: Schema<A::Event> + Persistable + Send + Sync + 'static,
+{
+    fn from((store, rebuilder): (&'a PgStore<A, S>, PgRebuilder<A, S>)) -> Self {
+        Self(Box::new(StoreSource { store, rebuilder }))
+    }
+}
+
+#[async_trait]
+trait Source: Send + Sync {
+    /// The [`Aggregate::NAME`] of the events of the store.
+    fn aggregate_type(&self) -> &'static str;
+
+    /// Adds the events of the store to the given stream.
+    fn add_to(&self, global_event_stream: GlobalEventStream) -> GlobalEventStream;
+
+    /// Prepares the rebuild, truncating the views of the event handlers.
+    async fn setup(&self, pool: &Pool<Postgres>) -> Result<(), PgStoreError>;
+
+    /// Replays the given event of the store, unless it is a poison event.
+    async fn replay(
+        &self,
+        event: GlobalEvent,
+        replayed_aggregate_ids: &mut HashSet<Uuid>,
+        transaction: &mut Transaction<'_, Postgres>,
+        pool: &Pool<Postgres>,
+        report: &mut RebuildReport,
+    ) -> Result<(), PgStoreError>;
+}
+
+struct StoreSource<'a, A, S>
+where
+    A: Aggregate,
+{
+    store: &'a PgStore<A, S>,
+    rebuilder: PgRebuilder<A, S>,
+}
+
+#[async_trait]
+impl<'a, A, S> Source for StoreSource<'a, A, S>
+where
+    A: Aggregate + 'static,
+    A::State: Send,
+    A::Event: Send + Sync + 'static,
+    S: Schema<A::Event> + Persistable + Send + Sync + 'static,
+{
+    fn aggregate_type(&self) -> &'static str {
+        A::NAME
+    }
+
+    fn add_to(&self, global_event_stream: GlobalEventStream) -> GlobalEventStream {
+        global_event_stream.with_store(self.store)
+    }
+
+    async fn setup(&self, pool: &Pool<Postgres>) -> Result<(), PgStoreError> {
+        self.rebuilder.setup_poison_events_table(pool).await?;
+        self.rebuilder.truncate_event_handlers().await;
+        Ok(())
+    }
+
+    async fn replay(
+        &self,
+        event: GlobalEvent,
+        replayed_aggregate_ids: &mut HashSet<Uuid>,
+        transaction: &mut Transaction<'_, Postgres>,
+        pool: &Pool<Postgres>,
+        report: &mut RebuildReport,
+    ) -> Result<(), PgStoreError> {
+        let raw_event = event.into_raw_store_event::<A::Event, S>();
+
+        let Some(event): Option<StoreEvent<A::Event>> = self.rebuilder.deserialize(raw_event, pool, report).await?
+        else {
+            return Ok(());
+        };
+
+        let first_event: bool = replayed_aggregate_ids.insert(event.aggregate_id);
+        self.rebuilder
+            .replay(&event, first_event, transaction, pool, report)
+            .await?;
+        report.replayed += 1;
+
+        Ok(())
+    }
+}
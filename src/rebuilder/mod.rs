@@ -1,12 +1,21 @@
 use async_trait::async_trait;
+use uuid::Uuid;
 
+#[cfg(feature = "postgres")]
+pub use merged_pg_rebuilder::{MergedPgRebuilder, RebuildSource};
 #[cfg(feature = "postgres")]
 pub use pg_rebuilder::PgRebuilder;
+#[cfg(feature = "postgres")]
+pub use shadow::ShadowEventHandler;
 
 use crate::Aggregate;
 
+#[cfg(feature = "postgres")]
+mod merged_pg_rebuilder;
 #[cfg(feature = "postgres")]
 mod pg_rebuilder;
+#[cfg(feature = "postgres")]
+mod shadow;
 
 #[async_trait]
 pub trait Rebuilder<A>
@@ -16,6 +25,27 @@ where
     type Executor;
     type Error: std::error::Error;
 
-    async fn by_aggregate_id(&self, executor: Self::Executor) -> Result<(), Self::Error>;
-    async fn all_at_once(&self, executor: Self::Executor) -> Result<(), Self::Error>;
+    async fn by_aggregate_id(&self, executor: Self::Executor) -> Result<RebuildReport, Self::Error>;
+    async fn all_at_once(&self, executor: Self::Executor) -> Result<RebuildReport, Self::Error>;
+}
+
+/// Summary of a completed rebuild.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct RebuildReport {
+    /// The number of events replayed.
+    pub replayed: usize,
+    /// The events skipped because they couldn't be deserialized or handled, if the rebuilder has
+    /// been told to skip them rather than aborting the rebuild.
+    pub poison_events: Vec<PoisonEvent>,
+}
+
+/// An event skipped during a rebuild, for manual follow-up.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PoisonEvent {
+    /// The id of the event.
+    pub event_id: Uuid,
+    /// The aggregate instance the event belongs to.
+    pub aggregate_id: Uuid,
+    /// Why the event has been skipped.
+    pub error: String,
 }
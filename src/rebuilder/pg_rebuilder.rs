@@ -1,16 +1,21 @@
+use std::collections::HashSet;
 use std::marker::PhantomData;
+use std::panic::AssertUnwindSafe;
+use std::time::Duration;
 
 use async_trait::async_trait;
-use futures::StreamExt;
-use sqlx::{PgConnection, Pool, Postgres, Transaction};
+use chrono::{DateTime, Utc};
+use futures::{FutureExt, StreamExt};
+use sqlx::{Acquire, PgConnection, Pool, Postgres, Transaction};
 use uuid::Uuid;
 
 use crate::bus::EventBus;
 use crate::handler::{ReplayableEventHandler, TransactionalEventHandler};
-use crate::rebuilder::Rebuilder;
+use crate::rebuilder::{PoisonEvent, RebuildReport, Rebuilder, ShadowEventHandler};
+use crate::sql::unqualified;
 use crate::store::postgres::persistable::Persistable;
-use crate::store::postgres::{PgStore, PgStoreBuilder, PgStoreError, Schema};
-use crate::store::{EventStore, StoreEvent};
+use crate::store::postgres::{PgStore, PgStoreBuilder, PgStoreError, RawStoreEvent, Schema};
+use crate::store::StoreEvent;
 use crate::Aggregate;
 
 pub struct PgRebuilder<A, Schema = <A as Aggregate>::Event>
@@ -20,6 +25,10 @@ where
     event_handlers: Vec<Box<dyn ReplayableEventHandler<A> + Send>>,
     transactional_event_handlers: Vec<Box<dyn TransactionalEventHandler<A, PgStoreError, PgConnection> + Send>>,
     event_buses: Vec<Box<dyn EventBus<A> + Send>>,
+    shadow_event_handlers: Vec<Box<dyn ShadowEventHandler<A> + Send>>,
+    settle_delay: Duration,
+    poison_events_table: Option<String>,
+    target_pool: Option<Pool<Postgres>>,
     _schema: PhantomData<Schema>,
 }
 
@@ -48,6 +57,44 @@ where
     pub fn with_event_buses(self, event_buses: Vec<Box<dyn EventBus<A> + Send>>) -> Self {
         Self { event_buses, ..self }
     }
+
+    /// Sets the event handlers rebuilt by [`PgRebuilder::into_shadow_tables`].
+    pub fn with_shadow_event_handlers(self, shadow_event_handlers: Vec<Box<dyn ShadowEventHandler<A> + Send>>) -> Self {
+        Self {
+            shadow_event_handlers,
+            ..self
+        }
+    }
+
+    /// Set how long before the start of a rebuild into shadow tables the events are replayed again
+    /// while catching up, so that the events persisted by transactions in flight when the rebuild
+    /// started (whose `occurred_on` precedes the commit) are not missed. It should be longer than
+    /// the longest persisting transaction. Defaults to 1 minute.
+    pub fn with_settle_delay(self, settle_delay: Duration) -> Self {
+        Self { settle_delay, ..self }
+    }
+
+    /// Skips the events that can't be deserialized, or make a handler fail (or panic), rather than
+    /// aborting the rebuild. Skipped events are recorded in the given table, created if missing, and
+    /// listed in the returned [`RebuildReport`].
+    pub fn with_poison_events_table(self, table_name: &str) -> Self {
+        Self {
+            poison_events_table: Some(table_name.to_string()),
+            ..self
+        }
+    }
+
+    /// Rebuilds the views into the database behind the given pool, rather than the one the events
+    /// are read from. The transactions given to the [`TransactionalEventHandler`]s are opened on it,
+    /// and the poison events table, if any, is created there.
+    ///
+    /// The [`ReplayableEventHandler`]s are expected to be bound to the target database as well.
+    pub fn with_target_pool(self, target_pool: Pool<Postgres>) -> Self {
+        Self {
+            target_pool: Some(target_pool),
+            ..self
+        }
+    }
 }
 
 impl<A> Default for PgRebuilder<A>
@@ -59,6 +106,10 @@ where
             event_handlers: vec![],
             transactional_event_handlers: vec![],
             event_buses: vec![],
+            shadow_event_handlers: vec![],
+            settle_delay: Duration::from_secs(60),
+            poison_events_table: None,
+            target_pool: None,
             _schema: PhantomData,
         }
     }
@@ -67,9 +118,9 @@ where
 #[async_trait]
 impl<A, S> Rebuilder<A> for PgRebuilder<A, S>
 where
-    A: Aggregate,
+    A: Aggregate + 'static,
     A::State: Send,
-    A::Event: Send + Sync,
+    A::Event: Send + Sync + 'static,
     S: Schema<A::Event> + Persistable + Send + Sync,
 {
     type Executor = Pool<Postgres>;
@@ -81,25 +132,33 @@ where
     /// [`crate::handler::EventHandler`], the corresponding aggregate is deleted, and the list of
     /// events is processed by the mentioned handlers.
     /// Finally the events are passed to every configured [`EventBus`].
-    async fn by_aggregate_id(&self, pool: Pool<Postgres>) -> Result<(), Self::Error> {
+    async fn by_aggregate_id(&self, pool: Pool<Postgres>) -> Result<RebuildReport, Self::Error> {
         let store: PgStore<A, _> = PgStoreBuilder::new(pool.clone())
             .without_running_migrations()
             .with_schema::<S>()
             .try_build()
             .await?;
 
+        let target: &Pool<Postgres> = self.target_pool.as_ref().unwrap_or(&pool);
+        self.setup_poison_events_table(target).await?;
+
         let aggregate_ids: Vec<Uuid> = get_all_aggregate_ids(&pool, store.table_name()).await?;
+        let mut report: RebuildReport = RebuildReport::default();
 
         for id in aggregate_ids {
-            let mut transaction: Transaction<Postgres> = pool.begin().await.unwrap();
+            let mut transaction: Transaction<Postgres> = target.begin().await.unwrap();
 
-            let events = store.by_aggregate_id(id).await.unwrap();
+            let mut events: Vec<StoreEvent<A::Event>> = vec![];
+            for raw_event in store.by_aggregate_id_raw(id).await? {
+                events.extend(self.deserialize(raw_event, target, &mut report).await?);
+            }
 
             for handler in self.transactional_event_handlers.iter() {
                 handler.delete(id, &mut transaction).await?;
 
                 for event in &events {
-                    handler.handle(event, &mut transaction).await?;
+                    self.handle_transactionally(handler.as_ref(), event, &mut transaction, target, &mut report)
+                        .await?;
                 }
             }
 
@@ -109,7 +168,7 @@ where
                 handler.delete(id).await;
 
                 for event in &events {
-                    handler.handle(event).await;
+                    self.handle(handler.as_ref(), event, target, &mut report).await?;
                 }
             }
 
@@ -118,53 +177,461 @@ where
                     bus.publish(event).await;
                 }
             }
+
+            report.replayed += events.len();
         }
 
-        Ok(())
+        Ok(report)
     }
 
     /// To process all events in the database, a single transaction is opened, and within this
-    /// transaction, all aggregates are deleted and for each [`TransactionalEventHandler`], the
-    /// events are handled. After the transaction ends, for each [`crate::handler::EventHandler`]
-    /// and [`EventBus`], the events are handled.
-    async fn all_at_once(&self, pool: Pool<Postgres>) -> Result<(), Self::Error> {
+    /// transaction, for each [`TransactionalEventHandler`], every aggregate is deleted before
+    /// handling its first event. After the transaction ends, each [`ReplayableEventHandler`] is
+    /// truncated and then handles the events, that are passed to every configured [`EventBus`] as
+    /// well.
+    ///
+    /// The events are streamed twice from the same snapshot of the event store, once for each of the
+    /// two phases, so that they are never all held in memory. Unless a target pool is set, this
+    /// requires two connections from the pool: one for the snapshot, and one for the transaction.
+    async fn all_at_once(&self, pool: Pool<Postgres>) -> Result<RebuildReport, Self::Error> {
         let store: PgStore<A, _> = PgStoreBuilder::new(pool.clone())
             .with_schema::<S>()
             .without_running_migrations()
             .try_build()
             .await?;
 
-        let mut transaction: Transaction<Postgres> = pool.begin().await.unwrap();
+        let target: &Pool<Postgres> = self.target_pool.as_ref().unwrap_or(&pool);
+        self.setup_poison_events_table(target).await?;
+
+        let mut report: RebuildReport = RebuildReport::default();
+        let mut snapshot: Transaction<Postgres> = pool.begin().await?;
+        let _ = sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY")
+            .execute(&mut *snapshot)
+            .await?;
+
+        let mut transaction: Transaction<Postgres> = target.begin().await.unwrap();
+        let mut deleted_aggregate_ids: HashSet<Uuid> = HashSet::new();
+        let mut raw_events = store.stream_raw_events(&mut *snapshot);
+
+        while let Some(raw_event) = raw_events.next().await {
+            let event = match self.deserialize(raw_event?, target, &mut report).await? {
+                Some(event) => event,
+                None => continue,
+            };
 
-        let events: Vec<StoreEvent<A::Event>> = store
-            .stream_events(&mut *transaction)
-            .collect::<Vec<Result<StoreEvent<A::Event>, Self::Error>>>()
-            .await
-            .into_iter()
-            .collect::<Result<Vec<StoreEvent<A::Event>>, Self::Error>>()?;
+            let first_event: bool = deleted_aggregate_ids.insert(event.aggregate_id);
 
-        for event in &events {
             for handler in self.transactional_event_handlers.iter() {
-                handler.delete(event.aggregate_id, &mut transaction).await?;
-                handler.handle(event, &mut transaction).await?;
+                if first_event {
+                    handler.delete(event.aggregate_id, &mut transaction).await?;
+                }
+
+                self.handle_transactionally(handler.as_ref(), &event, &mut transaction, target, &mut report)
+                    .await?;
             }
+
+            report.replayed += 1;
         }
 
+        drop(raw_events);
         transaction.commit().await?;
 
-        for event in &events {
+        for handler in self.event_handlers.iter() {
+            handler.truncate().await;
+        }
+
+        let mut raw_events = store.stream_raw_events(&mut *snapshot);
+
+        while let Some(raw_event) = raw_events.next().await {
+            let event = match raw_event?.into_store_event() {
+                Ok(Some(event)) => event,
+                Ok(None) => continue,
+                // Already recorded while replaying the events through the transactional handlers.
+                Err(_) if self.poison_events_table.is_some() => continue,
+                Err(error) => return Err(error.into()),
+            };
+
             for handler in self.event_handlers.iter() {
-                handler.delete(event.aggregate_id).await;
-                handler.handle(event).await;
+                self.handle(handler.as_ref(), &event, target, &mut report).await?;
             }
 
             for bus in self.event_buses.iter() {
+                bus.publish(&event).await;
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+impl<A, S> PgRebuilder<A, S>
+where
+    A: Aggregate + 'static,
+    A::State: Send,
+    A::Event: Send + Sync + 'static,
+    S: Schema<A::Event> + Persistable + Send + Sync,
+{
+    /// Rebuilds the read side of every [`ShadowEventHandler`] without downtime: the events are
+    /// replayed into a freshly created `{table_name}_shadow` copy of each live table, while the live
+    /// ones keep serving reads and writes, then the shadow tables replace the live ones.
+    ///
+    /// The events are replayed from a snapshot of the event store in a first transaction. Then, in a
+    /// second transaction, the live tables are locked, so that the writers projecting into them wait
+    /// for the swap: the aggregate instances with events occurred since the start of the rebuild,
+    /// minus the settle delay, are replayed again into the shadow tables to catch up, the live
+    /// tables are dropped and the shadow tables renamed after them. The writers then resume on the
+    /// rebuilt tables.
+    ///
+    /// The shadow tables are created `LIKE` the live ones, including their indexes, constraints and
+    /// defaults, whose names are generated by Postgres. Views and foreign keys depending on the live
+    /// tables prevent them from being dropped, failing the swap.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if any of the queries fails, an event can't be deserialized or a shadow
+    /// event handler fails, unless poison events are skipped.
+    pub async fn into_shadow_tables(&self, pool: Pool<Postgres>) -> Result<RebuildReport, PgStoreError> {
+        let store: PgStore<A, _> = PgStoreBuilder::new(pool.clone())
+            .with_schema::<S>()
+            .without_running_migrations()
+            .try_build()
+            .await?;
+
+        let target: &Pool<Postgres> = self.target_pool.as_ref().unwrap_or(&pool);
+        self.setup_poison_events_table(target).await?;
+
+        let mut report: RebuildReport = RebuildReport::default();
+        let mut snapshot: Transaction<Postgres> = pool.begin().await?;
+        let _ = sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY")
+            .execute(&mut *snapshot)
+            .await?;
+        let started_at: DateTime<Utc> = sqlx::query_scalar("SELECT now()").fetch_one(&mut *snapshot).await?;
+
+        let mut transaction: Transaction<Postgres> = target.begin().await?;
+
+        for handler in self.shadow_event_handlers.iter() {
+            let shadow_table_name: String = format!("{}_shadow", handler.table_name());
+
+            for migration in [
+                format!(
+                    include_str!("../sql/postgres/migrations/drop_table.sql"),
+                    shadow_table_name
+                ),
+                format!(
+                    include_str!("../sql/postgres/migrations/create_shadow_table.sql"),
+                    shadow_table_name,
+                    handler.table_name()
+                ),
+            ] {
+                let _ = sqlx::query(migration.as_str()).execute(&mut *transaction).await?;
+            }
+        }
+
+        let mut raw_events = store.stream_raw_events(&mut *snapshot);
+
+        while let Some(raw_event) = raw_events.next().await {
+            let event = match self.deserialize(raw_event?, target, &mut report).await? {
+                Some(event) => event,
+                None => continue,
+            };
+
+            for handler in self.shadow_event_handlers.iter() {
+                self.handle_into_shadow(handler.as_ref(), &event, &mut transaction, target, &mut report)
+                    .await?;
+            }
+
+            report.replayed += 1;
+        }
+
+        drop(raw_events);
+        snapshot.commit().await?;
+        transaction.commit().await?;
+
+        let mut transaction: Transaction<Postgres> = target.begin().await?;
+
+        for handler in self.shadow_event_handlers.iter() {
+            let _ = sqlx::query(
+                format!(
+                    include_str!("../sql/postgres/statements/lock_table.sql"),
+                    handler.table_name()
+                )
+                .as_str(),
+            )
+            .execute(&mut *transaction)
+            .await?;
+        }
+
+        let since: DateTime<Utc> = started_at - self.settle_delay;
+        let aggregate_ids: Vec<Uuid> = sqlx::query_scalar(
+            format!(
+                include_str!("../sql/postgres/statements/select_aggregate_ids_since.sql"),
+                store.table_name()
+            )
+            .as_str(),
+        )
+        .bind(since)
+        .fetch_all(&pool)
+        .await?;
+
+        for aggregate_id in aggregate_ids {
+            let mut events: Vec<StoreEvent<A::Event>> = vec![];
+            for raw_event in store.by_aggregate_id_raw(aggregate_id).await? {
+                events.extend(self.deserialize(raw_event, target, &mut report).await?);
+            }
+
+            for handler in self.shadow_event_handlers.iter() {
+                let shadow_table_name: String = format!("{}_shadow", handler.table_name());
+                handler
+                    .delete(aggregate_id, shadow_table_name.as_str(), &mut transaction)
+                    .await?;
+
                 for event in &events {
-                    bus.publish(event).await;
+                    self.handle_into_shadow(handler.as_ref(), event, &mut transaction, target, &mut report)
+                        .await?;
                 }
             }
         }
 
+        for handler in self.shadow_event_handlers.iter() {
+            let shadow_table_name: String = format!("{}_shadow", handler.table_name());
+
+            for migration in [
+                format!(
+                    include_str!("../sql/postgres/migrations/drop_table.sql"),
+                    handler.table_name()
+                ),
+                format!(
+                    include_str!("../sql/postgres/migrations/rename_table.sql"),
+                    shadow_table_name,
+                    unqualified(handler.table_name())
+                ),
+            ] {
+                let _ = sqlx::query(migration.as_str()).execute(&mut *transaction).await?;
+            }
+        }
+
+        transaction.commit().await?;
+
+        tracing::info!({
+            replayed = report.replayed,
+            shadow_event_handlers = self.shadow_event_handlers.len(),
+        }, "read side rebuilt into shadow tables and swapped");
+
+        Ok(report)
+    }
+
+    /// Lets the shadow event handler handle the event into its shadow table. When skipping poison
+    /// events, the handling is wrapped in a savepoint, so that a failure doesn't abort the whole
+    /// transaction.
+    async fn handle_into_shadow(
+        &self,
+        handler: &(dyn ShadowEventHandler<A> + Send),
+        event: &StoreEvent<A::Event>,
+        transaction: &mut Transaction<'_, Postgres>,
+        pool: &Pool<Postgres>,
+        report: &mut RebuildReport,
+    ) -> Result<(), PgStoreError> {
+        let shadow_table_name: String = format!("{}_shadow", handler.table_name());
+
+        if self.poison_events_table.is_none() {
+            return handler.handle(event, shadow_table_name.as_str(), transaction).await;
+        }
+
+        let mut savepoint: Transaction<Postgres> = Acquire::begin(&mut *transaction).await?;
+
+        match handler.handle(event, shadow_table_name.as_str(), &mut savepoint).await {
+            Ok(()) => Ok(savepoint.commit().await?),
+            Err(error) => {
+                savepoint.rollback().await?;
+
+                let poison_event: PoisonEvent = PoisonEvent {
+                    event_id: event.id,
+                    aggregate_id: event.aggregate_id,
+                    error: format!("{}: {}", handler.name(), error),
+                };
+                self.skip(poison_event, error, pool, report).await
+            }
+        }
+    }
+}
+
+impl<A, S> PgRebuilder<A, S>
+where
+    A: Aggregate,
+    A::Event: Send + Sync,
+    S: Schema<A::Event> + Persistable + Send + Sync,
+{
+    pub(super) async fn setup_poison_events_table(&self, pool: &Pool<Postgres>) -> Result<(), PgStoreError> {
+        if let Some(table_name) = self.poison_events_table.as_deref() {
+            let migration: String = format!(
+                include_str!("../sql/postgres/migrations/create_poison_events_table.sql"),
+                table_name
+            );
+            let _ = sqlx::query(migration.as_str()).execute(pool).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Truncates the views of every [`ReplayableEventHandler`], ahead of replaying all the events.
+    pub(super) async fn truncate_event_handlers(&self) {
+        for handler in self.event_handlers.iter() {
+            handler.truncate().await;
+        }
+    }
+
+    /// Replays the event through every handler, and passes it to every bus. If it is the first event
+    /// of its aggregate instance, each [`TransactionalEventHandler`] deletes the aggregate first.
+    pub(super) async fn replay(
+        &self,
+        event: &StoreEvent<A::Event>,
+        first_event: bool,
+        transaction: &mut Transaction<'_, Postgres>,
+        pool: &Pool<Postgres>,
+        report: &mut RebuildReport,
+    ) -> Result<(), PgStoreError> {
+        for handler in self.transactional_event_handlers.iter() {
+            if first_event {
+                handler.delete(event.aggregate_id, transaction).await?;
+            }
+
+            self.handle_transactionally(handler.as_ref(), event, transaction, pool, report)
+                .await?;
+        }
+
+        for handler in self.event_handlers.iter() {
+            self.handle(handler.as_ref(), event, pool, report).await?;
+        }
+
+        for bus in self.event_buses.iter() {
+            bus.publish(event).await;
+        }
+
+        Ok(())
+    }
+
+    /// Records the given poison event into the report table, and in the report returned at the
+    /// end of the rebuild. Fails with the given error if poison events aren't to be skipped.
+    async fn skip(
+        &self,
+        poison_event: PoisonEvent,
+        error: PgStoreError,
+        pool: &Pool<Postgres>,
+        report: &mut RebuildReport,
+    ) -> Result<(), PgStoreError> {
+        let table_name: &str = match self.poison_events_table.as_deref() {
+            Some(table_name) => table_name,
+            None => return Err(error),
+        };
+
+        tracing::error!({
+            event_id = %poison_event.event_id,
+            aggregate_id = %poison_event.aggregate_id,
+            error = ?error,
+        }, "rebuilder skipped poison event");
+
+        let _ = sqlx::query(
+            format!(
+                include_str!("../sql/postgres/statements/insert_poison_event.sql"),
+                table_name
+            )
+            .as_str(),
+        )
+        .bind(poison_event.event_id)
+        .bind(poison_event.aggregate_id)
+        .bind(poison_event.error.as_str())
+        .execute(pool)
+        .await?;
+
+        report.poison_events.push(poison_event);
+        Ok(())
+    }
+
+    /// Deserializes the given event. Returns `None` if the schema skips the event, or if the event
+    /// can't be deserialized and poison events are skipped.
+    pub(super) async fn deserialize(
+        &self,
+        raw_event: RawStoreEvent<A::Event, S>,
+        pool: &Pool<Postgres>,
+        report: &mut RebuildReport,
+    ) -> Result<Option<StoreEvent<A::Event>>, PgStoreError> {
+        let (event_id, aggregate_id) = (raw_event.id, raw_event.aggregate_id);
+
+        match raw_event.into_store_event() {
+            Ok(event) => Ok(event),
+            Err(error) => {
+                let poison_event: PoisonEvent = PoisonEvent {
+                    event_id,
+                    aggregate_id,
+                    error: error.to_string(),
+                };
+                self.skip(poison_event, error.into(), pool, report).await?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Lets the transactional event handler handle the event. When skipping poison events, the
+    /// handling is wrapped in a savepoint, so that a failure doesn't abort the whole transaction.
+    async fn handle_transactionally(
+        &self,
+        handler: &(dyn TransactionalEventHandler<A, PgStoreError, PgConnection> + Send),
+        event: &StoreEvent<A::Event>,
+        transaction: &mut Transaction<'_, Postgres>,
+        pool: &Pool<Postgres>,
+        report: &mut RebuildReport,
+    ) -> Result<(), PgStoreError> {
+        if self.poison_events_table.is_none() {
+            return handler.handle(event, transaction).await;
+        }
+
+        let mut savepoint: Transaction<Postgres> = Acquire::begin(&mut *transaction).await?;
+
+        match handler.handle(event, &mut savepoint).await {
+            Ok(()) => Ok(savepoint.commit().await?),
+            Err(error) => {
+                savepoint.rollback().await?;
+
+                let poison_event: PoisonEvent = PoisonEvent {
+                    event_id: event.id,
+                    aggregate_id: event.aggregate_id,
+                    error: format!("{}: {}", handler.name(), error),
+                };
+                self.skip(poison_event, error, pool, report).await
+            }
+        }
+    }
+
+    /// Lets the event handler handle the event. When skipping poison events, a panicking handler
+    /// doesn't abort the rebuild.
+    async fn handle(
+        &self,
+        handler: &(dyn ReplayableEventHandler<A> + Send),
+        event: &StoreEvent<A::Event>,
+        pool: &Pool<Postgres>,
+        report: &mut RebuildReport,
+    ) -> Result<(), PgStoreError> {
+        if self.poison_events_table.is_none() {
+            handler.handle(event).await;
+            return Ok(());
+        }
+
+        if let Err(panic) = AssertUnwindSafe(handler.handle(event)).catch_unwind().await {
+            let message: String = panic
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+
+            let poison_event: PoisonEvent = PoisonEvent {
+                event_id: event.id,
+                aggregate_id: event.aggregate_id,
+                error: format!("{} panicked: {}", handler.name(), message),
+            };
+            let error: PgStoreError = PgStoreError::Custom(poison_event.error.clone().into());
+            self.skip(poison_event, error, pool, report).await?;
+        }
+
         Ok(())
     }
 }
@@ -0,0 +1,46 @@
+use async_trait::async_trait;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::store::postgres::PgStoreError;
+use crate::store::StoreEvent;
+use crate::Aggregate;
+
+/// This trait is used to implement a [`ShadowEventHandler`]: a transactional event handler able to
+/// project the events into a table other than its live one, so that its read side can be rebuilt
+/// into a shadow table while the live table keeps serving reads, then swapped with it. See
+/// [`super::PgRebuilder::into_shadow_tables`].
+///
+/// A shadow event handler projects into a single table, and must write into the table it is given
+/// rather than into its live table.
+#[async_trait]
+pub trait ShadowEventHandler<A>: Sync
+where
+    A: Aggregate,
+{
+    /// The name of the live table of the read side, possibly schema qualified.
+    fn table_name(&self) -> &str;
+
+    /// Handles the event as the transactional event handler does, writing into the given table.
+    async fn handle(
+        &self,
+        event: &StoreEvent<A::Event>,
+        table_name: &str,
+        executor: &mut PgConnection,
+    ) -> Result<(), PgStoreError>;
+
+    /// Deletes the read side of the given aggregate instance from the given table.
+    async fn delete(
+        &self,
+        aggregate_id: Uuid,
+        table_name: &str,
+        executor: &mut PgConnection,
+    ) -> Result<(), PgStoreError>;
+
+    /// The name of the event handler. By default, this is the type name of the event handler,
+    /// but it can be overridden to provide a custom name. This name is used as
+    /// part of tracing spans, to identify the event handler being run.
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+}
@@ -0,0 +1,51 @@
+//! The few pieces of the async runtime the crate depends on: timers and detached tasks. tokio is
+//! preferred when both the `runtime-tokio` and `runtime-async-std` features are enabled.
+
+use std::future::Future;
+use std::time::Duration;
+
+/// Waits until the given duration has elapsed.
+pub(crate) async fn sleep(duration: Duration) {
+    #[cfg(feature = "runtime-tokio")]
+    tokio::time::sleep(duration).await;
+
+    #[cfg(all(feature = "runtime-async-std", not(feature = "runtime-tokio")))]
+    async_std::task::sleep(duration).await;
+}
+
+/// Awaits the given future, returning `None` if it doesn't complete within the given duration.
+pub(crate) async fn timeout<F>(duration: Duration, future: F) -> Option<F::Output>
+where
+    F: Future,
+{
+    #[cfg(feature = "runtime-tokio")]
+    let output = tokio::time::timeout(duration, future).await.ok();
+
+    #[cfg(all(feature = "runtime-async-std", not(feature = "runtime-tokio")))]
+    let output = async_std::future::timeout(duration, future).await.ok();
+
+    output
+}
+
+/// Runs the given future in a separate task, so that it completes even if the returned future is
+/// dropped, and waits for its output. A panic in the task is propagated to the caller.
+///
+/// Fails if the task is cancelled, e.g. because the runtime is shutting down.
+#[cfg(feature = "postgres")]
+pub(crate) async fn spawn<F>(future: F) -> Result<F::Output, Box<dyn std::error::Error + Send + Sync>>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    #[cfg(feature = "runtime-tokio")]
+    let output = match tokio::spawn(future).await {
+        Ok(output) => Ok(output),
+        Err(error) if error.is_panic() => std::panic::resume_unwind(error.into_panic()),
+        Err(error) => Err(error.into()),
+    };
+
+    #[cfg(all(feature = "runtime-async-std", not(feature = "runtime-tokio")))]
+    let output = Ok(async_std::task::spawn(future).await);
+
+    output
+}
@@ -0,0 +1,481 @@
+//! Durable timeouts, letting sagas and process managers implement "remind or cancel after a while"
+//! behaviours without external schedulers, and commands scheduled for a future time on top of them.
+
+use std::ops::Deref;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sqlx::postgres::PgQueryResult;
+use sqlx::{PgConnection, Pool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::manager::AggregateManager;
+use crate::store::EventStore;
+use crate::Aggregate;
+
+/// A timeout whose deadline passed, as notified to the [`TimeoutHandler`]s.
+#[derive(sqlx::FromRow, Debug, Clone, PartialEq)]
+pub struct TimeoutExpired {
+    /// Uniquely identifies the scheduled timeout.
+    pub id: Uuid,
+    /// The aggregate instance the timeout has been scheduled for.
+    pub aggregate_id: Uuid,
+    /// The name of the timeout, unique per aggregate instance.
+    pub name: String,
+    /// When the timeout was due.
+    pub deadline: DateTime<Utc>,
+    /// Arbitrary data attached to the timeout while scheduling it.
+    pub payload: serde_json::Value,
+    /// The number of times the timeout already failed to be handled.
+    pub attempts: i32,
+}
+
+/// This trait is used to implement a [`TimeoutHandler`]. A timeout handler is intended to be an
+/// entity (e.g. a saga) reacting to the expiration of the timeouts scheduled through a
+/// [`TimeoutScheduler`], usually sending commands to an aggregate.
+#[async_trait]
+pub trait TimeoutHandler: Sync {
+    /// Handle an expired timeout. Returning an error leaves the timeout in place, so that it is
+    /// handled again once the retry backoff of the [`TimeoutScheduler`] elapsed.
+    async fn handle(&self, timeout: &TimeoutExpired) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
+
+    /// The name of the timeout handler. By default, this is the type name of the timeout handler,
+    /// but it can be overridden to provide a custom name. This name is used as part of tracing
+    /// spans, to identify the timeout handler being run.
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+}
+
+#[async_trait]
+impl<Q, T> TimeoutHandler for T
+where
+    Q: TimeoutHandler,
+    T: Deref<Target = Q> + Send + Sync,
+{
+    /// Deref call to [`TimeoutHandler::handle`].
+    async fn handle(&self, timeout: &TimeoutExpired) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.deref().handle(timeout).await
+    }
+
+    /// Deref call to [`TimeoutHandler::name`].
+    fn name(&self) -> &'static str {
+        self.deref().name()
+    }
+}
+
+/// Postgres backed scheduler of timeouts, stored in the `{name}_timeouts` table.
+///
+/// Timeouts are scheduled (and cancelled on progress) through the given executor, so that they can
+/// be part of the transaction of a [`crate::handler::TransactionalEventHandler`]. Expired timeouts
+/// are notified to a [`TimeoutHandler`] by [`TimeoutScheduler::run`], that can be run on many
+/// replicas at once, each timeout being handled by one of them. Timeouts the handler fails to handle
+/// are retried with an exponential backoff, without holding back the other ones.
+pub struct TimeoutScheduler {
+    pool: Pool<Postgres>,
+    table_name: String,
+    tick_interval: Duration,
+    batch_size: i64,
+    base_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl TimeoutScheduler {
+    /// Creates a new instance of a [`TimeoutScheduler`], ticking every second, handling at most 100
+    /// timeouts per tick and retrying failed timeouts after 1 second, doubling the delay at every
+    /// attempt up to 5 minutes.
+    pub fn new(pool: Pool<Postgres>, name: &str) -> Self {
+        Self {
+            pool,
+            table_name: format!("{}_timeouts", name),
+            tick_interval: Duration::from_secs(1),
+            batch_size: 100,
+            base_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(300),
+        }
+    }
+
+    /// Set the interval between two checks for expired timeouts.
+    pub fn with_tick_interval(mut self, tick_interval: Duration) -> Self {
+        self.tick_interval = tick_interval;
+        self
+    }
+
+    /// Set the maximum number of expired timeouts handled per tick.
+    pub fn with_batch_size(mut self, batch_size: i64) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Set the delay before retrying a timeout after its first failure, doubled at every further
+    /// attempt up to the given maximum.
+    pub fn with_retry_backoff(mut self, base_backoff: Duration, max_backoff: Duration) -> Self {
+        self.base_backoff = base_backoff;
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Returns the name of the timeouts table.
+    pub fn table_name(&self) -> &str {
+        &self.table_name
+    }
+
+    /// Atomically creates the timeouts table, if it doesn't exist yet.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if there's an error running the migrations.
+    pub async fn setup(&self) -> Result<(), sqlx::Error> {
+        let mut transaction: Transaction<Postgres> = self.pool.begin().await?;
+
+        let migrations: Vec<String> = vec![
+            format!(
+                include_str!("sql/postgres/migrations/create_timeouts_table.sql"),
+                self.table_name
+            ),
+            format!(
+                include_str!("sql/postgres/migrations/create_timeouts_next_attempt_at_index.sql"),
+                self.table_name
+            ),
+        ];
+
+        for migration in migrations {
+            let _: PgQueryResult = sqlx::query(migration.as_str()).execute(&mut *transaction).await?;
+        }
+
+        transaction.commit().await
+    }
+
+    /// Schedules the timeout with the given name for the given aggregate instance, replacing the
+    /// one with the same name if any. Returns the id of the scheduled timeout.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the insert fails.
+    pub async fn schedule(
+        &self,
+        executor: &mut PgConnection,
+        aggregate_id: Uuid,
+        name: &str,
+        deadline: DateTime<Utc>,
+        payload: serde_json::Value,
+    ) -> Result<Uuid, sqlx::Error> {
+        let id: Uuid = Uuid::new_v4();
+
+        let _ = sqlx::query(
+            format!(
+                include_str!("sql/postgres/statements/insert_timeout.sql"),
+                self.table_name
+            )
+            .as_str(),
+        )
+        .bind(id)
+        .bind(aggregate_id)
+        .bind(name)
+        .bind(deadline)
+        .bind(payload)
+        .execute(executor)
+        .await?;
+
+        Ok(id)
+    }
+
+    /// Cancels the timeout with the given name for the given aggregate instance, if any.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the delete fails.
+    pub async fn cancel(&self, executor: &mut PgConnection, aggregate_id: Uuid, name: &str) -> Result<(), sqlx::Error> {
+        let _ = sqlx::query(
+            format!(
+                include_str!("sql/postgres/statements/delete_timeout.sql"),
+                self.table_name
+            )
+            .as_str(),
+        )
+        .bind(aggregate_id)
+        .bind(name)
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Cancels all the timeouts of the given aggregate instance.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the delete fails.
+    pub async fn cancel_all(&self, executor: &mut PgConnection, aggregate_id: Uuid) -> Result<(), sqlx::Error> {
+        let _ = sqlx::query(
+            format!(
+                include_str!("sql/postgres/statements/delete_timeouts_by_aggregate_id.sql"),
+                self.table_name
+            )
+            .as_str(),
+        )
+        .bind(aggregate_id)
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Notifies the expired timeouts, at most batch size of them, to the given handler, deleting the
+    /// handled ones and postponing the failed ones. Returns the number of handled timeouts.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if any of the queries fails.
+    pub async fn tick(&self, handler: &impl TimeoutHandler) -> Result<usize, sqlx::Error> {
+        let mut transaction: Transaction<Postgres> = self.pool.begin().await?;
+
+        let timeouts: Vec<TimeoutExpired> = sqlx::query_as::<_, TimeoutExpired>(
+            format!(
+                include_str!("sql/postgres/statements/select_due_timeouts.sql"),
+                self.table_name
+            )
+            .as_str(),
+        )
+        .bind(self.batch_size)
+        .fetch_all(&mut *transaction)
+        .await?;
+
+        let mut handled: usize = 0;
+
+        for timeout in &timeouts {
+            let span = tracing::trace_span!(
+                "esrs.timeout_handler",
+                timeout_id = %timeout.id,
+                aggregate_id = %timeout.aggregate_id,
+                timeout_handler = handler.name()
+            );
+            let _e = span.enter();
+
+            if let Err(error) = handler.handle(timeout).await {
+                tracing::error!({
+                    timeout_id = %timeout.id,
+                    aggregate_id = %timeout.aggregate_id,
+                    timeout_handler = handler.name(),
+                    error = ?error,
+                }, "timeout handler failed to handle expired timeout");
+
+                let next_attempt_at: DateTime<Utc> = Utc::now()
+                    + chrono::Duration::from_std(self.backoff(timeout.attempts))
+                        .map_err(|error| sqlx::Error::Encode(Box::new(error)))?;
+
+                let _ = sqlx::query(
+                    format!(
+                        include_str!("sql/postgres/statements/update_timeout_failure.sql"),
+                        self.table_name
+                    )
+                    .as_str(),
+                )
+                .bind(timeout.id)
+                .bind(next_attempt_at)
+                .bind(error.to_string())
+                .execute(&mut *transaction)
+                .await?;
+
+                continue;
+            }
+
+            let _ = sqlx::query(
+                format!(
+                    include_str!("sql/postgres/statements/delete_timeout_by_id.sql"),
+                    self.table_name
+                )
+                .as_str(),
+            )
+            .bind(timeout.id)
+            .execute(&mut *transaction)
+            .await?;
+
+            handled += 1;
+        }
+
+        transaction.commit().await?;
+
+        Ok(handled)
+    }
+
+    /// Ticks forever at every tick interval, notifying the expired timeouts to the given handler.
+    /// Batches are handled back to back while there are expired timeouts left.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` as soon as a tick fails.
+    pub async fn run(&self, handler: &impl TimeoutHandler) -> Result<(), sqlx::Error> {
+        loop {
+            let handled: usize = self.tick(handler).await?;
+
+            if (handled as i64) < self.batch_size {
+                crate::runtime::sleep(self.tick_interval).await;
+            }
+        }
+    }
+
+    /// The delay before the next attempt of a timeout that already failed the given number of times.
+    fn backoff(&self, attempts: i32) -> Duration {
+        let factor: u32 = 2u32.saturating_pow(attempts.max(0) as u32);
+        self.base_backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+}
+
+/// Postgres backed scheduler of commands, dispatched through an [`AggregateManager`] once due, e.g.
+/// to cancel an order if it is not paid within 15 minutes.
+///
+/// Commands are stored serialized in the `{name}_timeouts` table of a [`TimeoutScheduler`], as
+/// named timeouts of the aggregate instance they target, and can be scheduled (and cancelled) in
+/// the transaction of a [`crate::handler::TransactionalEventHandler`]. Due commands are dispatched
+/// by [`CommandScheduler::run`] at-least-once: a command whose outcome fails to be recorded is
+/// dispatched again with an exponential backoff, while a command denied by the aggregate is dropped.
+pub struct CommandScheduler<E>
+where
+    E: EventStore,
+{
+    scheduler: TimeoutScheduler,
+    manager: AggregateManager<E>,
+}
+
+impl<E> CommandScheduler<E>
+where
+    E: EventStore + Send + Sync,
+    E::Error: Send + Sync + 'static,
+    <E::Aggregate as Aggregate>::Command: Serialize + DeserializeOwned + Send,
+    <E::Aggregate as Aggregate>::State: Default + Send + Sync,
+    <E::Aggregate as Aggregate>::Event: Send,
+    <E::Aggregate as Aggregate>::Error: std::fmt::Debug + Send,
+{
+    /// Creates a new instance of a [`CommandScheduler`] dispatching the commands through the given
+    /// manager, ticking every second and dispatching at most 100 commands per tick.
+    pub fn new(pool: Pool<Postgres>, name: &str, manager: AggregateManager<E>) -> Self {
+        Self {
+            scheduler: TimeoutScheduler::new(pool, name),
+            manager,
+        }
+    }
+
+    /// Set the interval between two checks for due commands.
+    pub fn with_tick_interval(self, tick_interval: Duration) -> Self {
+        Self {
+            scheduler: self.scheduler.with_tick_interval(tick_interval),
+            ..self
+        }
+    }
+
+    /// Set the maximum number of due commands dispatched per tick.
+    pub fn with_batch_size(self, batch_size: i64) -> Self {
+        Self {
+            scheduler: self.scheduler.with_batch_size(batch_size),
+            ..self
+        }
+    }
+
+    /// Set the delay before dispatching a command again after its first failure, doubled at every
+    /// further attempt up to the given maximum.
+    pub fn with_retry_backoff(self, base_backoff: Duration, max_backoff: Duration) -> Self {
+        Self {
+            scheduler: self.scheduler.with_retry_backoff(base_backoff, max_backoff),
+            ..self
+        }
+    }
+
+    /// Returns the name of the table of the scheduled commands.
+    pub fn table_name(&self) -> &str {
+        self.scheduler.table_name()
+    }
+
+    /// Atomically creates the table of the scheduled commands, if it doesn't exist yet.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if there's an error running the migrations.
+    pub async fn setup(&self) -> Result<(), sqlx::Error> {
+        self.scheduler.setup().await
+    }
+
+    /// Schedules the given command for the given aggregate instance at the given time, under the
+    /// given name, replacing the command with the same name if any. Returns the id of the scheduled
+    /// command.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the command can't be serialized, or the insert fails.
+    pub async fn schedule(
+        &self,
+        executor: &mut PgConnection,
+        aggregate_id: Uuid,
+        name: &str,
+        command: &<E::Aggregate as Aggregate>::Command,
+        at: DateTime<Utc>,
+    ) -> Result<Uuid, sqlx::Error> {
+        let payload: serde_json::Value =
+            serde_json::to_value(command).map_err(|error| sqlx::Error::Encode(Box::new(error)))?;
+
+        self.scheduler.schedule(executor, aggregate_id, name, at, payload).await
+    }
+
+    /// Cancels the command with the given name scheduled for the given aggregate instance, if any.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the delete fails.
+    pub async fn cancel(&self, executor: &mut PgConnection, aggregate_id: Uuid, name: &str) -> Result<(), sqlx::Error> {
+        self.scheduler.cancel(executor, aggregate_id, name).await
+    }
+
+    /// Dispatches the due commands, at most batch size of them, deleting the dispatched ones.
+    /// Returns the number of dispatched commands.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if any of the queries fails.
+    pub async fn tick(&self) -> Result<usize, sqlx::Error> {
+        self.scheduler.tick(&ScheduledCommandHandler(&self.manager)).await
+    }
+
+    /// Ticks forever at every tick interval, dispatching the due commands.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` as soon as a tick fails.
+    pub async fn run(&self) -> Result<(), sqlx::Error> {
+        self.scheduler.run(&ScheduledCommandHandler(&self.manager)).await
+    }
+}
+
+/// Dispatches the scheduled commands, stored as the payload of the timeouts, through the manager.
+struct ScheduledCommandHandler<'a, E>(&'a AggregateManager<E>)
+where
+    E: EventStore;
+
+#[async_trait]
+impl<E> TimeoutHandler for ScheduledCommandHandler<'_, E>
+where
+    E: EventStore + Send + Sync,
+    E::Error: Send + Sync + 'static,
+    <E::Aggregate as Aggregate>::Command: DeserializeOwned + Send,
+    <E::Aggregate as Aggregate>::State: Default + Send + Sync,
+    <E::Aggregate as Aggregate>::Event: Send,
+    <E::Aggregate as Aggregate>::Error: std::fmt::Debug + Send,
+{
+    async fn handle(&self, timeout: &TimeoutExpired) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let command: <E::Aggregate as Aggregate>::Command = serde_json::from_value(timeout.payload.clone())?;
+
+        let aggregate_state = self.0.lock_and_load(timeout.aggregate_id).await?.unwrap_or_default();
+
+        if let Err(domain_error) = self.0.handle_command(aggregate_state, command).await? {
+            tracing::warn!({
+                aggregate_id = %timeout.aggregate_id,
+                command = %timeout.name,
+                error = ?domain_error,
+            }, "scheduled command denied");
+        }
+
+        Ok(())
+    }
+}
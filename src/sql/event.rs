@@ -1,12 +1,14 @@
 use std::convert::TryInto;
 
 use chrono::{DateTime, Utc};
+use serde_json::value::RawValue;
 use serde_json::Value;
+use sqlx::types::Json;
 use uuid::Uuid;
 
 use crate::store::postgres::persistable::Persistable;
-use crate::store::postgres::Schema;
-use crate::store::StoreEvent;
+use crate::store::postgres::{RawStoreEvent, Schema};
+use crate::store::{Metadata, StoreEvent};
 use crate::types::SequenceNumber;
 
 /// Event representation on the event store
@@ -18,6 +20,8 @@ pub struct DbEvent {
     pub occurred_on: DateTime<Utc>,
     pub sequence_number: SequenceNumber,
     pub version: Option<i32>,
+    #[sqlx(default)]
+    pub metadata: Option<Json<Metadata>>,
 }
 
 impl DbEvent {
@@ -32,32 +36,68 @@ impl DbEvent {
 
         Ok(match payload {
             None => None,
-            Some(payload) => Some(StoreEvent {
-                id: self.id,
-                aggregate_id: self.aggregate_id,
-                payload,
-                occurred_on: self.occurred_on,
-                sequence_number: self.sequence_number,
-                version: self.version,
-            }),
+            Some(payload) => Some(
+                StoreEvent::new(
+                    self.id,
+                    self.aggregate_id,
+                    payload,
+                    self.occurred_on,
+                    self.sequence_number,
+                    self.version,
+                )
+                .with_metadata(self.metadata.map(|metadata| metadata.0).unwrap_or_default()),
+            ),
         })
     }
 }
 
+/// Event representation on the event store, keeping the payload serialized
+#[derive(sqlx::FromRow, Debug)]
+pub struct DbRawEvent {
+    pub id: Uuid,
+    pub aggregate_id: Uuid,
+    pub payload: Json<Box<RawValue>>,
+    pub occurred_on: DateTime<Utc>,
+    pub sequence_number: SequenceNumber,
+    pub version: Option<i32>,
+    #[sqlx(default)]
+    pub metadata: Option<Json<Metadata>>,
+}
+
+impl DbRawEvent {
+    pub fn into_raw_store_event<E, S>(self) -> RawStoreEvent<E, S>
+    where
+        S: Schema<E>,
+    {
+        RawStoreEvent::new(
+            self.id,
+            self.aggregate_id,
+            self.payload.0,
+            self.occurred_on,
+            self.sequence_number,
+            self.version,
+            self.metadata.map(|metadata| metadata.0).unwrap_or_default(),
+        )
+    }
+}
+
 impl<E: Persistable> TryInto<StoreEvent<E>> for DbEvent {
     type Error = serde_json::Error;
 
     fn try_into(self) -> Result<StoreEvent<E>, Self::Error> {
-        Ok(StoreEvent {
-            id: self.id,
-            aggregate_id: self.aggregate_id,
-            #[cfg(feature = "upcasting")]
-            payload: E::upcast(self.payload, self.version)?,
-            #[cfg(not(feature = "upcasting"))]
-            payload: serde_json::from_value::<E>(self.payload)?,
-            occurred_on: self.occurred_on,
-            sequence_number: self.sequence_number,
-            version: self.version,
-        })
+        #[cfg(feature = "upcasting")]
+        let payload = E::upcast(self.payload, self.version)?;
+        #[cfg(not(feature = "upcasting"))]
+        let payload = serde_json::from_value::<E>(self.payload)?;
+
+        Ok(StoreEvent::new(
+            self.id,
+            self.aggregate_id,
+            payload,
+            self.occurred_on,
+            self.sequence_number,
+            self.version,
+        )
+        .with_metadata(self.metadata.map(|metadata| metadata.0).unwrap_or_default()))
     }
 }
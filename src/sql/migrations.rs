@@ -2,7 +2,9 @@ use async_trait::async_trait;
 use sqlx::postgres::PgQueryResult;
 use sqlx::{Database, Error, Pool, Postgres, Transaction};
 
-use crate::{statement, Aggregate};
+use crate::sql::unqualified;
+use crate::store::postgres::Column;
+use crate::Aggregate;
 
 /// Trait used to handle current code migrations.
 #[async_trait]
@@ -17,20 +19,179 @@ where
 
 pub struct Migrations;
 
+/// A step of the setup of an event store table, made of statements to be run atomically.
+///
+/// The statements are idempotent, so that the steps can be handed over to an external migration
+/// pipeline, as done by [`crate::store::postgres::PgStoreBuilder::migration_steps`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct MigrationStep {
+    /// A short description of the step, suitable to name a migration file.
+    pub description: String,
+    /// The statements to be run, in order.
+    pub statements: Vec<String>,
+}
+
+impl MigrationStep {
+    fn new(description: impl Into<String>, statements: Vec<String>) -> Self {
+        Self {
+            description: description.into(),
+            statements,
+        }
+    }
+
+    /// Returns the statements as a single SQL script.
+    pub fn sql(&self) -> String {
+        self.statements
+            .iter()
+            .map(|statement| format!("{};\n", statement))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    /// Atomically runs the statements.
+    pub async fn run(&self, pool: &Pool<Postgres>) -> Result<(), Error> {
+        let mut transaction: Transaction<Postgres> = pool.begin().await?;
+
+        for statement in &self.statements {
+            let _: PgQueryResult = sqlx::query(statement.as_str()).execute(&mut *transaction).await?;
+        }
+
+        transaction.commit().await
+    }
+}
+
 #[async_trait]
 impl MigrationsHandler<Postgres> for Migrations {
     async fn run<A>(pool: &Pool<Postgres>) -> Result<(), Error>
     where
         A: Aggregate,
     {
+        Migrations::create_table(format!("{}_events", A::NAME).as_str())
+            .run(pool)
+            .await
+    }
+}
+
+impl Migrations {
+    /// Atomically renames the event store table `old_table_name` to `new_table_name`, along with its
+    /// indexes and its locks, deferred, outbox, compactions, closed and archive tables. Nothing is
+    /// done if the old table doesn't exist or the new one already exists. Both tables must live in
+    /// the same schema.
+    pub async fn run_rename(
+        pool: &Pool<Postgres>,
+        old_table_name: &str,
+        new_table_name: &str,
+        custom_columns: &[Column],
+    ) -> Result<(), Error> {
         let mut transaction: Transaction<Postgres> = pool.begin().await?;
 
-        let migrations: Vec<String> = vec![
-            statement!("postgres/migrations/01_create_table.sql", A),
-            statement!("postgres/migrations/02_create_index.sql", A),
-            statement!("postgres/migrations/03_create_unique_constraint.sql", A),
-            statement!("postgres/migrations/04_add_version.sql", A),
+        let (old_exists, new_exists): (bool, bool) =
+            sqlx::query_as("SELECT to_regclass($1) IS NOT NULL, to_regclass($2) IS NOT NULL")
+                .bind(old_table_name)
+                .bind(new_table_name)
+                .fetch_one(&mut *transaction)
+                .await?;
+
+        if !old_exists || new_exists {
+            return transaction.commit().await;
+        }
+
+        let mut suffixes: Vec<String> = vec![
+            "pkey".to_string(),
+            "aggregate_id".to_string(),
+            "aggregate_id_sequence_number".to_string(),
+            "payload".to_string(),
+            "search_vector".to_string(),
+            "idempotency_token".to_string(),
         ];
+        suffixes.extend(custom_columns.iter().map(|column| column.name().to_string()));
+
+        // Renamed tables and indexes stay in their schema, so their new names are unqualified.
+        let new_name: &str = unqualified(new_table_name);
+
+        let mut migrations: Vec<String> = vec![
+            format!(
+                include_str!("postgres/migrations/rename_table.sql"),
+                old_table_name, new_name
+            ),
+            format!(
+                include_str!("postgres/migrations/rename_table.sql"),
+                format!("{}_locks", old_table_name),
+                format!("{}_locks", new_name)
+            ),
+            format!(
+                include_str!("postgres/migrations/rename_index.sql"),
+                format!("{}_locks_pkey", old_table_name),
+                format!("{}_locks_pkey", new_name)
+            ),
+            format!(
+                include_str!("postgres/migrations/rename_table.sql"),
+                format!("{}_compactions", old_table_name),
+                format!("{}_compactions", new_name)
+            ),
+            format!(
+                include_str!("postgres/migrations/rename_index.sql"),
+                format!("{}_compactions_pkey", old_table_name),
+                format!("{}_compactions_pkey", new_name)
+            ),
+            format!(
+                include_str!("postgres/migrations/rename_table.sql"),
+                format!("{}_deferred", old_table_name),
+                format!("{}_deferred", new_name)
+            ),
+            format!(
+                include_str!("postgres/migrations/rename_index.sql"),
+                format!("{}_deferred_pkey", old_table_name),
+                format!("{}_deferred_pkey", new_name)
+            ),
+            format!(
+                include_str!("postgres/migrations/rename_index.sql"),
+                format!("{}_deferred_visible_at", old_table_name),
+                format!("{}_deferred_visible_at", new_name)
+            ),
+            format!(
+                include_str!("postgres/migrations/rename_table.sql"),
+                format!("{}_outbox", old_table_name),
+                format!("{}_outbox", new_name)
+            ),
+            format!(
+                include_str!("postgres/migrations/rename_index.sql"),
+                format!("{}_outbox_pkey", old_table_name),
+                format!("{}_outbox_pkey", new_name)
+            ),
+            format!(
+                include_str!("postgres/migrations/rename_index.sql"),
+                format!("{}_outbox_next_attempt_at", old_table_name),
+                format!("{}_outbox_next_attempt_at", new_name)
+            ),
+            format!(
+                include_str!("postgres/migrations/rename_table.sql"),
+                format!("{}_closed", old_table_name),
+                format!("{}_closed", new_name)
+            ),
+            format!(
+                include_str!("postgres/migrations/rename_index.sql"),
+                format!("{}_closed_pkey", old_table_name),
+                format!("{}_closed_pkey", new_name)
+            ),
+            format!(
+                include_str!("postgres/migrations/rename_table.sql"),
+                format!("{}_archive", old_table_name),
+                format!("{}_archive", new_name)
+            ),
+            format!(
+                include_str!("postgres/migrations/rename_index.sql"),
+                format!("{}_archive_aggregate_id_sequence_number", old_table_name),
+                format!("{}_archive_aggregate_id_sequence_number", new_name)
+            ),
+        ];
+        migrations.extend(suffixes.iter().map(|suffix| {
+            format!(
+                include_str!("postgres/migrations/rename_index.sql"),
+                format!("{}_{}", old_table_name, suffix),
+                format!("{}_{}", new_name, suffix)
+            )
+        }));
 
         for migration in migrations {
             let _: PgQueryResult = sqlx::query(migration.as_str()).execute(&mut *transaction).await?;
@@ -38,6 +199,334 @@ impl MigrationsHandler<Postgres> for Migrations {
 
         transaction.commit().await
     }
+
+    /// Creates the event store table with the given, possibly schema qualified, name, with its
+    /// indexes. The schema, if any, must exist.
+    pub fn create_table(table_name: &str) -> MigrationStep {
+        let name: &str = unqualified(table_name);
+
+        MigrationStep::new(
+            "create_table",
+            vec![
+                format!(
+                    include_str!("postgres/migrations/01_create_table.sql"),
+                    table_name, name
+                ),
+                format!(
+                    include_str!("postgres/migrations/02_create_index.sql"),
+                    table_name, name
+                ),
+                format!(
+                    include_str!("postgres/migrations/03_create_unique_constraint.sql"),
+                    table_name, name
+                ),
+                format!(include_str!("postgres/migrations/04_add_version.sql"), table_name),
+                format!(include_str!("postgres/migrations/05_add_metadata.sql"), table_name),
+            ],
+        )
+    }
+
+    /// Creates the table shared by many aggregates, holding their events along with their
+    /// `aggregate_type`, with its indexes. The event store table of the aggregate is an updatable
+    /// view over the shared table, filtered by its [`Aggregate::NAME`], so that every statement of
+    /// the store works the same way on both layouts.
+    ///
+    /// The view is named `table_name`, as the event store table of the aggregate would be.
+    pub fn shared_table<A>(table_name: &str, shared_table_name: &str) -> MigrationStep
+    where
+        A: Aggregate,
+    {
+        let shared_name: &str = unqualified(shared_table_name);
+
+        MigrationStep::new(
+            "create_shared_table",
+            vec![
+                format!(
+                    include_str!("postgres/migrations/create_shared_table.sql"),
+                    shared_table_name, shared_name
+                ),
+                format!(
+                    include_str!("postgres/migrations/05_add_metadata.sql"),
+                    shared_table_name
+                ),
+                format!(
+                    include_str!("postgres/migrations/02_create_index.sql"),
+                    shared_table_name, shared_name
+                ),
+                format!(
+                    include_str!("postgres/migrations/03_create_unique_constraint.sql"),
+                    shared_table_name, shared_name
+                ),
+                format!(
+                    include_str!("postgres/migrations/create_shared_table_aggregate_type_index.sql"),
+                    shared_table_name, shared_name
+                ),
+                format!(
+                    include_str!("postgres/migrations/create_shared_table_view.sql"),
+                    table_name,
+                    shared_table_name,
+                    A::NAME
+                ),
+                format!(
+                    include_str!("postgres/migrations/set_shared_table_view_default.sql"),
+                    table_name,
+                    A::NAME
+                ),
+            ],
+        )
+    }
+
+    /// Creates the table holding a row for each aggregate instance, used by
+    /// [`crate::store::postgres::LockStrategy::RowLevel`].
+    pub fn locks_table(table_name: &str) -> MigrationStep {
+        let name: &str = unqualified(table_name);
+
+        MigrationStep::new(
+            "create_locks_table",
+            vec![format!(
+                include_str!("postgres/migrations/create_locks_table.sql"),
+                table_name, name
+            )],
+        )
+    }
+
+    /// See [`Migrations::locks_table`].
+    pub async fn run_locks_table(pool: &Pool<Postgres>, table_name: &str) -> Result<(), Error> {
+        Migrations::locks_table(table_name).run(pool).await
+    }
+
+    /// Creates the table holding the events not visible yet, used by
+    /// [`crate::store::postgres::PgStoreBuilder::with_visibility`].
+    pub fn deferred_table(table_name: &str) -> MigrationStep {
+        let name: &str = unqualified(table_name);
+
+        MigrationStep::new(
+            "create_deferred_table",
+            vec![
+                format!(
+                    include_str!("postgres/migrations/create_deferred_table.sql"),
+                    table_name, name
+                ),
+                format!(
+                    include_str!("postgres/migrations/create_deferred_index.sql"),
+                    table_name, name
+                ),
+            ],
+        )
+    }
+
+    /// See [`Migrations::deferred_table`].
+    pub async fn run_deferred_table(pool: &Pool<Postgres>, table_name: &str) -> Result<(), Error> {
+        Migrations::deferred_table(table_name).run(pool).await
+    }
+
+    /// Creates the table holding the events not published to the event buses yet, used by
+    /// [`crate::store::postgres::PgStoreBuilder::with_outbox`].
+    pub fn outbox_table(table_name: &str) -> MigrationStep {
+        let name: &str = unqualified(table_name);
+
+        MigrationStep::new(
+            "create_outbox_table",
+            vec![
+                format!(
+                    include_str!("postgres/migrations/create_outbox_table.sql"),
+                    table_name, name
+                ),
+                format!(
+                    include_str!("postgres/migrations/create_outbox_index.sql"),
+                    table_name, name
+                ),
+            ],
+        )
+    }
+
+    /// See [`Migrations::outbox_table`].
+    pub async fn run_outbox_table(pool: &Pool<Postgres>, table_name: &str) -> Result<(), Error> {
+        Migrations::outbox_table(table_name).run(pool).await
+    }
+
+    /// Adds the `signature` and `signature_key_id` columns to the event store table, used by
+    /// [`crate::store::postgres::PgStoreBuilder::with_key_provider`].
+    #[cfg(feature = "integrity")]
+    pub fn signature_columns(table_name: &str) -> MigrationStep {
+        MigrationStep::new(
+            "add_signature_columns",
+            vec![format!(
+                include_str!("postgres/migrations/add_signature_columns.sql"),
+                table_name
+            )],
+        )
+    }
+
+    /// See [`Migrations::signature_columns`].
+    #[cfg(feature = "integrity")]
+    pub async fn run_signature_columns(pool: &Pool<Postgres>, table_name: &str) -> Result<(), Error> {
+        Migrations::signature_columns(table_name).run(pool).await
+    }
+
+    /// Adds the `previous_hash` and `hash` columns to the event store table, used by
+    /// [`crate::store::postgres::PgStoreBuilder::with_hash_chain`].
+    #[cfg(feature = "integrity")]
+    pub fn hash_chain_columns(table_name: &str) -> MigrationStep {
+        MigrationStep::new(
+            "add_hash_chain_columns",
+            vec![format!(
+                include_str!("postgres/migrations/add_hash_chain_columns.sql"),
+                table_name
+            )],
+        )
+    }
+
+    /// See [`Migrations::hash_chain_columns`].
+    #[cfg(feature = "integrity")]
+    pub async fn run_hash_chain_columns(pool: &Pool<Postgres>, table_name: &str) -> Result<(), Error> {
+        Migrations::hash_chain_columns(table_name).run(pool).await
+    }
+
+    /// Adds the `deleted_at` column to the event store table, used by
+    /// [`crate::store::postgres::DeletionStrategy::Soft`].
+    pub fn deleted_at(table_name: &str) -> MigrationStep {
+        MigrationStep::new(
+            "add_deleted_at",
+            vec![format!(
+                include_str!("postgres/migrations/add_deleted_at.sql"),
+                table_name
+            )],
+        )
+    }
+
+    /// See [`Migrations::deleted_at`].
+    pub async fn run_deleted_at(pool: &Pool<Postgres>, table_name: &str) -> Result<(), Error> {
+        Migrations::deleted_at(table_name).run(pool).await
+    }
+
+    /// Creates the tables holding the closed aggregate instances and the archived events, used by
+    /// [`crate::store::postgres::PgStoreBuilder::with_archival`]. The archive table mirrors the
+    /// columns of the event store table at creation time.
+    pub fn archive_tables(table_name: &str) -> MigrationStep {
+        let name: &str = unqualified(table_name);
+
+        MigrationStep::new(
+            "create_archive_tables",
+            vec![
+                format!(
+                    include_str!("postgres/migrations/create_closed_table.sql"),
+                    table_name, name
+                ),
+                format!(include_str!("postgres/migrations/create_archive_table.sql"), table_name),
+                // Archive tables created before the metadata column was added lack it.
+                format!(
+                    include_str!("postgres/migrations/05_add_metadata.sql"),
+                    format!("{}_archive", table_name)
+                ),
+                format!(
+                    include_str!("postgres/migrations/create_archive_index.sql"),
+                    table_name, name
+                ),
+            ],
+        )
+    }
+
+    /// See [`Migrations::archive_tables`].
+    pub async fn run_archive_tables(pool: &Pool<Postgres>, table_name: &str) -> Result<(), Error> {
+        Migrations::archive_tables(table_name).run(pool).await
+    }
+
+    /// Adds the `idempotency_token` column, and its index, to the event store table, used by
+    /// [`crate::store::postgres::PgStoreBuilder::with_idempotency_tokens`].
+    pub fn idempotency_token(table_name: &str) -> MigrationStep {
+        MigrationStep::new(
+            "add_idempotency_token",
+            vec![
+                format!(
+                    include_str!("postgres/migrations/add_idempotency_token.sql"),
+                    table_name
+                ),
+                format!(
+                    include_str!("postgres/migrations/create_idempotency_token_index.sql"),
+                    table_name,
+                    unqualified(table_name)
+                ),
+            ],
+        )
+    }
+
+    /// See [`Migrations::idempotency_token`].
+    pub async fn run_idempotency_token(pool: &Pool<Postgres>, table_name: &str) -> Result<(), Error> {
+        Migrations::idempotency_token(table_name).run(pool).await
+    }
+
+    /// Creates a GIN index over the payloads of the event store table, supporting the containment
+    /// (`@>`) queries.
+    pub fn payload_index(table_name: &str) -> MigrationStep {
+        MigrationStep::new(
+            "create_payload_index",
+            vec![format!(
+                include_str!("postgres/migrations/create_payload_index.sql"),
+                table_name,
+                unqualified(table_name)
+            )],
+        )
+    }
+
+    /// See [`Migrations::payload_index`].
+    pub async fn run_payload_index(pool: &Pool<Postgres>, table_name: &str) -> Result<(), Error> {
+        Migrations::payload_index(table_name).run(pool).await
+    }
+
+    /// Adds the `search_vector` column, generated by the given expression, and its GIN index to the
+    /// event store table. Note that adding the column rewrites the whole table.
+    pub fn search_vector(table_name: &str, expression: &str) -> MigrationStep {
+        MigrationStep::new(
+            "add_search_vector",
+            vec![
+                format!(
+                    include_str!("postgres/migrations/add_search_vector.sql"),
+                    table_name, expression
+                ),
+                format!(
+                    include_str!("postgres/migrations/create_search_vector_index.sql"),
+                    table_name,
+                    unqualified(table_name)
+                ),
+            ],
+        )
+    }
+
+    /// See [`Migrations::search_vector`].
+    pub async fn run_search_vector(pool: &Pool<Postgres>, table_name: &str, expression: &str) -> Result<(), Error> {
+        Migrations::search_vector(table_name, expression).run(pool).await
+    }
+
+    /// Adds the given additional columns, and their indexes, to the event store table.
+    pub fn custom_columns(table_name: &str, columns: &[Column]) -> MigrationStep {
+        let mut statements: Vec<String> = vec![];
+
+        for column in columns {
+            statements.push(format!(
+                include_str!("postgres/migrations/add_custom_column.sql"),
+                table_name,
+                column.name(),
+                column.column_type().as_sql()
+            ));
+
+            if column.is_indexed() {
+                statements.push(format!(
+                    include_str!("postgres/migrations/create_custom_column_index.sql"),
+                    table_name,
+                    column.name(),
+                    unqualified(table_name)
+                ));
+            }
+        }
+
+        MigrationStep::new("add_custom_columns", statements)
+    }
+
+    /// See [`Migrations::custom_columns`].
+    pub async fn run_custom_columns(pool: &Pool<Postgres>, table_name: &str, columns: &[Column]) -> Result<(), Error> {
+        Migrations::custom_columns(table_name, columns).run(pool).await
+    }
 }
 
 #[cfg(test)]
@@ -2,9 +2,13 @@ pub mod event;
 pub mod migrations;
 pub mod statements;
 
-#[macro_export]
-macro_rules! statement {
-    ($file:expr, $ty:ty $(,)?) => {{
-        format!(include_str!($file), format!("{}_events", <$ty>::NAME))
-    }};
+/// Returns the name of the given, possibly schema qualified, table without its schema. Indexes and
+/// constraints are named after it, since their names can't be schema qualified.
+pub(crate) fn unqualified(table_name: &str) -> &str {
+    table_name.rsplit_once('.').map_or(table_name, |(_, name)| name)
+}
+
+/// Returns the schema of the given table, if qualified, `None` if it lives in the current schema.
+pub(crate) fn schema_of(table_name: &str) -> Option<&str> {
+    table_name.rsplit_once('.').map(|(schema, _)| schema)
 }
@@ -5,5 +5,5 @@ CREATE TABLE IF NOT EXISTS {0}
     payload jsonb NOT NULL,
     occurred_on TIMESTAMPTZ NOT NULL DEFAULT current_timestamp,
     sequence_number INT NOT NULL DEFAULT 1,
-    CONSTRAINT {0}_pkey PRIMARY KEY (id)
+    CONSTRAINT {1}_pkey PRIMARY KEY (id)
 )
\ No newline at end of file
@@ -1 +1 @@
-CREATE INDEX IF NOT EXISTS {0}_aggregate_id ON {0}(aggregate_id)
\ No newline at end of file
+CREATE INDEX IF NOT EXISTS {1}_aggregate_id ON {0}(aggregate_id)
\ No newline at end of file
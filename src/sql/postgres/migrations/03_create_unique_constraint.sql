@@ -1 +1 @@
-CREATE UNIQUE INDEX IF NOT EXISTS {0}_aggregate_id_sequence_number ON {0}(aggregate_id, sequence_number)
\ No newline at end of file
+CREATE UNIQUE INDEX IF NOT EXISTS {1}_aggregate_id_sequence_number ON {0}(aggregate_id, sequence_number)
\ No newline at end of file
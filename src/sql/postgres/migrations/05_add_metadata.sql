@@ -0,0 +1 @@
+ALTER TABLE {0} ADD COLUMN IF NOT EXISTS metadata jsonb
\ No newline at end of file
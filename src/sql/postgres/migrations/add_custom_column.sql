@@ -0,0 +1 @@
+ALTER TABLE {0} ADD COLUMN IF NOT EXISTS {1} {2};
\ No newline at end of file
@@ -0,0 +1 @@
+ALTER TABLE {0} ADD COLUMN IF NOT EXISTS previous_hash BYTEA, ADD COLUMN IF NOT EXISTS hash BYTEA
\ No newline at end of file
@@ -0,0 +1 @@
+ALTER TABLE {0} ADD COLUMN IF NOT EXISTS idempotency_token UUID
\ No newline at end of file
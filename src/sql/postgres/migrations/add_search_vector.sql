@@ -0,0 +1 @@
+ALTER TABLE {0} ADD COLUMN IF NOT EXISTS search_vector tsvector GENERATED ALWAYS AS ({1}) STORED
\ No newline at end of file
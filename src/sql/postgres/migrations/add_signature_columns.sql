@@ -0,0 +1 @@
+ALTER TABLE {0} ADD COLUMN IF NOT EXISTS signature BYTEA, ADD COLUMN IF NOT EXISTS signature_key_id TEXT
\ No newline at end of file
@@ -0,0 +1 @@
+CREATE INDEX IF NOT EXISTS {1}_archive_aggregate_id_sequence_number ON {0}_archive(aggregate_id, sequence_number)
\ No newline at end of file
@@ -0,0 +1 @@
+CREATE TABLE IF NOT EXISTS {0}_archive (LIKE {0})
\ No newline at end of file
@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS {0}_closed
+(
+    aggregate_id uuid NOT NULL,
+    closed_on TIMESTAMPTZ NOT NULL DEFAULT current_timestamp,
+    archived_on TIMESTAMPTZ,
+    CONSTRAINT {1}_closed_pkey PRIMARY KEY (aggregate_id)
+)
\ No newline at end of file
@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS {0}_compactions
+(
+    aggregate_id uuid NOT NULL,
+    event_type TEXT NOT NULL,
+    sequence_number INT NOT NULL,
+    removed BIGINT NOT NULL,
+    compacted_on TIMESTAMPTZ NOT NULL DEFAULT current_timestamp,
+    CONSTRAINT {1}_compactions_pkey PRIMARY KEY (aggregate_id, event_type)
+)
\ No newline at end of file
@@ -0,0 +1 @@
+CREATE INDEX IF NOT EXISTS {2}_{1} ON {0}({1})
\ No newline at end of file
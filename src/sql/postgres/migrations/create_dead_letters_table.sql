@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS {0}
+(
+    event_id uuid NOT NULL,
+    aggregate_id uuid NOT NULL,
+    event jsonb NOT NULL,
+    error TEXT NOT NULL,
+    dead_lettered_on TIMESTAMPTZ NOT NULL DEFAULT current_timestamp,
+    CONSTRAINT {1}_pkey PRIMARY KEY (event_id)
+)
\ No newline at end of file
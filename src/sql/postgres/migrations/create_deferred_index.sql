@@ -0,0 +1 @@
+CREATE INDEX IF NOT EXISTS {1}_deferred_visible_at ON {0}_deferred(visible_at) WHERE released_at IS NULL
\ No newline at end of file
@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS {0}_deferred
+(
+    event_id uuid NOT NULL REFERENCES {0}(id) ON DELETE CASCADE,
+    visible_at TIMESTAMPTZ NOT NULL,
+    released_at TIMESTAMPTZ,
+    CONSTRAINT {1}_deferred_pkey PRIMARY KEY (event_id)
+)
\ No newline at end of file
@@ -0,0 +1 @@
+CREATE INDEX IF NOT EXISTS {1}_idempotency_token ON {0}(aggregate_id, idempotency_token) WHERE idempotency_token IS NOT NULL
\ No newline at end of file
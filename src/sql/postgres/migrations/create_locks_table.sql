@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS {0}_locks
+(
+    aggregate_id uuid NOT NULL,
+    CONSTRAINT {1}_locks_pkey PRIMARY KEY (aggregate_id)
+)
\ No newline at end of file
@@ -0,0 +1 @@
+CREATE INDEX IF NOT EXISTS {1}_outbox_next_attempt_at ON {0}_outbox(next_attempt_at)
\ No newline at end of file
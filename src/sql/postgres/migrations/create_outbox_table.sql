@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS {0}_outbox
+(
+    event_id uuid NOT NULL REFERENCES {0}(id) ON DELETE CASCADE,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT current_timestamp,
+    last_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT clock_timestamp(),
+    CONSTRAINT {1}_outbox_pkey PRIMARY KEY (event_id)
+)
\ No newline at end of file
@@ -0,0 +1 @@
+CREATE INDEX IF NOT EXISTS {1}_payload ON {0} USING GIN (payload jsonb_path_ops)
\ No newline at end of file
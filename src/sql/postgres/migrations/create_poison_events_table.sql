@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS {0}
+(
+    event_id uuid NOT NULL,
+    aggregate_id uuid NOT NULL,
+    error TEXT NOT NULL,
+    recorded_on TIMESTAMPTZ NOT NULL DEFAULT current_timestamp
+)
\ No newline at end of file
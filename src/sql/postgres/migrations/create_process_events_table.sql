@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS {0}
+(
+    event_id uuid NOT NULL,
+    process_id uuid NOT NULL,
+    processed_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    CONSTRAINT {0}_pkey PRIMARY KEY (event_id)
+)
\ No newline at end of file
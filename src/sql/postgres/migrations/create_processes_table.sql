@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS {0}
+(
+    process_id uuid NOT NULL,
+    state jsonb NOT NULL,
+    completed_at TIMESTAMPTZ,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    CONSTRAINT {0}_pkey PRIMARY KEY (process_id)
+)
\ No newline at end of file
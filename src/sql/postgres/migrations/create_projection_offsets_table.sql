@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS {}
+(
+    name TEXT NOT NULL,
+    table_name TEXT NOT NULL,
+    last_event_id uuid,
+    last_occurred_on TIMESTAMPTZ,
+    last_aggregate_id uuid,
+    last_sequence_number INTEGER,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT current_timestamp,
+    CONSTRAINT projection_offsets_pkey PRIMARY KEY (name, table_name)
+)
\ No newline at end of file
@@ -0,0 +1 @@
+CREATE INDEX IF NOT EXISTS {1}_search_vector ON {0} USING GIN (search_vector)
\ No newline at end of file
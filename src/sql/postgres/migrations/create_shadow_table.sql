@@ -0,0 +1 @@
+CREATE TABLE {0} (LIKE {1} INCLUDING ALL)
\ No newline at end of file
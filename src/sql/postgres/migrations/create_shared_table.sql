@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS {0}
+(
+    id uuid NOT NULL,
+    aggregate_type TEXT NOT NULL,
+    aggregate_id uuid NOT NULL,
+    payload jsonb NOT NULL,
+    occurred_on TIMESTAMPTZ NOT NULL DEFAULT current_timestamp,
+    sequence_number INT NOT NULL DEFAULT 1,
+    version INTEGER,
+    CONSTRAINT {1}_pkey PRIMARY KEY (id)
+)
\ No newline at end of file
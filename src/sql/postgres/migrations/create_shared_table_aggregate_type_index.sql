@@ -0,0 +1 @@
+CREATE INDEX IF NOT EXISTS {1}_aggregate_type ON {0}(aggregate_type)
\ No newline at end of file
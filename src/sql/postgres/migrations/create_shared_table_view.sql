@@ -0,0 +1,3 @@
+CREATE OR REPLACE VIEW {0} AS
+SELECT id, aggregate_type, aggregate_id, payload, occurred_on, sequence_number, version, metadata FROM {1}
+WHERE aggregate_type = '{2}' WITH CHECK OPTION
\ No newline at end of file
@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS {0}
+(
+    aggregate_id uuid NOT NULL,
+    sequence_number INT NOT NULL,
+    version INTEGER,
+    state jsonb NOT NULL,
+    taken_on TIMESTAMPTZ NOT NULL,
+    CONSTRAINT {0}_pkey PRIMARY KEY (aggregate_id)
+)
\ No newline at end of file
@@ -0,0 +1 @@
+CREATE INDEX IF NOT EXISTS {0}_next_attempt_at ON {0}(next_attempt_at)
\ No newline at end of file
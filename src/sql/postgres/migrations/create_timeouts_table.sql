@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS {0}
+(
+    id uuid NOT NULL,
+    aggregate_id uuid NOT NULL,
+    name TEXT NOT NULL,
+    deadline TIMESTAMPTZ NOT NULL,
+    payload jsonb NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMPTZ NOT NULL,
+    last_error TEXT,
+    CONSTRAINT {0}_pkey PRIMARY KEY (id),
+    CONSTRAINT {0}_aggregate_id_name UNIQUE (aggregate_id, name)
+)
\ No newline at end of file
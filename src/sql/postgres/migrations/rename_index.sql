@@ -0,0 +1 @@
+ALTER INDEX IF EXISTS {0} RENAME TO {1}
\ No newline at end of file
@@ -0,0 +1 @@
+ALTER TABLE IF EXISTS {0} RENAME TO {1}
\ No newline at end of file
@@ -0,0 +1 @@
+ALTER VIEW {0} ALTER COLUMN aggregate_type SET DEFAULT '{1}'
\ No newline at end of file
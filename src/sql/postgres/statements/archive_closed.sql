@@ -0,0 +1,8 @@
+WITH closed AS (
+    SELECT aggregate_id FROM {0}_closed WHERE archived_on IS NULL ORDER BY closed_on LIMIT $1 FOR UPDATE SKIP LOCKED
+), moved AS (
+    DELETE FROM {0} WHERE aggregate_id IN (SELECT aggregate_id FROM closed) RETURNING *
+), archived AS (
+    INSERT INTO {0}_archive SELECT * FROM moved
+)
+UPDATE {0}_closed SET archived_on = now() WHERE aggregate_id IN (SELECT aggregate_id FROM closed)
\ No newline at end of file
@@ -0,0 +1,20 @@
+WITH latest AS (
+    SELECT aggregate_id, event_type, MAX(sequence_number) AS sequence_number
+    FROM (SELECT aggregate_id, sequence_number, {1} AS event_type FROM {0}) AS typed
+    WHERE event_type = ANY($1)
+    GROUP BY aggregate_id, event_type
+), superseded AS (
+    DELETE FROM {0} AS events USING latest
+    WHERE events.aggregate_id = latest.aggregate_id
+        AND {1} = latest.event_type
+        AND events.sequence_number < latest.sequence_number
+    RETURNING latest.aggregate_id, latest.event_type, latest.sequence_number
+), markers AS (
+    INSERT INTO {0}_compactions (aggregate_id, event_type, sequence_number, removed)
+    SELECT aggregate_id, event_type, MAX(sequence_number), COUNT(*) FROM superseded GROUP BY aggregate_id, event_type
+    ON CONFLICT (aggregate_id, event_type) DO UPDATE SET
+        sequence_number = EXCLUDED.sequence_number,
+        removed = {0}_compactions.removed + EXCLUDED.removed,
+        compacted_on = current_timestamp
+)
+SELECT COUNT(*) FROM superseded
\ No newline at end of file
@@ -0,0 +1 @@
+CREATE TABLE IF NOT EXISTS {} (id UUID PRIMARY KEY NOT NULL, aggregatetype VARCHAR(255) NOT NULL, aggregateid VARCHAR(255) NOT NULL, type VARCHAR(255) NOT NULL, payload JSONB NOT NULL)
\ No newline at end of file
@@ -0,0 +1 @@
+DELETE FROM {}_deferred WHERE event_id = $1
\ No newline at end of file
@@ -0,0 +1 @@
+DELETE FROM {} WHERE processed_at < $1
\ No newline at end of file
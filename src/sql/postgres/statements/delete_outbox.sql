@@ -0,0 +1 @@
+DELETE FROM {}_outbox WHERE event_id = $1
\ No newline at end of file
@@ -0,0 +1 @@
+DELETE FROM {} WHERE name = $1 AND table_name = $2
\ No newline at end of file
@@ -0,0 +1 @@
+DELETE FROM {} WHERE aggregate_id = $1 AND name = $2
\ No newline at end of file
@@ -0,0 +1 @@
+SELECT EXISTS(SELECT 1 FROM {} WHERE aggregate_id = $1 AND deleted_at IS NULL)
\ No newline at end of file
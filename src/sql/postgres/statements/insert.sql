@@ -1 +1 @@
-INSERT INTO {} (id, aggregate_id, payload, occurred_on, sequence_number, version) VALUES ($1, $2, $3, $4, $5, $6)
\ No newline at end of file
+INSERT INTO {} (id, aggregate_id, payload, occurred_on, sequence_number, version, metadata) VALUES ($1, $2, $3, COALESCE($4, now()), $5, $6, $7) RETURNING *
\ No newline at end of file
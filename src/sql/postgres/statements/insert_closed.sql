@@ -0,0 +1 @@
+INSERT INTO {}_closed (aggregate_id) VALUES ($1) ON CONFLICT DO NOTHING
\ No newline at end of file
@@ -0,0 +1 @@
+INSERT INTO {} (id, aggregatetype, aggregateid, type, payload) VALUES ($1, $2, $3, $4, $5)
\ No newline at end of file
@@ -0,0 +1 @@
+INSERT INTO {}_deferred (event_id, visible_at) VALUES ($1, $2)
\ No newline at end of file
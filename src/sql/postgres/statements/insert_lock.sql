@@ -0,0 +1 @@
+INSERT INTO {}_locks (aggregate_id) VALUES ($1) ON CONFLICT DO NOTHING
\ No newline at end of file
@@ -0,0 +1 @@
+INSERT INTO {}_outbox (event_id) VALUES ($1)
\ No newline at end of file
@@ -0,0 +1 @@
+INSERT INTO {} (event_id, aggregate_id, error) VALUES ($1, $2, $3)
\ No newline at end of file
@@ -0,0 +1 @@
+INSERT INTO {} (process_id, state) VALUES ($1, $2) ON CONFLICT DO NOTHING
\ No newline at end of file
@@ -0,0 +1 @@
+INSERT INTO {} (event_id, process_id) VALUES ($1, $2) ON CONFLICT DO NOTHING
\ No newline at end of file
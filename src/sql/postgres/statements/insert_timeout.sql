@@ -0,0 +1,3 @@
+INSERT INTO {} (id, aggregate_id, name, deadline, payload, next_attempt_at) VALUES ($1, $2, $3, $4, $5, $4)
+ON CONFLICT (aggregate_id, name) DO UPDATE SET id = EXCLUDED.id, deadline = EXCLUDED.deadline, payload = EXCLUDED.payload,
+attempts = 0, next_attempt_at = EXCLUDED.next_attempt_at, last_error = NULL
\ No newline at end of file
@@ -0,0 +1 @@
+INSERT INTO {0} (id, aggregate_id, payload, occurred_on, sequence_number, version, metadata{1}) VALUES ($1, $2, $3, COALESCE($4, now()), $5, $6, $7{2}) RETURNING *
\ No newline at end of file
@@ -0,0 +1 @@
+LOCK TABLE {} IN ACCESS EXCLUSIVE MODE
\ No newline at end of file
@@ -0,0 +1 @@
+INSERT INTO {0} ({1}) SELECT {2} FROM {0} WHERE aggregate_id = $1 ORDER BY sequence_number ASC
\ No newline at end of file
@@ -0,0 +1 @@
+UPDATE {} SET aggregate_id = $2, sequence_number = sequence_number + $3 WHERE aggregate_id = $1
\ No newline at end of file
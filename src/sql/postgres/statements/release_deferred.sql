@@ -0,0 +1 @@
+UPDATE {}_deferred SET released_at = now() WHERE event_id = $1
\ No newline at end of file
@@ -0,0 +1,3 @@
+SELECT *, occurred_on AS visible_at FROM {}
+WHERE ($1::timestamptz IS NULL OR (occurred_on, aggregate_id, sequence_number) > ($1, $2, $3))
+AND occurred_on <= now() - make_interval(secs => $4) ORDER BY occurred_on, aggregate_id, sequence_number ASC LIMIT $5
\ No newline at end of file
@@ -0,0 +1,6 @@
+SELECT * FROM (
+    SELECT events.*, GREATEST(events.occurred_on, deferred.visible_at) AS visible_at
+    FROM {0} AS events LEFT JOIN {0}_deferred AS deferred ON deferred.event_id = events.id
+) AS events
+WHERE ($1::timestamptz IS NULL OR (visible_at, aggregate_id, sequence_number) > ($1, $2, $3))
+AND visible_at <= now() - make_interval(secs => $4) ORDER BY visible_at, aggregate_id, sequence_number ASC LIMIT $5
\ No newline at end of file
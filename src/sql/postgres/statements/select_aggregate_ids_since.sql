@@ -0,0 +1 @@
+SELECT DISTINCT aggregate_id FROM {} WHERE occurred_on >= $1
\ No newline at end of file
@@ -0,0 +1 @@
+SELECT * FROM {} WHERE deleted_at IS NULL ORDER BY occurred_on, sequence_number ASC
\ No newline at end of file
@@ -0,0 +1 @@
+SELECT * FROM {}_archive WHERE aggregate_id = $1 ORDER BY sequence_number ASC
\ No newline at end of file
@@ -0,0 +1 @@
+SELECT * FROM {} WHERE id > $1 ORDER BY id ASC LIMIT $2
\ No newline at end of file
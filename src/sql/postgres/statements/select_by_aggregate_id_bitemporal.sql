@@ -0,0 +1 @@
+SELECT * FROM {} WHERE aggregate_id = $1 AND occurred_on <= $2 AND COALESCE(valid_at, occurred_on) <= $3 AND (valid_to IS NULL OR valid_to > $3) ORDER BY COALESCE(valid_at, occurred_on), sequence_number ASC
\ No newline at end of file
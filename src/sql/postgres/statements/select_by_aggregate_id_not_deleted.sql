@@ -0,0 +1 @@
+SELECT * FROM {} WHERE aggregate_id = $1 AND deleted_at IS NULL ORDER BY sequence_number ASC
\ No newline at end of file
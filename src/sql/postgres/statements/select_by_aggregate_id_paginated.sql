@@ -0,0 +1 @@
+SELECT * FROM ({}) AS events WHERE sequence_number >= $2 ORDER BY sequence_number ASC LIMIT $3
\ No newline at end of file
@@ -0,0 +1 @@
+SELECT * FROM {} WHERE id = $1 FOR UPDATE
\ No newline at end of file
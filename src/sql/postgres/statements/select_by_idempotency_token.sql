@@ -0,0 +1 @@
+SELECT * FROM {} WHERE aggregate_id = $1 AND idempotency_token = $2 ORDER BY sequence_number ASC
\ No newline at end of file
@@ -0,0 +1 @@
+SELECT * FROM {} WHERE search_vector @@ websearch_to_tsquery('simple', $1) ORDER BY ts_rank(search_vector, websearch_to_tsquery('simple', $1)) DESC, occurred_on DESC LIMIT $2
\ No newline at end of file
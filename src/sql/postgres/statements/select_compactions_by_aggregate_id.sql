@@ -0,0 +1 @@
+SELECT * FROM {}_compactions WHERE aggregate_id = $1 ORDER BY event_type
\ No newline at end of file
@@ -0,0 +1,4 @@
+SELECT events.* FROM {0} AS events JOIN {0}_deferred AS deferred ON deferred.event_id = events.id
+WHERE deferred.released_at IS NULL AND deferred.visible_at <= now()
+ORDER BY deferred.visible_at, events.sequence_number ASC LIMIT $1
+FOR UPDATE OF deferred SKIP LOCKED
\ No newline at end of file
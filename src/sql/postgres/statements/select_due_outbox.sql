@@ -0,0 +1,8 @@
+SELECT events.*, outbox.attempts FROM {0} AS events JOIN {0}_outbox AS outbox ON outbox.event_id = events.id
+WHERE outbox.next_attempt_at <= now() AND NOT EXISTS (
+    SELECT 1 FROM {0} AS previous JOIN {0}_outbox AS previous_outbox ON previous_outbox.event_id = previous.id
+    WHERE previous.aggregate_id = events.aggregate_id AND previous.sequence_number < events.sequence_number
+    AND previous_outbox.next_attempt_at > now()
+)
+ORDER BY outbox.created_at, events.sequence_number ASC LIMIT $1
+FOR UPDATE OF outbox SKIP LOCKED
\ No newline at end of file
@@ -0,0 +1 @@
+SELECT * FROM {} WHERE next_attempt_at <= now() ORDER BY next_attempt_at ASC LIMIT $1 FOR UPDATE SKIP LOCKED
\ No newline at end of file
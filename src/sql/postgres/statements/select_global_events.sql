@@ -0,0 +1 @@
+{0} ORDER BY occurred_on, aggregate_type, aggregate_id, sequence_number ASC
\ No newline at end of file
@@ -0,0 +1 @@
+SELECT aggregate_type, id, aggregate_id, payload, occurred_on, sequence_number, version, metadata FROM {0}
\ No newline at end of file
@@ -0,0 +1 @@
+SELECT '{0}' AS aggregate_type, id, aggregate_id, payload, occurred_on, sequence_number, version, metadata FROM {1}
\ No newline at end of file
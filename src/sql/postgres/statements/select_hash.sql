@@ -0,0 +1 @@
+SELECT hash FROM {} WHERE aggregate_id = $1 AND sequence_number = $2
\ No newline at end of file
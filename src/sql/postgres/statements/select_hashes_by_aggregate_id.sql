@@ -0,0 +1 @@
+SELECT id, aggregate_id, sequence_number, payload::text AS payload, previous_hash, hash FROM {} WHERE aggregate_id = $1 ORDER BY sequence_number ASC
\ No newline at end of file
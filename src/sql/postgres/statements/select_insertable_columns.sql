@@ -0,0 +1,4 @@
+SELECT column_name::text
+FROM information_schema.columns
+WHERE table_schema = COALESCE($2, current_schema()) AND table_name = $1 AND is_generated = 'NEVER'
+ORDER BY ordinal_position
\ No newline at end of file
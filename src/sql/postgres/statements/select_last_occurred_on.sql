@@ -0,0 +1 @@
+SELECT MAX(occurred_on) FROM {} WHERE aggregate_id = $1
\ No newline at end of file
@@ -0,0 +1 @@
+SELECT aggregate_id FROM {}_locks WHERE aggregate_id = $1 FOR UPDATE
\ No newline at end of file
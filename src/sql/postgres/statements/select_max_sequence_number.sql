@@ -0,0 +1 @@
+SELECT COALESCE(MAX(sequence_number), 0) FROM {} WHERE aggregate_id = $1
\ No newline at end of file
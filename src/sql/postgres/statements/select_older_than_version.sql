@@ -0,0 +1 @@
+SELECT * FROM {} WHERE version IS NULL OR version < $1 ORDER BY occurred_on, sequence_number ASC
\ No newline at end of file
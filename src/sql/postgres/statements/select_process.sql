@@ -0,0 +1 @@
+SELECT state, completed_at FROM {} WHERE process_id = $1
\ No newline at end of file
@@ -0,0 +1,3 @@
+SELECT last_event_id, last_occurred_on, last_aggregate_id, last_sequence_number FROM {}
+WHERE name = $1 AND table_name = $2
+FOR UPDATE SKIP LOCKED
\ No newline at end of file
@@ -0,0 +1 @@
+SELECT id, aggregate_id, sequence_number, payload::text AS payload, signature, signature_key_id FROM {} WHERE aggregate_id = $1 ORDER BY sequence_number ASC
\ No newline at end of file
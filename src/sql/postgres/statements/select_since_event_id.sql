@@ -0,0 +1,3 @@
+SELECT * FROM ({0}) AS events WHERE (occurred_on, aggregate_id, sequence_number) >
+    (SELECT occurred_on, aggregate_id, sequence_number FROM {1} WHERE id = $1)
+ORDER BY occurred_on, aggregate_id, sequence_number ASC LIMIT $2
\ No newline at end of file
@@ -0,0 +1 @@
+SELECT * FROM ({}) AS events WHERE occurred_on >= $1 ORDER BY occurred_on, aggregate_id, sequence_number ASC LIMIT $2
\ No newline at end of file
@@ -0,0 +1 @@
+SELECT * FROM {} WHERE aggregate_id = $1
\ No newline at end of file
@@ -0,0 +1,3 @@
+SELECT column_name::text, data_type::text
+FROM information_schema.columns
+WHERE table_schema = COALESCE($2, current_schema()) AND table_name = $1
\ No newline at end of file
@@ -0,0 +1,3 @@
+SELECT indexname::text
+FROM pg_indexes
+WHERE schemaname = COALESCE($2, current_schema()) AND tablename = $1
\ No newline at end of file
@@ -0,0 +1 @@
+UPDATE {} SET deleted_at = now() WHERE aggregate_id = $1 AND deleted_at IS NULL
\ No newline at end of file
@@ -0,0 +1 @@
+UPDATE {0} SET {1} WHERE id = $1
\ No newline at end of file
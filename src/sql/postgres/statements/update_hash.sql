@@ -0,0 +1 @@
+UPDATE {} SET previous_hash = $2, hash = $3 WHERE id = $1
\ No newline at end of file
@@ -0,0 +1 @@
+UPDATE {} SET idempotency_token = $2 WHERE id = $1
\ No newline at end of file
@@ -0,0 +1 @@
+UPDATE {} SET payload = $2, version = $3 WHERE id = $1
\ No newline at end of file
@@ -0,0 +1 @@
+UPDATE {} SET state = $2, completed_at = $3, updated_at = now() WHERE process_id = $1
\ No newline at end of file
@@ -0,0 +1,3 @@
+UPDATE {} SET last_event_id = $3, last_occurred_on = $4, last_aggregate_id = $5,
+    last_sequence_number = $6, updated_at = current_timestamp
+WHERE name = $1 AND table_name = $2
\ No newline at end of file
@@ -0,0 +1 @@
+UPDATE {} SET signature = $2, signature_key_id = $3 WHERE id = $1
\ No newline at end of file
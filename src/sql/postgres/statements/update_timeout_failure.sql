@@ -0,0 +1 @@
+UPDATE {} SET attempts = attempts + 1, next_attempt_at = $2, last_error = $3 WHERE id = $1
\ No newline at end of file
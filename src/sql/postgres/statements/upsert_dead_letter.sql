@@ -0,0 +1 @@
+INSERT INTO {0} (event_id, aggregate_id, event, error) VALUES ($1, $2, $3, $4) ON CONFLICT (event_id) DO UPDATE SET event = EXCLUDED.event, error = EXCLUDED.error, dead_lettered_on = current_timestamp
\ No newline at end of file
@@ -0,0 +1 @@
+INSERT INTO {0} (aggregate_id, sequence_number, version, state, taken_on) VALUES ($1, $2, $3, $4, $5) ON CONFLICT (aggregate_id) DO UPDATE SET sequence_number = EXCLUDED.sequence_number, version = EXCLUDED.version, state = EXCLUDED.state, taken_on = EXCLUDED.taken_on WHERE {0}.sequence_number < EXCLUDED.sequence_number OR {0}.version IS DISTINCT FROM EXCLUDED.version
\ No newline at end of file
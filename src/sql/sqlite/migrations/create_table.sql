@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS {0}
+(
+    id BLOB PRIMARY KEY NOT NULL,
+    aggregate_id BLOB NOT NULL,
+    payload TEXT NOT NULL,
+    occurred_on TEXT NOT NULL,
+    sequence_number INTEGER NOT NULL,
+    version INTEGER,
+    metadata TEXT,
+    CONSTRAINT {0}_aggregate_id_sequence_number_key UNIQUE (aggregate_id, sequence_number)
+)
\ No newline at end of file
@@ -0,0 +1 @@
+DELETE FROM {0} WHERE aggregate_id = ?1
\ No newline at end of file
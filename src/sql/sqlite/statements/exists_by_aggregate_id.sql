@@ -0,0 +1 @@
+SELECT EXISTS (SELECT 1 FROM {0} WHERE aggregate_id = ?1)
\ No newline at end of file
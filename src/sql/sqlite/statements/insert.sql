@@ -0,0 +1 @@
+INSERT INTO {0} (id, aggregate_id, payload, occurred_on, sequence_number, version, metadata) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
\ No newline at end of file
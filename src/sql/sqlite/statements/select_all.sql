@@ -0,0 +1 @@
+SELECT id, aggregate_id, payload, occurred_on, sequence_number, version, metadata FROM {0} ORDER BY rowid ASC
\ No newline at end of file
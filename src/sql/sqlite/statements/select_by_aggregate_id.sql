@@ -0,0 +1 @@
+SELECT id, aggregate_id, payload, occurred_on, sequence_number, version, metadata FROM {0} WHERE aggregate_id = ?1 ORDER BY sequence_number ASC
\ No newline at end of file
@@ -0,0 +1 @@
+SELECT id, aggregate_id, payload, occurred_on, sequence_number, version, metadata FROM {0} WHERE julianday(occurred_on) >= julianday(?1) ORDER BY rowid ASC LIMIT ?2
\ No newline at end of file
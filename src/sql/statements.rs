@@ -1,5 +1,6 @@
 use sqlx::{Database, Postgres};
 
+use crate::store::postgres::Column;
 use crate::Aggregate;
 
 pub trait StatementsHandler<D>
@@ -11,7 +12,29 @@ where
         A: Aggregate;
     fn table_name(&self) -> &str;
     fn by_aggregate_id(&self) -> &str;
+    fn by_aggregate_id_after(&self) -> &str;
+    fn by_aggregate_id_including_deleted(&self) -> &str;
+    fn by_aggregate_id_paginated(&self) -> &str;
+    fn select_since_occurred_on(&self) -> &str;
+    fn select_since_event_id(&self) -> &str;
+    fn exists_by_aggregate_id(&self) -> &str;
     fn select_all(&self) -> &str;
+    fn select_all_including_deleted(&self) -> &str;
+    fn select_by_version(&self) -> &str;
+    fn select_older_than_version(&self) -> &str;
+    fn last_occurred_on(&self) -> &str;
+    fn insert_lock(&self) -> &str;
+    fn select_lock_for_update(&self) -> &str;
+    fn insert_deferred(&self) -> &str;
+    fn select_due_deferred(&self) -> &str;
+    fn release_deferred(&self) -> &str;
+    fn delete_deferred(&self) -> &str;
+    fn insert_outbox(&self) -> &str;
+    fn select_due_outbox(&self) -> &str;
+    fn delete_outbox(&self) -> &str;
+    fn update_outbox_failure(&self) -> &str;
+    fn select_by_idempotency_token(&self) -> &str;
+    fn update_idempotency_token(&self) -> &str;
     fn insert(&self) -> &str;
     fn delete_by_aggregate_id(&self) -> &str;
 }
@@ -20,25 +43,155 @@ where
 pub struct Statements {
     table_name: String,
     select_by_aggregate_id: String,
+    select_by_aggregate_id_after: String,
+    select_by_aggregate_id_including_deleted: String,
+    select_by_aggregate_id_paginated: String,
+    select_since_occurred_on: String,
+    select_since_event_id: String,
+    exists_by_aggregate_id: String,
     select_all: String,
+    select_all_including_deleted: String,
+    select_by_version: String,
+    select_older_than_version: String,
+    select_last_occurred_on: String,
+    insert_lock: String,
+    select_lock_for_update: String,
+    insert_deferred: String,
+    select_due_deferred: String,
+    release_deferred: String,
+    delete_deferred: String,
+    insert_outbox: String,
+    select_due_outbox: String,
+    delete_outbox: String,
+    update_outbox_failure: String,
+    select_by_idempotency_token: String,
+    update_idempotency_token: String,
     insert: String,
     delete_by_aggregate_id: String,
 }
 
-impl StatementsHandler<Postgres> for Statements {
-    fn new<A>() -> Self
-    where
-        A: Aggregate,
-    {
-        let table_name: String = format!("{}_events", A::NAME);
+impl Statements {
+    /// Rebuilds the insert statement in order to populate the given additional columns, bound after
+    /// the default ones.
+    pub fn with_custom_columns(mut self, columns: &[Column]) -> Self {
+        let names: String = columns.iter().map(|column| format!(", {}", column.name())).collect();
+        let placeholders: String = (0..columns.len()).map(|i| format!(", ${}", i + 8)).collect();
+
+        self.insert = format!(
+            include_str!("postgres/statements/insert_with_custom_columns.sql"),
+            self.table_name, names, placeholders
+        );
+        self
+    }
+
+    /// Rebuilds the statements in order to hide the soft deleted aggregate instances, and to soft
+    /// delete them instead of removing their events.
+    pub fn with_soft_delete(mut self) -> Self {
+        self.select_by_aggregate_id = format!(
+            include_str!("postgres/statements/select_by_aggregate_id_not_deleted.sql"),
+            self.table_name
+        );
+        self.select_by_aggregate_id_after = format!(
+            include_str!("postgres/statements/select_by_aggregate_id_after.sql"),
+            self.select_by_aggregate_id
+        );
+        self.select_by_aggregate_id_paginated = format!(
+            include_str!("postgres/statements/select_by_aggregate_id_paginated.sql"),
+            self.select_by_aggregate_id
+        );
+        self.exists_by_aggregate_id = format!(
+            include_str!("postgres/statements/exists_by_aggregate_id_not_deleted.sql"),
+            self.table_name
+        );
+        self.select_all = format!(
+            include_str!("postgres/statements/select_all_not_deleted.sql"),
+            self.table_name
+        );
+        self.select_since_occurred_on = format!(
+            include_str!("postgres/statements/select_since_occurred_on.sql"),
+            self.select_all
+        );
+        self.select_since_event_id = format!(
+            include_str!("postgres/statements/select_since_event_id.sql"),
+            self.select_all, self.table_name
+        );
+        self.delete_by_aggregate_id = format!(
+            include_str!("postgres/statements/soft_delete_by_aggregate_id.sql"),
+            self.table_name
+        );
+        self
+    }
+
+    /// Builds the statements of the event store table with the given, possibly schema qualified,
+    /// name.
+    pub fn for_table(table_name: &str) -> Self {
+        let table_name: String = table_name.to_string();
+
+        let select_by_aggregate_id: String = format!(
+            include_str!("postgres/statements/select_by_aggregate_id.sql"),
+            table_name
+        );
+        let select_all: String = format!(include_str!("postgres/statements/select_all.sql"), table_name);
 
         Self {
             table_name: table_name.clone(),
-            select_by_aggregate_id: format!(
-                include_str!("postgres/statements/select_by_aggregate_id.sql"),
+            select_by_aggregate_id_after: format!(
+                include_str!("postgres/statements/select_by_aggregate_id_after.sql"),
+                select_by_aggregate_id
+            ),
+            select_by_aggregate_id_including_deleted: select_by_aggregate_id.clone(),
+            select_by_aggregate_id_paginated: format!(
+                include_str!("postgres/statements/select_by_aggregate_id_paginated.sql"),
+                select_by_aggregate_id
+            ),
+            select_since_occurred_on: format!(
+                include_str!("postgres/statements/select_since_occurred_on.sql"),
+                select_all
+            ),
+            select_since_event_id: format!(
+                include_str!("postgres/statements/select_since_event_id.sql"),
+                select_all, table_name
+            ),
+            select_by_aggregate_id,
+            exists_by_aggregate_id: format!(
+                include_str!("postgres/statements/exists_by_aggregate_id.sql"),
+                table_name
+            ),
+            select_all_including_deleted: select_all.clone(),
+            select_all,
+            select_by_version: format!(include_str!("postgres/statements/select_by_version.sql"), table_name),
+            select_older_than_version: format!(
+                include_str!("postgres/statements/select_older_than_version.sql"),
+                table_name
+            ),
+            select_last_occurred_on: format!(
+                include_str!("postgres/statements/select_last_occurred_on.sql"),
+                table_name
+            ),
+            insert_lock: format!(include_str!("postgres/statements/insert_lock.sql"), table_name),
+            select_lock_for_update: format!(
+                include_str!("postgres/statements/select_lock_for_update.sql"),
+                table_name
+            ),
+            insert_deferred: format!(include_str!("postgres/statements/insert_deferred.sql"), table_name),
+            select_due_deferred: format!(include_str!("postgres/statements/select_due_deferred.sql"), table_name),
+            release_deferred: format!(include_str!("postgres/statements/release_deferred.sql"), table_name),
+            delete_deferred: format!(include_str!("postgres/statements/delete_deferred.sql"), table_name),
+            insert_outbox: format!(include_str!("postgres/statements/insert_outbox.sql"), table_name),
+            select_due_outbox: format!(include_str!("postgres/statements/select_due_outbox.sql"), table_name),
+            delete_outbox: format!(include_str!("postgres/statements/delete_outbox.sql"), table_name),
+            update_outbox_failure: format!(
+                include_str!("postgres/statements/update_outbox_failure.sql"),
+                table_name
+            ),
+            select_by_idempotency_token: format!(
+                include_str!("postgres/statements/select_by_idempotency_token.sql"),
+                table_name
+            ),
+            update_idempotency_token: format!(
+                include_str!("postgres/statements/update_idempotency_token.sql"),
                 table_name
             ),
-            select_all: format!(include_str!("postgres/statements/select_all.sql"), table_name),
             insert: format!(include_str!("postgres/statements/insert.sql"), table_name),
             delete_by_aggregate_id: format!(
                 include_str!("postgres/statements/delete_by_aggregate_id.sql"),
@@ -46,6 +199,15 @@ impl StatementsHandler<Postgres> for Statements {
             ),
         }
     }
+}
+
+impl StatementsHandler<Postgres> for Statements {
+    fn new<A>() -> Self
+    where
+        A: Aggregate,
+    {
+        Self::for_table(format!("{}_events", A::NAME).as_str())
+    }
 
     fn table_name(&self) -> &str {
         &self.table_name
@@ -55,10 +217,98 @@ impl StatementsHandler<Postgres> for Statements {
         &self.select_by_aggregate_id
     }
 
+    fn by_aggregate_id_after(&self) -> &str {
+        &self.select_by_aggregate_id_after
+    }
+
+    fn by_aggregate_id_including_deleted(&self) -> &str {
+        &self.select_by_aggregate_id_including_deleted
+    }
+
+    fn by_aggregate_id_paginated(&self) -> &str {
+        &self.select_by_aggregate_id_paginated
+    }
+
+    fn select_since_occurred_on(&self) -> &str {
+        &self.select_since_occurred_on
+    }
+
+    fn select_since_event_id(&self) -> &str {
+        &self.select_since_event_id
+    }
+
+    fn exists_by_aggregate_id(&self) -> &str {
+        &self.exists_by_aggregate_id
+    }
+
     fn select_all(&self) -> &str {
         &self.select_all
     }
 
+    fn select_all_including_deleted(&self) -> &str {
+        &self.select_all_including_deleted
+    }
+
+    fn select_by_version(&self) -> &str {
+        &self.select_by_version
+    }
+
+    fn select_older_than_version(&self) -> &str {
+        &self.select_older_than_version
+    }
+
+    fn last_occurred_on(&self) -> &str {
+        &self.select_last_occurred_on
+    }
+
+    fn insert_lock(&self) -> &str {
+        &self.insert_lock
+    }
+
+    fn select_lock_for_update(&self) -> &str {
+        &self.select_lock_for_update
+    }
+
+    fn insert_deferred(&self) -> &str {
+        &self.insert_deferred
+    }
+
+    fn select_due_deferred(&self) -> &str {
+        &self.select_due_deferred
+    }
+
+    fn release_deferred(&self) -> &str {
+        &self.release_deferred
+    }
+
+    fn delete_deferred(&self) -> &str {
+        &self.delete_deferred
+    }
+
+    fn insert_outbox(&self) -> &str {
+        &self.insert_outbox
+    }
+
+    fn select_due_outbox(&self) -> &str {
+        &self.select_due_outbox
+    }
+
+    fn delete_outbox(&self) -> &str {
+        &self.delete_outbox
+    }
+
+    fn update_outbox_failure(&self) -> &str {
+        &self.update_outbox_failure
+    }
+
+    fn select_by_idempotency_token(&self) -> &str {
+        &self.select_by_idempotency_token
+    }
+
+    fn update_idempotency_token(&self) -> &str {
+        &self.update_idempotency_token
+    }
+
     fn insert(&self) -> &str {
         &self.insert
     }
@@ -0,0 +1,135 @@
+/// Implements [`crate::Aggregate`] for a workflow-like aggregate described as an explicit state
+/// machine.
+///
+/// The aggregate is declared by listing:
+/// - the commands accepted in every state, and the events they emit (`commands` block);
+/// - the state reached when an event is applied onto a state (`transitions` block).
+///
+/// Both blocks are expanded into a `match` over the `(state, command)` and `(state, event)` pairs,
+/// meaning that the compiler checks their exhaustiveness: adding a new state or command variant
+/// won't compile until every combination is explicitly accepted or rejected. Avoid catch-all arms
+/// (`_ => ...`) in the `commands` block to keep this guarantee.
+///
+/// The `transitions` block only needs to list the pairs that change the state: applying an event
+/// onto a state that has no matching arm leaves the state unchanged, so that replaying a stream
+/// written by an older version of the machine never panics.
+///
+/// Arms support or-patterns and guards, and the bindings of the patterns are available in the arm
+/// body. Unlike in a `match`, arms are always separated by commas, block bodies included.
+///
+/// ```rust
+/// # use esrs::Aggregate;
+/// pub struct Door;
+///
+/// #[derive(Default)]
+/// pub enum DoorState {
+///     #[default]
+///     Closed,
+///     Opened,
+///     Locked { key: u32 },
+/// }
+///
+/// pub enum DoorCommand {
+///     Open,
+///     Close,
+///     Lock { key: u32 },
+///     Unlock { key: u32 },
+/// }
+///
+/// pub enum DoorEvent {
+///     Opened,
+///     Closed,
+///     Locked { key: u32 },
+///     Unlocked,
+/// }
+///
+/// #[derive(Debug, thiserror::Error)]
+/// pub enum DoorError {
+///     #[error("Illegal transition")]
+///     IllegalTransition,
+///     #[error("Wrong key")]
+///     WrongKey,
+/// }
+///
+/// esrs::state_machine! {
+///     aggregate Door {
+///         name: "door",
+///         state: DoorState,
+///         command: DoorCommand,
+///         event: DoorEvent,
+///         error: DoorError,
+///     }
+///
+///     commands {
+///         (DoorState::Closed, DoorCommand::Open) => Ok(vec![DoorEvent::Opened]),
+///         (DoorState::Closed, DoorCommand::Lock { key }) => Ok(vec![DoorEvent::Locked { key }]),
+///         (DoorState::Opened, DoorCommand::Close) => Ok(vec![DoorEvent::Closed]),
+///         (DoorState::Locked { key }, DoorCommand::Unlock { key: attempt }) if *key == attempt => {
+///             Ok(vec![DoorEvent::Unlocked])
+///         },
+///         (DoorState::Locked { .. }, DoorCommand::Unlock { .. }) => Err(DoorError::WrongKey),
+///         (DoorState::Closed, DoorCommand::Close | DoorCommand::Unlock { .. })
+///         | (DoorState::Opened, DoorCommand::Open | DoorCommand::Lock { .. } | DoorCommand::Unlock { .. })
+///         | (DoorState::Locked { .. }, DoorCommand::Open | DoorCommand::Close | DoorCommand::Lock { .. }) => {
+///             Err(DoorError::IllegalTransition)
+///         },
+///     }
+///
+///     transitions {
+///         (DoorState::Closed, DoorEvent::Opened) => DoorState::Opened,
+///         (DoorState::Closed, DoorEvent::Locked { key }) => DoorState::Locked { key },
+///         (DoorState::Opened, DoorEvent::Closed) => DoorState::Closed,
+///         (DoorState::Locked { .. }, DoorEvent::Unlocked) => DoorState::Closed,
+///     }
+/// }
+///
+/// let events = Door::handle_command(&DoorState::Closed, DoorCommand::Lock { key: 42 }).unwrap();
+/// let state = events.into_iter().fold(DoorState::Closed, Door::apply_event);
+/// assert!(matches!(state, DoorState::Locked { key: 42 }));
+/// assert!(Door::handle_command(&state, DoorCommand::Open).is_err());
+/// ```
+#[macro_export]
+macro_rules! state_machine {
+    (
+        aggregate $aggregate:ty {
+            name: $name:expr,
+            state: $state:ty,
+            command: $command:ty,
+            event: $event:ty,
+            error: $error:ty $(,)?
+        }
+
+        commands {
+            $( $($command_pattern:pat_param)|+ $(if $command_guard:expr)? => $command_body:expr ),* $(,)?
+        }
+
+        transitions {
+            $( $($transition_pattern:pat_param)|+ $(if $transition_guard:expr)? => $transition_body:expr ),* $(,)?
+        }
+    ) => {
+        impl $crate::Aggregate for $aggregate {
+            const NAME: &'static str = $name;
+            type State = $state;
+            type Command = $command;
+            type Event = $event;
+            type Error = $error;
+
+            fn handle_command(
+                state: &Self::State,
+                command: Self::Command,
+            ) -> ::std::result::Result<::std::vec::Vec<Self::Event>, Self::Error> {
+                match (state, command) {
+                    $( $($command_pattern)|+ $(if $command_guard)? => $command_body, )*
+                }
+            }
+
+            fn apply_event(state: Self::State, payload: Self::Event) -> Self::State {
+                match (state, payload) {
+                    $( $($transition_pattern)|+ $(if $transition_guard)? => $transition_body, )*
+                    #[allow(unreachable_patterns)]
+                    (state, _) => state,
+                }
+            }
+        }
+    };
+}
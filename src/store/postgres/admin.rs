@@ -0,0 +1,220 @@
+use async_trait::async_trait;
+use sqlx::types::Json;
+use sqlx::{PgConnection, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::sql::event::DbRawEvent;
+use crate::store::{EventStore, EventStoreLockGuard};
+use crate::types::SequenceNumber;
+use crate::Aggregate;
+
+use super::persistable::Persistable;
+use super::{ErrorContext, PgStore, PgStoreError, Schema};
+
+/// The `CorrectionKind` enum defines the kind of correction applied to a single event:
+///
+/// - `PayloadUpdate`: The payload of the event has been replaced through
+///   [`PgStore::update_event_payload`].
+/// - `Deletion`: The event has been deleted through [`PgStore::delete_event`].
+/// - `Redaction`: The payload of the event has been rewritten in place, keeping its version, through
+///   a [`super::Redactor`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CorrectionKind {
+    PayloadUpdate,
+    Deletion,
+    Redaction,
+}
+
+/// A correction applied to a single event, passed to the [`AuditHook`]s of the store.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EventCorrection {
+    /// The kind of correction.
+    pub kind: CorrectionKind,
+    /// The id of the corrected event.
+    pub event_id: Uuid,
+    /// The aggregate instance the corrected event belongs to.
+    pub aggregate_id: Uuid,
+    /// The sequence number of the corrected event.
+    pub sequence_number: SequenceNumber,
+    /// The payload of the event before the correction.
+    pub previous_payload: serde_json::Value,
+    /// The payload of the event after the correction, `None` if the event has been deleted.
+    pub payload: Option<serde_json::Value>,
+}
+
+/// This trait is used to implement an [`AuditHook`]. An audit hook is called for every correction
+/// applied to single events through [`PgStore::update_event_payload`], [`PgStore::delete_event`] and
+/// the [`super::Redactor`], e.g. to record who changed what in an audit table.
+///
+/// It is called inside of the transaction applying the correction: returning an error vetoes the
+/// correction, rolling the transaction back.
+#[async_trait]
+pub trait AuditHook: Sync {
+    /// Called with the correction being applied, inside of its transaction.
+    async fn audit(&self, correction: &EventCorrection, executor: &mut PgConnection) -> Result<(), PgStoreError>;
+
+    /// The name of the audit hook. By default, this is the type name of the audit hook, but it can
+    /// be overridden to provide a custom name.
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+}
+
+impl<A, S> PgStore<A, S>
+where
+    A: Aggregate + 'static,
+    A::State: Send,
+    A::Event: Send + Sync + 'static,
+    S: Schema<A::Event> + Persistable + Send + Sync,
+{
+    /// Replaces the payload of the event with the given id, returning `false` if there is no such
+    /// event. The version of the event is set to the current one, as the payload is serialized with
+    /// the current schema.
+    ///
+    /// **Dangerous**: events are meant to be immutable. This is an escape hatch for operational
+    /// corrections only (e.g. fixing a payload written by a bug). The read side projections and the
+    /// aggregate instances already loaded aren't updated, no event handler nor event bus is called,
+    /// and the hash chain of the aggregate instance, if any, is broken. The aggregate instance is
+    /// locked while the event is being updated, and every [`AuditHook`] is called in the same
+    /// transaction.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if locking the aggregate instance fails, any of the queries fails or an
+    /// audit hook fails.
+    pub async fn update_event_payload(&self, event_id: Uuid, payload: A::Event) -> Result<bool, PgStoreError> {
+        let context: ErrorContext = ErrorContext::new("update_event_payload").with_event_id(event_id);
+        let schema = S::from_event(payload);
+        let payload: serde_json::Value =
+            serde_json::to_value(&schema).map_err(|error| PgStoreError::from(error).with_context(context.clone()))?;
+
+        self.correct_event(event_id, Some(payload))
+            .await
+            .map_err(|error| error.with_context(context))
+    }
+
+    /// Deletes the event with the given id, returning `false` if there is no such event.
+    ///
+    /// **Dangerous**: events are meant to be immutable. This is an escape hatch for operational
+    /// corrections only (e.g. removing an event written by a bug). The sequence numbers of the
+    /// following events are left untouched, leaving a gap. The read side projections and the
+    /// aggregate instances already loaded aren't updated, no event handler nor event bus is called,
+    /// and the hash chain of the aggregate instance, if any, is broken. The aggregate instance is
+    /// locked while the event is being deleted, and every [`AuditHook`] is called in the same
+    /// transaction.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if locking the aggregate instance fails, any of the queries fails or an
+    /// audit hook fails.
+    pub async fn delete_event(&self, event_id: Uuid) -> Result<bool, PgStoreError> {
+        let context: ErrorContext = ErrorContext::new("delete_event").with_event_id(event_id);
+
+        self.correct_event(event_id, None)
+            .await
+            .map_err(|error| error.with_context(context))
+    }
+
+    /// Locks the aggregate instance of the given event, then replaces its payload with the given one,
+    /// or deletes it if `None`, and calls the audit hooks, in a single transaction.
+    async fn correct_event(&self, event_id: Uuid, payload: Option<serde_json::Value>) -> Result<bool, PgStoreError> {
+        let select_by_id: String = format!(
+            include_str!("../../sql/postgres/statements/select_by_id_for_update.sql"),
+            self.table_name()
+        );
+
+        // The aggregate instance is unknown until the event is read, so it is read twice: once to
+        // know what to lock, and once, locked, to apply the correction.
+        let aggregate_id: Option<Uuid> = sqlx::query_as::<_, DbRawEvent>(select_by_id.as_str())
+            .bind(event_id)
+            .fetch_optional(&self.inner.pool)
+            .await?
+            .map(|event| event.aggregate_id);
+
+        let Some(aggregate_id) = aggregate_id else {
+            return Ok(false);
+        };
+
+        let _lock: EventStoreLockGuard = self.lock(aggregate_id).await?;
+        let mut transaction: Transaction<'static, Postgres> = self.begin().await?;
+
+        let Some(event) = sqlx::query_as::<_, DbRawEvent>(select_by_id.as_str())
+            .bind(event_id)
+            .fetch_optional(&mut *transaction)
+            .await?
+        else {
+            return Ok(false);
+        };
+
+        let kind: CorrectionKind = match payload.as_ref() {
+            Some(payload) => {
+                #[cfg(feature = "upcasting")]
+                let version: Option<i32> = S::current_version();
+                #[cfg(not(feature = "upcasting"))]
+                let version: Option<i32> = None;
+
+                let _ = sqlx::query(
+                    format!(
+                        include_str!("../../sql/postgres/statements/update_payload_by_id.sql"),
+                        self.table_name()
+                    )
+                    .as_str(),
+                )
+                .bind(event_id)
+                .bind(Json(payload))
+                .bind(version)
+                .execute(&mut *transaction)
+                .await?;
+
+                CorrectionKind::PayloadUpdate
+            }
+            None => {
+                let _ = sqlx::query(
+                    format!(
+                        include_str!("../../sql/postgres/statements/delete_by_id.sql"),
+                        self.table_name()
+                    )
+                    .as_str(),
+                )
+                .bind(event_id)
+                .execute(&mut *transaction)
+                .await?;
+
+                CorrectionKind::Deletion
+            }
+        };
+
+        let correction: EventCorrection = EventCorrection {
+            kind,
+            event_id,
+            aggregate_id: event.aggregate_id,
+            sequence_number: event.sequence_number,
+            previous_payload: serde_json::from_str(event.payload.0.get())?,
+            payload,
+        };
+
+        for audit_hook in &self.inner.audit_hooks {
+            if let Err(error) = audit_hook.audit(&correction, &mut transaction).await {
+                tracing::error!({
+                    event_id = %event_id,
+                    aggregate_id = %correction.aggregate_id,
+                    audit_hook = audit_hook.name(),
+                    error = ?error,
+                }, "audit hook vetoed event correction");
+
+                return Err(error);
+            }
+        }
+
+        transaction.commit().await?;
+
+        tracing::warn!({
+            event_id = %event_id,
+            aggregate_id = %correction.aggregate_id,
+            sequence_number = correction.sequence_number,
+            kind = ?kind,
+        }, "event corrected");
+
+        Ok(true)
+    }
+}
@@ -0,0 +1,259 @@
+//! Analysis utilities over the content of the event store table.
+
+use std::collections::HashSet;
+use std::ops::Range;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use uuid::Uuid;
+
+use crate::Aggregate;
+
+use super::persistable::Persistable;
+use super::{PgStore, PgStoreError, Schema};
+
+/// The `EventTypeLocation` enum defines where the event type is found in the serialized payloads:
+///
+/// - `ExternallyTagged`: The payload is either the name of a unit variant, or an object whose only key
+///   is the name of the variant (serde default enum representation).
+/// - `InternallyTagged`: The payload is an object holding the name of the variant in the given key
+///   (serde `#[serde(tag = "...")]` enum representation).
+pub enum EventTypeLocation {
+    ExternallyTagged,
+    InternallyTagged(&'static str),
+}
+
+impl EventTypeLocation {
+    /// SQL expression extracting the event type from the `payload` column.
+    pub(crate) fn as_sql(&self) -> String {
+        match self {
+            Self::ExternallyTagged => "CASE jsonb_typeof(payload) \
+                WHEN 'string' THEN payload #>> '{}' \
+                WHEN 'object' THEN (SELECT key FROM jsonb_object_keys(payload) AS key LIMIT 1) \
+                END"
+            .to_string(),
+            Self::InternallyTagged(tag) => format!("payload ->> '{}'", tag),
+        }
+    }
+
+    /// Extracts the event type from the given serialized payload, mirroring [`Self::as_sql`].
+    pub(crate) fn event_type(&self, payload: &serde_json::Value) -> Option<String> {
+        match self {
+            Self::ExternallyTagged => match payload {
+                serde_json::Value::String(variant) => Some(variant.clone()),
+                serde_json::Value::Object(object) => object.keys().next().cloned(),
+                _ => None,
+            },
+            Self::InternallyTagged(tag) => payload
+                .get(*tag)
+                .and_then(serde_json::Value::as_str)
+                .map(str::to_string),
+        }
+    }
+}
+
+/// Usage of a single event type in the event store.
+#[derive(sqlx::FromRow, Debug, Clone, Eq, PartialEq)]
+pub struct EventTypeUsage {
+    /// The event type.
+    pub event_type: String,
+    /// The number of events of this type.
+    pub count: i64,
+    /// The timestamp of the latest event of this type.
+    pub last_occurred_on: DateTime<Utc>,
+}
+
+/// Report of the event types that are candidates for removal.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct DeadEventsReport {
+    /// Event types that haven't been written since the given timestamp.
+    pub stale: Vec<EventTypeUsage>,
+    /// Known event types that have never been written.
+    pub unused: Vec<String>,
+}
+
+/// Number of events written in a single day.
+#[derive(sqlx::FromRow, Debug, Clone, Eq, PartialEq)]
+pub struct DailyCount {
+    /// The day, in UTC.
+    pub day: NaiveDate,
+    /// The number of events written in the day.
+    pub count: i64,
+}
+
+/// Number of events of a single event type.
+#[derive(sqlx::FromRow, Debug, Clone, Eq, PartialEq)]
+pub struct EventTypeCount {
+    /// The event type.
+    pub event_type: String,
+    /// The number of events of this type.
+    pub count: i64,
+}
+
+/// Number of events of a single aggregate instance.
+#[derive(sqlx::FromRow, Debug, Clone, Eq, PartialEq)]
+pub struct AggregateCount {
+    /// The aggregate instance id.
+    pub aggregate_id: Uuid,
+    /// The number of events of this aggregate instance.
+    pub count: i64,
+}
+
+/// Number of events written at a single version.
+#[derive(sqlx::FromRow, Debug, Clone, Eq, PartialEq)]
+pub struct VersionCount {
+    /// The version of the events, `None` for the events written without a version.
+    pub version: Option<i32>,
+    /// The number of events written at this version.
+    pub count: i64,
+}
+
+/// Volume statistics of the events written in a time range.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Statistics {
+    /// Events per day, sorted by day.
+    pub events_per_day: Vec<DailyCount>,
+    /// Events per event type, sorted by event type.
+    pub events_per_type: Vec<EventTypeCount>,
+    /// Average size of the stored payloads in bytes, `None` if there are no events.
+    pub average_payload_size: Option<f64>,
+    /// Aggregate instances with the most events, sorted by descending count.
+    pub top_aggregates: Vec<AggregateCount>,
+}
+
+impl<A, S> PgStore<A, S>
+where
+    A: Aggregate,
+    A::Event: Send + Sync,
+    S: Schema<A::Event> + Persistable + Send + Sync,
+{
+    /// Returns the usage of every event type written in the event store, sorted by event type.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the query fails.
+    pub async fn event_type_usages(&self, location: &EventTypeLocation) -> Result<Vec<EventTypeUsage>, PgStoreError> {
+        let query: String = format!(
+            "SELECT event_type, COUNT(*) AS count, MAX(occurred_on) AS last_occurred_on \
+            FROM (SELECT {} AS event_type, occurred_on FROM {}) AS events \
+            WHERE event_type IS NOT NULL GROUP BY event_type ORDER BY event_type",
+            location.as_sql(),
+            self.table_name()
+        );
+
+        Ok(sqlx::query_as::<_, EventTypeUsage>(query.as_str())
+            .fetch_all(&self.inner.pool)
+            .await?)
+    }
+
+    /// Reports the event types that haven't been written since `not_written_since`, and the given
+    /// `known_event_types` (e.g. all the variants of the event enum) that have never been written,
+    /// guiding the safe removal of dead code paths and upcasters.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the query fails.
+    pub async fn dead_events(
+        &self,
+        location: &EventTypeLocation,
+        known_event_types: &[&str],
+        not_written_since: DateTime<Utc>,
+    ) -> Result<DeadEventsReport, PgStoreError> {
+        let usages: Vec<EventTypeUsage> = self.event_type_usages(location).await?;
+        let written: HashSet<&str> = usages.iter().map(|usage| usage.event_type.as_str()).collect();
+
+        let unused: Vec<String> = known_event_types
+            .iter()
+            .filter(|event_type| !written.contains(*event_type))
+            .map(|event_type| event_type.to_string())
+            .collect();
+
+        let stale: Vec<EventTypeUsage> = usages
+            .into_iter()
+            .filter(|usage| usage.last_occurred_on < not_written_since)
+            .collect();
+
+        Ok(DeadEventsReport { stale, unused })
+    }
+
+    /// Returns the volume statistics of the events written in the given time range, with at most
+    /// `top_aggregates` aggregate instances by event count.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if any of the queries fails.
+    pub async fn statistics(
+        &self,
+        range: Range<DateTime<Utc>>,
+        location: &EventTypeLocation,
+        top_aggregates: i64,
+    ) -> Result<Statistics, PgStoreError> {
+        let events: String = format!(
+            "(SELECT * FROM {} WHERE occurred_on >= $1 AND occurred_on < $2) AS events",
+            self.table_name()
+        );
+
+        let events_per_day_query: String = format!(
+            "SELECT (occurred_on AT TIME ZONE 'UTC')::date AS day, COUNT(*) AS count FROM {} GROUP BY 1 ORDER BY 1",
+            events
+        );
+        let events_per_day: Vec<DailyCount> = sqlx::query_as::<_, DailyCount>(events_per_day_query.as_str())
+            .bind(range.start)
+            .bind(range.end)
+            .fetch_all(&self.inner.pool)
+            .await?;
+
+        let events_per_type_query: String = format!(
+            "SELECT event_type, COUNT(*) AS count FROM (SELECT {} AS event_type FROM {}) AS types \
+            WHERE event_type IS NOT NULL GROUP BY event_type ORDER BY event_type",
+            location.as_sql(),
+            events
+        );
+        let events_per_type: Vec<EventTypeCount> = sqlx::query_as::<_, EventTypeCount>(events_per_type_query.as_str())
+            .bind(range.start)
+            .bind(range.end)
+            .fetch_all(&self.inner.pool)
+            .await?;
+
+        let average_payload_size_query: String = format!("SELECT AVG(pg_column_size(payload))::float8 FROM {}", events);
+        let average_payload_size: Option<f64> = sqlx::query_scalar(average_payload_size_query.as_str())
+            .bind(range.start)
+            .bind(range.end)
+            .fetch_one(&self.inner.pool)
+            .await?;
+
+        let top_aggregates_query: String = format!(
+            "SELECT aggregate_id, COUNT(*) AS count FROM {} GROUP BY aggregate_id ORDER BY count DESC LIMIT $3",
+            events
+        );
+        let top_aggregates: Vec<AggregateCount> = sqlx::query_as::<_, AggregateCount>(top_aggregates_query.as_str())
+            .bind(range.start)
+            .bind(range.end)
+            .bind(top_aggregates)
+            .fetch_all(&self.inner.pool)
+            .await?;
+
+        Ok(Statistics {
+            events_per_day,
+            events_per_type,
+            average_payload_size,
+            top_aggregates,
+        })
+    }
+
+    /// Returns the number of events written at each version, sorted by version with the events
+    /// without a version first, measuring the progress of a migration to a new event version.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the query fails.
+    pub async fn events_per_version(&self) -> Result<Vec<VersionCount>, PgStoreError> {
+        let query: String = format!(
+            "SELECT version, COUNT(*) AS count FROM {} GROUP BY version ORDER BY version NULLS FIRST",
+            self.table_name()
+        );
+
+        Ok(sqlx::query_as::<_, VersionCount>(query.as_str())
+            .fetch_all(&self.inner.pool)
+            .await?)
+    }
+}
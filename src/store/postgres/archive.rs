@@ -0,0 +1,102 @@
+use uuid::Uuid;
+
+use crate::sql::event::DbRawEvent;
+use crate::store::StoreEvent;
+use crate::{Aggregate, AggregateState};
+
+use super::persistable::Persistable;
+use super::temporal::into_aggregate_state;
+use super::{PgStore, PgStoreError, Schema};
+
+impl<A, S> PgStore<A, S>
+where
+    A: Aggregate,
+    A::Event: Send + Sync,
+    S: Schema<A::Event> + Persistable + Send + Sync,
+{
+    /// Marks the given aggregate instance as closed, in the `{table}_closed` table, so that its
+    /// events are moved to the `{table}_archive` table by the next [`PgStore::archive_closed`].
+    ///
+    /// A closed aggregate instance is not expected to receive any further event: once archived, it
+    /// isn't found by the regular loading anymore, but only by [`PgStore::load_archived`].
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the store hasn't been built with
+    /// [`super::PgStoreBuilder::with_archival`], or the insert fails.
+    pub async fn close(&self, aggregate_id: Uuid) -> Result<(), PgStoreError> {
+        let _ = sqlx::query(
+            format!(
+                include_str!("../../sql/postgres/statements/insert_closed.sql"),
+                self.table_name()
+            )
+            .as_str(),
+        )
+        .bind(aggregate_id)
+        .execute(&self.inner.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Moves the events of at most `batch_size` closed aggregate instances from the event store
+    /// table to the `{table}_archive` table, keeping the former limited to the active aggregate
+    /// instances. Returns the number of archived aggregate instances.
+    ///
+    /// Many movers can run at once, each closed aggregate instance being archived by one of them.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the store hasn't been built with
+    /// [`super::PgStoreBuilder::with_archival`], or the query fails.
+    pub async fn archive_closed(&self, batch_size: i64) -> Result<u64, PgStoreError> {
+        let result = sqlx::query(
+            format!(
+                include_str!("../../sql/postgres/statements/archive_closed.sql"),
+                self.table_name()
+            )
+            .as_str(),
+        )
+        .bind(batch_size)
+        .execute(&self.inner.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Loads the archived events of the given aggregate instance, ordered by sequence number.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the store hasn't been built with
+    /// [`super::PgStoreBuilder::with_archival`], or the query fails.
+    pub async fn by_aggregate_id_archived(
+        &self,
+        aggregate_id: Uuid,
+    ) -> Result<Vec<StoreEvent<A::Event>>, PgStoreError> {
+        let query: String = format!(
+            include_str!("../../sql/postgres/statements/select_archived_by_aggregate_id.sql"),
+            self.table_name()
+        );
+
+        self.fetch_events(sqlx::query_as::<_, DbRawEvent>(query.as_str()).bind(aggregate_id))
+            .await
+    }
+
+    /// Reconstructs the state of the given archived aggregate instance. Returns `None` if the
+    /// aggregate instance hasn't been archived.
+    ///
+    /// The returned state is meant to be inspected only: it must not be used to handle commands.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the store hasn't been built with
+    /// [`super::PgStoreBuilder::with_archival`], or the query fails.
+    pub async fn load_archived(&self, aggregate_id: Uuid) -> Result<Option<AggregateState<A::State>>, PgStoreError>
+    where
+        A::State: Default,
+    {
+        let store_events = self.by_aggregate_id_archived(aggregate_id).await?;
+        Ok(into_aggregate_state::<A>(aggregate_id, store_events))
+    }
+}
@@ -0,0 +1,120 @@
+use std::time::Duration;
+
+use sqlx::{Postgres, Transaction};
+use typed_builder::TypedBuilder;
+use uuid::Uuid;
+
+use crate::sql::event::DbRawEvent;
+use crate::store::StoreEvent;
+use crate::Aggregate;
+
+use super::persistable::Persistable;
+use super::{ColumnValue, CustomColumns, PgStore, PgStoreError, Schema};
+
+#[derive(TypedBuilder)]
+pub struct BackfillConfig {
+    /// The number of rows updated in every transaction.
+    #[builder(default = 1000)]
+    pub(crate) batch_size: i64,
+    /// The time to wait between two batches, to limit the load on the database.
+    #[builder(default = Duration::ZERO)]
+    pub(crate) throttle: Duration,
+}
+
+/// Summary of a completed backfill.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub struct BackfillReport {
+    /// The number of updated rows.
+    pub rows: u64,
+    /// The number of committed transactions.
+    pub batches: u64,
+}
+
+impl<A, S> PgStore<A, S>
+where
+    A: Aggregate,
+    A::Event: Send + Sync,
+    S: Schema<A::Event> + Persistable + Send + Sync,
+{
+    /// Populates the given [`CustomColumns`] for all the events already in the event store, e.g.
+    /// after introducing them in an existing installation.
+    ///
+    /// Rows are walked in id order and updated in batched transactions, waiting for the configured
+    /// throttle between them. Since the values are computed from the events, it is safe to run it
+    /// again if interrupted. Events skipped by the schema are left untouched.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if an event can't be deserialized or a batch fails to be updated. The
+    /// batches committed until then are kept.
+    pub async fn backfill(
+        &self,
+        custom_columns: &impl CustomColumns<A::Event>,
+        config: BackfillConfig,
+    ) -> Result<BackfillReport, PgStoreError> {
+        let assignments: Vec<String> = custom_columns
+            .columns()
+            .iter()
+            .enumerate()
+            .map(|(i, column)| format!("{} = ${}", column.name(), i + 2))
+            .collect();
+
+        let mut report = BackfillReport::default();
+
+        if assignments.is_empty() {
+            return Ok(report);
+        }
+
+        let select: String = format!(
+            include_str!("../../sql/postgres/statements/select_batch_after_id.sql"),
+            self.table_name()
+        );
+        let update: String = format!(
+            include_str!("../../sql/postgres/statements/update_custom_columns.sql"),
+            self.table_name(),
+            assignments.join(", ")
+        );
+
+        let mut last_id: Uuid = Uuid::nil();
+
+        loop {
+            let db_events: Vec<DbRawEvent> = sqlx::query_as::<_, DbRawEvent>(select.as_str())
+                .bind(last_id)
+                .bind(config.batch_size)
+                .fetch_all(&self.inner.pool)
+                .await?;
+
+            let Some(last) = db_events.last() else {
+                return Ok(report);
+            };
+            last_id = last.id;
+
+            let mut transaction: Transaction<Postgres> = self.inner.pool.begin().await?;
+
+            for db_event in db_events {
+                let store_event: Option<StoreEvent<A::Event>> = self.inner.decode_event::<S>(db_event).await?;
+
+                if let Some(store_event) = store_event {
+                    let _ = custom_columns
+                        .values(&store_event.payload)
+                        .into_iter()
+                        .fold(
+                            sqlx::query(update.as_str()).bind(store_event.id),
+                            |query, value: ColumnValue| value.bind(query),
+                        )
+                        .execute(&mut *transaction)
+                        .await?;
+
+                    report.rows += 1;
+                }
+            }
+
+            transaction.commit().await?;
+            report.batches += 1;
+
+            if !config.throttle.is_zero() {
+                crate::runtime::sleep(config.throttle).await;
+            }
+        }
+    }
+}
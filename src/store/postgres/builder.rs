@@ -1,18 +1,33 @@
 use std::marker::PhantomData;
 use std::sync::Arc;
+use std::time::Duration;
 
 use sqlx::{PgConnection, Pool, Postgres};
-use tokio::sync::RwLock;
+use tokio::sync::{RwLock, Semaphore};
+use uuid::Uuid;
 
-use crate::bus::EventBus;
-use crate::handler::{EventHandler, TransactionalEventHandler};
-use crate::sql::migrations::{Migrations, MigrationsHandler};
-use crate::sql::statements::{Statements, StatementsHandler};
+use crate::bus::{EventBus, PublishPolicy, RetryingEventBus};
+use crate::handler::{ErrorObserver, EventHandler, TransactionalEventHandler};
+use crate::interceptor::PersistInterceptor;
+use crate::sql::migrations::{MigrationStep, Migrations};
+use crate::sql::statements::Statements;
 use crate::store::postgres::{InnerPgStore, PgStoreError};
+use crate::types::SequenceNumber;
 use crate::Aggregate;
 
+use super::analysis::EventTypeLocation;
+use super::drift::schema_drift;
+#[cfg(feature = "encryption")]
+use super::encryption::DataKeyProvider;
+#[cfg(feature = "integrity")]
+use super::integrity::KeyProvider;
 use super::persistable::Persistable;
-use super::{PgStore, Schema};
+use super::search::search_vector_expression;
+use super::valid_time::{VALID_AT_COLUMN, VALID_TO_COLUMN};
+use super::{
+    AuditHook, Column, CustomColumns, DebeziumOutbox, EventLog, EventSchemaValidator, PgStore, Schema,
+    SchemaDriftError, SchemaDriftPolicy, ValidTime, Visibility,
+};
 
 /// The `UuidFormat` enum defines the UUID format preference:
 ///
@@ -23,17 +38,146 @@ pub enum UuidFormat {
     V7,
 }
 
+impl EventIdGenerator for UuidFormat {
+    fn generate(&self, _aggregate_id: Uuid, _sequence_number: SequenceNumber) -> Uuid {
+        match self {
+            UuidFormat::V4 => Uuid::new_v4(),
+            UuidFormat::V7 => Uuid::now_v7(),
+        }
+    }
+}
+
+/// Trait used to generate the ids of the events persisted by a [`PgStore`].
+pub trait EventIdGenerator: Send + Sync {
+    /// Generates the id of the event with the given sequence number of the given aggregate instance.
+    fn generate(&self, aggregate_id: Uuid, sequence_number: SequenceNumber) -> Uuid;
+}
+
+/// [`EventIdGenerator`] deriving the event ids from the aggregate id and the sequence number, using
+/// UUID version 5 as defined by RFC 9562 section 5.5 in the given namespace.
+///
+/// Persisting the same event twice generates the same id, making re-persisting idempotent.
+pub struct DeterministicEventIdGenerator {
+    namespace: Uuid,
+}
+
+impl DeterministicEventIdGenerator {
+    /// Creates a new [`DeterministicEventIdGenerator`] generating ids in the given namespace.
+    pub const fn new(namespace: Uuid) -> Self {
+        Self { namespace }
+    }
+}
+
+impl EventIdGenerator for DeterministicEventIdGenerator {
+    fn generate(&self, aggregate_id: Uuid, sequence_number: SequenceNumber) -> Uuid {
+        let mut name: Vec<u8> = aggregate_id.as_bytes().to_vec();
+        name.extend_from_slice(&sequence_number.to_be_bytes());
+        Uuid::new_v5(&self.namespace, &name)
+    }
+}
+
+/// Trait used to name the event store table of a [`PgStore`] after the name of its aggregate, e.g.
+/// to adopt an existing database following different conventions. Defaults to `{aggregate_name}_events`.
+///
+/// Any `Fn(&str) -> String` closure, taking the [`Aggregate::NAME`], is a [`TableNamingStrategy`].
+pub trait TableNamingStrategy: Send + Sync {
+    /// Returns the name of the event store table of the aggregate with the given [`Aggregate::NAME`].
+    fn table_name(&self, aggregate_name: &str) -> String;
+}
+
+impl<F> TableNamingStrategy for F
+where
+    F: Fn(&str) -> String + Send + Sync,
+{
+    fn table_name(&self, aggregate_name: &str) -> String {
+        self(aggregate_name)
+    }
+}
+
+/// The `OccurredOnStrategy` enum defines how the `occurred_on` timestamp of persisted events is
+/// computed:
+///
+/// - `Local`: Uses the clock of the application instance persisting the events.
+/// - `Database`: Uses the clock of the database, read at the beginning of the persisting transaction.
+/// - `Clamped`: Uses the clock of the application instance, clamped to be greater than or equal to
+///   the `occurred_on` of the latest event of the same aggregate instance.
+///
+/// With `Local`, clock skew across replicas might produce non-monotonic timestamps for the events of
+/// a single aggregate instance; both `Database` and `Clamped` avoid that.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OccurredOnStrategy {
+    Local,
+    Database,
+    Clamped,
+}
+
+/// The `LockStrategy` enum defines how the [`PgStore`] locks aggregate instances:
+///
+/// - `Advisory`: Uses a Postgres advisory lock keyed on the aggregate id.
+/// - `RowLevel`: Uses a `SELECT ... FOR UPDATE` on a per-aggregate row of the `{table}_locks` table,
+///   inside of a transaction held until the lock guard is dropped.
+pub enum LockStrategy {
+    Advisory,
+    RowLevel,
+}
+
+/// The `DeletionStrategy` enum defines how the [`PgStore`] deletes aggregate instances:
+///
+/// - `Hard`: Removes all the events of the aggregate instance from the event store table.
+/// - `Soft`: Marks all the events of the aggregate instance with a `deleted_at` timestamp, hiding
+///   them from loading and streaming while preserving the history. Soft deleted events are still
+///   available through [`PgStore::by_aggregate_id_including_deleted`] and
+///   [`PgStore::stream_events_including_deleted`].
+///
+/// In both cases the event handlers and the transactional event handlers are asked to delete their
+/// read side projections. Note that a soft deleted aggregate id cannot be reused.
+pub enum DeletionStrategy {
+    Hard,
+    Soft,
+}
+
 /// Struct used to build a brand new [`PgStore`].
 pub struct PgStoreBuilder<A, Schema = <A as Aggregate>::Event>
 where
     A: Aggregate,
 {
     pool: Pool<Postgres>,
-    statements: Statements,
+    read_pool: Option<Pool<Postgres>>,
+    table_name: Option<String>,
+    table_naming_strategy: Option<Box<dyn TableNamingStrategy>>,
+    database_schema: Option<String>,
     event_handlers: Vec<Box<dyn EventHandler<A> + Send>>,
+    event_handlers_concurrency: Option<usize>,
+    error_observers: Vec<Box<dyn ErrorObserver<A> + Send>>,
     transactional_event_handlers: Vec<Box<dyn TransactionalEventHandler<A, PgStoreError, PgConnection> + Send>>,
     event_buses: Vec<Box<dyn EventBus<A> + Send>>,
-    event_id_format: UuidFormat,
+    persist_interceptors: Vec<Box<dyn PersistInterceptor<A, PgStoreError, PgConnection> + Send>>,
+    audit_hooks: Vec<Box<dyn AuditHook + Send>>,
+    event_id_generator: Box<dyn EventIdGenerator>,
+    occurred_on_strategy: OccurredOnStrategy,
+    lock_strategy: LockStrategy,
+    statement_timeout: Option<Duration>,
+    deletion_strategy: DeletionStrategy,
+    custom_columns: Option<Box<dyn CustomColumns<A::Event> + Send>>,
+    valid_time: Option<Box<dyn ValidTime<A::Event> + Send>>,
+    visibility: Option<Box<dyn Visibility<A::Event> + Send>>,
+    schema_validator: Option<Box<dyn EventSchemaValidator>>,
+    outbox: bool,
+    #[cfg(feature = "upcasting")]
+    downcaster: Option<Box<dyn crate::event::Downcaster>>,
+    #[cfg(feature = "integrity")]
+    key_provider: Option<Box<dyn KeyProvider>>,
+    #[cfg(feature = "encryption")]
+    data_key_provider: Option<Box<dyn DataKeyProvider>>,
+    #[cfg(feature = "integrity")]
+    hash_chain: bool,
+    renamed_from: Option<String>,
+    shared_table: Option<String>,
+    schema_drift_policy: SchemaDriftPolicy,
+    archival: bool,
+    idempotency_tokens: bool,
+    payload_index: bool,
+    search_fields: Vec<String>,
     run_migrations: bool,
     _schema: PhantomData<Schema>,
 }
@@ -46,11 +190,42 @@ where
     pub fn new(pool: Pool<Postgres>) -> PgStoreBuilder<A, <A as Aggregate>::Event> {
         PgStoreBuilder {
             pool,
-            statements: Statements::new::<A>(),
+            read_pool: None,
+            table_name: None,
+            table_naming_strategy: None,
+            database_schema: None,
             event_handlers: vec![],
+            event_handlers_concurrency: None,
+            error_observers: vec![],
             transactional_event_handlers: vec![],
             event_buses: vec![],
-            event_id_format: UuidFormat::V4,
+            persist_interceptors: vec![],
+            audit_hooks: vec![],
+            event_id_generator: Box::new(UuidFormat::V4),
+            occurred_on_strategy: OccurredOnStrategy::Local,
+            lock_strategy: LockStrategy::Advisory,
+            statement_timeout: None,
+            deletion_strategy: DeletionStrategy::Hard,
+            custom_columns: None,
+            valid_time: None,
+            visibility: None,
+            schema_validator: None,
+            outbox: false,
+            #[cfg(feature = "upcasting")]
+            downcaster: None,
+            #[cfg(feature = "integrity")]
+            key_provider: None,
+            #[cfg(feature = "encryption")]
+            data_key_provider: None,
+            #[cfg(feature = "integrity")]
+            hash_chain: false,
+            renamed_from: None,
+            shared_table: None,
+            schema_drift_policy: SchemaDriftPolicy::Ignore,
+            archival: false,
+            idempotency_tokens: false,
+            payload_index: false,
+            search_fields: vec![],
             run_migrations: true,
             _schema: PhantomData,
         }
@@ -73,6 +248,42 @@ where
         self
     }
 
+    /// Add an [`EventLog`] event handler, emitting a structured `tracing` event for each persisted
+    /// event, reading the event types from the payloads according to the given location.
+    pub fn with_event_log(self, event_type_location: EventTypeLocation) -> Self
+    where
+        A::Event: Send + Sync,
+    {
+        self.add_event_handler(EventLog::new(event_type_location))
+    }
+
+    /// Add a [`DebeziumOutbox`], writing each persisted event to an outbox table following the
+    /// Debezium outbox event router conventions, in the same transaction.
+    pub fn with_debezium_outbox(self, debezium_outbox: DebeziumOutbox) -> Self
+    where
+        A::Event: Send + Sync,
+    {
+        self.add_transactional_event_handler(debezium_outbox)
+    }
+
+    /// Set the maximum number of event handlers running at the same time, across all the
+    /// concurrently handled commands.
+    ///
+    /// Event handlers sharing the pool of the store might otherwise take all of its connections,
+    /// starving the persistence of new events. Bounding them to less than the size of the pool (or
+    /// giving them their own pool) keeps the write path available. A limit of `0` is treated as `1`.
+    pub fn with_event_handlers_concurrency(mut self, limit: usize) -> Self {
+        self.event_handlers_concurrency = Some(limit.max(1));
+        self
+    }
+
+    /// Add an observer of the failures of the event handlers, as reported by
+    /// [`EventHandler::try_handle`]. See [`ErrorObserver`].
+    pub fn add_error_observer(mut self, error_observer: impl ErrorObserver<A> + Send + 'static) -> Self {
+        self.error_observers.push(Box::new(error_observer));
+        self
+    }
+
     /// Set transactional event handlers list
     pub fn with_transactional_event_handlers(
         mut self,
@@ -104,6 +315,56 @@ where
         self
     }
 
+    /// Add a single event bus, publishing to it according to the given [`PublishPolicy`]: failed
+    /// publishings are retried with an exponential backoff, and then dead-lettered. See
+    /// [`RetryingEventBus`].
+    pub fn add_event_bus_with_policy<B>(self, event_bus: B, policy: PublishPolicy<A>) -> Self
+    where
+        A: Sync + 'static,
+        A::Event: Sync,
+        B: EventBus<A> + Send + 'static,
+    {
+        self.add_event_bus(RetryingEventBus::new(event_bus, policy))
+    }
+
+    /// Set persist interceptors list
+    pub fn with_persist_interceptors(
+        mut self,
+        persist_interceptors: Vec<Box<dyn PersistInterceptor<A, PgStoreError, PgConnection> + Send>>,
+    ) -> Self {
+        self.persist_interceptors = persist_interceptors;
+        self
+    }
+
+    /// Add a single persist interceptor
+    pub fn add_persist_interceptor(
+        mut self,
+        persist_interceptor: impl PersistInterceptor<A, PgStoreError, PgConnection> + Send + 'static,
+    ) -> Self {
+        self.persist_interceptors.push(Box::new(persist_interceptor));
+        self
+    }
+
+    /// Add a single audit hook, called for every correction applied through
+    /// [`PgStore::update_event_payload`] and [`PgStore::delete_event`].
+    pub fn add_audit_hook(mut self, audit_hook: impl AuditHook + Send + 'static) -> Self {
+        self.audit_hooks.push(Box::new(audit_hook));
+        self
+    }
+
+    /// Set the pool the events are loaded from by [`crate::store::EventStore::by_aggregate_id`] and
+    /// [`crate::store::EventStore::by_aggregate_id_after`], e.g. connected to a read replica, so that
+    /// loads don't weigh on the primary. Persisting, deleting, locking and running migrations keep
+    /// using the pool of the store. See [`PgStore::read_pool`] to stream the events from it.
+    ///
+    /// Note that a lagging replica might return a stale aggregate state, even after locking it: the
+    /// events then fail to be persisted on the sequence number uniqueness constraint, and the command
+    /// can be retried through [`crate::manager::AggregateManager::handle_command_with_retry`].
+    pub fn with_read_pool(mut self, read_pool: Pool<Postgres>) -> Self {
+        self.read_pool = Some(read_pool);
+        self
+    }
+
     /// Calling this function the caller avoid running migrations. It is recommend to run migrations
     /// at least once per store per startup.
     pub fn without_running_migrations(mut self) -> Self {
@@ -118,22 +379,425 @@ where
     {
         PgStoreBuilder {
             pool: self.pool,
-            statements: self.statements,
+            read_pool: self.read_pool,
+            table_name: self.table_name,
+            table_naming_strategy: self.table_naming_strategy,
+            database_schema: self.database_schema,
             run_migrations: self.run_migrations,
             event_handlers: self.event_handlers,
+            event_handlers_concurrency: self.event_handlers_concurrency,
+            error_observers: self.error_observers,
             transactional_event_handlers: self.transactional_event_handlers,
             event_buses: self.event_buses,
-            event_id_format: self.event_id_format,
+            persist_interceptors: self.persist_interceptors,
+            audit_hooks: self.audit_hooks,
+            event_id_generator: self.event_id_generator,
+            occurred_on_strategy: self.occurred_on_strategy,
+            lock_strategy: self.lock_strategy,
+            statement_timeout: self.statement_timeout,
+            deletion_strategy: self.deletion_strategy,
+            custom_columns: self.custom_columns,
+            valid_time: self.valid_time,
+            visibility: self.visibility,
+            schema_validator: self.schema_validator,
+            outbox: self.outbox,
+            #[cfg(feature = "upcasting")]
+            downcaster: self.downcaster,
+            #[cfg(feature = "integrity")]
+            key_provider: self.key_provider,
+            #[cfg(feature = "encryption")]
+            data_key_provider: self.data_key_provider,
+            #[cfg(feature = "integrity")]
+            hash_chain: self.hash_chain,
+            renamed_from: self.renamed_from,
+            shared_table: self.shared_table,
+            schema_drift_policy: self.schema_drift_policy,
+            archival: self.archival,
+            idempotency_tokens: self.idempotency_tokens,
+            payload_index: self.payload_index,
+            search_fields: self.search_fields,
             _schema: PhantomData,
         }
     }
 
     /// Set the UUID format of event IDs.
     pub fn with_event_id_format(mut self, event_id_format: UuidFormat) -> Self {
-        self.event_id_format = event_id_format;
+        self.event_id_generator = Box::new(event_id_format);
+        self
+    }
+
+    /// Set the generator of event IDs. Defaults to [`UuidFormat::V4`].
+    pub fn with_event_id_generator(mut self, event_id_generator: impl EventIdGenerator + 'static) -> Self {
+        self.event_id_generator = Box::new(event_id_generator);
         self
     }
 
+    /// Set the strategy used to compute the `occurred_on` timestamp of persisted events.
+    pub fn with_occurred_on_strategy(mut self, occurred_on_strategy: OccurredOnStrategy) -> Self {
+        self.occurred_on_strategy = occurred_on_strategy;
+        self
+    }
+
+    /// Set the strategy used to lock aggregate instances. Defaults to [`LockStrategy::Advisory`].
+    pub fn with_lock_strategy(mut self, lock_strategy: LockStrategy) -> Self {
+        self.lock_strategy = lock_strategy;
+        self
+    }
+
+    /// Set the maximum time each statement run by the store may take, independently from the
+    /// defaults of the pool, so that a runaway query or a lock wait can't hold a connection
+    /// indefinitely. Exceeding it makes the statement fail with an error.
+    ///
+    /// It applies to the transactions in which the events are persisted and deleted, to the row level
+    /// locks (see [`LockStrategy::RowLevel`]), to [`crate::store::EventStore::by_aggregate_id`] and
+    /// to the streams run on a transaction begun with [`PgStore::begin`].
+    pub fn with_statement_timeout(mut self, statement_timeout: Duration) -> Self {
+        self.statement_timeout = Some(statement_timeout);
+        self
+    }
+
+    /// Set the strategy used to delete aggregate instances. Defaults to [`DeletionStrategy::Hard`].
+    pub fn with_deletion_strategy(mut self, deletion_strategy: DeletionStrategy) -> Self {
+        self.deletion_strategy = deletion_strategy;
+        self
+    }
+
+    /// Set the additional columns of the event store table, populated with the values computed
+    /// from each persisted event. See [`CustomColumns`].
+    pub fn with_custom_columns(mut self, custom_columns: impl CustomColumns<A::Event> + Send + 'static) -> Self {
+        self.custom_columns = Some(Box::new(custom_columns));
+        self
+    }
+
+    /// Set the hook providing the business time of the events, stored in the `valid_at` and
+    /// `valid_to` columns.
+    /// See [`ValidTime`].
+    pub fn with_valid_time(mut self, valid_time: impl ValidTime<A::Event> + Send + 'static) -> Self {
+        self.valid_time = Some(Box::new(valid_time));
+        self
+    }
+
+    /// Set the hook providing the visibility time of the events, deferring the handling and the
+    /// publishing of the events visible in the future. See [`Visibility`].
+    pub fn with_visibility(mut self, visibility: impl Visibility<A::Event> + Send + 'static) -> Self {
+        self.visibility = Some(Box::new(visibility));
+        self
+    }
+
+    /// Writes the events to be published to the `{table}_outbox` table, in the same transaction as
+    /// the events, instead of publishing them to the event buses right after persisting them. The
+    /// events are then published by an [`super::OutboxRelay`], retrying until the buses accept
+    /// them, so that no event is lost if the process crashes or a bus is unavailable.
+    ///
+    /// Publishing becomes at-least-once: the buses might receive the same event more than once.
+    pub fn with_outbox(mut self) -> Self {
+        self.outbox = true;
+        self
+    }
+
+    /// Set the hook validating the serialized payloads of the events before they are written,
+    /// rejecting the events violating their schema. See [`EventSchemaValidator`].
+    pub fn with_schema_validator(mut self, schema_validator: impl EventSchemaValidator + 'static) -> Self {
+        self.schema_validator = Some(Box::new(schema_validator));
+        self
+    }
+
+    /// Set the hook rewriting the events before they are written, so that they can be read by the
+    /// instances running the previous version of the code. See [`crate::event::Downcaster`].
+    #[cfg(feature = "upcasting")]
+    pub fn with_downcaster(mut self, downcaster: impl crate::event::Downcaster + 'static) -> Self {
+        self.downcaster = Some(Box::new(downcaster));
+        self
+    }
+
+    /// Set the provider of the keys used to sign the persisted events with an HMAC, stored in the
+    /// `signature` and `signature_key_id` columns. See [`PgStore::verify_signatures`].
+    #[cfg(feature = "integrity")]
+    pub fn with_key_provider(mut self, key_provider: impl KeyProvider + 'static) -> Self {
+        self.key_provider = Some(Box::new(key_provider));
+        self
+    }
+
+    /// Set the provider of the data keys encrypting the payloads of the events at rest, one per
+    /// aggregate instance. See [`super::DataKeyProvider`] and [`PgStore::shred`].
+    #[cfg(feature = "encryption")]
+    pub fn with_data_key_provider(mut self, data_key_provider: impl DataKeyProvider + 'static) -> Self {
+        self.data_key_provider = Some(Box::new(data_key_provider));
+        self
+    }
+
+    /// Link each persisted event to the previous one of its aggregate instance through a SHA-256
+    /// hash chain, stored in the `previous_hash` and `hash` columns. See [`PgStore::verify_chain`].
+    #[cfg(feature = "integrity")]
+    pub fn with_hash_chain(mut self) -> Self {
+        self.hash_chain = true;
+        self
+    }
+
+    /// Set the name of the event store table, instead of the one given by the
+    /// [`TableNamingStrategy`]. The tables backing the options of the store (locks, deferred, outbox,
+    /// closed and archive) are named after it.
+    ///
+    /// The name is interpolated as is in the statements, so it must be a valid identifier.
+    pub fn with_table_name(mut self, table_name: &str) -> Self {
+        self.table_name = Some(table_name.to_string());
+        self
+    }
+
+    /// Set the strategy naming the event store table after the name of the aggregate. Defaults to
+    /// `{aggregate_name}_events`. See [`TableNamingStrategy`].
+    pub fn with_table_naming_strategy(mut self, table_naming_strategy: impl TableNamingStrategy + 'static) -> Self {
+        self.table_naming_strategy = Some(Box::new(table_naming_strategy));
+        self
+    }
+
+    /// Set the Postgres schema holding the event store table, and the shared table if any, instead of
+    /// the current schema of the connections. The schema must exist: it is not created while running
+    /// migrations.
+    pub fn with_database_schema(mut self, database_schema: &str) -> Self {
+        self.database_schema = Some(database_schema.to_string());
+        self
+    }
+
+    /// Set the previous name of the aggregate, when it has been renamed. While running migrations,
+    /// the event store table of the old name (with its indexes) is renamed after the new one, if the
+    /// latter doesn't exist yet. The old table is expected to be named by the same
+    /// [`TableNamingStrategy`], in the same schema.
+    pub fn renamed_from(mut self, old_name: &str) -> Self {
+        self.renamed_from = Some(old_name.to_string());
+        self
+    }
+
+    /// Store the events in the given table, shared by many aggregates and holding the name of the
+    /// aggregate of each event in its `aggregate_type` column, instead of a table per aggregate. This
+    /// simplifies the global ordering of the events, the subscriptions across aggregates and the
+    /// operations of systems with many small aggregates.
+    ///
+    /// The event store table of the aggregate becomes an updatable view over the shared table,
+    /// filtered by [`Aggregate::NAME`]. The options adding columns or indexes to the event store
+    /// table, or tables referencing it (custom columns, valid time, soft deletion, idempotency
+    /// tokens, payload index, search fields, integrity, archival, outbox, visibility and renaming)
+    /// aren't supported, and building the store fails if any is set.
+    ///
+    /// The view lists the columns of the shared table explicitly: columns added to the shared table
+    /// by hand aren't visible through it.
+    pub fn with_shared_table(mut self, shared_table_name: &str) -> Self {
+        self.shared_table = Some(shared_table_name.to_string());
+        self
+    }
+
+    /// Creates the `{table}_closed` and `{table}_archive` tables while running migrations, enabling
+    /// [`PgStore::close`] and [`PgStore::archive_closed`]. The archive table mirrors the event store
+    /// table as it is when first created: columns added afterwards must be added to both.
+    pub fn with_archival(mut self) -> Self {
+        self.archival = true;
+        self
+    }
+
+    /// Adds the `idempotency_token` column to the event store table while running migrations,
+    /// enabling [`PgStore::persist_idempotent`].
+    pub fn with_idempotency_tokens(mut self) -> Self {
+        self.idempotency_tokens = true;
+        self
+    }
+
+    /// Creates a GIN index over the event payloads while running migrations, so that payload
+    /// containment queries perform acceptably on large tables.
+    pub fn with_payload_index(mut self) -> Self {
+        self.payload_index = true;
+        self
+    }
+
+    /// Set the payload fields, found at any depth of the payloads, whose values are indexed for full
+    /// text search through [`PgStore::search_events`]. Field names are interpolated as is in the
+    /// generated column definition, so they must be valid identifiers.
+    ///
+    /// Note that the generated `search_vector` column is created only once: changing the fields
+    /// afterwards requires dropping the column.
+    pub fn with_search_fields(mut self, fields: &[&str]) -> Self {
+        self.search_fields = fields.iter().map(|field| field.to_string()).collect();
+        self
+    }
+
+    /// Set how to react when the event store table doesn't match the schema expected by the current
+    /// version of the crate, checked after running migrations. Defaults to
+    /// [`SchemaDriftPolicy::Ignore`].
+    pub fn with_schema_drift_policy(mut self, schema_drift_policy: SchemaDriftPolicy) -> Self {
+        self.schema_drift_policy = schema_drift_policy;
+        self
+    }
+
+    /// Returns the [`MigrationSteps`] that [`PgStoreBuilder::try_build`] would run to set up the
+    /// database, in order, so that they can be run through an external migration pipeline instead,
+    /// along with [`PgStoreBuilder::without_running_migrations`].
+    ///
+    /// The rename of the tables set through [`PgStoreBuilder::renamed_from`] is not included, since
+    /// it depends on the current state of the database.
+    ///
+    /// [`MigrationSteps`]: MigrationStep
+    pub fn migration_steps(&self) -> Vec<MigrationStep> {
+        let table_name: &str = &self.table_name();
+        let columns: Vec<Column> = self.columns();
+        let mut steps: Vec<MigrationStep> = vec![match self.shared_table_name() {
+            Some(shared_table_name) => Migrations::shared_table::<A>(table_name, &shared_table_name),
+            None => Migrations::create_table(table_name),
+        }];
+
+        if let LockStrategy::RowLevel = self.lock_strategy {
+            steps.push(Migrations::locks_table(table_name));
+        }
+
+        if self.visibility.is_some() {
+            steps.push(Migrations::deferred_table(table_name));
+        }
+
+        if self.outbox {
+            steps.push(Migrations::outbox_table(table_name));
+        }
+
+        #[cfg(feature = "integrity")]
+        if self.key_provider.is_some() {
+            steps.push(Migrations::signature_columns(table_name));
+        }
+
+        #[cfg(feature = "integrity")]
+        if self.hash_chain {
+            steps.push(Migrations::hash_chain_columns(table_name));
+        }
+
+        if let DeletionStrategy::Soft = self.deletion_strategy {
+            steps.push(Migrations::deleted_at(table_name));
+        }
+
+        if self.idempotency_tokens {
+            steps.push(Migrations::idempotency_token(table_name));
+        }
+
+        if self.payload_index {
+            steps.push(Migrations::payload_index(table_name));
+        }
+
+        if !self.search_fields.is_empty() {
+            let expression: String = search_vector_expression(&self.search_fields);
+            steps.push(Migrations::search_vector(table_name, &expression));
+        }
+
+        if !columns.is_empty() {
+            steps.push(Migrations::custom_columns(table_name, &columns));
+        }
+
+        // The archive table mirrors the event store table, so it must be created last.
+        if self.archival {
+            steps.push(Migrations::archive_tables(table_name));
+        }
+
+        steps
+    }
+
+    /// The pool the store is being built on.
+    pub(super) fn pool(&self) -> &Pool<Postgres> {
+        &self.pool
+    }
+
+    /// The name of the event store table, qualified with the database schema, if any.
+    pub(super) fn table_name(&self) -> String {
+        let table_name: String = match self.table_name.as_ref() {
+            Some(table_name) => table_name.clone(),
+            None => self.table_name_of(A::NAME),
+        };
+
+        self.qualified(table_name)
+    }
+
+    /// The name given by the [`TableNamingStrategy`] to the event store table of the aggregate with
+    /// the given name.
+    fn table_name_of(&self, aggregate_name: &str) -> String {
+        match self.table_naming_strategy.as_ref() {
+            Some(table_naming_strategy) => table_naming_strategy.table_name(aggregate_name),
+            None => format!("{}_events", aggregate_name),
+        }
+    }
+
+    /// The name of the shared table, if any, qualified with the database schema, if any.
+    fn shared_table_name(&self) -> Option<String> {
+        self.shared_table
+            .clone()
+            .map(|shared_table| self.qualified(shared_table))
+    }
+
+    /// Qualifies the given table name with the database schema, if any and if not qualified already.
+    fn qualified(&self, table_name: String) -> String {
+        match self.database_schema.as_deref() {
+            Some(database_schema) if !table_name.contains('.') => format!("{}.{}", database_schema, table_name),
+            _ => table_name,
+        }
+    }
+
+    /// The options set along with [`PgStoreBuilder::with_shared_table`] that require an event store
+    /// table of its own.
+    fn shared_table_conflicts(&self) -> Vec<&'static str> {
+        let mut conflicts: Vec<&'static str> = vec![];
+
+        if !self.columns().is_empty() {
+            conflicts.push("custom columns and valid time");
+        }
+
+        if let DeletionStrategy::Soft = self.deletion_strategy {
+            conflicts.push("soft deletion");
+        }
+
+        if self.idempotency_tokens {
+            conflicts.push("idempotency tokens");
+        }
+
+        if self.payload_index {
+            conflicts.push("payload index");
+        }
+
+        if !self.search_fields.is_empty() {
+            conflicts.push("search fields");
+        }
+
+        #[cfg(feature = "integrity")]
+        if self.key_provider.is_some() || self.hash_chain {
+            conflicts.push("integrity");
+        }
+
+        if self.archival {
+            conflicts.push("archival");
+        }
+
+        if self.outbox {
+            conflicts.push("outbox");
+        }
+
+        if self.visibility.is_some() {
+            conflicts.push("visibility");
+        }
+
+        if self.renamed_from.is_some() {
+            conflicts.push("renaming");
+        }
+
+        conflicts
+    }
+
+    /// The additional columns of the event store table.
+    fn columns(&self) -> Vec<Column> {
+        let mut columns: Vec<Column> = vec![];
+
+        if self.valid_time.is_some() {
+            columns.push(VALID_AT_COLUMN);
+            columns.push(VALID_TO_COLUMN);
+        }
+
+        if let Some(custom_columns) = self.custom_columns.as_ref() {
+            columns.extend(custom_columns.columns());
+        }
+
+        columns
+    }
+
     /// This function runs all the needed [`Migrations`], atomically setting up the database if
     /// `run_migrations` isn't explicitly set to false. [`Migrations`] should be run only at application
     /// startup due to avoid performance issues.
@@ -142,20 +806,111 @@ where
     ///
     /// # Errors
     ///
-    /// Will return an `Err` if there's an error running [`Migrations`].
+    /// Will return an `Err` if there's an error running [`Migrations`], if the event store table
+    /// drifted from the expected schema and the [`SchemaDriftPolicy`] is `Deny`, or if options not
+    /// supported by the shared table set through [`PgStoreBuilder::with_shared_table`] are set.
     pub async fn try_build(self) -> Result<PgStore<A, S>, sqlx::Error> {
+        let columns: Vec<Column> = self.columns();
+        let table_name: String = self.table_name();
+        let shared_table_name: Option<String> = self.shared_table_name();
+
+        if let Some(shared_table_name) = shared_table_name.as_deref() {
+            let conflicts: Vec<&str> = self.shared_table_conflicts();
+
+            if !conflicts.is_empty() {
+                return Err(sqlx::Error::Configuration(
+                    format!(
+                        "shared table `{}` doesn't support: {}",
+                        shared_table_name,
+                        conflicts.join(", ")
+                    )
+                    .into(),
+                ));
+            }
+        }
+
         if self.run_migrations {
-            Migrations::run::<A>(&self.pool).await?;
+            if let Some(old_name) = self.renamed_from.as_deref() {
+                let old_table_name: String = self.qualified(self.table_name_of(old_name));
+                Migrations::run_rename(&self.pool, &old_table_name, &table_name, &columns).await?;
+            }
+
+            for step in self.migration_steps() {
+                step.run(&self.pool).await?;
+            }
         }
 
+        if !matches!(self.schema_drift_policy, SchemaDriftPolicy::Ignore) {
+            let table_name: &str = table_name.as_str();
+            let mismatches: Vec<String> = schema_drift(
+                &self.pool,
+                table_name,
+                shared_table_name.as_deref().unwrap_or(table_name),
+                &columns,
+                &self.lock_strategy,
+                self.payload_index,
+                !self.search_fields.is_empty(),
+            )
+            .await?;
+
+            if !mismatches.is_empty() {
+                if let SchemaDriftPolicy::Deny = self.schema_drift_policy {
+                    return Err(sqlx::Error::Configuration(Box::new(SchemaDriftError {
+                        table_name: table_name.to_string(),
+                        mismatches,
+                    })));
+                }
+
+                tracing::error!({
+                    table_name = table_name,
+                    mismatches = ?mismatches,
+                }, "event store table drifted from the expected schema");
+            }
+        }
+
+        let statements: Statements = Statements::for_table(&table_name);
+        let statements = if columns.is_empty() {
+            statements
+        } else {
+            statements.with_custom_columns(&columns)
+        };
+
+        let statements = match self.deletion_strategy {
+            DeletionStrategy::Hard => statements,
+            DeletionStrategy::Soft => statements.with_soft_delete(),
+        };
+
         Ok(PgStore {
             inner: Arc::new(InnerPgStore {
                 pool: self.pool,
-                statements: self.statements,
+                read_pool: self.read_pool,
+                statements,
+                database_schema: self.database_schema,
                 event_handlers: RwLock::new(self.event_handlers),
+                event_handlers_budget: self.event_handlers_concurrency.map(Semaphore::new),
+                error_observers: self.error_observers,
                 transactional_event_handlers: self.transactional_event_handlers,
-                event_buses: self.event_buses,
-                event_id_format: self.event_id_format,
+                event_buses: RwLock::new(self.event_buses),
+                persist_interceptors: self.persist_interceptors,
+                audit_hooks: self.audit_hooks,
+                event_id_generator: self.event_id_generator,
+                occurred_on_strategy: self.occurred_on_strategy,
+                lock_strategy: self.lock_strategy,
+                statement_timeout: self.statement_timeout,
+                custom_columns: self.custom_columns,
+                valid_time: self.valid_time,
+                visibility: self.visibility,
+                schema_validator: self.schema_validator,
+                outbox: self.outbox,
+                #[cfg(feature = "upcasting")]
+                downcaster: self.downcaster,
+                #[cfg(feature = "integrity")]
+                key_provider: self.key_provider,
+                #[cfg(feature = "encryption")]
+                data_key_provider: self.data_key_provider,
+                #[cfg(feature = "integrity")]
+                hash_chain: self.hash_chain,
+                idempotency_tokens: self.idempotency_tokens,
             }),
             _schema: self._schema,
         })
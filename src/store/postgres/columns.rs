@@ -0,0 +1,186 @@
+use chrono::{DateTime, Utc};
+use sqlx::postgres::PgArguments;
+use sqlx::query::Query;
+use sqlx::Postgres;
+use uuid::Uuid;
+
+/// Postgres type of a [`Column`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ColumnType {
+    Text,
+    Uuid,
+    Integer,
+    BigInt,
+    Boolean,
+    Timestamp,
+    Jsonb,
+}
+
+impl ColumnType {
+    pub(crate) const fn as_sql(&self) -> &'static str {
+        match self {
+            Self::Text => "TEXT",
+            Self::Uuid => "UUID",
+            Self::Integer => "INTEGER",
+            Self::BigInt => "BIGINT",
+            Self::Boolean => "BOOLEAN",
+            Self::Timestamp => "TIMESTAMPTZ",
+            Self::Jsonb => "JSONB",
+        }
+    }
+
+    /// Name of the type as reported by `information_schema.columns`.
+    pub(crate) const fn as_data_type(&self) -> &'static str {
+        match self {
+            Self::Text => "text",
+            Self::Uuid => "uuid",
+            Self::Integer => "integer",
+            Self::BigInt => "bigint",
+            Self::Boolean => "boolean",
+            Self::Timestamp => "timestamp with time zone",
+            Self::Jsonb => "jsonb",
+        }
+    }
+}
+
+/// Definition of an additional, nullable column of the event store table.
+///
+/// The column name is interpolated as is in the statements, so it must be a valid Postgres
+/// identifier and must not clash with the default columns of the table.
+#[derive(Clone, Debug)]
+pub struct Column {
+    name: &'static str,
+    column_type: ColumnType,
+    indexed: bool,
+}
+
+impl Column {
+    /// Creates a new, non indexed, [`Column`].
+    pub const fn new(name: &'static str, column_type: ColumnType) -> Self {
+        Self {
+            name,
+            column_type,
+            indexed: false,
+        }
+    }
+
+    /// Creates an index over this column while setting up the store.
+    pub const fn indexed(mut self) -> Self {
+        self.indexed = true;
+        self
+    }
+
+    /// Returns the name of the column.
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns the Postgres type of the column.
+    pub const fn column_type(&self) -> ColumnType {
+        self.column_type
+    }
+
+    /// Returns whether the column is indexed.
+    pub const fn is_indexed(&self) -> bool {
+        self.indexed
+    }
+}
+
+/// Value of a [`Column`] computed from an event. `None` is stored as `NULL`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ColumnValue {
+    Text(Option<String>),
+    Uuid(Option<Uuid>),
+    Integer(Option<i32>),
+    BigInt(Option<i64>),
+    Boolean(Option<bool>),
+    Timestamp(Option<DateTime<Utc>>),
+    Jsonb(Option<serde_json::Value>),
+}
+
+impl ColumnValue {
+    pub(crate) fn bind<'q>(self, query: Query<'q, Postgres, PgArguments>) -> Query<'q, Postgres, PgArguments> {
+        match self {
+            Self::Text(value) => query.bind(value),
+            Self::Uuid(value) => query.bind(value),
+            Self::Integer(value) => query.bind(value),
+            Self::BigInt(value) => query.bind(value),
+            Self::Boolean(value) => query.bind(value),
+            Self::Timestamp(value) => query.bind(value),
+            Self::Jsonb(value) => query.bind(value),
+        }
+    }
+}
+
+macro_rules! impl_from_for_column_value {
+    ($($ty:ty => $variant:ident),* $(,)?) => {
+        $(
+            impl From<$ty> for ColumnValue {
+                fn from(value: $ty) -> Self {
+                    Self::$variant(Some(value.into()))
+                }
+            }
+
+            impl From<Option<$ty>> for ColumnValue {
+                fn from(value: Option<$ty>) -> Self {
+                    Self::$variant(value.map(Into::into))
+                }
+            }
+        )*
+    };
+}
+
+impl_from_for_column_value!(
+    String => Text,
+    &str => Text,
+    Uuid => Uuid,
+    i32 => Integer,
+    i64 => BigInt,
+    bool => Boolean,
+    DateTime<Utc> => Timestamp,
+    serde_json::Value => Jsonb,
+);
+
+/// Hook letting an aggregate contribute additional typed columns to its event store table, so that
+/// common query dimensions (e.g. `customer_id`, `status`) are available without a projection.
+///
+/// The columns are created while setting up the [`super::PgStore`], and populated with the values
+/// computed from each event in the same insert statement persisting it.
+///
+/// ```rust
+/// # use esrs::store::postgres::{Column, ColumnType, ColumnValue, CustomColumns};
+/// # use uuid::Uuid;
+/// pub enum OrderEvent {
+///     Placed { customer_id: Uuid },
+///     Shipped,
+/// }
+///
+/// pub struct OrderColumns;
+///
+/// impl CustomColumns<OrderEvent> for OrderColumns {
+///     fn columns(&self) -> Vec<Column> {
+///         vec![
+///             Column::new("customer_id", ColumnType::Uuid).indexed(),
+///             Column::new("status", ColumnType::Text),
+///         ]
+///     }
+///
+///     fn values(&self, event: &OrderEvent) -> Vec<ColumnValue> {
+///         match event {
+///             OrderEvent::Placed { customer_id } => vec![
+///                 ColumnValue::Uuid(Some(*customer_id)),
+///                 ColumnValue::Text(Some("placed".to_string())),
+///             ],
+///             OrderEvent::Shipped => vec![ColumnValue::Uuid(None), ColumnValue::Text(Some("shipped".to_string()))],
+///         }
+///     }
+/// }
+/// ```
+pub trait CustomColumns<E>: Sync {
+    /// Returns the additional columns of the event store table.
+    fn columns(&self) -> Vec<Column>;
+
+    /// Returns the values of the additional columns for the given event. Values must be returned
+    /// in the same order as [`CustomColumns::columns`], one for each column.
+    fn values(&self, event: &E) -> Vec<ColumnValue>;
+}
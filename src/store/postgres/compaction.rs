@@ -0,0 +1,89 @@
+use chrono::{DateTime, Utc};
+use sqlx::{Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::sql::unqualified;
+use crate::types::SequenceNumber;
+use crate::Aggregate;
+
+use super::analysis::EventTypeLocation;
+use super::persistable::Persistable;
+use super::{PgStore, PgStoreError, Schema};
+
+/// Marker left by [`PgStore::compact`] for every compacted event type of an aggregate instance,
+/// telling that the gaps in the sequence numbers of the aggregate instance are intended.
+#[derive(sqlx::FromRow, Debug, Clone, Eq, PartialEq)]
+pub struct Compaction {
+    /// The compacted aggregate instance.
+    pub aggregate_id: Uuid,
+    /// The compacted event type.
+    pub event_type: String,
+    /// The sequence number of the latest event of this type, kept by the compaction.
+    pub sequence_number: SequenceNumber,
+    /// The total number of superseded events removed.
+    pub removed: i64,
+    /// When the latest compaction happened.
+    pub compacted_on: DateTime<Utc>,
+}
+
+impl<A, S> PgStore<A, S>
+where
+    A: Aggregate,
+    A::Event: Send + Sync,
+    S: Schema<A::Event> + Persistable + Send + Sync,
+{
+    /// Removes the events of the given ephemeral event types (e.g. heartbeats, position updates)
+    /// superseded by a newer event of the same type of the same aggregate instance, so that only
+    /// the latest one is kept. Returns the number of removed events.
+    ///
+    /// A [`Compaction`] marker is stored in the `{table}_compactions` table, created if missing,
+    /// for every compacted event type of every aggregate instance. The markers are informational
+    /// only, see [`PgStore::compactions`]: loading, replaying and rebuilding read the remaining events
+    /// alone, so the aggregate state stays correct only as long as applying an ephemeral event
+    /// overrides what was applied by the previous events of the same type.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if any of the queries fails.
+    pub async fn compact(&self, location: &EventTypeLocation, event_types: &[&str]) -> Result<u64, PgStoreError> {
+        let mut transaction: Transaction<Postgres> = self.inner.pool.begin().await?;
+
+        let migration: String = format!(
+            include_str!("../../sql/postgres/migrations/create_compactions_table.sql"),
+            self.table_name(),
+            unqualified(self.table_name())
+        );
+        let _ = sqlx::query(migration.as_str()).execute(&mut *transaction).await?;
+
+        let statement: String = format!(
+            include_str!("../../sql/postgres/statements/compact.sql"),
+            self.table_name(),
+            location.as_sql()
+        );
+        let removed: i64 = sqlx::query_scalar(statement.as_str())
+            .bind(event_types)
+            .fetch_one(&mut *transaction)
+            .await?;
+
+        transaction.commit().await?;
+
+        Ok(removed as u64)
+    }
+
+    /// Returns the [`Compaction`] markers of the given aggregate instance, sorted by event type.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the store has never been compacted, or the query fails.
+    pub async fn compactions(&self, aggregate_id: Uuid) -> Result<Vec<Compaction>, PgStoreError> {
+        let statement: String = format!(
+            include_str!("../../sql/postgres/statements/select_compactions_by_aggregate_id.sql"),
+            self.table_name()
+        );
+
+        Ok(sqlx::query_as::<_, Compaction>(statement.as_str())
+            .bind(aggregate_id)
+            .fetch_all(&self.inner.pool)
+            .await?)
+    }
+}
@@ -0,0 +1,95 @@
+use async_trait::async_trait;
+use serde::Serialize;
+use sqlx::postgres::PgQueryResult;
+use sqlx::types::Json;
+use sqlx::{Pool, Postgres};
+
+use crate::bus::{serialize_store_event, DeadLetterHandler};
+use crate::store::StoreEvent;
+use crate::Aggregate;
+
+use super::PgStoreBuilder;
+
+/// Postgres [`DeadLetterHandler`], storing the events the event buses failed to publish in the
+/// `{table}_dead_letters` table, serialized as published, along with the last publishing error. A
+/// dead-lettered event published again overwrites its previous row.
+pub struct PgDeadLetterTable {
+    pool: Pool<Postgres>,
+    table_name: String,
+}
+
+impl PgDeadLetterTable {
+    /// Creates a new instance of a [`PgDeadLetterTable`] for the events of the store being built by
+    /// the given builder, named after its event store table and living in its database schema.
+    ///
+    /// The table name and the database schema are read from the builder as they are: set them
+    /// before creating the [`PgDeadLetterTable`].
+    pub fn new<A, S>(builder: &PgStoreBuilder<A, S>) -> Self
+    where
+        A: Aggregate,
+    {
+        Self {
+            pool: builder.pool().clone(),
+            table_name: format!("{}_dead_letters", builder.table_name()),
+        }
+    }
+
+    /// Returns the name of the dead letters table.
+    pub fn table_name(&self) -> &str {
+        &self.table_name
+    }
+
+    /// Creates the dead letters table, if it doesn't exist yet.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if there's an error running the migration.
+    pub async fn setup(&self) -> Result<(), sqlx::Error> {
+        let migration: String = format!(
+            include_str!("../../sql/postgres/migrations/create_dead_letters_table.sql"),
+            self.table_name,
+            crate::sql::unqualified(&self.table_name)
+        );
+
+        let _: PgQueryResult = sqlx::query(migration.as_str()).execute(&self.pool).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<A> DeadLetterHandler<A> for PgDeadLetterTable
+where
+    A: Aggregate,
+    A::Event: Serialize + Sync,
+{
+    async fn handle(&self, store_event: &StoreEvent<A::Event>, error: &(dyn std::error::Error + Send + Sync)) {
+        let result: Result<(), Box<dyn std::error::Error + Send + Sync>> = async {
+            let event: serde_json::Value = serde_json::from_slice(&serialize_store_event(store_event, false)?)?;
+
+            let _ = sqlx::query(
+                format!(
+                    include_str!("../../sql/postgres/statements/upsert_dead_letter.sql"),
+                    self.table_name
+                )
+                .as_str(),
+            )
+            .bind(store_event.id)
+            .bind(store_event.aggregate_id)
+            .bind(Json(event))
+            .bind(error.to_string())
+            .execute(&self.pool)
+            .await?;
+
+            Ok(())
+        }
+        .await;
+
+        if let Err(dead_letter_error) = result {
+            tracing::error!({
+                event_id = %store_event.id,
+                aggregate_id = %store_event.aggregate_id,
+                error = ?dead_letter_error,
+            }, "failed to store dead-lettered event");
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use sqlx::{Postgres, Transaction};
+
+use crate::sql::event::DbRawEvent;
+use crate::sql::statements::StatementsHandler;
+use crate::store::StoreEvent;
+use crate::Aggregate;
+
+use super::persistable::Persistable;
+use super::{PgStore, PgStoreError, Schema};
+
+/// Hook providing the visibility time of the events, letting them be persisted as embargoed or
+/// scheduled domain facts.
+///
+/// When set in the [`super::PgStoreBuilder`], the events visible in the future are persisted as
+/// usual (and they contribute to the aggregate state), but they are neither handled by the event
+/// handlers nor published to the event buses until due. Due events are then released by
+/// [`PgStore::release_deferred_events`]. Released events are kept in the `{table}_deferred` table,
+/// marked as released, so that [`super::Subscription`]s read them at their visibility time.
+///
+/// Note that the handlers might see the events of an aggregate instance out of order, if a deferred
+/// event is followed by non deferred ones.
+pub trait Visibility<E>: Sync {
+    /// Returns when the given event becomes visible. `None`, or a time in the past, means that the
+    /// event is visible straight away.
+    fn visible_at(&self, event: &E) -> Option<DateTime<Utc>>;
+}
+
+impl<A, S> PgStore<A, S>
+where
+    A: Aggregate,
+    A::State: Send,
+    A::Event: Send + Sync,
+    S: Schema<A::Event> + Persistable + Send + Sync,
+{
+    /// Releases at most `batch_size` deferred events that are due, handling them with the
+    /// transactional event handlers in the releasing transaction, and then with the event handlers
+    /// and the event buses. Returns the number of released events.
+    ///
+    /// The due events skipped by the [`Schema`] have nothing to release: their deferred rows are
+    /// deleted, and they are counted as released.
+    ///
+    /// When the store has been built with [`super::PgStoreBuilder::with_outbox`], the released
+    /// events are written to the outbox table instead of being published.
+    ///
+    /// Many releasers can run at once, each deferred event being released by one of them.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the store hasn't been built with a [`Visibility`] hook, any of the
+    /// queries fails or a transactional event handler fails.
+    pub async fn release_deferred_events(&self, batch_size: i64) -> Result<usize, PgStoreError> {
+        let mut transaction: Transaction<Postgres> = self.inner.pool.begin().await?;
+
+        let events: Vec<DbRawEvent> = sqlx::query_as::<_, DbRawEvent>(self.inner.statements.select_due_deferred())
+            .bind(batch_size)
+            .fetch_all(&mut *transaction)
+            .await?;
+        let due: usize = events.len();
+
+        let mut store_events: Vec<StoreEvent<A::Event>> = Vec::with_capacity(due);
+        for event in events {
+            let event_id = event.id;
+
+            match self.inner.decode_event::<S>(event).await? {
+                Some(store_event) => store_events.push(store_event),
+                None => {
+                    let _ = sqlx::query(self.inner.statements.delete_deferred())
+                        .bind(event_id)
+                        .execute(&mut *transaction)
+                        .await?;
+                }
+            }
+        }
+
+        for store_event in &store_events {
+            let _ = sqlx::query(self.inner.statements.release_deferred())
+                .bind(store_event.id)
+                .execute(&mut *transaction)
+                .await?;
+
+            if self.inner.outbox {
+                let _ = sqlx::query(self.inner.statements.insert_outbox())
+                    .bind(store_event.id)
+                    .execute(&mut *transaction)
+                    .await?;
+            }
+
+            for transactional_event_handler in &self.inner.transactional_event_handlers {
+                let span = tracing::trace_span!(
+                    "esrs.transactional_event_handler",
+                    event_id = %store_event.id,
+                    aggregate_id = %store_event.aggregate_id,
+                    transactional_event_handler = transactional_event_handler.name()
+                );
+                let _e = span.enter();
+
+                if let Err(error) = transactional_event_handler.handle(store_event, &mut transaction).await {
+                    tracing::error!({
+                        event_id = %store_event.id,
+                        aggregate_id = %store_event.aggregate_id,
+                        transactional_event_handler = transactional_event_handler.name(),
+                        error = ?error,
+                    }, "transactional event handler failed to handle deferred event");
+
+                    return Err(error);
+                }
+            }
+        }
+
+        transaction.commit().await?;
+
+        let event_handlers = self.inner.event_handlers.read().await;
+        for store_event in &store_events {
+            for event_handler in event_handlers.iter() {
+                self.inner.run_event_handler(event_handler.as_ref(), store_event).await;
+            }
+        }
+
+        if !self.inner.outbox {
+            let released_store_events: Vec<&StoreEvent<A::Event>> = store_events.iter().collect();
+            self.publish_events(&released_store_events).await;
+        }
+
+        Ok(due)
+    }
+
+    /// Releases the due deferred events forever, checking for them at every interval. Batches are
+    /// released back to back while there are due events left.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` as soon as a release fails.
+    pub async fn run_deferred_events_releaser(&self, interval: Duration, batch_size: i64) -> Result<(), PgStoreError> {
+        loop {
+            let released: usize = self.release_deferred_events(batch_size).await?;
+
+            if (released as i64) < batch_size {
+                crate::runtime::sleep(interval).await;
+            }
+        }
+    }
+}
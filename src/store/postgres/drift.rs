@@ -0,0 +1,127 @@
+use sqlx::{Pool, Postgres};
+
+use crate::sql::{schema_of, unqualified};
+
+use super::{Column, LockStrategy};
+
+/// The `SchemaDriftPolicy` enum defines how the [`super::PgStoreBuilder`] reacts when the live
+/// event store table doesn't match the schema expected by the current version of the crate:
+///
+/// - `Ignore`: The table isn't checked at all. This is the default.
+/// - `Log`: Every mismatch is logged as an error, and the store is built anyway.
+/// - `Deny`: Building the store fails with a [`SchemaDriftError`].
+pub enum SchemaDriftPolicy {
+    Ignore,
+    Log,
+    Deny,
+}
+
+/// Error returned, wrapped in a [`sqlx::Error::Configuration`], when building a store with
+/// [`SchemaDriftPolicy::Deny`] over a table drifted from the expected schema.
+#[derive(thiserror::Error, Debug)]
+#[error("Event store table `{table_name}` drifted from the expected schema: {}", .mismatches.join("; "))]
+pub struct SchemaDriftError {
+    /// The name of the event store table.
+    pub table_name: String,
+    /// The description of every mismatch found.
+    pub mismatches: Vec<String>,
+}
+
+/// Columns of the event store table, with their `information_schema` data types.
+const DEFAULT_COLUMNS: [(&str, &str); 7] = [
+    ("id", "uuid"),
+    ("aggregate_id", "uuid"),
+    ("payload", "jsonb"),
+    ("occurred_on", "timestamp with time zone"),
+    ("sequence_number", "integer"),
+    ("version", "integer"),
+    ("metadata", "jsonb"),
+];
+
+/// Compares the live event store table with the expected columns, indexes and locks table,
+/// returning the description of every mismatch found.
+///
+/// The indexes are looked up on `indexed_table_name`, which is the shared table when the event store
+/// table is a view over it.
+pub(crate) async fn schema_drift(
+    pool: &Pool<Postgres>,
+    table_name: &str,
+    indexed_table_name: &str,
+    custom_columns: &[Column],
+    lock_strategy: &LockStrategy,
+    payload_index: bool,
+    search_vector: bool,
+) -> Result<Vec<String>, sqlx::Error> {
+    let live_columns: Vec<(String, String)> =
+        sqlx::query_as(include_str!("../../sql/postgres/statements/select_table_columns.sql"))
+            .bind(unqualified(table_name))
+            .bind(schema_of(table_name))
+            .fetch_all(pool)
+            .await?;
+
+    if live_columns.is_empty() {
+        return Ok(vec![format!("table `{}` doesn't exist", table_name)]);
+    }
+
+    let expected_columns = DEFAULT_COLUMNS
+        .iter()
+        .copied()
+        .chain(search_vector.then_some(("search_vector", "tsvector")))
+        .chain(
+            custom_columns
+                .iter()
+                .map(|column| (column.name(), column.column_type().as_data_type())),
+        );
+
+    let mut mismatches: Vec<String> = vec![];
+
+    for (name, data_type) in expected_columns {
+        match live_columns.iter().find(|(live_name, _)| live_name == name) {
+            None => mismatches.push(format!("column `{}` is missing", name)),
+            Some((_, live_data_type)) if live_data_type != data_type => mismatches.push(format!(
+                "column `{}` has type `{}` instead of `{}`",
+                name, live_data_type, data_type
+            )),
+            Some(_) => {}
+        }
+    }
+
+    let live_indexes: Vec<String> =
+        sqlx::query_scalar(include_str!("../../sql/postgres/statements/select_table_indexes.sql"))
+            .bind(unqualified(indexed_table_name))
+            .bind(schema_of(indexed_table_name))
+            .fetch_all(pool)
+            .await?;
+
+    let expected_indexes = ["pkey", "aggregate_id", "aggregate_id_sequence_number"]
+        .iter()
+        .chain(payload_index.then_some(&"payload"))
+        .chain(search_vector.then_some(&"search_vector"))
+        .map(|suffix| format!("{}_{}", unqualified(indexed_table_name), suffix))
+        .chain(
+            custom_columns
+                .iter()
+                .filter(|column| column.is_indexed())
+                .map(|column| format!("{}_{}", unqualified(indexed_table_name), column.name())),
+        );
+
+    for index in expected_indexes {
+        if !live_indexes.contains(&index) {
+            mismatches.push(format!("index `{}` is missing", index));
+        }
+    }
+
+    if let LockStrategy::RowLevel = lock_strategy {
+        let locks_table_name: String = format!("{}_locks", table_name);
+        let locks_table_exists: bool = sqlx::query_scalar("SELECT to_regclass($1) IS NOT NULL")
+            .bind(locks_table_name.as_str())
+            .fetch_one(pool)
+            .await?;
+
+        if !locks_table_exists {
+            mismatches.push(format!("table `{}` doesn't exist", locks_table_name));
+        }
+    }
+
+    Ok(mismatches)
+}
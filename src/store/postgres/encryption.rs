@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use uuid::Uuid;
+
+use crate::sql::event::DbRawEvent;
+use crate::store::StoreEvent;
+use crate::Aggregate;
+
+use super::{PgStore, PgStoreError, Schema};
+
+/// The length of the AES-GCM nonce, prepended to the ciphertext.
+const NONCE_LENGTH: usize = 12;
+
+/// Provider of the data keys encrypting the payloads of the events at rest, one per aggregate
+/// instance, e.g. backed by a KMS or Vault.
+///
+/// Deleting the data key of an aggregate instance crypto-shreds it: its events are kept, but their
+/// payloads can't be decrypted anymore. Providers backed by a remote service should cache the keys,
+/// as they are requested for every persisted and loaded event.
+#[async_trait]
+pub trait DataKeyProvider: Send + Sync {
+    /// Returns the 256 bits data key of the given aggregate instance, creating it if it doesn't
+    /// exist yet.
+    async fn data_key(&self, aggregate_id: Uuid) -> Result<[u8; 32], Box<dyn std::error::Error + Send + Sync>>;
+
+    /// Returns the data key of the given aggregate instance, or `None` if it doesn't exist, e.g.
+    /// because it has been deleted.
+    async fn existing_data_key(
+        &self,
+        aggregate_id: Uuid,
+    ) -> Result<Option<[u8; 32]>, Box<dyn std::error::Error + Send + Sync>>;
+
+    /// Deletes the data key of the given aggregate instance.
+    async fn delete_data_key(&self, aggregate_id: Uuid) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
+}
+
+/// [`DataKeyProvider`] keeping randomly generated keys in memory, meant for tests and local
+/// development: the keys, and hence the payloads, are lost when it is dropped.
+#[derive(Default)]
+pub struct InMemoryDataKeyProvider {
+    keys: Mutex<HashMap<Uuid, [u8; 32]>>,
+}
+
+impl InMemoryDataKeyProvider {
+    /// Creates a new [`InMemoryDataKeyProvider`], without any key.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl DataKeyProvider for InMemoryDataKeyProvider {
+    async fn data_key(&self, aggregate_id: Uuid) -> Result<[u8; 32], Box<dyn std::error::Error + Send + Sync>> {
+        let mut keys = self.keys.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        Ok(*keys.entry(aggregate_id).or_insert_with(|| {
+            let mut key: [u8; 32] = [0; 32];
+            key.copy_from_slice(&Aes256Gcm::generate_key(&mut OsRng));
+            key
+        }))
+    }
+
+    async fn existing_data_key(
+        &self,
+        aggregate_id: Uuid,
+    ) -> Result<Option<[u8; 32]>, Box<dyn std::error::Error + Send + Sync>> {
+        let keys = self.keys.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        Ok(keys.get(&aggregate_id).copied())
+    }
+
+    async fn delete_data_key(&self, aggregate_id: Uuid) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut keys = self.keys.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let _ = keys.remove(&aggregate_id);
+        Ok(())
+    }
+}
+
+/// The payload of an event as stored once encrypted: the nonce followed by the ciphertext, base64
+/// encoded.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EncryptedPayload {
+    esrs_ciphertext: String,
+}
+
+/// Encrypts the given serialized payload with the data key of the given aggregate instance,
+/// binding it to the aggregate instance so that it can't be moved to another one.
+pub(super) async fn encrypt(
+    data_key_provider: &dyn DataKeyProvider,
+    aggregate_id: Uuid,
+    plaintext: &RawValue,
+) -> Result<serde_json::Value, PgStoreError> {
+    let data_key: [u8; 32] = data_key_provider
+        .data_key(aggregate_id)
+        .await
+        .map_err(PgStoreError::Custom)?;
+
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&data_key));
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext: Vec<u8> = cipher
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: plaintext.get().as_bytes(),
+                aad: aggregate_id.as_bytes(),
+            },
+        )
+        .map_err(|_| PgStoreError::Custom("Failed to encrypt the event payload".to_string().into()))?;
+
+    let mut bytes: Vec<u8> = nonce.to_vec();
+    bytes.extend(ciphertext);
+
+    Ok(serde_json::to_value(EncryptedPayload {
+        esrs_ciphertext: STANDARD.encode(bytes),
+    })?)
+}
+
+/// Converts the given row into a [`StoreEvent`], decrypting its payload. Returns `None` if the
+/// schema skips the event, or the data key of its aggregate instance has been deleted.
+///
+/// Payloads persisted before enabling the encryption are read as they are. The serialized payload
+/// kept as [`StoreEvent::raw_payload`] is the decrypted one, so that the event buses and the outbox
+/// never publish the ciphertext.
+pub(super) async fn decrypt_event<E, S>(
+    data_key_provider: &dyn DataKeyProvider,
+    mut event: DbRawEvent,
+) -> Result<Option<StoreEvent<E>>, PgStoreError>
+where
+    S: Schema<E>,
+{
+    let Ok(encrypted_payload) = serde_json::from_str::<EncryptedPayload>(event.payload.0.get()) else {
+        return Ok(event.into_raw_store_event::<E, S>().into_store_event()?);
+    };
+
+    let Some(data_key) = data_key_provider
+        .existing_data_key(event.aggregate_id)
+        .await
+        .map_err(PgStoreError::Custom)?
+    else {
+        return Ok(None);
+    };
+
+    let bytes: Vec<u8> = STANDARD
+        .decode(encrypted_payload.esrs_ciphertext)
+        .map_err(|error| PgStoreError::Custom(Box::new(error)))?;
+
+    if bytes.len() < NONCE_LENGTH {
+        return Err(PgStoreError::Custom(
+            "The encrypted event payload is truncated".to_string().into(),
+        ));
+    }
+
+    let (nonce, ciphertext) = bytes.split_at(NONCE_LENGTH);
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&data_key));
+    let plaintext: Vec<u8> = cipher
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: event.aggregate_id.as_bytes(),
+            },
+        )
+        .map_err(|_| PgStoreError::Custom("Failed to decrypt the event payload".to_string().into()))?;
+
+    let plaintext: String = String::from_utf8(plaintext).map_err(|error| PgStoreError::Custom(Box::new(error)))?;
+    event.payload.0 = RawValue::from_string(plaintext)?;
+
+    Ok(event.into_raw_store_event::<E, S>().into_store_event()?)
+}
+
+impl<A, S> PgStore<A, S>
+where
+    A: Aggregate,
+{
+    /// Crypto-shreds the given aggregate instance, deleting its data key. Its events are kept, but
+    /// their payloads can't be decrypted anymore: they are skipped when loading or streaming the
+    /// events, as if the schema skipped them, without rewriting the history.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the store hasn't been built with a [`DataKeyProvider`], or the key
+    /// can't be deleted.
+    pub async fn shred(&self, aggregate_id: Uuid) -> Result<(), PgStoreError> {
+        let Some(data_key_provider) = self.inner.data_key_provider.as_ref() else {
+            return Err(PgStoreError::Custom(
+                "The store has been built without a data key provider"
+                    .to_string()
+                    .into(),
+            ));
+        };
+
+        data_key_provider
+            .delete_data_key(aggregate_id)
+            .await
+            .map_err(PgStoreError::Custom)
+    }
+}
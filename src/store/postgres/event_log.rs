@@ -0,0 +1,52 @@
+use async_trait::async_trait;
+use chrono::Utc;
+
+use crate::handler::EventHandler;
+use crate::store::StoreEvent;
+use crate::Aggregate;
+
+use super::analysis::EventTypeLocation;
+
+/// [`EventHandler`] emitting a structured `tracing` event for each handled event, with the aggregate
+/// name and id, the event id, type, sequence number and version, and the latency between the
+/// persistence and the handling of the event.
+///
+/// Register it through [`super::PgStoreBuilder::with_event_log`] to get a consistent log stream of
+/// the events of the store. As any other event handler, it only sees the events once committed, and
+/// the deferred events once released.
+pub struct EventLog {
+    event_type_location: EventTypeLocation,
+}
+
+impl EventLog {
+    /// Creates an [`EventLog`], reading the event types from the serialized payloads according to
+    /// the given [`EventTypeLocation`].
+    pub fn new(event_type_location: EventTypeLocation) -> Self {
+        Self { event_type_location }
+    }
+}
+
+#[async_trait]
+impl<A> EventHandler<A> for EventLog
+where
+    A: Aggregate,
+    A::Event: Send + Sync,
+{
+    async fn handle(&self, event: &StoreEvent<A::Event>) {
+        let event_type: Option<String> = event
+            .raw_payload()
+            .and_then(|raw_payload| serde_json::from_str::<serde_json::Value>(raw_payload.get()).ok())
+            .and_then(|payload| self.event_type_location.event_type(&payload));
+        let latency_ms: i64 = (Utc::now() - event.occurred_on).num_milliseconds();
+
+        tracing::info!({
+            aggregate_name = A::NAME,
+            aggregate_id = %event.aggregate_id,
+            event_id = %event.id,
+            event_type = event_type.as_deref().unwrap_or("unknown"),
+            sequence_number = event.sequence_number,
+            version = ?event.version,
+            latency_ms = latency_ms,
+        }, "event persisted");
+    }
+}
@@ -1,26 +1,33 @@
 use std::marker::PhantomData;
 use std::sync::Arc;
+use std::time::Duration;
 
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use futures::stream::BoxStream;
 use futures::StreamExt;
+use serde_json::value::RawValue;
 use sqlx::pool::PoolConnection;
-use sqlx::postgres::{PgAdvisoryLock, PgAdvisoryLockGuard, PgAdvisoryLockKey};
+use sqlx::postgres::{PgAdvisoryLock, PgAdvisoryLockGuard, PgAdvisoryLockKey, PgArguments, PgRow};
+use sqlx::query::QueryAs;
 use sqlx::types::Json;
-use sqlx::{Executor, PgConnection, Pool, Postgres, Transaction};
-use tokio::sync::RwLock;
+use sqlx::{Executor, FromRow, PgConnection, Pool, Postgres, Transaction};
+use tokio::sync::{RwLock, Semaphore, SemaphorePermit};
 use uuid::Uuid;
 
 use crate::bus::EventBus;
-use crate::handler::{EventHandler, TransactionalEventHandler};
-use crate::sql::event::DbEvent;
+use crate::handler::{ErrorObserver, EventHandler, TransactionalEventHandler};
+use crate::interceptor::PersistInterceptor;
+use crate::sql::event::DbRawEvent;
 use crate::sql::statements::{Statements, StatementsHandler};
 use crate::store::postgres::persistable::Persistable;
-use crate::store::postgres::PgStoreError;
 use crate::store::postgres::Schema;
-use crate::store::postgres::UuidFormat;
-use crate::store::{EventStore, EventStoreLockGuard, StoreEvent, UnlockOnDrop};
+use crate::store::postgres::{
+    AuditHook, ColumnValue, CustomColumns, EventIdGenerator, EventSchemaValidator, LockStrategy, OccurredOnStrategy,
+    RawStoreEvent, ValidTime, Visibility,
+};
+use crate::store::postgres::{ErrorContext, PgStoreError};
+use crate::store::{EventStore, EventStoreLockGuard, IdempotentEventStore, Metadata, Since, StoreEvent, UnlockOnDrop};
 use crate::types::SequenceNumber;
 use crate::{Aggregate, AggregateState};
 
@@ -54,12 +61,194 @@ where
     A: Aggregate,
 {
     pub(super) pool: Pool<Postgres>,
+    pub(super) read_pool: Option<Pool<Postgres>>,
     pub(super) statements: Statements,
+    pub(super) database_schema: Option<String>,
     pub(super) event_handlers: RwLock<Vec<Box<dyn EventHandler<A> + Send>>>,
+    pub(super) event_handlers_budget: Option<Semaphore>,
+    pub(super) error_observers: Vec<Box<dyn ErrorObserver<A> + Send>>,
     pub(super) transactional_event_handlers:
         Vec<Box<dyn TransactionalEventHandler<A, PgStoreError, PgConnection> + Send>>,
-    pub(super) event_buses: Vec<Box<dyn EventBus<A> + Send>>,
-    pub(super) event_id_format: UuidFormat,
+    pub(super) event_buses: RwLock<Vec<Box<dyn EventBus<A> + Send>>>,
+    pub(super) persist_interceptors: Vec<Box<dyn PersistInterceptor<A, PgStoreError, PgConnection> + Send>>,
+    pub(super) audit_hooks: Vec<Box<dyn AuditHook + Send>>,
+    pub(super) event_id_generator: Box<dyn EventIdGenerator>,
+    pub(super) occurred_on_strategy: OccurredOnStrategy,
+    pub(super) lock_strategy: LockStrategy,
+    pub(super) statement_timeout: Option<Duration>,
+    pub(super) custom_columns: Option<Box<dyn CustomColumns<A::Event> + Send>>,
+    pub(super) valid_time: Option<Box<dyn ValidTime<A::Event> + Send>>,
+    pub(super) visibility: Option<Box<dyn Visibility<A::Event> + Send>>,
+    pub(super) schema_validator: Option<Box<dyn EventSchemaValidator>>,
+    pub(super) outbox: bool,
+    #[cfg(feature = "upcasting")]
+    pub(super) downcaster: Option<Box<dyn crate::event::Downcaster>>,
+    #[cfg(feature = "integrity")]
+    pub(super) key_provider: Option<Box<dyn super::integrity::KeyProvider>>,
+    #[cfg(feature = "encryption")]
+    pub(super) data_key_provider: Option<Box<dyn super::encryption::DataKeyProvider>>,
+    #[cfg(feature = "integrity")]
+    pub(super) hash_chain: bool,
+    pub(super) idempotency_tokens: bool,
+}
+
+impl<A> InnerPgStore<A>
+where
+    A: Aggregate,
+    A::Event: Send + Sync,
+{
+    /// Begins a transaction on the pool, applying the statement timeout, if any, to it.
+    pub(super) async fn begin(&self) -> Result<Transaction<'static, Postgres>, PgStoreError> {
+        self.begin_on(&self.pool).await
+    }
+
+    /// Begins a transaction on the read pool, applying the statement timeout, if any, to it.
+    pub(super) async fn begin_read(&self) -> Result<Transaction<'static, Postgres>, PgStoreError> {
+        self.begin_on(self.read_pool()).await
+    }
+
+    /// Qualifies the given table name with the database schema of the store, if any. Meant for the
+    /// tables shared by all the stores, e.g. `projection_offsets`.
+    pub(super) fn qualified(&self, table_name: &str) -> String {
+        match self.database_schema.as_deref() {
+            Some(database_schema) => format!("{}.{}", database_schema, table_name),
+            None => table_name.to_string(),
+        }
+    }
+
+    /// The pool the loads are run on: the read pool, if any, or the pool.
+    pub(super) fn read_pool(&self) -> &Pool<Postgres> {
+        self.read_pool.as_ref().unwrap_or(&self.pool)
+    }
+
+    async fn begin_on(&self, pool: &Pool<Postgres>) -> Result<Transaction<'static, Postgres>, PgStoreError> {
+        let mut transaction: Transaction<'static, Postgres> = pool.begin().await?;
+
+        if let Some(statement_timeout) = self.statement_timeout {
+            let _ = sqlx::query("SELECT set_config('statement_timeout', $1, true)")
+                .bind(format!("{}ms", statement_timeout.as_millis()))
+                .execute(&mut *transaction)
+                .await?;
+        }
+
+        Ok(transaction)
+    }
+
+    /// Converts the given row into a [`StoreEvent`], decrypting its payload if needed. Returns `None`
+    /// if the schema skips the event, or its payload has been shredded.
+    pub(super) async fn decode_event<S>(&self, event: DbRawEvent) -> Result<Option<StoreEvent<A::Event>>, PgStoreError>
+    where
+        S: Schema<A::Event>,
+    {
+        #[cfg(feature = "encryption")]
+        if let Some(data_key_provider) = self.data_key_provider.as_ref() {
+            return super::encryption::decrypt_event::<_, S>(data_key_provider.as_ref(), event).await;
+        }
+
+        Ok(event.into_raw_store_event::<_, S>().into_store_event()?)
+    }
+
+    /// Converts the given rows into [`StoreEvent`]s, decrypting their payloads if needed. The events
+    /// skipped by the schema, or whose payload has been shredded, are left out.
+    pub(super) async fn decode_events<S>(
+        &self,
+        events: Vec<DbRawEvent>,
+    ) -> Result<Vec<StoreEvent<A::Event>>, PgStoreError>
+    where
+        S: Schema<A::Event>,
+    {
+        let mut store_events: Vec<StoreEvent<A::Event>> = Vec::with_capacity(events.len());
+
+        for event in events {
+            store_events.extend(self.decode_event::<S>(event).await?);
+        }
+
+        Ok(store_events)
+    }
+
+    /// Waits for a slot in the concurrency budget of the event handlers, if any. The slot is released
+    /// when the returned permit is dropped.
+    pub(super) async fn event_handler_permit(&self) -> Option<SemaphorePermit<'_>> {
+        match self.event_handlers_budget.as_ref() {
+            Some(budget) => budget.acquire().await.ok(),
+            None => None,
+        }
+    }
+
+    /// Lets the given event handler handle the given event, within the concurrency budget of the
+    /// event handlers, reporting its failure, if any, to the error observers.
+    pub(super) async fn run_event_handler(
+        &self,
+        event_handler: &(dyn EventHandler<A> + Send),
+        store_event: &StoreEvent<A::Event>,
+    ) {
+        let _permit = self.event_handler_permit().await;
+        let span = tracing::debug_span!(
+            "esrs.event_handler",
+            event_id = %store_event.id,
+            aggregate_id = %store_event.aggregate_id,
+            event_handler = event_handler.name()
+        );
+        let _e = span.enter();
+
+        if let Err(error) = event_handler.try_handle(store_event).await {
+            tracing::error!({
+                event_id = %store_event.id,
+                aggregate_id = %store_event.aggregate_id,
+                event_handler = event_handler.name(),
+                error = ?error,
+            }, "event handler failed to handle event");
+
+            for error_observer in &self.error_observers {
+                error_observer
+                    .observe(store_event, event_handler.name(), error.as_ref())
+                    .await;
+            }
+        }
+    }
+
+    /// Notifies the persist interceptors of the given committed events, then lets the event handlers
+    /// handle the visible ones and publishes them to the event buses, unless they are left to the
+    /// outbox relay.
+    pub(super) async fn dispatch(&self, store_events: &[StoreEvent<A::Event>], visible_events: &[bool]) {
+        for persist_interceptor in &self.persist_interceptors {
+            persist_interceptor.after_commit(store_events).await;
+        }
+
+        let visible_store_events: Vec<&StoreEvent<A::Event>> = store_events
+            .iter()
+            .zip(visible_events)
+            .filter_map(|(store_event, visible)| visible.then_some(store_event))
+            .collect();
+
+        let event_handlers = self.event_handlers.read().await;
+        for store_event in visible_store_events.iter().copied() {
+            // NOTE: should this be parallelized?
+            for event_handler in event_handlers.iter() {
+                self.run_event_handler(event_handler.as_ref(), store_event).await;
+            }
+        }
+
+        // Publishing to subscribed event buses
+        if !self.outbox {
+            self.publish_events(&visible_store_events).await;
+        }
+    }
+
+    /// Publishes the given events to all the event buses, concurrently.
+    async fn publish_events(&self, store_events: &[&StoreEvent<A::Event>]) {
+        let event_buses = self.event_buses.read().await;
+        let futures: Vec<_> = event_buses
+            .iter()
+            .map(|bus| async move {
+                for store_event in store_events {
+                    bus.publish(store_event).await;
+                }
+            })
+            .collect();
+
+        let _ = futures::future::join_all(futures).await;
+    }
 }
 
 impl<A, S> PgStore<A, S>
@@ -84,7 +273,22 @@ where
         guard.push(Box::new(event_handler))
     }
 
-    /// Save an event in the event store and return a new [`StoreEvent`] instance.
+    /// Safely add an event bus to [`PgStore`]. Since it appends an event bus to a [`RwLock`] this
+    /// function needs to be `async`.
+    ///
+    /// This is mostly used while there's the need to have an event bus whose consumer needs the
+    /// store itself, or when the bus can only be wired after the store has been built.
+    pub async fn add_event_bus(&self, event_bus: impl EventBus<A> + Send + 'static) {
+        let mut guard = self.inner.event_buses.write().await;
+
+        guard.push(Box::new(event_bus))
+    }
+
+    /// Save an event in the event store and return a new [`StoreEvent`] instance, built from the
+    /// row actually written in the database, along with the payload as stored, i.e. encrypted if the
+    /// store has been built with a data key provider.
+    ///
+    /// If `occurred_on` is `None`, the database clock is used.
     ///
     /// # Errors
     ///
@@ -93,42 +297,246 @@ where
         &self,
         aggregate_id: Uuid,
         event: A::Event,
-        occurred_on: DateTime<Utc>,
+        occurred_on: Option<DateTime<Utc>>,
         sequence_number: SequenceNumber,
+        metadata: &Metadata,
         executor: impl Executor<'_, Database = Postgres>,
-    ) -> Result<StoreEvent<A::Event>, PgStoreError> {
-        let id: Uuid = match self.inner.event_id_format {
-            UuidFormat::V4 => Uuid::new_v4(),
-            UuidFormat::V7 => Uuid::now_v7(),
-        };
+    ) -> Result<(StoreEvent<A::Event>, Box<RawValue>), PgStoreError> {
+        let id: Uuid = self.inner.event_id_generator.generate(aggregate_id, sequence_number);
 
         #[cfg(feature = "upcasting")]
-        let version: Option<i32> = S::current_version();
+        let version: Option<i32> = match self.inner.downcaster.as_ref() {
+            Some(downcaster) => downcaster.version(),
+            None => S::current_version(),
+        };
         #[cfg(not(feature = "upcasting"))]
         let version: Option<i32> = None;
+        // Additional columns values must follow the order of the columns set in the builder.
+        let mut column_values: Vec<ColumnValue> = vec![];
+
+        if let Some(valid_time) = self.inner.valid_time.as_ref() {
+            column_values.push(ColumnValue::Timestamp(valid_time.valid_at(&event)));
+            column_values.push(ColumnValue::Timestamp(valid_time.valid_to(&event)));
+        }
+
+        if let Some(custom_columns) = self.inner.custom_columns.as_ref() {
+            column_values.extend(custom_columns.values(&event));
+        }
         let schema = S::from_event(event);
 
-        let _ = sqlx::query(self.inner.statements.insert())
+        #[cfg(feature = "upcasting")]
+        let payload: serde_json::Value = match self.inner.downcaster.as_ref() {
+            Some(downcaster) => downcaster.downcast(serde_json::to_value(&schema)?),
+            None => serde_json::to_value(&schema)?,
+        };
+        #[cfg(not(feature = "upcasting"))]
+        let payload: &S = &schema;
+
+        if let Some(schema_validator) = self.inner.schema_validator.as_ref() {
+            #[cfg(feature = "upcasting")]
+            let value: serde_json::Value = payload.clone();
+            #[cfg(not(feature = "upcasting"))]
+            let value: serde_json::Value = serde_json::to_value(payload)?;
+
+            schema_validator
+                .validate(&value, version)
+                .map_err(|violation| PgStoreError::Custom(Box::new(violation)))?;
+        }
+
+        // Payloads are validated in clear, and then encrypted. The payload in clear is kept, so that
+        // it's the one published by the event buses and the outbox.
+        #[cfg(feature = "encryption")]
+        let (payload, clear_payload): (serde_json::Value, Option<Box<RawValue>>) = {
+            let payload: serde_json::Value = serde_json::to_value(payload)?;
+
+            match self.inner.data_key_provider.as_ref() {
+                Some(data_key_provider) => {
+                    let clear_payload: Box<RawValue> = serde_json::value::to_raw_value(&payload)?;
+                    let payload: serde_json::Value =
+                        super::encryption::encrypt(data_key_provider.as_ref(), aggregate_id, &clear_payload).await?;
+                    (payload, Some(clear_payload))
+                }
+                None => (payload, None),
+            }
+        };
+
+        let query = sqlx::query(self.inner.statements.insert())
             .bind(id)
             .bind(aggregate_id)
-            .bind(Json(&schema))
+            .bind(Json(&payload))
             .bind(occurred_on)
             .bind(sequence_number)
             .bind(version)
-            .execute(executor)
+            .bind(Json(metadata));
+
+        let row: PgRow = column_values
+            .into_iter()
+            .fold(query, |query, column_value| column_value.bind(query))
+            .fetch_one(executor)
             .await?;
+        let db_event: DbRawEvent = DbRawEvent::from_row(&row)?;
+
+        // The payload is taken from the schema rather than deserialized back from the returned row,
+        // as they are guaranteed to be the same. The serialized payload is taken from the row instead,
+        // so that it is the same that will be loaded afterwards.
+        let stored_payload: Box<RawValue> = db_event.payload.0;
+        #[cfg(feature = "encryption")]
+        let raw_payload: Box<RawValue> = clear_payload.unwrap_or_else(|| stored_payload.clone());
+        #[cfg(not(feature = "encryption"))]
+        let raw_payload: Box<RawValue> = stored_payload.clone();
 
-        Ok(StoreEvent {
-            id,
-            aggregate_id,
-            payload: schema.to_event().expect(
+        let store_event: StoreEvent<A::Event> = StoreEvent::new(
+            db_event.id,
+            db_event.aggregate_id,
+            schema.to_event().expect(
                 "For any type that implements Schema the following contract should be upheld:\
                 assert_eq!(Some(event.clone()), Schema::from_event(event).to_event())",
             ),
-            occurred_on,
-            sequence_number,
-            version,
-        })
+            db_event.occurred_on,
+            db_event.sequence_number,
+            db_event.version,
+        )
+        .with_metadata(metadata.clone())
+        .with_raw_payload(raw_payload);
+
+        Ok((store_event, stored_payload))
+    }
+
+    /// Runs the given query loading the events of the given aggregate instance, then deserializes
+    /// them.
+    async fn load_events<'q>(
+        &self,
+        aggregate_id: Uuid,
+        query: QueryAs<'q, Postgres, DbRawEvent, PgArguments>,
+    ) -> Result<Vec<StoreEvent<A::Event>>, PgStoreError> {
+        let context: ErrorContext = ErrorContext::new("load").with_aggregate_id(aggregate_id);
+
+        // The statement timeout can only be applied to a transaction, which is avoided when unneeded.
+        let result: Result<Vec<DbRawEvent>, PgStoreError> = async {
+            if self.inner.statement_timeout.is_some() {
+                let mut transaction: Transaction<Postgres> = self.inner.begin_read().await?;
+                let events: Vec<DbRawEvent> = query.fetch_all(&mut *transaction).await?;
+                transaction.commit().await?;
+                Ok(events)
+            } else {
+                Ok(query.fetch_all(self.inner.read_pool()).await?)
+            }
+        }
+        .await;
+        let events: Vec<DbRawEvent> = result.map_err(|error| error.with_context(context.clone()))?;
+
+        let mut store_events: Vec<StoreEvent<A::Event>> = Vec::with_capacity(events.len());
+
+        for event in events {
+            let event_context: ErrorContext = context
+                .clone()
+                .with_event_id(event.id)
+                .with_sequence_number(event.sequence_number);
+
+            let store_event: Option<StoreEvent<A::Event>> = self
+                .inner
+                .decode_event::<S>(event)
+                .await
+                .map_err(|error| error.with_context(event_context))?;
+            store_events.extend(store_event);
+        }
+
+        Ok(store_events)
+    }
+
+    /// Acquires the lock on the given aggregate instance, according to the configured
+    /// [`LockStrategy`].
+    async fn lock_aggregate(&self, aggregate_id: Uuid) -> Result<EventStoreLockGuard, PgStoreError> {
+        if let LockStrategy::RowLevel = self.inner.lock_strategy {
+            // The row is inserted outside of the locking transaction, so that concurrent lockers
+            // don't wait on the uniqueness check but on the row lock itself.
+            let _ = sqlx::query(self.inner.statements.insert_lock())
+                .bind(aggregate_id)
+                .execute(&self.inner.pool)
+                .await?;
+
+            let mut transaction: Transaction<'static, Postgres> = self.inner.begin().await?;
+            let _ = sqlx::query(self.inner.statements.select_lock_for_update())
+                .bind(aggregate_id)
+                .fetch_one(&mut *transaction)
+                .await?;
+
+            return Ok(EventStoreLockGuard::new(PgStoreRowLockGuard {
+                _transaction: transaction,
+            }));
+        }
+
+        let (key, _) = aggregate_id.as_u64_pair();
+        let connection = self.inner.pool.acquire().await?;
+        let lock_guard = PgStoreLockGuardAsyncSendTryBuilder {
+            lock: PgAdvisoryLock::with_key(PgAdvisoryLockKey::BigInt(key as i64)),
+            guard_builder: |lock: &PgAdvisoryLock| Box::pin(async move { lock.acquire(connection).await }),
+        }
+        .try_build()
+        .await?;
+        Ok(EventStoreLockGuard::new(lock_guard))
+    }
+
+    /// Computes the `occurred_on` timestamp of the events about to be persisted for the given
+    /// aggregate instance, according to the configured [`OccurredOnStrategy`]. `None` means that the
+    /// database clock is used while inserting the events.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the latest event timestamp can't be read.
+    pub(crate) async fn occurred_on(
+        &self,
+        aggregate_id: Uuid,
+        executor: impl Executor<'_, Database = Postgres>,
+    ) -> Result<Option<DateTime<Utc>>, PgStoreError> {
+        match self.inner.occurred_on_strategy {
+            OccurredOnStrategy::Local => Ok(Some(Utc::now())),
+            OccurredOnStrategy::Database => Ok(None),
+            OccurredOnStrategy::Clamped => {
+                let last_occurred_on: Option<DateTime<Utc>> =
+                    sqlx::query_scalar(self.inner.statements.last_occurred_on())
+                        .bind(aggregate_id)
+                        .fetch_one(executor)
+                        .await?;
+
+                Ok(Some(
+                    last_occurred_on.map_or_else(Utc::now, |last| last.max(Utc::now())),
+                ))
+            }
+        }
+    }
+
+    /// Publishes the given events to all the event buses, concurrently.
+    pub(crate) async fn publish_events(&self, store_events: &[&StoreEvent<A::Event>]) {
+        self.inner.publish_events(store_events).await;
+    }
+
+    /// Begins a transaction on the pool of the store, applying the statement timeout set through
+    /// [`super::PgStoreBuilder::with_statement_timeout`], if any. Meant to bound the statements run
+    /// on it, such as the streams of events.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the transaction can't be begun.
+    pub async fn begin(&self) -> Result<Transaction<'static, Postgres>, PgStoreError> {
+        self.inner.begin().await
+    }
+
+    /// Same as [`PgStore::begin`], on the read pool set through
+    /// [`super::PgStoreBuilder::with_read_pool`], if any, e.g. to stream the events from a replica.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the transaction can't be begun.
+    pub async fn begin_read(&self) -> Result<Transaction<'static, Postgres>, PgStoreError> {
+        self.inner.begin_read().await
+    }
+
+    /// Returns the pool the events are loaded from: the read pool set through
+    /// [`super::PgStoreBuilder::with_read_pool`], if any, or the pool of the store. Meant to be given
+    /// to [`PgStore::stream_events`] and the other streams of events.
+    pub fn read_pool(&self) -> &Pool<Postgres> {
+        self.inner.read_pool()
     }
 
     /// This function returns a stream representing the full event store table content. This should
@@ -136,15 +544,115 @@ where
     pub fn stream_events<'s>(
         &'s self,
         executor: impl Executor<'s, Database = Postgres> + 's,
-    ) -> BoxStream<Result<StoreEvent<A::Event>, PgStoreError>> {
+    ) -> BoxStream<'s, Result<StoreEvent<A::Event>, PgStoreError>> {
+        Box::pin({
+            sqlx::query_as::<_, DbRawEvent>(self.inner.statements.select_all())
+                .fetch(executor)
+                .then(move |res| async move { self.inner.decode_event::<S>(res?).await })
+                .map(Result::transpose)
+                .filter_map(std::future::ready)
+        })
+    }
+
+    /// This function returns a stream representing the full event store table content, including
+    /// the events of the soft deleted aggregate instances. See [`super::DeletionStrategy::Soft`].
+    pub fn stream_events_including_deleted<'s>(
+        &'s self,
+        executor: impl Executor<'s, Database = Postgres> + 's,
+    ) -> BoxStream<'s, Result<StoreEvent<A::Event>, PgStoreError>> {
+        Box::pin({
+            sqlx::query_as::<_, DbRawEvent>(self.inner.statements.select_all_including_deleted())
+                .fetch(executor)
+                .then(move |res| async move { self.inner.decode_event::<S>(res?).await })
+                .map(Result::transpose)
+                .filter_map(std::future::ready)
+        })
+    }
+
+    /// This function returns a stream of the events written at the given version, `None` standing
+    /// for the events written without a version. See [`PgStore::events_per_version`].
+    pub fn stream_events_by_version<'s>(
+        &'s self,
+        version: Option<i32>,
+        executor: impl Executor<'s, Database = Postgres> + 's,
+    ) -> BoxStream<'s, Result<StoreEvent<A::Event>, PgStoreError>> {
         Box::pin({
-            sqlx::query_as::<_, DbEvent>(self.inner.statements.select_all())
+            sqlx::query_as::<_, DbRawEvent>(self.inner.statements.select_by_version())
+                .bind(version)
                 .fetch(executor)
-                .map(|res| Ok(res?.try_into_store_event::<_, S>()?))
+                .then(move |res| async move { self.inner.decode_event::<S>(res?).await })
                 .map(Result::transpose)
                 .filter_map(std::future::ready)
         })
     }
+
+    /// This function returns a stream of the events written at a version older than the given one,
+    /// including the events written without a version, i.e. the events not migrated to it yet.
+    pub fn stream_events_older_than<'s>(
+        &'s self,
+        version: i32,
+        executor: impl Executor<'s, Database = Postgres> + 's,
+    ) -> BoxStream<'s, Result<StoreEvent<A::Event>, PgStoreError>> {
+        Box::pin({
+            sqlx::query_as::<_, DbRawEvent>(self.inner.statements.select_older_than_version())
+                .bind(version)
+                .fetch(executor)
+                .then(move |res| async move { self.inner.decode_event::<S>(res?).await })
+                .map(Result::transpose)
+                .filter_map(std::future::ready)
+        })
+    }
+
+    /// Loads the events of the given aggregate instance, even if it has been soft deleted. See
+    /// [`super::DeletionStrategy::Soft`].
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the query fails or the events cannot be deserialized.
+    pub async fn by_aggregate_id_including_deleted(
+        &self,
+        aggregate_id: Uuid,
+    ) -> Result<Vec<StoreEvent<A::Event>>, PgStoreError> {
+        let events: Vec<DbRawEvent> =
+            sqlx::query_as::<_, DbRawEvent>(self.inner.statements.by_aggregate_id_including_deleted())
+                .bind(aggregate_id)
+                .fetch_all(&self.inner.pool)
+                .await?;
+
+        self.inner.decode_events::<S>(events).await
+    }
+
+    /// Loads the events of the given aggregate instance, without deserializing their payloads. See
+    /// [`RawStoreEvent`].
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the query fails.
+    pub async fn by_aggregate_id_raw(
+        &self,
+        aggregate_id: Uuid,
+    ) -> Result<Vec<RawStoreEvent<A::Event, S>>, PgStoreError> {
+        Ok(sqlx::query_as::<_, DbRawEvent>(self.inner.statements.by_aggregate_id())
+            .bind(aggregate_id)
+            .fetch_all(&self.inner.pool)
+            .await?
+            .into_iter()
+            .map(|event| event.into_raw_store_event::<_, S>())
+            .collect())
+    }
+
+    /// This function returns a stream representing the full event store table content, without
+    /// deserializing the events payloads. See [`RawStoreEvent`].
+    pub fn stream_raw_events<'s>(
+        &'s self,
+        executor: impl Executor<'s, Database = Postgres> + 's,
+    ) -> BoxStream<'s, Result<RawStoreEvent<A::Event, S>, PgStoreError>> {
+        Box::pin({
+            sqlx::query_as::<_, DbRawEvent>(self.inner.statements.select_all())
+                .fetch(executor)
+                .map(|res| Ok(res?.into_raw_store_event::<_, S>()))
+        })
+    }
 }
 
 /// Concrete implementation of [`EventStoreLockGuard`] for the [`PgStore`].
@@ -162,69 +670,215 @@ pub struct PgStoreLockGuard {
 /// Marking [`PgStoreLockGuard`] as an [`UnlockOnDrop`] trait object.
 impl UnlockOnDrop for PgStoreLockGuard {}
 
-#[async_trait]
-impl<A, S> EventStore for PgStore<A, S>
+/// Concrete implementation of [`EventStoreLockGuard`] for the [`PgStore`] using
+/// [`LockStrategy::RowLevel`].
+///
+/// It holds the [`Transaction`] in which the aggregate row has been locked. When dropped, the
+/// [`Transaction`] is rolled back thus releasing the row lock.
+pub struct PgStoreRowLockGuard {
+    _transaction: Transaction<'static, Postgres>,
+}
+
+/// Marking [`PgStoreRowLockGuard`] as an [`UnlockOnDrop`] trait object.
+impl UnlockOnDrop for PgStoreRowLockGuard {}
+
+impl<A, S> PgStore<A, S>
 where
-    A: Aggregate,
+    A: Aggregate + 'static,
     A::State: Send,
-    A::Event: Send + Sync,
+    A::Event: Send + Sync + 'static,
     S: Schema<A::Event> + Persistable + Send + Sync,
 {
-    type Aggregate = A;
-    type Error = PgStoreError;
-
-    async fn lock(&self, aggregate_id: Uuid) -> Result<EventStoreLockGuard, Self::Error> {
-        let (key, _) = aggregate_id.as_u64_pair();
-        let connection = self.inner.pool.acquire().await?;
-        let lock_guard = PgStoreLockGuardAsyncSendTryBuilder {
-            lock: PgAdvisoryLock::with_key(PgAdvisoryLockKey::BigInt(key as i64)),
-            guard_builder: |lock: &PgAdvisoryLock| Box::pin(async move { lock.acquire(connection).await }),
+    /// Persists the given events like [`EventStore::persist`], storing the given idempotency token
+    /// along with them. If the events of a previous call with the same token for the same aggregate
+    /// instance have been persisted, nothing is persisted and they are returned instead.
+    ///
+    /// This makes it safe to retry a persist failed with an ambiguous error (e.g. the connection
+    /// dropped while committing), reusing the same token, without writing the events twice.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the store hasn't been built with
+    /// [`super::PgStoreBuilder::with_idempotency_tokens`], or persisting the events fails.
+    pub async fn persist_idempotent(
+        &self,
+        aggregate_state: &mut AggregateState<A::State>,
+        events: Vec<A::Event>,
+        idempotency_token: Uuid,
+    ) -> Result<Vec<StoreEvent<A::Event>>, PgStoreError> {
+        if !self.inner.idempotency_tokens {
+            return Err(PgStoreError::Custom(
+                "The store has been built without idempotency tokens".to_string().into(),
+            ));
         }
-        .try_build()
-        .await?;
-        Ok(EventStoreLockGuard::new(lock_guard))
-    }
 
-    async fn by_aggregate_id(&self, aggregate_id: Uuid) -> Result<Vec<StoreEvent<A::Event>>, Self::Error> {
-        Ok(sqlx::query_as::<_, DbEvent>(self.inner.statements.by_aggregate_id())
-            .bind(aggregate_id)
-            .fetch_all(&self.inner.pool)
-            .await?
-            .into_iter()
-            .map(|event| Ok(event.try_into_store_event::<_, S>()?))
-            .filter_map(Result::transpose)
-            .collect::<Result<Vec<StoreEvent<A::Event>>, Self::Error>>()?)
+        let aggregate_id: Uuid = *aggregate_state.id();
+
+        self.persist_events(aggregate_state, events, Some(idempotency_token), Metadata::default())
+            .await
+            .map_err(|error| error.with_context(ErrorContext::new("persist").with_aggregate_id(aggregate_id)))
     }
 
-    // Clippy introduced `blocks_in_conditions` lint. With certain version of rust and tracing this
-    // line throws an error see: https://github.com/rust-lang/rust-clippy/issues/12281
-    #[tracing::instrument(skip_all, fields(aggregate_id = % aggregate_state.id()), err)]
-    async fn persist(
+    async fn persist_events(
         &self,
         aggregate_state: &mut AggregateState<A::State>,
         events: Vec<A::Event>,
-    ) -> Result<Vec<StoreEvent<A::Event>>, Self::Error> {
-        let mut transaction: Transaction<Postgres> = self.inner.pool.begin().await?;
-        let occurred_on: DateTime<Utc> = Utc::now();
-        let mut store_events: Vec<StoreEvent<A::Event>> = vec![];
+        idempotency_token: Option<Uuid>,
+        metadata: Metadata,
+    ) -> Result<Vec<StoreEvent<A::Event>>, PgStoreError> {
+        let mut transaction: Transaction<Postgres> = self.inner.begin().await?;
+        let aggregate_id = *aggregate_state.id();
+
+        // A previous attempt with the same idempotency token succeeded: its outcome is returned.
+        if let Some(idempotency_token) = idempotency_token {
+            let events: Vec<DbRawEvent> =
+                sqlx::query_as::<_, DbRawEvent>(self.inner.statements.select_by_idempotency_token())
+                    .bind(aggregate_id)
+                    .bind(idempotency_token)
+                    .fetch_all(&mut *transaction)
+                    .await?;
+            let store_events: Vec<StoreEvent<A::Event>> = self.inner.decode_events::<S>(events).await?;
+
+            if !store_events.is_empty() {
+                drop(aggregate_state.take_lock());
+                return Ok(store_events);
+            }
+        }
+
+        let (store_events, visible_events) = self
+            .write_events(aggregate_state, events, idempotency_token, &metadata, &mut transaction)
+            .await?;
+
+        // From the commit onwards the work is done in a separate task, so that cancelling this future
+        // (e.g. on timeout) can't leave the events committed but not dispatched: the outcome is either
+        // fully rolled back, or fully committed and dispatched.
+        let inner: Arc<InnerPgStore<A>> = Arc::clone(&self.inner);
+        let lock: Option<EventStoreLockGuard> = aggregate_state.take_lock();
+
+        let task = crate::runtime::spawn(async move {
+            transaction.commit().await?;
+
+            // We need to drop the lock on the aggregate state here as:
+            // 1. the events have already been persisted, hence the DB has the latest aggregate;
+            // 2. the event handlers below might need to access this aggregate atomically (causing a deadlock!).
+            drop(lock);
+
+            inner.dispatch(&store_events, &visible_events).await;
+
+            Ok::<_, PgStoreError>(store_events)
+        });
 
+        task.await.map_err(PgStoreError::Custom)?
+    }
+
+    /// Writes the given events in the given transaction, running the persist interceptors and the
+    /// transactional event handlers, without committing it. Returns the written events, along with
+    /// whether each of them is visible straight away.
+    pub(super) async fn write_events(
+        &self,
+        aggregate_state: &mut AggregateState<A::State>,
+        mut events: Vec<A::Event>,
+        idempotency_token: Option<Uuid>,
+        metadata: &Metadata,
+        transaction: &mut Transaction<'static, Postgres>,
+    ) -> Result<(Vec<StoreEvent<A::Event>>, Vec<bool>), PgStoreError> {
         let aggregate_id = *aggregate_state.id();
 
+        for persist_interceptor in &self.inner.persist_interceptors {
+            let span = tracing::trace_span!(
+                "esrs.persist_interceptor",
+                aggregate_id = %aggregate_id,
+                persist_interceptor = persist_interceptor.name()
+            );
+            let _e = span.enter();
+
+            if let Err(error) = persist_interceptor
+                .before_persist(aggregate_id, &mut events, transaction)
+                .await
+            {
+                tracing::error!({
+                    aggregate_id = %aggregate_id,
+                    persist_interceptor = persist_interceptor.name(),
+                    error = ?error,
+                }, "persist interceptor vetoed the events persistence");
+
+                return Err(error);
+            }
+        }
+
+        let occurred_on: Option<DateTime<Utc>> = self.occurred_on(aggregate_id, &mut **transaction).await?;
+        let mut store_events: Vec<StoreEvent<A::Event>> = vec![];
+        // Events visible in the future are neither handled nor published until released.
+        let mut visible_events: Vec<bool> = vec![];
+
         for event in events.into_iter() {
-            let store_event: StoreEvent<<A as Aggregate>::Event> = self
+            let visible_at: Option<DateTime<Utc>> = self
+                .inner
+                .visibility
+                .as_ref()
+                .and_then(|visibility| visibility.visible_at(&event))
+                .filter(|visible_at| *visible_at > Utc::now());
+
+            let sequence_number: SequenceNumber = aggregate_state.next_sequence_number();
+            let context: ErrorContext = ErrorContext::new("persist")
+                .with_aggregate_id(aggregate_id)
+                .with_sequence_number(sequence_number);
+
+            let (store_event, _stored_payload) = self
                 .save_event(
                     aggregate_id,
                     event,
                     occurred_on,
-                    aggregate_state.next_sequence_number(),
-                    &mut *transaction,
+                    sequence_number,
+                    metadata,
+                    &mut **transaction,
                 )
-                .await?;
+                .await
+                .map_err(|error| error.with_context(context.clone()))?;
+
+            let result: Result<(), PgStoreError> = async {
+                if let Some(idempotency_token) = idempotency_token {
+                    let _ = sqlx::query(self.inner.statements.update_idempotency_token())
+                        .bind(store_event.id)
+                        .bind(idempotency_token)
+                        .execute(&mut **transaction)
+                        .await?;
+                }
+
+                #[cfg(feature = "integrity")]
+                self.sign_event(&store_event, &_stored_payload, transaction).await?;
+                #[cfg(feature = "integrity")]
+                self.chain_event(&store_event, &_stored_payload, transaction).await?;
+
+                if let Some(visible_at) = visible_at {
+                    let _ = sqlx::query(self.inner.statements.insert_deferred())
+                        .bind(store_event.id)
+                        .bind(visible_at)
+                        .execute(&mut **transaction)
+                        .await?;
+                } else if self.inner.outbox {
+                    let _ = sqlx::query(self.inner.statements.insert_outbox())
+                        .bind(store_event.id)
+                        .execute(&mut **transaction)
+                        .await?;
+                }
+
+                Ok(())
+            }
+            .await;
+            result.map_err(|error| error.with_context(context.with_event_id(store_event.id)))?;
 
+            visible_events.push(visible_at.is_none());
             store_events.push(store_event);
         }
 
-        for store_event in &store_events {
+        let visible_store_events: Vec<&StoreEvent<A::Event>> = store_events
+            .iter()
+            .zip(&visible_events)
+            .filter_map(|(store_event, visible)| visible.then_some(store_event))
+            .collect();
+
+        for store_event in visible_store_events.iter().copied() {
             for transactional_event_handler in &self.inner.transactional_event_handlers {
                 let span = tracing::trace_span!(
                     "esrs.transactional_event_handler",
@@ -234,7 +888,7 @@ where
                 );
                 let _e = span.enter();
 
-                if let Err(error) = transactional_event_handler.handle(store_event, &mut transaction).await {
+                if let Err(error) = transactional_event_handler.handle(store_event, transaction).await {
                     tracing::error!({
                         event_id = %store_event.id,
                         aggregate_id = %store_event.aggregate_id,
@@ -242,75 +896,155 @@ where
                         error = ?error,
                     }, "transactional event handler failed to handle event");
 
-                    return Err(error);
+                    return Err(error.with_context(
+                        ErrorContext::new("persist")
+                            .with_aggregate_id(store_event.aggregate_id)
+                            .with_event_id(store_event.id)
+                            .with_sequence_number(store_event.sequence_number),
+                    ));
                 }
             }
         }
 
-        transaction.commit().await?;
+        Ok((store_events, visible_events))
+    }
+}
 
-        // We need to drop the lock on the aggregate state here as:
-        // 1. the events have already been persisted, hence the DB has the latest aggregate;
-        // 2. the event handlers below might need to access this aggregate atomically (causing a deadlock!).
-        drop(aggregate_state.take_lock());
+#[async_trait]
+impl<A, S> EventStore for PgStore<A, S>
+where
+    A: Aggregate + 'static,
+    A::State: Send,
+    A::Event: Send + Sync + 'static,
+    S: Schema<A::Event> + Persistable + Send + Sync,
+{
+    type Aggregate = A;
+    type Error = PgStoreError;
 
-        let event_handlers = self.inner.event_handlers.read().await;
-        for store_event in &store_events {
-            // NOTE: should this be parallelized?
-            for event_handler in event_handlers.iter() {
-                let span = tracing::debug_span!(
-                    "esrs.event_handler",
-                    event_id = %store_event.id,
-                    aggregate_id = %store_event.aggregate_id,
-                    event_handler = event_handler.name()
-                );
-                let _e = span.enter();
+    async fn lock(&self, aggregate_id: Uuid) -> Result<EventStoreLockGuard, Self::Error> {
+        self.lock_aggregate(aggregate_id)
+            .await
+            .map_err(|error| error.with_context(ErrorContext::new("lock").with_aggregate_id(aggregate_id)))
+    }
+
+    async fn by_aggregate_id(&self, aggregate_id: Uuid) -> Result<Vec<StoreEvent<A::Event>>, Self::Error> {
+        let query = sqlx::query_as::<_, DbRawEvent>(self.inner.statements.by_aggregate_id()).bind(aggregate_id);
+        self.load_events(aggregate_id, query).await
+    }
+
+    async fn by_aggregate_id_after(
+        &self,
+        aggregate_id: Uuid,
+        sequence_number: SequenceNumber,
+    ) -> Result<Vec<StoreEvent<A::Event>>, Self::Error> {
+        let query = sqlx::query_as::<_, DbRawEvent>(self.inner.statements.by_aggregate_id_after())
+            .bind(aggregate_id)
+            .bind(sequence_number);
+        self.load_events(aggregate_id, query).await
+    }
 
-                event_handler.handle(store_event).await;
+    async fn by_aggregate_id_paginated(
+        &self,
+        aggregate_id: Uuid,
+        from_sequence_number: SequenceNumber,
+        limit: usize,
+    ) -> Result<Vec<StoreEvent<A::Event>>, Self::Error> {
+        let query = sqlx::query_as::<_, DbRawEvent>(self.inner.statements.by_aggregate_id_paginated())
+            .bind(aggregate_id)
+            .bind(from_sequence_number)
+            .bind(limit as i64);
+        self.load_events(aggregate_id, query).await
+    }
+
+    async fn events_since(&self, since: Since, limit: usize) -> Result<Vec<StoreEvent<A::Event>>, Self::Error> {
+        let query = match since {
+            Since::OccurredOn(occurred_on) => {
+                sqlx::query_as::<_, DbRawEvent>(self.inner.statements.select_since_occurred_on()).bind(occurred_on)
             }
-        }
+            Since::EventId(event_id) => {
+                sqlx::query_as::<_, DbRawEvent>(self.inner.statements.select_since_event_id()).bind(event_id)
+            }
+        };
 
-        // Publishing to subscribed event buses
-        self.publish(&store_events).await;
+        let result: Result<Vec<StoreEvent<A::Event>>, PgStoreError> = async {
+            let events: Vec<DbRawEvent> = query.bind(limit as i64).fetch_all(self.inner.read_pool()).await?;
 
-        Ok(store_events)
+            self.inner.decode_events::<S>(events).await
+        }
+        .await;
+
+        result.map_err(|error| error.with_context(ErrorContext::new("events_since")))
     }
 
-    async fn publish(&self, store_events: &[StoreEvent<A::Event>]) {
-        let futures: Vec<_> = self
-            .inner
-            .event_buses
-            .iter()
-            .map(|bus| async move {
-                for store_event in store_events {
-                    bus.publish(store_event).await;
-                }
+    async fn exists(&self, aggregate_id: Uuid) -> Result<bool, Self::Error> {
+        sqlx::query_scalar(self.inner.statements.exists_by_aggregate_id())
+            .bind(aggregate_id)
+            .fetch_one(&self.inner.pool)
+            .await
+            .map_err(|error| {
+                PgStoreError::from(error).with_context(ErrorContext::new("exists").with_aggregate_id(aggregate_id))
             })
-            .collect();
+    }
 
-        let _ = futures::future::join_all(futures).await;
+    // Clippy introduced `blocks_in_conditions` lint. With certain version of rust and tracing this
+    // line throws an error see: https://github.com/rust-lang/rust-clippy/issues/12281
+    #[tracing::instrument(skip_all, fields(aggregate_id = % aggregate_state.id()), err)]
+    async fn persist(
+        &self,
+        aggregate_state: &mut AggregateState<A::State>,
+        events: Vec<A::Event>,
+    ) -> Result<Vec<StoreEvent<A::Event>>, Self::Error> {
+        let aggregate_id: Uuid = *aggregate_state.id();
+
+        self.persist_events(aggregate_state, events, None, Metadata::default())
+            .await
+            .map_err(|error| error.with_context(ErrorContext::new("persist").with_aggregate_id(aggregate_id)))
     }
 
-    async fn delete(&self, aggregate_id: Uuid) -> Result<(), Self::Error> {
-        let mut transaction: Transaction<Postgres> = self.inner.pool.begin().await?;
+    #[tracing::instrument(skip_all, fields(aggregate_id = % aggregate_state.id()), err)]
+    async fn persist_with_metadata(
+        &self,
+        aggregate_state: &mut AggregateState<A::State>,
+        events: Vec<A::Event>,
+        metadata: Metadata,
+    ) -> Result<Vec<StoreEvent<A::Event>>, Self::Error> {
+        let aggregate_id: Uuid = *aggregate_state.id();
 
-        let _ = sqlx::query(self.inner.statements.delete_by_aggregate_id())
-            .bind(aggregate_id)
-            .execute(&mut *transaction)
+        self.persist_events(aggregate_state, events, None, metadata)
             .await
-            .map(|_| ())?;
+            .map_err(|error| error.with_context(ErrorContext::new("persist").with_aggregate_id(aggregate_id)))
+    }
 
-        for transactional_event_handler in self.inner.transactional_event_handlers.iter() {
-            transactional_event_handler
-                .delete(aggregate_id, &mut transaction)
-                .await?;
-        }
+    async fn publish(&self, store_events: &[StoreEvent<A::Event>]) {
+        let store_events: Vec<&StoreEvent<A::Event>> = store_events.iter().collect();
+        self.publish_events(&store_events).await;
+    }
+
+    async fn delete(&self, aggregate_id: Uuid) -> Result<(), Self::Error> {
+        let result: Result<(), PgStoreError> = async {
+            let mut transaction: Transaction<Postgres> = self.inner.begin().await?;
 
-        transaction.commit().await?;
+            let _ = sqlx::query(self.inner.statements.delete_by_aggregate_id())
+                .bind(aggregate_id)
+                .execute(&mut *transaction)
+                .await
+                .map(|_| ())?;
+
+            for transactional_event_handler in self.inner.transactional_event_handlers.iter() {
+                transactional_event_handler
+                    .delete(aggregate_id, &mut transaction)
+                    .await?;
+            }
+
+            Ok(transaction.commit().await?)
+        }
+        .await;
+        result.map_err(|error| error.with_context(ErrorContext::new("delete").with_aggregate_id(aggregate_id)))?;
 
         let event_handlers = self.inner.event_handlers.read().await;
         // NOTE: should this be parallelized?
         for event_handler in event_handlers.iter() {
+            let _permit = self.inner.event_handler_permit().await;
             event_handler.delete(aggregate_id).await;
         }
 
@@ -318,6 +1052,44 @@ where
     }
 }
 
+#[async_trait]
+impl<A, S> IdempotentEventStore for PgStore<A, S>
+where
+    A: Aggregate + 'static,
+    A::State: Send,
+    A::Event: Send + Sync + 'static,
+    S: Schema<A::Event> + Persistable + Send + Sync,
+{
+    async fn by_idempotency_key(
+        &self,
+        aggregate_id: Uuid,
+        idempotency_key: Uuid,
+    ) -> Result<Vec<StoreEvent<A::Event>>, Self::Error> {
+        if !self.inner.idempotency_tokens {
+            return Err(PgStoreError::Custom(
+                "The store has been built without idempotency tokens".to_string().into(),
+            ));
+        }
+
+        self.fetch_events(
+            sqlx::query_as::<_, DbRawEvent>(self.inner.statements.select_by_idempotency_token())
+                .bind(aggregate_id)
+                .bind(idempotency_key),
+        )
+        .await
+        .map_err(|error| error.with_context(ErrorContext::new("by_idempotency_key").with_aggregate_id(aggregate_id)))
+    }
+
+    async fn persist_idempotent(
+        &self,
+        aggregate_state: &mut AggregateState<A::State>,
+        events: Vec<A::Event>,
+        idempotency_key: Uuid,
+    ) -> Result<Vec<StoreEvent<A::Event>>, Self::Error> {
+        PgStore::persist_idempotent(self, aggregate_state, events, idempotency_key).await
+    }
+}
+
 /// Debug implementation for [`PgStore`]. It just shows the statements, that are the only thing
 /// that might be useful to debug.
 impl<T: Aggregate> std::fmt::Debug for PgStore<T> {
@@ -0,0 +1,173 @@
+use chrono::{DateTime, Utc};
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+use crate::sql::event::DbRawEvent;
+use crate::sql::statements::StatementsHandler;
+use crate::types::SequenceNumber;
+use crate::Aggregate;
+
+use super::{PgStore, PgStoreError, RawStoreEvent, Schema};
+
+/// An event of any of the aggregates streamed by a [`GlobalEventStream`], with its payload still
+/// serialized.
+#[derive(sqlx::FromRow, Debug)]
+pub struct GlobalEvent {
+    /// The [`Aggregate::NAME`] of the aggregate that emitted the event.
+    pub aggregate_type: String,
+    #[sqlx(flatten)]
+    event: DbRawEvent,
+}
+
+impl GlobalEvent {
+    /// Uniquely identifies the event among all events emitted from all aggregates.
+    pub fn id(&self) -> Uuid {
+        self.event.id
+    }
+
+    /// The aggregate instance that emitted the event.
+    pub fn aggregate_id(&self) -> Uuid {
+        self.event.aggregate_id
+    }
+
+    /// The timestamp of when the event is persisted.
+    pub fn occurred_on(&self) -> DateTime<Utc> {
+        self.event.occurred_on
+    }
+
+    /// The sequence number of the event, within its specific aggregate instance.
+    pub fn sequence_number(&self) -> SequenceNumber {
+        self.event.sequence_number
+    }
+
+    /// Returns whether the event has been emitted by the given aggregate.
+    pub fn is<A>(&self) -> bool
+    where
+        A: Aggregate,
+    {
+        self.aggregate_type == A::NAME
+    }
+
+    /// Converts self into a [`RawStoreEvent`] of the given event type, whose payload is deserialized
+    /// through the given schema. Meant to be called once [`GlobalEvent::is`] identified the
+    /// aggregate of the event.
+    pub fn into_raw_store_event<E, S>(self) -> RawStoreEvent<E, S>
+    where
+        S: Schema<E>,
+    {
+        self.event.into_raw_store_event()
+    }
+}
+
+/// Stream of the events of many aggregates, ordered by `occurred_on` across all of them, so that
+/// the projections shared by many aggregates can be rebuilt without merging the streams of their
+/// stores by hand.
+///
+/// Events occurred at the same time are ordered by aggregate type, aggregate id and sequence number,
+/// so that the order is stable across runs and follows the sequence numbers within each aggregate
+/// instance.
+///
+/// ```ignore
+/// let global_event_stream = GlobalEventStream::new()
+///     .with_aggregate::<AggregateA>()
+///     .with_aggregate::<AggregateB>();
+///
+/// let mut events = global_event_stream.stream(&pool);
+/// while let Some(event) = events.next().await {
+///     let event = event?;
+///     if event.is::<AggregateA>() {
+///         let store_event = event.into_raw_store_event::<EventA, EventA>().into_store_event()?;
+///     }
+/// }
+/// ```
+///
+/// Note that the soft deleted events are streamed as well, and that the events of transactions
+/// committed while streaming might be missed: the stream isn't meant to be tailed.
+#[derive(Default)]
+pub struct GlobalEventStream {
+    sources: Vec<String>,
+    query: String,
+}
+
+impl GlobalEventStream {
+    /// Creates a new, empty, [`GlobalEventStream`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the events of the given aggregate, read from its event store table.
+    pub fn with_aggregate<A>(self) -> Self
+    where
+        A: Aggregate,
+    {
+        self.with_aggregate_table::<A>(format!("{}_events", A::NAME).as_str())
+    }
+
+    /// Adds the events of the given aggregate, read from the given event store table. See
+    /// [`super::PgStoreBuilder::with_table_name`].
+    pub fn with_aggregate_table<A>(self, table_name: &str) -> Self
+    where
+        A: Aggregate,
+    {
+        let source: String = format!(
+            include_str!("../../sql/postgres/statements/select_global_events_source.sql"),
+            A::NAME,
+            table_name
+        );
+
+        self.with_source(source)
+    }
+
+    /// Adds the events of the given store, read as [`PgStore::stream_raw_events`] does: from its
+    /// event store table (or its view over the shared table), leaving out the soft deleted events.
+    pub fn with_store<A, S>(self, store: &PgStore<A, S>) -> Self
+    where
+        A: Aggregate,
+    {
+        let source: String = format!(
+            include_str!("../../sql/postgres/statements/select_global_events_store_source.sql"),
+            A::NAME,
+            store.inner.statements.select_all()
+        );
+
+        self.with_source(source)
+    }
+
+    /// Adds the events of all the aggregates stored in the given shared table. See
+    /// [`super::PgStoreBuilder::with_shared_table`].
+    pub fn with_shared_table(self, shared_table_name: &str) -> Self {
+        let source: String = format!(
+            include_str!("../../sql/postgres/statements/select_global_events_shared_source.sql"),
+            shared_table_name
+        );
+
+        self.with_source(source)
+    }
+
+    fn with_source(mut self, source: String) -> Self {
+        self.sources.push(source);
+        self.query = format!(
+            include_str!("../../sql/postgres/statements/select_global_events.sql"),
+            self.sources.join(" UNION ALL ")
+        );
+        self
+    }
+
+    /// Returns a stream of the events of all the added aggregates, in order.
+    pub fn stream<'s>(
+        &'s self,
+        executor: impl Executor<'s, Database = Postgres> + 's,
+    ) -> BoxStream<'s, Result<GlobalEvent, PgStoreError>> {
+        if self.sources.is_empty() {
+            return Box::pin(futures::stream::empty());
+        }
+
+        Box::pin(
+            sqlx::query_as::<_, GlobalEvent>(self.query.as_str())
+                .fetch(executor)
+                .map(|res| Ok(res?)),
+        )
+    }
+}
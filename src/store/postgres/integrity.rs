@@ -0,0 +1,334 @@
+//! Tamper detection of the events persisted in the event store table, through HMAC signatures and
+//! per-aggregate hash chains.
+
+use hmac::{Hmac, Mac};
+use serde_json::value::RawValue;
+use sha2::{Digest, Sha256};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::store::StoreEvent;
+use crate::types::SequenceNumber;
+use crate::Aggregate;
+
+use super::persistable::Persistable;
+use super::{PgStore, PgStoreError, Schema};
+
+/// Provider of the keys used to sign the persisted events, supporting keys rotation: events are
+/// signed with the current key, and verified with the key they have been signed with.
+pub trait KeyProvider: Send + Sync {
+    /// Returns the id and the value of the key used to sign the events being persisted.
+    fn current_key(&self) -> (String, Vec<u8>);
+
+    /// Returns the value of the key with the given id, if known.
+    fn key(&self, key_id: &str) -> Option<Vec<u8>>;
+}
+
+/// The `TamperReason` enum defines why an event failed the signature verification:
+///
+/// - `MissingSignature`: The event has no signature, e.g. it has been inserted via direct SQL, or
+///   persisted before enabling the signing.
+/// - `UnknownKey`: The event has been signed with a key unknown to the [`KeyProvider`].
+/// - `InvalidSignature`: The signature doesn't match the event, that has been modified.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum TamperReason {
+    MissingSignature,
+    UnknownKey(String),
+    InvalidSignature,
+}
+
+/// An event failing the signature verification.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TamperedEvent {
+    /// The id of the event.
+    pub id: Uuid,
+    /// The sequence number of the event.
+    pub sequence_number: SequenceNumber,
+    /// Why the event failed the verification.
+    pub reason: TamperReason,
+}
+
+/// The `ChainBreakReason` enum defines why the hash chain of an aggregate instance is broken:
+///
+/// - `MissingHash`: The event is not part of the chain, e.g. it has been inserted via direct SQL, or
+///   persisted before enabling the hash chain.
+/// - `InvalidHash`: The hash doesn't match the event, that has been modified or moved.
+/// - `BrokenLink`: The previous hash doesn't match the hash of the preceding event, that has been
+///   deleted, replaced or reordered.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ChainBreakReason {
+    MissingHash,
+    InvalidHash,
+    BrokenLink,
+}
+
+/// The first event breaking the hash chain of an aggregate instance.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ChainBreak {
+    /// The id of the event.
+    pub id: Uuid,
+    /// The sequence number of the event.
+    pub sequence_number: SequenceNumber,
+    /// Why the event breaks the chain.
+    pub reason: ChainBreakReason,
+}
+
+#[derive(sqlx::FromRow)]
+struct ChainedEvent {
+    id: Uuid,
+    aggregate_id: Uuid,
+    sequence_number: SequenceNumber,
+    payload: String,
+    previous_hash: Option<Vec<u8>>,
+    hash: Option<Vec<u8>>,
+}
+
+#[derive(sqlx::FromRow)]
+struct SignedEvent {
+    id: Uuid,
+    aggregate_id: Uuid,
+    sequence_number: SequenceNumber,
+    payload: String,
+    signature: Option<Vec<u8>>,
+    signature_key_id: Option<String>,
+}
+
+/// Builds the HMAC over the id, the aggregate id, the sequence number and the serialized payload
+/// of an event.
+fn hmac(key: &[u8], id: Uuid, aggregate_id: Uuid, sequence_number: SequenceNumber, payload: &str) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC can take a key of any size");
+    mac.update(id.as_bytes());
+    mac.update(aggregate_id.as_bytes());
+    mac.update(&sequence_number.to_be_bytes());
+    mac.update(payload.as_bytes());
+    mac
+}
+
+/// Builds the SHA-256 hash over the hash of the previous event, the id, the aggregate id, the
+/// sequence number and the serialized payload of an event.
+fn chain_hash(
+    previous_hash: Option<&[u8]>,
+    id: Uuid,
+    aggregate_id: Uuid,
+    sequence_number: SequenceNumber,
+    payload: &str,
+) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.update(previous_hash.unwrap_or_default());
+    hasher.update(id.as_bytes());
+    hasher.update(aggregate_id.as_bytes());
+    hasher.update(sequence_number.to_be_bytes());
+    hasher.update(payload.as_bytes());
+    hasher.finalize().to_vec()
+}
+
+impl<A, S> PgStore<A, S>
+where
+    A: Aggregate,
+    A::Event: Send + Sync,
+    S: Schema<A::Event> + Persistable + Send + Sync,
+{
+    /// Signs the given just persisted event, if the store has been built with a [`KeyProvider`].
+    /// The signature is computed over the given payload, as stored in the database.
+    pub(crate) async fn sign_event(
+        &self,
+        store_event: &StoreEvent<A::Event>,
+        stored_payload: &RawValue,
+        executor: &mut PgConnection,
+    ) -> Result<(), PgStoreError> {
+        let Some(key_provider) = &self.inner.key_provider else {
+            return Ok(());
+        };
+
+        let (key_id, key) = key_provider.current_key();
+        let signature: Vec<u8> = hmac(
+            &key,
+            store_event.id,
+            store_event.aggregate_id,
+            store_event.sequence_number,
+            stored_payload.get(),
+        )
+        .finalize()
+        .into_bytes()
+        .to_vec();
+
+        let _ = sqlx::query(
+            format!(
+                include_str!("../../sql/postgres/statements/update_signature.sql"),
+                self.table_name()
+            )
+            .as_str(),
+        )
+        .bind(store_event.id)
+        .bind(signature)
+        .bind(key_id)
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Links the given just persisted event to the hash chain of its aggregate instance, if the
+    /// store has been built with the hash chain enabled.
+    pub(crate) async fn chain_event(
+        &self,
+        store_event: &StoreEvent<A::Event>,
+        stored_payload: &RawValue,
+        executor: &mut PgConnection,
+    ) -> Result<(), PgStoreError> {
+        if !self.inner.hash_chain {
+            return Ok(());
+        }
+
+        let previous_hash: Option<Vec<u8>> = if store_event.sequence_number > 1 {
+            sqlx::query_scalar::<_, Option<Vec<u8>>>(
+                format!(
+                    include_str!("../../sql/postgres/statements/select_hash.sql"),
+                    self.table_name()
+                )
+                .as_str(),
+            )
+            .bind(store_event.aggregate_id)
+            .bind(store_event.sequence_number - 1)
+            .fetch_optional(&mut *executor)
+            .await?
+            .flatten()
+        } else {
+            None
+        };
+
+        let hash: Vec<u8> = chain_hash(
+            previous_hash.as_deref(),
+            store_event.id,
+            store_event.aggregate_id,
+            store_event.sequence_number,
+            stored_payload.get(),
+        );
+
+        let _ = sqlx::query(
+            format!(
+                include_str!("../../sql/postgres/statements/update_hash.sql"),
+                self.table_name()
+            )
+            .as_str(),
+        )
+        .bind(store_event.id)
+        .bind(previous_hash)
+        .bind(hash)
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Verifies the hash chain of the given aggregate instance, returning the first event breaking
+    /// it, if any. `None` proves that no event of the stream has been rewritten, removed or
+    /// reordered, but for the trailing ones.
+    ///
+    /// Note that compacting or rekeying an aggregate instance breaks its hash chain.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the query fails.
+    pub async fn verify_chain(&self, aggregate_id: Uuid) -> Result<Option<ChainBreak>, PgStoreError> {
+        let chained_events: Vec<ChainedEvent> = sqlx::query_as::<_, ChainedEvent>(
+            format!(
+                include_str!("../../sql/postgres/statements/select_hashes_by_aggregate_id.sql"),
+                self.table_name()
+            )
+            .as_str(),
+        )
+        .bind(aggregate_id)
+        .fetch_all(&self.inner.pool)
+        .await?;
+
+        let mut expected_previous_hash: Option<Vec<u8>> = None;
+
+        for event in chained_events {
+            let reason: Option<ChainBreakReason> = match event.hash.as_deref() {
+                None => Some(ChainBreakReason::MissingHash),
+                Some(_) if event.previous_hash != expected_previous_hash => Some(ChainBreakReason::BrokenLink),
+                Some(hash) => {
+                    let expected_hash: Vec<u8> = chain_hash(
+                        event.previous_hash.as_deref(),
+                        event.id,
+                        event.aggregate_id,
+                        event.sequence_number,
+                        &event.payload,
+                    );
+                    (hash != expected_hash.as_slice()).then_some(ChainBreakReason::InvalidHash)
+                }
+            };
+
+            if let Some(reason) = reason {
+                return Ok(Some(ChainBreak {
+                    id: event.id,
+                    sequence_number: event.sequence_number,
+                    reason,
+                }));
+            }
+
+            expected_previous_hash = event.hash;
+        }
+
+        Ok(None)
+    }
+
+    /// Verifies the signatures of all the events of the given aggregate instance, returning the
+    /// ones failing the verification, sorted by sequence number. An empty result means that no
+    /// event has been tampered with via direct SQL.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the store hasn't been built with a [`KeyProvider`], or the query
+    /// fails.
+    pub async fn verify_signatures(&self, aggregate_id: Uuid) -> Result<Vec<TamperedEvent>, PgStoreError> {
+        let key_provider = self.inner.key_provider.as_ref().ok_or_else(|| {
+            PgStoreError::Custom("The store has been built without a key provider".to_string().into())
+        })?;
+
+        let signed_events: Vec<SignedEvent> = sqlx::query_as::<_, SignedEvent>(
+            format!(
+                include_str!("../../sql/postgres/statements/select_signatures_by_aggregate_id.sql"),
+                self.table_name()
+            )
+            .as_str(),
+        )
+        .bind(aggregate_id)
+        .fetch_all(&self.inner.pool)
+        .await?;
+
+        Ok(signed_events
+            .into_iter()
+            .filter_map(|event| {
+                let reason: TamperReason = match (event.signature.as_deref(), event.signature_key_id) {
+                    (Some(signature), Some(key_id)) => match key_provider.key(&key_id) {
+                        None => TamperReason::UnknownKey(key_id),
+                        Some(key) => {
+                            let mac: Hmac<Sha256> = hmac(
+                                &key,
+                                event.id,
+                                event.aggregate_id,
+                                event.sequence_number,
+                                &event.payload,
+                            );
+
+                            if mac.verify_slice(signature).is_ok() {
+                                return None;
+                            }
+
+                            TamperReason::InvalidSignature
+                        }
+                    },
+                    _ => TamperReason::MissingSignature,
+                };
+
+                Some(TamperedEvent {
+                    id: event.id,
+                    sequence_number: event.sequence_number,
+                    reason,
+                })
+            })
+            .collect())
+    }
+}
@@ -1,11 +1,69 @@
+pub use admin::{AuditHook, CorrectionKind, EventCorrection};
+pub use backfill::*;
 pub use builder::*;
+pub use columns::*;
+pub use compaction::Compaction;
+pub use dead_letter::PgDeadLetterTable;
+pub use deferred::Visibility;
+pub use drift::{SchemaDriftError, SchemaDriftPolicy};
+#[cfg(feature = "encryption")]
+pub use encryption::{DataKeyProvider, InMemoryDataKeyProvider};
+pub use event_log::EventLog;
 pub use event_store::*;
+pub use global::{GlobalEvent, GlobalEventStream};
+#[cfg(feature = "integrity")]
+pub use integrity::{ChainBreak, ChainBreakReason, KeyProvider, TamperReason, TamperedEvent};
+pub use outbox::DebeziumOutbox;
+pub use raw_store_event::*;
+pub use redactor::Redactor;
+pub use rekey::RekeyMode;
+pub use relay::OutboxRelay;
 pub use schema::*;
+pub use snapshot::PgSnapshotStore;
+pub use subscription::{Checkpoint, Subscription};
+pub use unit_of_work::UnitOfWork;
+pub use valid_time::ValidTime;
+#[cfg(feature = "json-schema")]
+pub use validation::JsonSchemaValidator;
+pub use validation::{EventSchemaValidator, SchemaViolation};
 
+mod admin;
+pub mod analysis;
+mod archive;
+mod backfill;
 mod builder;
+mod columns;
+mod compaction;
+mod dead_letter;
+mod deferred;
+mod drift;
+#[cfg(feature = "encryption")]
+mod encryption;
+mod event_log;
 mod event_store;
+mod global;
+#[cfg(feature = "integrity")]
+mod integrity;
+mod outbox;
 pub mod persistable;
+pub mod projection;
+mod raw_store_event;
+mod redactor;
+mod rekey;
+mod relay;
 mod schema;
+mod search;
+mod snapshot;
+mod subscription;
+mod temporal;
+mod unit_of_work;
+mod valid_time;
+mod validation;
+
+use uuid::Uuid;
+
+use crate::manager::ConflictError;
+use crate::types::SequenceNumber;
 
 // Trait aliases are experimental. See issue #41517 <https://github.com/rust-lang/rust/issues/41517>
 // trait PgTransactionalEventHandler<A> = TransactionalEventHandler<A, PgStoreError, PgConnection> where A: Aggregate;
@@ -21,4 +79,117 @@ pub enum PgStoreError {
     /// Error while running a TransactionalEventHandler inside of the event store.
     #[error(transparent)]
     Custom(Box<dyn std::error::Error + Send + Sync>),
+    /// Error raised by an operation of the store, along with the context it failed in.
+    #[error("{context}: {source}")]
+    Context {
+        /// Where the error has been raised.
+        context: ErrorContext,
+        /// The underlying error.
+        source: Box<PgStoreError>,
+    },
+}
+
+impl PgStoreError {
+    /// Returns the underlying error, without the context it has been raised in, if any.
+    pub fn root_cause(&self) -> &PgStoreError {
+        match self {
+            Self::Context { source, .. } => source.root_cause(),
+            error => error,
+        }
+    }
+
+    /// Attaches the given context to self. The innermost context is kept, as it is the most
+    /// detailed one.
+    pub(crate) fn with_context(self, context: ErrorContext) -> Self {
+        match self {
+            Self::Context { .. } => self,
+            error => Self::Context {
+                context,
+                source: Box::new(error),
+            },
+        }
+    }
+}
+
+impl ConflictError for PgStoreError {
+    /// Returns `true` if the events violated the uniqueness of the aggregate id and sequence number.
+    fn is_conflict(&self) -> bool {
+        match self.root_cause() {
+            Self::Sqlx(sqlx::Error::Database(error)) => {
+                error.is_unique_violation()
+                    && error
+                        .constraint()
+                        .is_some_and(|constraint| constraint.ends_with("_aggregate_id_sequence_number"))
+            }
+            _ => false,
+        }
+    }
+}
+
+/// The operation of the store that failed, and the aggregate instance and event it was working
+/// on, if any. See [`PgStoreError::Context`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ErrorContext {
+    /// The name of the operation, e.g. `persist` or `load`.
+    pub operation: &'static str,
+    /// The aggregate instance the operation was working on.
+    pub aggregate_id: Option<Uuid>,
+    /// The event the operation was working on, once it has an id.
+    pub event_id: Option<Uuid>,
+    /// The sequence number of the event the operation was working on.
+    pub sequence_number: Option<SequenceNumber>,
+}
+
+impl ErrorContext {
+    pub(crate) fn new(operation: &'static str) -> Self {
+        Self {
+            operation,
+            aggregate_id: None,
+            event_id: None,
+            sequence_number: None,
+        }
+    }
+
+    pub(crate) fn with_aggregate_id(mut self, aggregate_id: Uuid) -> Self {
+        self.aggregate_id = Some(aggregate_id);
+        self
+    }
+
+    pub(crate) fn with_event_id(mut self, event_id: Uuid) -> Self {
+        self.event_id = Some(event_id);
+        self
+    }
+
+    pub(crate) fn with_sequence_number(mut self, sequence_number: SequenceNumber) -> Self {
+        self.sequence_number = Some(sequence_number);
+        self
+    }
+}
+
+impl std::fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} failed", self.operation)?;
+
+        let details: Vec<String> = vec![
+            self.aggregate_id.map(|id| format!("aggregate_id: {}", id)),
+            self.event_id.map(|id| format!("event_id: {}", id)),
+            self.sequence_number
+                .map(|number| format!("sequence_number: {}", number)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        if details.is_empty() {
+            Ok(())
+        } else {
+            write!(f, " ({})", details.join(", "))
+        }
+    }
+}
+
+impl From<crate::manager::CommandTimeout> for PgStoreError {
+    fn from(timeout: crate::manager::CommandTimeout) -> Self {
+        Self::Custom(Box::new(timeout))
+    }
 }
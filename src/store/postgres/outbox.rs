@@ -0,0 +1,108 @@
+use async_trait::async_trait;
+use sqlx::types::Json;
+use sqlx::{Executor, PgConnection, Postgres};
+
+use crate::handler::TransactionalEventHandler;
+use crate::store::StoreEvent;
+use crate::Aggregate;
+
+use super::analysis::EventTypeLocation;
+use super::PgStoreError;
+
+/// [`TransactionalEventHandler`] writing each persisted event to an outbox table laid out after the
+/// conventions of the Debezium outbox event router: `id`, `aggregatetype`, `aggregateid`, `type` and
+/// `payload`. This lets the events flow into existing CDC pipelines without a custom relay.
+///
+/// The rows are written in the same transaction as the events: the `id` is the event id, the
+/// `aggregatetype` is [`Aggregate::NAME`], the `type` is read from the serialized payload according
+/// to the given [`EventTypeLocation`], and the `payload` is the serialized payload, as stored in the
+/// event store table.
+///
+/// Register it through [`super::PgStoreBuilder::with_debezium_outbox`], after creating its table
+/// through [`DebeziumOutbox::create_table`].
+pub struct DebeziumOutbox {
+    table_name: String,
+    event_type_location: EventTypeLocation,
+    delete_after_insert: bool,
+}
+
+impl DebeziumOutbox {
+    /// Creates a [`DebeziumOutbox`] writing to the given table, reading the event types from the
+    /// serialized payloads according to the given [`EventTypeLocation`].
+    pub fn new(table_name: impl Into<String>, event_type_location: EventTypeLocation) -> Self {
+        Self {
+            table_name: table_name.into(),
+            event_type_location,
+            delete_after_insert: false,
+        }
+    }
+
+    /// Deletes the rows right after inserting them, in the same transaction. Debezium captures the
+    /// inserts from the write-ahead log anyway, so this keeps the outbox table from growing.
+    pub fn with_delete_after_insert(mut self) -> Self {
+        self.delete_after_insert = true;
+        self
+    }
+
+    /// Creates the outbox table, if it doesn't exist.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the query fails.
+    pub async fn create_table<'e>(&self, executor: impl Executor<'e, Database = Postgres>) -> Result<(), sqlx::Error> {
+        let query: String = format!(
+            include_str!("../../sql/postgres/statements/create_debezium_outbox.sql"),
+            self.table_name
+        );
+
+        sqlx::query(query.as_str()).execute(executor).await.map(|_| ())
+    }
+}
+
+#[async_trait]
+impl<A> TransactionalEventHandler<A, PgStoreError, PgConnection> for DebeziumOutbox
+where
+    A: Aggregate,
+    A::Event: Send + Sync,
+{
+    async fn handle(&self, event: &StoreEvent<A::Event>, executor: &mut PgConnection) -> Result<(), PgStoreError> {
+        let raw_payload = event
+            .raw_payload()
+            .ok_or_else(|| PgStoreError::Custom(format!("event {} has no serialized payload", event.id).into()))?;
+        let payload: serde_json::Value = serde_json::from_str(raw_payload.get())?;
+        let event_type: String = self
+            .event_type_location
+            .event_type(&payload)
+            .unwrap_or_else(|| "unknown".to_string());
+
+        let _ = sqlx::query(
+            format!(
+                include_str!("../../sql/postgres/statements/insert_debezium_outbox.sql"),
+                self.table_name
+            )
+            .as_str(),
+        )
+        .bind(event.id)
+        .bind(A::NAME)
+        .bind(event.aggregate_id.to_string())
+        .bind(event_type)
+        .bind(Json(&payload))
+        .execute(&mut *executor)
+        .await?;
+
+        if self.delete_after_insert {
+            let _ = sqlx::query(
+                format!(
+                    include_str!("../../sql/postgres/statements/delete_by_id.sql"),
+                    self.table_name
+                )
+                .as_str(),
+            )
+            .bind(event.id)
+            .execute(&mut *executor)
+            .await?;
+        }
+
+        Ok(())
+    }
+}
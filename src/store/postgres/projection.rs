@@ -0,0 +1,92 @@
+//! Projection primitives meant to be composed by [`crate::handler::TransactionalEventHandler`]s.
+//!
+//! All the functions work over a projection table having a uuid `id` primary key. Upserts and
+//! deletes are idempotent, making them safe to be used while rebuilding read models; increments
+//! are not, so they should be paired with a delete of the row in the handler `delete` function.
+//!
+//! Table and column names are interpolated as is in the statements, so they must be valid
+//! Postgres identifiers.
+
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use super::ColumnValue;
+
+/// Inserts a row with the given `id` and column values in the projection table, overwriting the
+/// given columns if a row with the same `id` already exists.
+///
+/// # Errors
+///
+/// Will return an `Err` if the upsert fails.
+pub async fn upsert_by_id(
+    executor: &mut PgConnection,
+    table_name: &str,
+    id: Uuid,
+    columns: Vec<(&str, ColumnValue)>,
+) -> Result<(), sqlx::Error> {
+    let names: String = columns.iter().map(|(name, _)| format!(", {}", name)).collect();
+    let placeholders: String = (0..columns.len()).map(|i| format!(", ${}", i + 2)).collect();
+    let conflict_action: String = if columns.is_empty() {
+        "NOTHING".to_string()
+    } else {
+        let assignments: Vec<String> = columns
+            .iter()
+            .map(|(name, _)| format!("{0} = EXCLUDED.{0}", name))
+            .collect();
+        format!("UPDATE SET {}", assignments.join(", "))
+    };
+
+    let query: String = format!(
+        "INSERT INTO {} (id{}) VALUES ($1{}) ON CONFLICT (id) DO {}",
+        table_name, names, placeholders, conflict_action
+    );
+
+    let _ = columns
+        .into_iter()
+        .fold(sqlx::query(query.as_str()).bind(id), |query, (_, value)| {
+            value.bind(query)
+        })
+        .execute(executor)
+        .await?;
+
+    Ok(())
+}
+
+/// Increments the given numeric column of the row with the given `id` by `amount`. Rows not
+/// existing yet are left untouched.
+///
+/// Note that this is not idempotent: handling the same event twice increments the column twice.
+///
+/// # Errors
+///
+/// Will return an `Err` if the update fails.
+pub async fn increment_column(
+    executor: &mut PgConnection,
+    table_name: &str,
+    id: Uuid,
+    column_name: &str,
+    amount: i64,
+) -> Result<(), sqlx::Error> {
+    let query: String = format!("UPDATE {0} SET {1} = {1} + $2 WHERE id = $1", table_name, column_name);
+
+    let _ = sqlx::query(query.as_str())
+        .bind(id)
+        .bind(amount)
+        .execute(executor)
+        .await?;
+
+    Ok(())
+}
+
+/// Deletes the row with the given `id` from the projection table, if any.
+///
+/// # Errors
+///
+/// Will return an `Err` if the delete fails.
+pub async fn delete_by_id(executor: &mut PgConnection, table_name: &str, id: Uuid) -> Result<(), sqlx::Error> {
+    let query: String = format!("DELETE FROM {} WHERE id = $1", table_name);
+
+    let _ = sqlx::query(query.as_str()).bind(id).execute(executor).await?;
+
+    Ok(())
+}
@@ -0,0 +1,114 @@
+use std::marker::PhantomData;
+
+use chrono::{DateTime, Utc};
+use serde_json::value::RawValue;
+use uuid::Uuid;
+
+use crate::store::{Metadata, StoreEvent};
+use crate::types::SequenceNumber;
+
+use super::Schema;
+
+/// A `RawStoreEvent` contains the serialized payload of an event alongside the event's metadata.
+///
+/// Differently from [`StoreEvent`], the payload deserialization is deferred until
+/// [`RawStoreEvent::payload`] is called, so that consumers only needing the metadata (or a subset
+/// of the events) don't pay for it. This is particularly useful while replaying large event stores.
+///
+/// The `Schema` type parameter is used to deserialize the payload, as in [`super::PgStore`].
+pub struct RawStoreEvent<Event, Schema = Event> {
+    /// Uniquely identifies an event among all events emitted from all aggregates.
+    pub id: Uuid,
+    /// The aggregate instance that emitted the event.
+    pub aggregate_id: Uuid,
+    /// The timestamp of when the event is persisted.
+    pub occurred_on: DateTime<Utc>,
+    /// The sequence number of the event, within its specific aggregate instance.
+    pub sequence_number: SequenceNumber,
+    /// The version of the event.
+    pub version: Option<i32>,
+    /// The metadata attached to the event when persisted.
+    pub metadata: Metadata,
+    raw_payload: Box<RawValue>,
+    _event: PhantomData<fn() -> (Event, Schema)>,
+}
+
+impl<E, S> RawStoreEvent<E, S>
+where
+    S: Schema<E>,
+{
+    pub(crate) fn new(
+        id: Uuid,
+        aggregate_id: Uuid,
+        raw_payload: Box<RawValue>,
+        occurred_on: DateTime<Utc>,
+        sequence_number: SequenceNumber,
+        version: Option<i32>,
+        metadata: Metadata,
+    ) -> Self {
+        Self {
+            id,
+            aggregate_id,
+            occurred_on,
+            sequence_number,
+            version,
+            metadata,
+            raw_payload,
+            _event: PhantomData,
+        }
+    }
+
+    /// Returns the serialized payload, as stored in the database.
+    pub fn raw_payload(&self) -> &RawValue {
+        &self.raw_payload
+    }
+
+    /// Deserializes the payload. Returns `None` if the schema skips this event.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the payload can't be deserialized.
+    pub fn payload(&self) -> Result<Option<E>, serde_json::Error> {
+        #[cfg(feature = "upcasting")]
+        let schema = S::upcast(serde_json::from_str(self.raw_payload.get())?, self.version)?;
+        #[cfg(not(feature = "upcasting"))]
+        let schema = serde_json::from_str::<S>(self.raw_payload.get())?;
+
+        Ok(schema.to_event())
+    }
+
+    /// Converts self into a [`StoreEvent`], deserializing the payload. Returns `None` if the schema
+    /// skips this event. The serialized payload is kept as [`StoreEvent::raw_payload`].
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the payload can't be deserialized.
+    pub fn into_store_event(self) -> Result<Option<StoreEvent<E>>, serde_json::Error> {
+        Ok(self.payload()?.map(|payload| {
+            StoreEvent::new(
+                self.id,
+                self.aggregate_id,
+                payload,
+                self.occurred_on,
+                self.sequence_number,
+                self.version,
+            )
+            .with_metadata(self.metadata)
+            .with_raw_payload(self.raw_payload)
+        }))
+    }
+}
+
+impl<E, S> std::fmt::Debug for RawStoreEvent<E, S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RawStoreEvent")
+            .field("id", &self.id)
+            .field("aggregate_id", &self.aggregate_id)
+            .field("raw_payload", &self.raw_payload)
+            .field("occurred_on", &self.occurred_on)
+            .field("sequence_number", &self.sequence_number)
+            .field("version", &self.version)
+            .field("metadata", &self.metadata)
+            .finish()
+    }
+}
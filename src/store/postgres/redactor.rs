@@ -0,0 +1,236 @@
+use serde_json::Value;
+use sqlx::types::Json;
+use sqlx::{Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::handler::ReplayableEventHandler;
+use crate::sql::event::DbRawEvent;
+use crate::sql::statements::StatementsHandler;
+use crate::store::{EventStore, EventStoreLockGuard, StoreEvent};
+use crate::Aggregate;
+
+use super::persistable::Persistable;
+use super::{CorrectionKind, ErrorContext, EventCorrection, PgStore, PgStoreError, Schema};
+
+/// The `Redactor` rewrites the payloads of persisted events in place, e.g. to mask the personal data
+/// they carry when it has to be erased, then re-projects the affected aggregate instance.
+///
+/// The payloads are rewritten by a redaction function, given the stored payload as JSON. Their
+/// version is left untouched, so a redacted payload must still be readable at its version: the
+/// redaction is rolled back otherwise. Every rewritten event is reported to the
+/// [`super::AuditHook`]s of the store as a [`CorrectionKind::Redaction`], in the same transaction,
+/// making up the audit trail of the redactions.
+///
+/// Once the events are rewritten, the projections of the aggregate instance are rebuilt: the
+/// transactional event handlers of the store delete and re-handle them in the same transaction,
+/// while the given [`ReplayableEventHandler`]s do it after the commit. No event bus is called.
+///
+/// The hash chain of the aggregate instance, if any, is broken by the redaction. Stores built with a
+/// data key provider keep the payloads encrypted: use `PgStore::shred` to erase them instead.
+pub struct Redactor<A, S = <A as Aggregate>::Event>
+where
+    A: Aggregate,
+{
+    store: PgStore<A, S>,
+    event_handlers: Vec<Box<dyn ReplayableEventHandler<A> + Send>>,
+}
+
+impl<A, S> Redactor<A, S>
+where
+    A: Aggregate + 'static,
+    A::State: Send,
+    A::Event: Send + Sync + 'static,
+    S: Schema<A::Event> + Persistable + Send + Sync,
+{
+    pub fn new(store: PgStore<A, S>) -> Self {
+        Self {
+            store,
+            event_handlers: vec![],
+        }
+    }
+
+    /// Sets the event handlers re-handling the events of the redacted aggregate instances, after
+    /// their read side has been deleted.
+    pub fn with_event_handlers(self, event_handlers: Vec<Box<dyn ReplayableEventHandler<A> + Send>>) -> Self {
+        Self { event_handlers, ..self }
+    }
+
+    /// Rewrites the payload of the event with the given id through the given redaction function,
+    /// then re-projects its aggregate instance. Returns `false` if there is no such event, or if the
+    /// redaction left its payload unchanged.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if locking the aggregate instance fails, any of the queries fails, the
+    /// redacted payload can't be deserialized, an audit hook or a transactional event handler fails.
+    pub async fn redact_event<F>(&self, event_id: Uuid, redaction: F) -> Result<bool, PgStoreError>
+    where
+        F: Fn(&mut Value) + Sync,
+    {
+        let context: ErrorContext = ErrorContext::new("redact_event").with_event_id(event_id);
+
+        let result: Result<bool, PgStoreError> = async {
+            let aggregate_id: Option<Uuid> = sqlx::query_as::<_, DbRawEvent>(
+                format!(
+                    include_str!("../../sql/postgres/statements/select_by_id_for_update.sql"),
+                    self.store.table_name()
+                )
+                .as_str(),
+            )
+            .bind(event_id)
+            .fetch_optional(&self.store.inner.pool)
+            .await?
+            .map(|event| event.aggregate_id);
+
+            match aggregate_id {
+                Some(aggregate_id) => Ok(self.redact(aggregate_id, Some(event_id), &redaction).await? > 0),
+                None => Ok(false),
+            }
+        }
+        .await;
+
+        result.map_err(|error| error.with_context(context))
+    }
+
+    /// Rewrites the payloads of all the events of the aggregate instance with the given id, soft
+    /// deleted ones included, through the given redaction function, then re-projects it. Returns the
+    /// number of events whose payload has been changed by the redaction.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if locking the aggregate instance fails, any of the queries fails, a
+    /// redacted payload can't be deserialized, an audit hook or a transactional event handler fails.
+    pub async fn redact_aggregate<F>(&self, aggregate_id: Uuid, redaction: F) -> Result<usize, PgStoreError>
+    where
+        F: Fn(&mut Value) + Sync,
+    {
+        self.redact(aggregate_id, None, &redaction)
+            .await
+            .map_err(|error| error.with_context(ErrorContext::new("redact_aggregate").with_aggregate_id(aggregate_id)))
+    }
+
+    /// Locks the given aggregate instance, then rewrites the payloads of its events, or of the given
+    /// event only, calls the audit hooks and re-projects the aggregate instance.
+    async fn redact<F>(&self, aggregate_id: Uuid, event_id: Option<Uuid>, redaction: &F) -> Result<usize, PgStoreError>
+    where
+        F: Fn(&mut Value) + Sync,
+    {
+        let _lock: EventStoreLockGuard = self.store.lock(aggregate_id).await?;
+        let mut transaction: Transaction<'static, Postgres> = self.store.begin().await?;
+
+        let events: Vec<DbRawEvent> =
+            sqlx::query_as::<_, DbRawEvent>(self.store.inner.statements.by_aggregate_id_including_deleted())
+                .bind(aggregate_id)
+                .fetch_all(&mut *transaction)
+                .await?;
+
+        let update_payload: String = format!(
+            include_str!("../../sql/postgres/statements/update_payload_by_id.sql"),
+            self.store.table_name()
+        );
+
+        let mut redacted: usize = 0;
+        for event in events {
+            if event_id.is_some_and(|event_id| event_id != event.id) {
+                continue;
+            }
+
+            let previous_payload: Value = serde_json::from_str(event.payload.0.get())?;
+            let mut payload: Value = previous_payload.clone();
+            redaction(&mut payload);
+
+            if payload == previous_payload {
+                continue;
+            }
+
+            let _ = sqlx::query(update_payload.as_str())
+                .bind(event.id)
+                .bind(Json(&payload))
+                .bind(event.version)
+                .execute(&mut *transaction)
+                .await?;
+
+            let correction: EventCorrection = EventCorrection {
+                kind: CorrectionKind::Redaction,
+                event_id: event.id,
+                aggregate_id,
+                sequence_number: event.sequence_number,
+                previous_payload,
+                payload: Some(payload),
+            };
+
+            for audit_hook in &self.store.inner.audit_hooks {
+                if let Err(error) = audit_hook.audit(&correction, &mut transaction).await {
+                    tracing::error!({
+                        event_id = %event.id,
+                        aggregate_id = %aggregate_id,
+                        audit_hook = audit_hook.name(),
+                        error = ?error,
+                    }, "audit hook vetoed event redaction");
+
+                    return Err(error);
+                }
+            }
+
+            redacted += 1;
+        }
+
+        if redacted == 0 {
+            return Ok(0);
+        }
+
+        // Loading the redacted events also checks that they can still be deserialized.
+        let mut store_events: Vec<StoreEvent<A::Event>> = vec![];
+        for event in sqlx::query_as::<_, DbRawEvent>(self.store.inner.statements.by_aggregate_id())
+            .bind(aggregate_id)
+            .fetch_all(&mut *transaction)
+            .await?
+        {
+            store_events.extend(self.store.inner.decode_event::<S>(event).await?);
+        }
+
+        for transactional_event_handler in &self.store.inner.transactional_event_handlers {
+            let result: Result<(), PgStoreError> = async {
+                transactional_event_handler
+                    .delete(aggregate_id, &mut transaction)
+                    .await?;
+
+                for store_event in &store_events {
+                    transactional_event_handler
+                        .handle(store_event, &mut transaction)
+                        .await?;
+                }
+
+                Ok(())
+            }
+            .await;
+
+            if let Err(error) = result {
+                tracing::error!({
+                    aggregate_id = %aggregate_id,
+                    transactional_event_handler = transactional_event_handler.name(),
+                    error = ?error,
+                }, "transactional event handler failed to re-project redacted aggregate");
+
+                return Err(error);
+            }
+        }
+
+        transaction.commit().await?;
+
+        for event_handler in &self.event_handlers {
+            event_handler.delete(aggregate_id).await;
+
+            for store_event in &store_events {
+                event_handler.handle(store_event).await;
+            }
+        }
+
+        tracing::warn!({
+            aggregate_id = %aggregate_id,
+            redacted = redacted,
+        }, "events redacted");
+
+        Ok(redacted)
+    }
+}
@@ -0,0 +1,140 @@
+use sqlx::{Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::sql::{schema_of, unqualified};
+use crate::store::{EventStore, EventStoreLockGuard};
+use crate::types::SequenceNumber;
+use crate::Aggregate;
+
+use super::persistable::Persistable;
+use super::{PgStore, PgStoreError, Schema};
+
+/// The `RekeyMode` enum defines what [`PgStore::rekey`] does with the events of the source
+/// aggregate instance:
+///
+/// - `Move`: The events are moved to the target aggregate instance, keeping their ids.
+/// - `Copy`: The events are copied to the target aggregate instance, with new ids deterministically
+///   derived from the original ones. The source aggregate instance is left untouched.
+pub enum RekeyMode {
+    Move,
+    Copy,
+}
+
+impl<A, S> PgStore<A, S>
+where
+    A: Aggregate + 'static,
+    A::State: Send,
+    A::Event: Send + Sync + 'static,
+    S: Schema<A::Event> + Persistable + Send + Sync,
+{
+    /// Moves or copies the events of the aggregate instance `from` to the aggregate instance `to`,
+    /// e.g. when two aggregate instances must be merged or an id was created incorrectly. Returns the
+    /// number of moved or copied events.
+    ///
+    /// The events are appended after the events of the target aggregate instance, if any, being
+    /// re-sequenced accordingly. Both aggregate instances are locked, and the events are moved or
+    /// copied in a single transaction, in which [`crate::handler::TransactionalEventHandler::rekey`]
+    /// is called. [`crate::handler::EventHandler::rekey`] is called after the commit.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if locking the aggregate instances fails, any of the queries fails or a
+    /// transactional event handler fails.
+    pub async fn rekey(&self, from: Uuid, to: Uuid, mode: RekeyMode) -> Result<u64, PgStoreError> {
+        if from == to {
+            return Ok(0);
+        }
+
+        // Aggregate instances are always locked in the same order to avoid deadlocks.
+        let (first, second) = if from < to { (from, to) } else { (to, from) };
+        let _first_lock: EventStoreLockGuard = self.lock(first).await?;
+        let _second_lock: EventStoreLockGuard = self.lock(second).await?;
+
+        let mut transaction: Transaction<Postgres> = self.inner.pool.begin().await?;
+
+        let max_sequence_number: SequenceNumber = sqlx::query_scalar(
+            format!(
+                include_str!("../../sql/postgres/statements/select_max_sequence_number.sql"),
+                self.table_name()
+            )
+            .as_str(),
+        )
+        .bind(to)
+        .fetch_one(&mut *transaction)
+        .await?;
+
+        let rekeyed: u64 = match mode {
+            RekeyMode::Move => sqlx::query(
+                format!(
+                    include_str!("../../sql/postgres/statements/rekey_move.sql"),
+                    self.table_name()
+                )
+                .as_str(),
+            )
+            .bind(from)
+            .bind(to)
+            .bind(max_sequence_number)
+            .execute(&mut *transaction)
+            .await?
+            .rows_affected(),
+            RekeyMode::Copy => {
+                let columns: Vec<String> = sqlx::query_scalar(include_str!(
+                    "../../sql/postgres/statements/select_insertable_columns.sql"
+                ))
+                .bind(unqualified(self.table_name()))
+                .bind(schema_of(self.table_name()))
+                .fetch_all(&mut *transaction)
+                .await?;
+
+                let values: Vec<&str> = columns
+                    .iter()
+                    .map(|column| match column.as_str() {
+                        "id" => "md5(id::text || $2::text)::uuid",
+                        "aggregate_id" => "$2",
+                        "sequence_number" => "sequence_number + $3",
+                        column => column,
+                    })
+                    .collect();
+
+                sqlx::query(
+                    format!(
+                        include_str!("../../sql/postgres/statements/rekey_copy.sql"),
+                        self.table_name(),
+                        columns.join(", "),
+                        values.join(", ")
+                    )
+                    .as_str(),
+                )
+                .bind(from)
+                .bind(to)
+                .bind(max_sequence_number)
+                .execute(&mut *transaction)
+                .await?
+                .rows_affected()
+            }
+        };
+
+        for transactional_event_handler in &self.inner.transactional_event_handlers {
+            if let Err(error) = transactional_event_handler.rekey(from, to, &mut transaction).await {
+                tracing::error!({
+                    from = %from,
+                    to = %to,
+                    transactional_event_handler = transactional_event_handler.name(),
+                    error = ?error,
+                }, "transactional event handler failed to rekey aggregate");
+
+                return Err(error);
+            }
+        }
+
+        transaction.commit().await?;
+
+        let event_handlers = self.inner.event_handlers.read().await;
+        for event_handler in event_handlers.iter() {
+            let _permit = self.inner.event_handler_permit().await;
+            event_handler.rekey(from, to).await;
+        }
+
+        Ok(rekeyed)
+    }
+}
@@ -0,0 +1,206 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use sqlx::{Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::bus::EventBus;
+use crate::sql::event::DbRawEvent;
+use crate::sql::statements::StatementsHandler;
+use crate::store::StoreEvent;
+use crate::Aggregate;
+
+use super::persistable::Persistable;
+use super::{PgStore, PgStoreError, Schema};
+
+#[derive(sqlx::FromRow)]
+struct DbOutboxEvent {
+    #[sqlx(flatten)]
+    event: DbRawEvent,
+    attempts: i32,
+}
+
+/// Worker publishing the events written to the outbox table of a [`PgStore`] built with
+/// [`super::PgStoreBuilder::with_outbox`] to its event buses, through [`EventBus::try_publish`].
+///
+/// Published events are removed from the outbox, while the ones any of the buses fails to publish
+/// are retried with an exponential backoff. Publishing is at-least-once: an event might be published
+/// more than once, e.g. when a bus fails after another one already published it.
+///
+/// The events of an aggregate instance are published in order: once one of them fails, either to be
+/// published or to be deserialized, the following ones wait for it to be published. Many relays can run at once, each outbox row being handled by
+/// one of them, but the order is only guaranteed when a single relay runs.
+pub struct OutboxRelay<A, S = <A as Aggregate>::Event>
+where
+    A: Aggregate,
+{
+    store: PgStore<A, S>,
+    tick_interval: Duration,
+    batch_size: i64,
+    base_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl<A, S> OutboxRelay<A, S>
+where
+    A: Aggregate,
+    A::Event: Send + Sync,
+    S: Schema<A::Event> + Persistable + Send + Sync,
+{
+    /// Creates a new instance of an [`OutboxRelay`] for the given store, ticking every second,
+    /// publishing at most 100 events per tick and retrying failed events after 1 second, doubling
+    /// the delay at every attempt up to 5 minutes.
+    pub fn new(store: PgStore<A, S>) -> Self {
+        Self {
+            store,
+            tick_interval: Duration::from_secs(1),
+            batch_size: 100,
+            base_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(300),
+        }
+    }
+
+    /// Set the interval between two checks for events to publish.
+    pub fn with_tick_interval(mut self, tick_interval: Duration) -> Self {
+        self.tick_interval = tick_interval;
+        self
+    }
+
+    /// Set the maximum number of events published per tick.
+    pub fn with_batch_size(mut self, batch_size: i64) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Set the delay before retrying an event after its first failure, doubled at every further
+    /// attempt up to the given maximum.
+    pub fn with_retry_backoff(mut self, base_backoff: Duration, max_backoff: Duration) -> Self {
+        self.base_backoff = base_backoff;
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Publishes the events of the outbox that are due, at most batch size of them, to all the event
+    /// buses of the store. Returns the number of published events.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the store hasn't been built with the outbox, or any of the queries
+    /// fails.
+    pub async fn tick(&self) -> Result<usize, PgStoreError> {
+        let inner = &self.store.inner;
+
+        if !inner.outbox {
+            return Err(PgStoreError::Custom(
+                "The store has been built without the outbox".to_string().into(),
+            ));
+        }
+
+        let mut transaction: Transaction<Postgres> = inner.pool.begin().await?;
+
+        let outbox_events: Vec<DbOutboxEvent> =
+            sqlx::query_as::<_, DbOutboxEvent>(inner.statements.select_due_outbox())
+                .bind(self.batch_size)
+                .fetch_all(&mut *transaction)
+                .await?;
+
+        let event_buses = inner.event_buses.read().await;
+        // Aggregate instances with a failed event in this tick, whose following events must wait.
+        let mut failed_aggregate_ids: HashSet<Uuid> = HashSet::new();
+        let mut published: usize = 0;
+
+        for DbOutboxEvent { event, attempts } in outbox_events {
+            let event_id: Uuid = event.id;
+            let aggregate_id: Uuid = event.aggregate_id;
+
+            if failed_aggregate_ids.contains(&aggregate_id) {
+                continue;
+            }
+
+            // Events failing to be decoded are retried like the ones failing to be published,
+            // without holding back the events of the other aggregate instances.
+            let result: Result<(), Box<dyn std::error::Error + Send + Sync>> =
+                match inner.decode_event::<S>(event).await {
+                    Ok(Some(store_event)) => publish(&event_buses, &store_event).await,
+                    // Events not part of the schema have nothing to publish.
+                    Ok(None) => Ok(()),
+                    Err(error) => Err(Box::new(error)),
+                };
+
+            match result {
+                Ok(()) => {
+                    let _ = sqlx::query(inner.statements.delete_outbox())
+                        .bind(event_id)
+                        .execute(&mut *transaction)
+                        .await?;
+
+                    published += 1;
+                }
+                Err(error) => {
+                    tracing::error!({
+                        event_id = %event_id,
+                        aggregate_id = %aggregate_id,
+                        attempts = attempts + 1,
+                        error = ?error,
+                    }, "outbox relay failed to publish event");
+
+                    let next_attempt_at: DateTime<Utc> = Utc::now()
+                        + chrono::Duration::from_std(self.backoff(attempts))
+                            .map_err(|error| PgStoreError::Custom(Box::new(error)))?;
+
+                    let _ = sqlx::query(inner.statements.update_outbox_failure())
+                        .bind(event_id)
+                        .bind(next_attempt_at)
+                        .bind(error.to_string())
+                        .execute(&mut *transaction)
+                        .await?;
+
+                    let _ = failed_aggregate_ids.insert(aggregate_id);
+                }
+            }
+        }
+
+        transaction.commit().await?;
+
+        Ok(published)
+    }
+
+    /// Ticks forever at every tick interval, publishing the events of the outbox. Batches are
+    /// published back to back while there are due events left.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` as soon as a tick fails.
+    pub async fn run(&self) -> Result<(), PgStoreError> {
+        loop {
+            let published: usize = self.tick().await?;
+
+            if (published as i64) < self.batch_size {
+                crate::runtime::sleep(self.tick_interval).await;
+            }
+        }
+    }
+
+    /// The delay before the next attempt of an event that already failed the given number of times.
+    fn backoff(&self, attempts: i32) -> Duration {
+        let factor: u32 = 2u32.saturating_pow(attempts.max(0) as u32);
+        self.base_backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+}
+
+/// Publishes the given event to all the event buses, stopping at the first failure.
+async fn publish<A>(
+    event_buses: &[Box<dyn EventBus<A> + Send>],
+    store_event: &StoreEvent<A::Event>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>>
+where
+    A: Aggregate,
+    A::Event: Sync,
+{
+    for event_bus in event_buses {
+        event_bus.try_publish(store_event).await?;
+    }
+
+    Ok(())
+}
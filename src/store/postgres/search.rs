@@ -0,0 +1,50 @@
+use crate::sql::event::DbRawEvent;
+use crate::store::StoreEvent;
+use crate::Aggregate;
+
+use super::persistable::Persistable;
+use super::{PgStore, PgStoreError, Schema};
+
+/// Builds the expression generating the `search_vector` column from the string and numeric values
+/// of the given payload fields, found at any depth of the payload.
+pub(crate) fn search_vector_expression(fields: &[String]) -> String {
+    fields
+        .iter()
+        .map(|field| {
+            format!(
+                "jsonb_to_tsvector('simple', jsonb_path_query_array(payload, '$.**.{}'), '[\"string\", \"numeric\"]')",
+                field
+            )
+        })
+        .collect::<Vec<String>>()
+        .join(" || ")
+}
+
+impl<A, S> PgStore<A, S>
+where
+    A: Aggregate,
+    A::Event: Send + Sync,
+    S: Schema<A::Event> + Persistable + Send + Sync,
+{
+    /// Finds at most `limit` events by free text, matching the values of the payload fields set
+    /// through [`super::PgStoreBuilder::with_search_fields`]. The query supports the web search
+    /// syntax (e.g. `"john doe" -cancelled`), and events are sorted by relevance.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the store hasn't been built with search fields, or the query fails.
+    pub async fn search_events(&self, query: &str, limit: i64) -> Result<Vec<StoreEvent<A::Event>>, PgStoreError> {
+        let statement: String = format!(
+            include_str!("../../sql/postgres/statements/select_by_search.sql"),
+            self.table_name()
+        );
+
+        let events: Vec<DbRawEvent> = sqlx::query_as::<_, DbRawEvent>(statement.as_str())
+            .bind(query)
+            .bind(limit)
+            .fetch_all(&self.inner.pool)
+            .await?;
+
+        self.inner.decode_events::<S>(events).await
+    }
+}
@@ -0,0 +1,132 @@
+use std::marker::PhantomData;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sqlx::postgres::PgQueryResult;
+use sqlx::types::Json;
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+use crate::store::{Snapshot, SnapshotStore};
+use crate::types::SequenceNumber;
+use crate::Aggregate;
+
+/// Postgres implementation of the [`SnapshotStore`], keeping the latest snapshot of each aggregate
+/// instance in the `{aggregate_name}_snapshots` table, with the state serialized as JSON.
+pub struct PgSnapshotStore<S> {
+    pool: Pool<Postgres>,
+    table_name: String,
+    _state: PhantomData<fn() -> S>,
+}
+
+#[derive(sqlx::FromRow)]
+struct DbSnapshot {
+    aggregate_id: Uuid,
+    sequence_number: SequenceNumber,
+    version: Option<i32>,
+    state: Json<serde_json::Value>,
+    taken_on: DateTime<Utc>,
+}
+
+impl<S> PgSnapshotStore<S> {
+    /// Creates a new instance of a [`PgSnapshotStore`] for the snapshots of the given aggregate.
+    pub fn new<A>(pool: Pool<Postgres>) -> Self
+    where
+        A: Aggregate<State = S>,
+    {
+        Self {
+            pool,
+            table_name: format!("{}_snapshots", A::NAME),
+            _state: PhantomData,
+        }
+    }
+
+    /// Returns the name of the snapshots table.
+    pub fn table_name(&self) -> &str {
+        &self.table_name
+    }
+
+    /// Creates the snapshots table, if it doesn't exist yet.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if there's an error running the migration.
+    pub async fn setup(&self) -> Result<(), sqlx::Error> {
+        let migration: String = format!(
+            include_str!("../../sql/postgres/migrations/create_snapshots_table.sql"),
+            self.table_name
+        );
+
+        let _: PgQueryResult = sqlx::query(migration.as_str()).execute(&self.pool).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<S> SnapshotStore<S> for PgSnapshotStore<S>
+where
+    S: Serialize + DeserializeOwned + Send + Sync,
+{
+    async fn latest(
+        &self,
+        aggregate_id: Uuid,
+    ) -> Result<Option<Snapshot<S>>, Box<dyn std::error::Error + Send + Sync>> {
+        let db_snapshot: Option<DbSnapshot> = sqlx::query_as::<_, DbSnapshot>(
+            format!(
+                include_str!("../../sql/postgres/statements/select_snapshot.sql"),
+                self.table_name
+            )
+            .as_str(),
+        )
+        .bind(aggregate_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match db_snapshot {
+            None => Ok(None),
+            Some(db_snapshot) => Ok(Some(Snapshot {
+                aggregate_id: db_snapshot.aggregate_id,
+                sequence_number: db_snapshot.sequence_number,
+                version: db_snapshot.version,
+                state: serde_json::from_value(db_snapshot.state.0)?,
+                taken_on: db_snapshot.taken_on,
+            })),
+        }
+    }
+
+    async fn save(&self, snapshot: &Snapshot<S>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let _ = sqlx::query(
+            format!(
+                include_str!("../../sql/postgres/statements/upsert_snapshot.sql"),
+                self.table_name
+            )
+            .as_str(),
+        )
+        .bind(snapshot.aggregate_id)
+        .bind(snapshot.sequence_number)
+        .bind(snapshot.version)
+        .bind(Json(&snapshot.state))
+        .bind(snapshot.taken_on)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn delete(&self, aggregate_id: Uuid) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let _ = sqlx::query(
+            format!(
+                include_str!("../../sql/postgres/statements/delete_by_aggregate_id.sql"),
+                self.table_name
+            )
+            .as_str(),
+        )
+        .bind(aggregate_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,357 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use sqlx::postgres::PgQueryResult;
+use sqlx::{Pool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::handler::EventHandler;
+use crate::sql::event::DbRawEvent;
+use crate::store::StoreEvent;
+use crate::types::SequenceNumber;
+use crate::Aggregate;
+
+use super::persistable::Persistable;
+use super::{PgStore, PgStoreError, Schema};
+
+/// The position of a [`Subscription`] in the event store table: the last event handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checkpoint {
+    /// The id of the last handled event.
+    pub event_id: Uuid,
+    /// The `occurred_on` of the last handled event, or its visibility time if it has been deferred.
+    /// See [`super::Visibility`].
+    pub occurred_on: DateTime<Utc>,
+    /// The aggregate id of the last handled event.
+    pub aggregate_id: Uuid,
+    /// The sequence number of the last handled event.
+    pub sequence_number: SequenceNumber,
+}
+
+#[derive(sqlx::FromRow)]
+struct DbCheckpoint {
+    last_event_id: Option<Uuid>,
+    last_occurred_on: Option<DateTime<Utc>>,
+    last_aggregate_id: Option<Uuid>,
+    last_sequence_number: Option<SequenceNumber>,
+}
+
+/// An event read by a [`Subscription`], along with the time it became visible: its `occurred_on`,
+/// or its visibility time if it has been deferred.
+#[derive(sqlx::FromRow)]
+struct DbSubscriptionEvent {
+    #[sqlx(flatten)]
+    event: DbRawEvent,
+    visible_at: DateTime<Utc>,
+}
+
+impl DbCheckpoint {
+    fn checkpoint(&self) -> Option<Checkpoint> {
+        Some(Checkpoint {
+            event_id: self.last_event_id?,
+            occurred_on: self.last_occurred_on?,
+            aggregate_id: self.last_aggregate_id?,
+            sequence_number: self.last_sequence_number?,
+        })
+    }
+}
+
+/// Catch-up consumer running an [`EventHandler`] over the events of a [`PgStore`], detached from
+/// the write path: the handler should not be added to the store too.
+///
+/// The subscription reads the events by order of `occurred_on`, aggregate id and sequence number,
+/// keeping track of the last handled one in the `projection_offsets` table (in the database schema
+/// of the store, if any), keyed by the name of the subscription and the event store table. A
+/// restarted subscription resumes from its checkpoint instead of replaying the whole store, and a
+/// brand new one catches up from the first event.
+///
+/// When the store has been built with a [`super::Visibility`] hook, the deferred events are read
+/// once they become visible, as if they occurred at their visibility time: they are not handled
+/// while embargoed, and the events of an aggregate instance might be handled out of order.
+///
+/// Events are handled at-least-once: the checkpoint is saved after each batch, so the events of a
+/// batch interrupted by a crash are handled again. Many replicas can run the same subscription, but
+/// only one of them handles the events at a time.
+///
+/// Events are read once they are older than the settle delay, measured by the database clock, so
+/// that the events persisted by transactions still in flight (whose `occurred_on` precedes the
+/// commit) are not skipped. An event is skipped for good, and never handled, if it's committed more
+/// than the settle delay after its `occurred_on`, i.e. when:
+///
+/// - the transaction persisting it lasts longer than the settle delay, e.g. because of a slow
+///   transactional event handler or a long lock wait;
+/// - its `occurred_on` is taken from the application clock (see [`super::OccurredOnStrategy`]),
+///   and that clock runs behind the database one by more than the settle delay, minus the
+///   duration of the transaction.
+///
+/// The delay should then be comfortably longer than the longest persisting transaction, plus the
+/// clock skew between the application and the database.
+pub struct Subscription<A, S = <A as Aggregate>::Event>
+where
+    A: Aggregate,
+{
+    store: PgStore<A, S>,
+    name: String,
+    event_handler: Box<dyn EventHandler<A> + Send>,
+    tick_interval: Duration,
+    batch_size: i64,
+    settle_delay: Duration,
+}
+
+impl<A, S> Subscription<A, S>
+where
+    A: Aggregate,
+    A::Event: Send + Sync,
+    S: Schema<A::Event> + Persistable + Send + Sync,
+{
+    /// Creates a new instance of a [`Subscription`] of the given event handler to the given store,
+    /// named after the event handler, ticking every second, handling at most 100 events per tick and
+    /// reading the events older than 1 second.
+    pub fn new(store: PgStore<A, S>, event_handler: impl EventHandler<A> + Send + 'static) -> Self {
+        Self {
+            store,
+            name: event_handler.name().to_string(),
+            event_handler: Box::new(event_handler),
+            tick_interval: Duration::from_secs(1),
+            batch_size: 100,
+            settle_delay: Duration::from_secs(1),
+        }
+    }
+
+    /// Set the name of the subscription, identifying its checkpoint. Defaults to the name of the
+    /// event handler, which changes if the handler type is renamed or moved.
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    /// Set the interval between two checks for new events.
+    pub fn with_tick_interval(mut self, tick_interval: Duration) -> Self {
+        self.tick_interval = tick_interval;
+        self
+    }
+
+    /// Set the maximum number of events handled per tick.
+    pub fn with_batch_size(mut self, batch_size: i64) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Set how old the events must be to be read. See [`Subscription`] for the events skipped when
+    /// it's too short.
+    pub fn with_settle_delay(mut self, settle_delay: Duration) -> Self {
+        self.settle_delay = settle_delay;
+        self
+    }
+
+    /// Returns the name of the subscription.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Creates the `projection_offsets` table, if it doesn't exist yet.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if there's an error running the migration.
+    pub async fn setup(&self) -> Result<(), sqlx::Error> {
+        let _: PgQueryResult = sqlx::query(
+            format!(
+                include_str!("../../sql/postgres/migrations/create_projection_offsets_table.sql"),
+                self.projection_offsets()
+            )
+            .as_str(),
+        )
+        .execute(&self.store.inner.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Returns the checkpoint of the subscription, if it handled any event.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the query fails.
+    pub async fn checkpoint(&self) -> Result<Option<Checkpoint>, PgStoreError> {
+        let db_checkpoint: Option<DbCheckpoint> = sqlx::query_as::<_, DbCheckpoint>(
+            format!(
+                include_str!("../../sql/postgres/statements/select_projection_offset.sql"),
+                self.projection_offsets()
+            )
+            .as_str(),
+        )
+        .bind(self.name.as_str())
+        .bind(self.store.table_name())
+        .fetch_optional(&self.store.inner.pool)
+        .await?;
+
+        Ok(db_checkpoint.as_ref().and_then(DbCheckpoint::checkpoint))
+    }
+
+    /// Removes the checkpoint of the subscription, so that it handles all the events again from the
+    /// first one. The read side of the handler should be cleared beforehand.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the query fails.
+    pub async fn reset(&self) -> Result<(), PgStoreError> {
+        let _ = sqlx::query(
+            format!(
+                include_str!("../../sql/postgres/statements/delete_projection_offset.sql"),
+                self.projection_offsets()
+            )
+            .as_str(),
+        )
+        .bind(self.name.as_str())
+        .bind(self.store.table_name())
+        .execute(&self.store.inner.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Handles the events following the checkpoint, at most batch size of them, and moves the
+    /// checkpoint after the last one. Returns the number of handled events, zero if another replica
+    /// is running the subscription.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if any of the queries fails, or an event can't be deserialized.
+    pub async fn tick(&self) -> Result<usize, PgStoreError> {
+        let pool: &Pool<Postgres> = &self.store.inner.pool;
+        let table_name: &str = self.store.table_name();
+
+        let projection_offsets: String = self.projection_offsets();
+
+        let _ = sqlx::query(
+            format!(
+                include_str!("../../sql/postgres/statements/insert_projection_offset.sql"),
+                projection_offsets
+            )
+            .as_str(),
+        )
+        .bind(self.name.as_str())
+        .bind(table_name)
+        .execute(pool)
+        .await?;
+
+        let mut transaction: Transaction<Postgres> = pool.begin().await?;
+
+        let db_checkpoint: Option<DbCheckpoint> = sqlx::query_as::<_, DbCheckpoint>(
+            format!(
+                include_str!("../../sql/postgres/statements/select_projection_offset_for_update.sql"),
+                projection_offsets
+            )
+            .as_str(),
+        )
+        .bind(self.name.as_str())
+        .bind(table_name)
+        .fetch_optional(&mut *transaction)
+        .await?;
+
+        // Another replica is running the subscription.
+        let Some(db_checkpoint) = db_checkpoint else {
+            return Ok(0);
+        };
+
+        let checkpoint: Option<Checkpoint> = db_checkpoint.checkpoint();
+        // The deferred events table only exists when the store has a visibility hook.
+        let statement: String = if self.store.inner.visibility.is_some() {
+            format!(
+                include_str!("../../sql/postgres/statements/select_after_offset_deferred.sql"),
+                table_name
+            )
+        } else {
+            format!(
+                include_str!("../../sql/postgres/statements/select_after_offset.sql"),
+                table_name
+            )
+        };
+
+        let events: Vec<DbSubscriptionEvent> = sqlx::query_as::<_, DbSubscriptionEvent>(statement.as_str())
+            .bind(checkpoint.map(|checkpoint| checkpoint.occurred_on))
+            .bind(checkpoint.map(|checkpoint| checkpoint.aggregate_id))
+            .bind(checkpoint.map(|checkpoint| checkpoint.sequence_number))
+            .bind(self.settle_delay.as_secs_f64())
+            .bind(self.batch_size)
+            .fetch_all(&mut *transaction)
+            .await?;
+
+        let Some(last) = events
+            .last()
+            .map(|DbSubscriptionEvent { event, visible_at }| Checkpoint {
+                event_id: event.id,
+                occurred_on: *visible_at,
+                aggregate_id: event.aggregate_id,
+                sequence_number: event.sequence_number,
+            })
+        else {
+            return Ok(0);
+        };
+        let handled: usize = events.len();
+
+        for DbSubscriptionEvent { event, .. } in events {
+            // Events skipped by the schema are skipped by the subscription as well.
+            let Some(store_event) = self.store.inner.decode_event::<S>(event).await? else {
+                continue;
+            };
+
+            self.handle(&store_event).await;
+        }
+
+        let _ = sqlx::query(
+            format!(
+                include_str!("../../sql/postgres/statements/update_projection_offset.sql"),
+                projection_offsets
+            )
+            .as_str(),
+        )
+        .bind(self.name.as_str())
+        .bind(table_name)
+        .bind(last.event_id)
+        .bind(last.occurred_on)
+        .bind(last.aggregate_id)
+        .bind(last.sequence_number)
+        .execute(&mut *transaction)
+        .await?;
+
+        transaction.commit().await?;
+
+        Ok(handled)
+    }
+
+    /// Ticks forever at every tick interval, handling the new events. Batches are handled back to
+    /// back while the subscription is catching up.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` as soon as a tick fails.
+    pub async fn run(&self) -> Result<(), PgStoreError> {
+        loop {
+            let handled: usize = self.tick().await?;
+
+            if (handled as i64) < self.batch_size {
+                crate::runtime::sleep(self.tick_interval).await;
+            }
+        }
+    }
+
+    /// The name of the `projection_offsets` table, qualified with the database schema of the store.
+    fn projection_offsets(&self) -> String {
+        self.store.inner.qualified("projection_offsets")
+    }
+
+    async fn handle(&self, store_event: &StoreEvent<A::Event>) {
+        let span = tracing::debug_span!(
+            "esrs.subscription",
+            subscription = self.name.as_str(),
+            event_id = %store_event.id,
+            aggregate_id = %store_event.aggregate_id,
+            event_handler = self.event_handler.name()
+        );
+        let _e = span.enter();
+
+        self.event_handler.handle(store_event).await;
+    }
+}
@@ -0,0 +1,160 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::sql::event::DbRawEvent;
+use crate::store::StoreEvent;
+use crate::{Aggregate, AggregateState};
+
+use super::persistable::Persistable;
+use super::{PgStore, PgStoreError, Schema};
+
+impl<A, S> PgStore<A, S>
+where
+    A: Aggregate,
+    A::Event: Send + Sync,
+    S: Schema<A::Event> + Persistable + Send + Sync,
+{
+    /// Loads the events of the given aggregate instance as known at the given system time, that is
+    /// the events recorded (`occurred_on`) until then, ordered by sequence number.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the query fails.
+    pub async fn by_aggregate_id_known_at(
+        &self,
+        aggregate_id: Uuid,
+        known_at: DateTime<Utc>,
+    ) -> Result<Vec<StoreEvent<A::Event>>, PgStoreError> {
+        let query: String = format!(
+            include_str!("../../sql/postgres/statements/select_by_aggregate_id_known_at.sql"),
+            self.table_name()
+        );
+
+        self.fetch_events(
+            sqlx::query_as::<_, DbRawEvent>(query.as_str())
+                .bind(aggregate_id)
+                .bind(known_at),
+        )
+        .await
+    }
+
+    /// Loads the events of the given aggregate instance as known at the given system time and
+    /// effective at the given business time, ordered by business time. See
+    /// [`PgStore::by_aggregate_id_valid_until`].
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the store hasn't been built with a [`super::ValidTime`] hook, or the
+    /// query fails.
+    pub async fn by_aggregate_id_bitemporal(
+        &self,
+        aggregate_id: Uuid,
+        known_at: DateTime<Utc>,
+        valid_until: DateTime<Utc>,
+    ) -> Result<Vec<StoreEvent<A::Event>>, PgStoreError> {
+        let query: String = format!(
+            include_str!("../../sql/postgres/statements/select_by_aggregate_id_bitemporal.sql"),
+            self.table_name()
+        );
+
+        self.fetch_events(
+            sqlx::query_as::<_, DbRawEvent>(query.as_str())
+                .bind(aggregate_id)
+                .bind(known_at)
+                .bind(valid_until),
+        )
+        .await
+    }
+
+    /// Reconstructs the state of the given aggregate instance as known at the given system time.
+    /// Returns `None` if no event was recorded until then.
+    ///
+    /// The returned state is meant to be inspected only: it must not be used to handle commands.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the query fails.
+    pub async fn load_known_at(
+        &self,
+        aggregate_id: Uuid,
+        known_at: DateTime<Utc>,
+    ) -> Result<Option<AggregateState<A::State>>, PgStoreError>
+    where
+        A::State: Default,
+    {
+        let store_events = self.by_aggregate_id_known_at(aggregate_id, known_at).await?;
+        Ok(into_aggregate_state::<A>(aggregate_id, store_events))
+    }
+
+    /// Reconstructs the state of the given aggregate instance as effective at the given business
+    /// time, according to all the events recorded so far. Returns `None` if no event is effective
+    /// until then.
+    ///
+    /// The returned state is meant to be inspected only: it must not be used to handle commands.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the store hasn't been built with a [`super::ValidTime`] hook, or the
+    /// query fails.
+    pub async fn load_valid_until(
+        &self,
+        aggregate_id: Uuid,
+        valid_until: DateTime<Utc>,
+    ) -> Result<Option<AggregateState<A::State>>, PgStoreError>
+    where
+        A::State: Default,
+    {
+        let store_events = self.by_aggregate_id_valid_until(aggregate_id, valid_until).await?;
+        Ok(into_aggregate_state::<A>(aggregate_id, store_events))
+    }
+
+    /// Reconstructs the state of the given aggregate instance as effective at the given business
+    /// time, according to the events known at the given system time. This tells what the state was
+    /// believed to be at some point, before any later retroactive correction. Returns `None` if no
+    /// event matches.
+    ///
+    /// The returned state is meant to be inspected only: it must not be used to handle commands.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the store hasn't been built with a [`super::ValidTime`] hook, or the
+    /// query fails.
+    pub async fn load_bitemporal(
+        &self,
+        aggregate_id: Uuid,
+        known_at: DateTime<Utc>,
+        valid_until: DateTime<Utc>,
+    ) -> Result<Option<AggregateState<A::State>>, PgStoreError>
+    where
+        A::State: Default,
+    {
+        let store_events = self
+            .by_aggregate_id_bitemporal(aggregate_id, known_at, valid_until)
+            .await?;
+        Ok(into_aggregate_state::<A>(aggregate_id, store_events))
+    }
+
+    pub(super) async fn fetch_events(
+        &self,
+        query: sqlx::query::QueryAs<'_, sqlx::Postgres, DbRawEvent, sqlx::postgres::PgArguments>,
+    ) -> Result<Vec<StoreEvent<A::Event>>, PgStoreError> {
+        let events: Vec<DbRawEvent> = query.fetch_all(&self.inner.pool).await?;
+
+        self.inner.decode_events::<S>(events).await
+    }
+}
+
+pub(super) fn into_aggregate_state<A>(
+    aggregate_id: Uuid,
+    store_events: Vec<StoreEvent<A::Event>>,
+) -> Option<AggregateState<A::State>>
+where
+    A: Aggregate,
+    A::State: Default,
+{
+    if store_events.is_empty() {
+        None
+    } else {
+        Some(AggregateState::with_id(aggregate_id).replay::<A>(store_events))
+    }
+}
@@ -0,0 +1,124 @@
+use std::sync::Arc;
+
+use futures::future::BoxFuture;
+use sqlx::{Postgres, Transaction};
+
+use crate::store::{EventStoreLockGuard, Metadata, StoreEvent};
+use crate::{Aggregate, AggregateState};
+
+use super::persistable::Persistable;
+use super::{ErrorContext, PgStore, PgStoreError, Schema};
+
+/// A database transaction persisting the events of many aggregate instances, possibly of different
+/// aggregate types, at once: either all of them are committed, or none is. Meant to keep simple
+/// cross-aggregate invariants without resorting to a saga.
+///
+/// The events are written as [`crate::store::EventStore::persist`] does, running the persist
+/// interceptors and the transactional event handlers of their store in the shared transaction.
+/// The locks held by the persisted aggregate states are released once the unit of work is
+/// committed, and only then the events are handled by the event handlers and published to the event
+/// buses of their store. Dropping the unit of work without committing it rolls everything back.
+///
+/// All the stores passed to [`UnitOfWork::persist`] must share the pool of the store the unit of
+/// work has been begun on.
+pub struct UnitOfWork {
+    transaction: Transaction<'static, Postgres>,
+    locks: Vec<EventStoreLockGuard>,
+    dispatches: Vec<BoxFuture<'static, ()>>,
+}
+
+impl UnitOfWork {
+    /// Begins a new [`UnitOfWork`] on the pool of the given store, applying its statement timeout, if
+    /// any.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the transaction can't be begun.
+    pub async fn begin<A, S>(store: &PgStore<A, S>) -> Result<Self, PgStoreError>
+    where
+        A: Aggregate,
+        A::Event: Send + Sync,
+    {
+        Ok(Self {
+            transaction: store.inner.begin().await?,
+            locks: vec![],
+            dispatches: vec![],
+        })
+    }
+
+    /// Writes the given events of the given aggregate instance through the given store, returning
+    /// them. They are not visible outside of the unit of work until it is committed.
+    ///
+    /// The lock held by the aggregate state, if any, is kept until the unit of work is committed or
+    /// dropped. Persisting more events for the same aggregate instance requires applying the
+    /// returned events onto its state first.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if writing the events fails, a persist interceptor vetoes them or a
+    /// transactional event handler fails. The unit of work should then be dropped.
+    pub async fn persist<A, S>(
+        &mut self,
+        store: &PgStore<A, S>,
+        aggregate_state: &mut AggregateState<A::State>,
+        events: Vec<A::Event>,
+    ) -> Result<Vec<StoreEvent<A::Event>>, PgStoreError>
+    where
+        A: Aggregate + 'static,
+        A::State: Send,
+        A::Event: Clone + Send + Sync + 'static,
+        S: Schema<A::Event> + Persistable + Send + Sync,
+    {
+        let aggregate_id = *aggregate_state.id();
+        let (store_events, visible_events) = store
+            .write_events(
+                aggregate_state,
+                events,
+                None,
+                &Metadata::default(),
+                &mut self.transaction,
+            )
+            .await
+            .map_err(|error| error.with_context(ErrorContext::new("persist").with_aggregate_id(aggregate_id)))?;
+
+        self.locks.extend(aggregate_state.take_lock());
+
+        let inner = Arc::clone(&store.inner);
+        let dispatched_store_events: Vec<StoreEvent<A::Event>> = store_events.clone();
+        self.dispatches.push(Box::pin(async move {
+            inner.dispatch(&dispatched_store_events, &visible_events).await;
+        }));
+
+        Ok(store_events)
+    }
+
+    /// Commits all the events persisted through the unit of work, then releases the locks and
+    /// dispatches the events to the event handlers and buses of their stores, in the order they were
+    /// persisted.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the commit fails, in which case nothing is persisted.
+    pub async fn commit(self) -> Result<(), PgStoreError> {
+        let Self {
+            transaction,
+            locks,
+            dispatches,
+        } = self;
+
+        // As for the single store persist, the work is done in a separate task so that cancelling
+        // this future can't leave the events committed but not dispatched.
+        let task = crate::runtime::spawn(async move {
+            transaction.commit().await?;
+            drop(locks);
+
+            for dispatch in dispatches {
+                dispatch.await;
+            }
+
+            Ok::<_, PgStoreError>(())
+        });
+
+        task.await.map_err(PgStoreError::Custom)?
+    }
+}
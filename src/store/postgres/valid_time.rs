@@ -0,0 +1,71 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::sql::event::DbRawEvent;
+use crate::store::StoreEvent;
+use crate::Aggregate;
+
+use super::persistable::Persistable;
+use super::{Column, ColumnType, PgStore, PgStoreError, Schema};
+
+/// Name of the column holding the business time of the events.
+pub(crate) const VALID_AT_COLUMN: Column = Column::new("valid_at", ColumnType::Timestamp).indexed();
+
+/// Name of the column holding the end of the business time of the events.
+pub(crate) const VALID_TO_COLUMN: Column = Column::new("valid_to", ColumnType::Timestamp);
+
+/// Hook providing the business time of the events, i.e. when an event is effective in the domain,
+/// as opposed to `occurred_on` that is when the event has been recorded.
+///
+/// An event can also be effective only until a given business time, e.g. a temporary coverage
+/// extension of an insurance policy: its effects are then left out of the states reconstructed
+/// from that time on.
+///
+/// When set in the [`super::PgStoreBuilder`], the business time is stored in the `valid_at` and
+/// `valid_to` columns of the event store table, and it can be queried through
+/// [`PgStore::by_aggregate_id_valid_until`].
+pub trait ValidTime<E>: Sync {
+    /// Returns the business time of the given event. `None` means that the event is effective from
+    /// when it has been recorded.
+    fn valid_at(&self, event: &E) -> Option<DateTime<Utc>>;
+
+    /// Returns the business time the given event stops being effective at, excluded. `None`, the
+    /// default, means that the event is effective indefinitely.
+    fn valid_to(&self, _event: &E) -> Option<DateTime<Utc>> {
+        None
+    }
+}
+
+impl<A, S> PgStore<A, S>
+where
+    A: Aggregate,
+    A::Event: Send + Sync,
+    S: Schema<A::Event> + Persistable + Send + Sync,
+{
+    /// Loads the events of the given aggregate instance effective at the given business time, that
+    /// is effective from that time or before and not ended by then, ordered by business time.
+    /// Events without a business time are effective from their `occurred_on`.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the store hasn't been built with a [`ValidTime`] hook, or the query
+    /// fails.
+    pub async fn by_aggregate_id_valid_until(
+        &self,
+        aggregate_id: Uuid,
+        valid_until: DateTime<Utc>,
+    ) -> Result<Vec<StoreEvent<A::Event>>, PgStoreError> {
+        let query: String = format!(
+            include_str!("../../sql/postgres/statements/select_by_aggregate_id_valid_until.sql"),
+            self.table_name()
+        );
+
+        let events: Vec<DbRawEvent> = sqlx::query_as::<_, DbRawEvent>(query.as_str())
+            .bind(aggregate_id)
+            .bind(valid_until)
+            .fetch_all(&self.inner.pool)
+            .await?;
+
+        self.inner.decode_events::<S>(events).await
+    }
+}
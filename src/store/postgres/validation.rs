@@ -0,0 +1,92 @@
+#[cfg(feature = "json-schema")]
+use std::collections::HashMap;
+
+/// Hook validating the serialized payloads of the events before they are written, so that the
+/// payloads which would poison the downstream consumers are rejected.
+///
+/// When set in the [`super::PgStoreBuilder`], persisting an event whose payload is rejected fails
+/// with a [`SchemaViolation`], wrapped in a [`super::PgStoreError::Custom`], and none of the events
+/// is persisted.
+pub trait EventSchemaValidator: Send + Sync {
+    /// Validates the given payload, in the form it is written (i.e. downcast, if a downcaster is
+    /// set), tagged with the given version.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the payload violates the schema of its version.
+    fn validate(&self, payload: &serde_json::Value, version: Option<i32>) -> Result<(), SchemaViolation>;
+}
+
+/// Error returned by an [`EventSchemaValidator`] rejecting a payload.
+#[derive(thiserror::Error, Debug)]
+#[error("event payload violates the schema of version {version:?}: {}", errors.join("; "))]
+pub struct SchemaViolation {
+    /// The version of the rejected payload.
+    pub version: Option<i32>,
+    /// The description of each violation.
+    pub errors: Vec<String>,
+}
+
+/// [`EventSchemaValidator`] validating the payloads against the JSON Schema registered for their
+/// version. Payloads of versions without a registered schema are accepted.
+#[cfg(feature = "json-schema")]
+#[derive(Default)]
+pub struct JsonSchemaValidator {
+    schemas: HashMap<Option<i32>, jsonschema::Validator>,
+}
+
+#[cfg(feature = "json-schema")]
+impl JsonSchemaValidator {
+    /// Creates a new [`JsonSchemaValidator`], without any schema.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the given JSON Schema for the payloads of the given version, replacing any
+    /// previously registered one.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the given schema isn't a valid JSON Schema.
+    pub fn with_schema(
+        mut self,
+        version: Option<i32>,
+        schema: &serde_json::Value,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let validator: jsonschema::Validator = jsonschema::validator_for(schema).map_err(|error| error.to_string())?;
+
+        let _ = self.schemas.insert(version, validator);
+        Ok(self)
+    }
+
+    /// Registers the JSON Schema generated from the given type, usually the event type or its
+    /// [`super::Schema`], for the payloads of the given version.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the generated schema can't be compiled.
+    pub fn with_schema_for<T>(self, version: Option<i32>) -> Result<Self, Box<dyn std::error::Error + Send + Sync>>
+    where
+        T: schemars::JsonSchema,
+    {
+        let schema: serde_json::Value = serde_json::to_value(schemars::schema_for!(T))?;
+        self.with_schema(version, &schema)
+    }
+}
+
+#[cfg(feature = "json-schema")]
+impl EventSchemaValidator for JsonSchemaValidator {
+    fn validate(&self, payload: &serde_json::Value, version: Option<i32>) -> Result<(), SchemaViolation> {
+        let Some(validator) = self.schemas.get(&version) else {
+            return Ok(());
+        };
+
+        let errors: Vec<String> = validator.iter_errors(payload).map(|error| error.to_string()).collect();
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(SchemaViolation { version, errors })
+        }
+    }
+}
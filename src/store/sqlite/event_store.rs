@@ -0,0 +1,468 @@
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use serde_json::value::RawValue;
+use sqlx::query::QueryAs;
+use sqlx::sqlite::SqliteArguments;
+use sqlx::types::Json;
+use sqlx::{Executor, Pool, Sqlite, SqliteConnection, Transaction};
+use tokio::sync::{OwnedMutexGuard, RwLock};
+use uuid::Uuid;
+
+use crate::bus::EventBus;
+use crate::handler::{EventHandler, TransactionalEventHandler};
+use crate::sql::event::DbRawEvent;
+use crate::store::postgres::persistable::Persistable;
+use crate::store::postgres::Schema;
+use crate::store::{EventStore, EventStoreLockGuard, Metadata, Since, StoreEvent, UnlockOnDrop};
+use crate::types::SequenceNumber;
+use crate::{Aggregate, AggregateState};
+
+use super::SqliteStoreError;
+
+/// SQLite implementation for the [`EventStore`], meant for embedded tooling and for tests running
+/// without a Postgres instance. It follows the [`crate::store::postgres::PgStore`] API: events are
+/// persisted along with the transactional event handlers in a single transaction, then handled by the
+/// event handlers and published to the event buses.
+///
+/// Aggregate instances are locked in-process, so that only the commands handled through the same
+/// store (or its clones) are serialized; concurrent writers in other processes are still prevented
+/// from persisting conflicting events by the uniqueness of the sequence numbers.
+///
+/// The store is protected by an [`Arc`] that allows it to be cloneable still having the same memory
+/// reference.
+pub struct SqliteStore<A, Schema = <A as Aggregate>::Event>
+where
+    A: Aggregate,
+{
+    pub(super) inner: Arc<InnerSqliteStore<A>>,
+    pub(super) _schema: PhantomData<Schema>,
+}
+
+pub(super) struct InnerSqliteStore<A>
+where
+    A: Aggregate,
+{
+    pub(super) pool: Pool<Sqlite>,
+    pub(super) statements: SqliteStatements,
+    pub(super) event_handlers: RwLock<Vec<Box<dyn EventHandler<A> + Send>>>,
+    pub(super) transactional_event_handlers:
+        Vec<Box<dyn TransactionalEventHandler<A, SqliteStoreError, SqliteConnection> + Send>>,
+    pub(super) event_buses: RwLock<Vec<Box<dyn EventBus<A> + Send>>>,
+    pub(super) locks: Mutex<HashMap<Uuid, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+/// The statements of a [`SqliteStore`], built once for the table of the aggregate.
+#[derive(Debug)]
+pub(super) struct SqliteStatements {
+    pub(super) table_name: String,
+    pub(super) create_table: String,
+    select_by_aggregate_id: String,
+    select_by_aggregate_id_after: String,
+    select_by_aggregate_id_paginated: String,
+    select_since_occurred_on: String,
+    select_since_event_id: String,
+    exists_by_aggregate_id: String,
+    select_all: String,
+    insert: String,
+    delete_by_aggregate_id: String,
+}
+
+impl SqliteStatements {
+    pub(super) fn new<A>() -> Self
+    where
+        A: Aggregate,
+    {
+        let table_name: String = format!("{}_events", A::NAME);
+
+        Self {
+            create_table: format!(include_str!("../../sql/sqlite/migrations/create_table.sql"), table_name),
+            select_by_aggregate_id: format!(
+                include_str!("../../sql/sqlite/statements/select_by_aggregate_id.sql"),
+                table_name
+            ),
+            select_by_aggregate_id_after: format!(
+                include_str!("../../sql/sqlite/statements/select_by_aggregate_id_after.sql"),
+                table_name
+            ),
+            select_by_aggregate_id_paginated: format!(
+                include_str!("../../sql/sqlite/statements/select_by_aggregate_id_paginated.sql"),
+                table_name
+            ),
+            select_since_occurred_on: format!(
+                include_str!("../../sql/sqlite/statements/select_since_occurred_on.sql"),
+                table_name
+            ),
+            select_since_event_id: format!(
+                include_str!("../../sql/sqlite/statements/select_since_event_id.sql"),
+                table_name
+            ),
+            exists_by_aggregate_id: format!(
+                include_str!("../../sql/sqlite/statements/exists_by_aggregate_id.sql"),
+                table_name
+            ),
+            select_all: format!(include_str!("../../sql/sqlite/statements/select_all.sql"), table_name),
+            insert: format!(include_str!("../../sql/sqlite/statements/insert.sql"), table_name),
+            delete_by_aggregate_id: format!(
+                include_str!("../../sql/sqlite/statements/delete_by_aggregate_id.sql"),
+                table_name
+            ),
+            table_name,
+        }
+    }
+}
+
+impl<A, S> SqliteStore<A, S>
+where
+    A: Aggregate,
+    A::Event: Send + Sync,
+    S: Schema<A::Event> + Persistable + Send + Sync,
+{
+    /// Returns the name of the event store table
+    pub fn table_name(&self) -> &str {
+        &self.inner.statements.table_name
+    }
+
+    /// Safely add an event handler to [`SqliteStore`]. Since it appends an event handler to a
+    /// [`RwLock`] this function needs to be `async`.
+    pub async fn add_event_handler(&self, event_handler: impl EventHandler<A> + Send + 'static) {
+        let mut guard = self.inner.event_handlers.write().await;
+
+        guard.push(Box::new(event_handler))
+    }
+
+    /// Safely add an event bus to [`SqliteStore`]. Since it appends an event bus to a [`RwLock`]
+    /// this function needs to be `async`.
+    pub async fn add_event_bus(&self, event_bus: impl EventBus<A> + Send + 'static) {
+        let mut guard = self.inner.event_buses.write().await;
+
+        guard.push(Box::new(event_bus))
+    }
+
+    /// Save an event in the event store and return a new [`StoreEvent`] instance.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the insert of the values into the database fails.
+    async fn save_event(
+        &self,
+        aggregate_id: Uuid,
+        event: A::Event,
+        occurred_on: DateTime<Utc>,
+        sequence_number: SequenceNumber,
+        metadata: &Metadata,
+        executor: impl Executor<'_, Database = Sqlite>,
+    ) -> Result<StoreEvent<A::Event>, SqliteStoreError> {
+        let id: Uuid = Uuid::new_v4();
+
+        #[cfg(feature = "upcasting")]
+        let version: Option<i32> = S::current_version();
+        #[cfg(not(feature = "upcasting"))]
+        let version: Option<i32> = None;
+
+        let schema = S::from_event(event);
+        let raw_payload: Box<RawValue> = RawValue::from_string(serde_json::to_string(&schema)?)?;
+
+        let _ = sqlx::query(self.inner.statements.insert.as_str())
+            .bind(id)
+            .bind(aggregate_id)
+            .bind(Json(&raw_payload))
+            .bind(occurred_on)
+            .bind(sequence_number)
+            .bind(version)
+            .bind(Json(metadata))
+            .execute(executor)
+            .await?;
+
+        Ok(StoreEvent::new(
+            id,
+            aggregate_id,
+            schema.to_event().expect(
+                "For any type that implements Schema the following contract should be upheld:\
+                assert_eq!(Some(event.clone()), Schema::from_event(event).to_event())",
+            ),
+            occurred_on,
+            sequence_number,
+            version,
+        )
+        .with_metadata(metadata.clone())
+        .with_raw_payload(raw_payload))
+    }
+
+    /// Runs the given query loading the events of an aggregate instance, then deserializes them.
+    async fn load_events<'q>(
+        &self,
+        query: QueryAs<'q, Sqlite, DbRawEvent, SqliteArguments<'q>>,
+    ) -> Result<Vec<StoreEvent<A::Event>>, SqliteStoreError> {
+        query
+            .fetch_all(&self.inner.pool)
+            .await?
+            .into_iter()
+            .map(|event| Ok(event.into_raw_store_event::<_, S>().into_store_event()?))
+            .filter_map(Result::transpose)
+            .collect::<Result<Vec<StoreEvent<A::Event>>, SqliteStoreError>>()
+    }
+
+    /// This function returns a stream representing the full event store table content, in insertion
+    /// order. This should be mainly used to rebuild read models.
+    pub fn stream_events<'s>(
+        &'s self,
+        executor: impl Executor<'s, Database = Sqlite> + 's,
+    ) -> BoxStream<'s, Result<StoreEvent<A::Event>, SqliteStoreError>> {
+        Box::pin({
+            sqlx::query_as::<_, DbRawEvent>(self.inner.statements.select_all.as_str())
+                .fetch(executor)
+                .map(|res| Ok(res?.into_raw_store_event::<_, S>().into_store_event()?))
+                .map(Result::transpose)
+                .filter_map(std::future::ready)
+        })
+    }
+
+    /// Lets the event handlers handle the given committed events, then publishes them to the event
+    /// buses.
+    async fn dispatch(&self, store_events: &[StoreEvent<A::Event>]) {
+        let event_handlers = self.inner.event_handlers.read().await;
+        for store_event in store_events {
+            for event_handler in event_handlers.iter() {
+                let span = tracing::debug_span!(
+                    "esrs.event_handler",
+                    event_id = %store_event.id,
+                    aggregate_id = %store_event.aggregate_id,
+                    event_handler = event_handler.name()
+                );
+                let _e = span.enter();
+
+                event_handler.handle(store_event).await;
+            }
+        }
+
+        self.publish_events(store_events).await;
+    }
+
+    /// Publishes the given events to all the event buses, concurrently.
+    async fn publish_events(&self, store_events: &[StoreEvent<A::Event>]) {
+        let event_buses = self.inner.event_buses.read().await;
+        let futures: Vec<_> = event_buses
+            .iter()
+            .map(|bus| async move {
+                for store_event in store_events {
+                    bus.publish(store_event).await;
+                }
+            })
+            .collect();
+
+        let _ = futures::future::join_all(futures).await;
+    }
+}
+
+/// Concrete implementation of [`EventStoreLockGuard`] for the [`SqliteStore`].
+///
+/// It holds the in-process lock of the aggregate instance, released when dropped.
+pub struct SqliteStoreLockGuard {
+    _guard: OwnedMutexGuard<()>,
+}
+
+/// Marking [`SqliteStoreLockGuard`] as an [`UnlockOnDrop`] trait object.
+impl UnlockOnDrop for SqliteStoreLockGuard {}
+
+#[async_trait]
+impl<A, S> EventStore for SqliteStore<A, S>
+where
+    A: Aggregate,
+    A::State: Send,
+    A::Event: Send + Sync,
+    S: Schema<A::Event> + Persistable + Send + Sync,
+{
+    type Aggregate = A;
+    type Error = SqliteStoreError;
+
+    async fn lock(&self, aggregate_id: Uuid) -> Result<EventStoreLockGuard, Self::Error> {
+        let lock: Arc<tokio::sync::Mutex<()>> = {
+            let mut locks = self.inner.locks.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            // The locks nobody is holding nor waiting for are dropped, to keep the map bounded.
+            locks.retain(|_, lock| Arc::strong_count(lock) > 1);
+            Arc::clone(locks.entry(aggregate_id).or_default())
+        };
+
+        Ok(EventStoreLockGuard::new(SqliteStoreLockGuard {
+            _guard: lock.lock_owned().await,
+        }))
+    }
+
+    async fn by_aggregate_id(&self, aggregate_id: Uuid) -> Result<Vec<StoreEvent<A::Event>>, Self::Error> {
+        let query =
+            sqlx::query_as::<_, DbRawEvent>(self.inner.statements.select_by_aggregate_id.as_str()).bind(aggregate_id);
+        self.load_events(query).await
+    }
+
+    async fn by_aggregate_id_after(
+        &self,
+        aggregate_id: Uuid,
+        sequence_number: SequenceNumber,
+    ) -> Result<Vec<StoreEvent<A::Event>>, Self::Error> {
+        let query = sqlx::query_as::<_, DbRawEvent>(self.inner.statements.select_by_aggregate_id_after.as_str())
+            .bind(aggregate_id)
+            .bind(sequence_number);
+        self.load_events(query).await
+    }
+
+    async fn by_aggregate_id_paginated(
+        &self,
+        aggregate_id: Uuid,
+        from_sequence_number: SequenceNumber,
+        limit: usize,
+    ) -> Result<Vec<StoreEvent<A::Event>>, Self::Error> {
+        let query = sqlx::query_as::<_, DbRawEvent>(self.inner.statements.select_by_aggregate_id_paginated.as_str())
+            .bind(aggregate_id)
+            .bind(from_sequence_number)
+            .bind(limit as i64);
+        self.load_events(query).await
+    }
+
+    async fn events_since(&self, since: Since, limit: usize) -> Result<Vec<StoreEvent<A::Event>>, Self::Error> {
+        let query = match since {
+            Since::OccurredOn(occurred_on) => {
+                sqlx::query_as::<_, DbRawEvent>(self.inner.statements.select_since_occurred_on.as_str())
+                    .bind(occurred_on)
+            }
+            Since::EventId(event_id) => {
+                sqlx::query_as::<_, DbRawEvent>(self.inner.statements.select_since_event_id.as_str()).bind(event_id)
+            }
+        };
+        self.load_events(query.bind(limit as i64)).await
+    }
+
+    async fn exists(&self, aggregate_id: Uuid) -> Result<bool, Self::Error> {
+        Ok(
+            sqlx::query_scalar(self.inner.statements.exists_by_aggregate_id.as_str())
+                .bind(aggregate_id)
+                .fetch_one(&self.inner.pool)
+                .await?,
+        )
+    }
+
+    async fn persist(
+        &self,
+        aggregate_state: &mut AggregateState<A::State>,
+        events: Vec<A::Event>,
+    ) -> Result<Vec<StoreEvent<A::Event>>, Self::Error> {
+        self.persist_with_metadata(aggregate_state, events, Metadata::default())
+            .await
+    }
+
+    async fn persist_with_metadata(
+        &self,
+        aggregate_state: &mut AggregateState<A::State>,
+        events: Vec<A::Event>,
+        metadata: Metadata,
+    ) -> Result<Vec<StoreEvent<A::Event>>, Self::Error> {
+        let mut transaction: Transaction<Sqlite> = self.inner.pool.begin().await?;
+        let aggregate_id: Uuid = *aggregate_state.id();
+        let occurred_on: DateTime<Utc> = Utc::now();
+        let mut store_events: Vec<StoreEvent<A::Event>> = vec![];
+
+        for event in events.into_iter() {
+            let store_event: StoreEvent<A::Event> = self
+                .save_event(
+                    aggregate_id,
+                    event,
+                    occurred_on,
+                    aggregate_state.next_sequence_number(),
+                    &metadata,
+                    &mut *transaction,
+                )
+                .await?;
+
+            store_events.push(store_event);
+        }
+
+        for store_event in &store_events {
+            for transactional_event_handler in &self.inner.transactional_event_handlers {
+                let span = tracing::trace_span!(
+                    "esrs.transactional_event_handler",
+                    event_id = %store_event.id,
+                    aggregate_id = %store_event.aggregate_id,
+                    transactional_event_handler = transactional_event_handler.name()
+                );
+                let _e = span.enter();
+
+                if let Err(error) = transactional_event_handler.handle(store_event, &mut transaction).await {
+                    tracing::error!({
+                        event_id = %store_event.id,
+                        aggregate_id = %store_event.aggregate_id,
+                        transactional_event_handler = transactional_event_handler.name(),
+                        error = ?error,
+                    }, "transactional event handler failed to handle event");
+
+                    return Err(error);
+                }
+            }
+        }
+
+        transaction.commit().await?;
+
+        // We need to drop the lock on the aggregate state here as:
+        // 1. the events have already been persisted, hence the DB has the latest aggregate;
+        // 2. the event handlers below might need to access this aggregate atomically (causing a deadlock!).
+        drop(aggregate_state.take_lock());
+
+        self.dispatch(&store_events).await;
+
+        Ok(store_events)
+    }
+
+    async fn publish(&self, store_events: &[StoreEvent<A::Event>]) {
+        self.publish_events(store_events).await;
+    }
+
+    async fn delete(&self, aggregate_id: Uuid) -> Result<(), Self::Error> {
+        let mut transaction: Transaction<Sqlite> = self.inner.pool.begin().await?;
+
+        let _ = sqlx::query(self.inner.statements.delete_by_aggregate_id.as_str())
+            .bind(aggregate_id)
+            .execute(&mut *transaction)
+            .await?;
+
+        for transactional_event_handler in self.inner.transactional_event_handlers.iter() {
+            transactional_event_handler
+                .delete(aggregate_id, &mut transaction)
+                .await?;
+        }
+
+        transaction.commit().await?;
+
+        let event_handlers = self.inner.event_handlers.read().await;
+        for event_handler in event_handlers.iter() {
+            event_handler.delete(aggregate_id).await;
+        }
+
+        Ok(())
+    }
+}
+
+/// Debug implementation for [`SqliteStore`]. It just shows the statements, that are the only thing
+/// that might be useful to debug.
+impl<T: Aggregate> std::fmt::Debug for SqliteStore<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SqliteStore")
+            .field("statements", &self.inner.statements)
+            .finish()
+    }
+}
+
+impl<A, S> Clone for SqliteStore<A, S>
+where
+    A: Aggregate,
+{
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+            _schema: PhantomData,
+        }
+    }
+}
@@ -0,0 +1,31 @@
+pub use builder::*;
+pub use event_store::*;
+
+mod builder;
+mod event_store;
+
+use crate::manager::ConflictError;
+
+// Trait aliases are experimental. See issue #41517 <https://github.com/rust-lang/rust/issues/41517>
+// trait SqliteTransactionalEventHandler<A> = TransactionalEventHandler<A, SqliteStoreError, SqliteConnection> where A: Aggregate;
+
+#[derive(thiserror::Error, Debug)]
+pub enum SqliteStoreError {
+    /// Sql error
+    #[error(transparent)]
+    Sqlx(#[from] sqlx::Error),
+    /// Serialization/deserialization error
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    /// Error while running a TransactionalEventHandler inside of the event store.
+    #[error(transparent)]
+    Custom(Box<dyn std::error::Error + Send + Sync>),
+}
+
+impl ConflictError for SqliteStoreError {
+    /// Returns `true` if the events violated the uniqueness of the aggregate id and sequence number,
+    /// the only unique constraint of the events table besides the event id.
+    fn is_conflict(&self) -> bool {
+        matches!(self, Self::Sqlx(sqlx::Error::Database(error)) if error.is_unique_violation())
+    }
+}
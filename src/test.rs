@@ -0,0 +1,191 @@
+//! Utilities meant to be used in the tests of the applications built on top of this crate.
+
+pub mod loadgen;
+pub mod simulation;
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::handler::{EventHandler, TransactionalEventHandler};
+use crate::store::{EventStore, StoreEvent};
+use crate::types::SequenceNumber;
+use crate::{Aggregate, AggregateState};
+
+/// Persists the given pre-built events for the given aggregate instance, bypassing the command
+/// handling, so that tests can concisely set up historical scenarios.
+///
+/// The events are appended to the events already stored for the aggregate instance, if any, so that
+/// they get the correct sequence numbers. Their timestamps are set by the store, as usual.
+///
+/// # Errors
+///
+/// Will return an `Err` if loading the stored events or persisting the given ones fails.
+pub async fn seed<S>(
+    store: &S,
+    aggregate_id: Uuid,
+    events: Vec<<S::Aggregate as Aggregate>::Event>,
+) -> Result<Vec<StoreEvent<<S::Aggregate as Aggregate>::Event>>, S::Error>
+where
+    S: EventStore,
+    <S::Aggregate as Aggregate>::State: Default,
+{
+    let store_events = store.by_aggregate_id(aggregate_id).await?;
+    let mut aggregate_state: AggregateState<<S::Aggregate as Aggregate>::State> =
+        AggregateState::with_id(aggregate_id).replay::<S::Aggregate>(store_events);
+
+    store.persist(&mut aggregate_state, events).await
+}
+
+/// Asserts that the serialized form of the given named payloads (e.g. one for each event variant)
+/// matches the golden files stored in the given directory, protecting against accidental wire
+/// format breakages of the persisted events.
+///
+/// Golden files are named after the payload name and the given version (e.g. the
+/// `Upcaster::current_version` of the event), and are written when missing. This means that the
+/// serialized form of a payload can change only along with a version bump, committing the new
+/// golden files.
+///
+/// # Panics
+///
+/// Will panic if a payload fails to be serialized, a golden file can't be read or written, or the
+/// serialized form of a payload differs from its golden file.
+pub fn assert_golden_payloads<T>(directory: impl AsRef<std::path::Path>, version: Option<i32>, payloads: &[(&str, T)])
+where
+    T: serde::Serialize,
+{
+    let directory = directory.as_ref();
+    std::fs::create_dir_all(directory).expect("Failed to create golden files directory");
+
+    for (name, payload) in payloads {
+        let actual: serde_json::Value = serde_json::to_value(payload).expect("Failed to serialize payload");
+        let path = directory.join(format!("{}.v{}.json", name, version.unwrap_or_default()));
+
+        if path.exists() {
+            let content: String = std::fs::read_to_string(&path).expect("Failed to read golden file");
+            let expected: serde_json::Value = serde_json::from_str(&content).expect("Failed to parse golden file");
+
+            assert_eq!(
+                actual,
+                expected,
+                "Serialized form of `{}` changed without a version bump (golden file: {})",
+                name,
+                path.display()
+            );
+        } else {
+            let content: String = serde_json::to_string_pretty(&actual).expect("Failed to serialize payload");
+            std::fs::write(&path, content).expect("Failed to write golden file");
+        }
+    }
+}
+
+/// A scripted sequence of [`StoreEvent`]s of an aggregate instance, to be fed to event handlers in
+/// isolation, without going through an event store.
+///
+/// ```ignore
+/// let script = EventScript::new(aggregate_id).then(Event::Created).then(Event::Renamed("new".into()));
+///
+/// let mut transaction = pool.begin().await?;
+/// script.feed_transactional(&handler, &mut *transaction).await?;
+///
+/// assert_view_rows(&mut *transaction, "SELECT name FROM view", &[("new".to_string(),)]).await;
+/// ```
+///
+/// Events get consecutive sequence numbers, starting from 1, and are timestamped when added to the
+/// script.
+pub struct EventScript<E> {
+    aggregate_id: Uuid,
+    version: Option<i32>,
+    events: Vec<StoreEvent<E>>,
+}
+
+impl<E> EventScript<E> {
+    /// Creates an empty script for the given aggregate instance.
+    pub fn new(aggregate_id: impl Into<Uuid>) -> Self {
+        Self {
+            aggregate_id: aggregate_id.into(),
+            version: None,
+            events: vec![],
+        }
+    }
+
+    /// Set the version of the events added from now on.
+    pub fn with_version(mut self, version: Option<i32>) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Appends an event with the given payload to the script.
+    pub fn then(mut self, payload: E) -> Self {
+        let sequence_number: SequenceNumber = self.events.last().map_or(0, |event| event.sequence_number) + 1;
+
+        self.events.push(StoreEvent::new(
+            Uuid::new_v4(),
+            self.aggregate_id,
+            payload,
+            Utc::now(),
+            sequence_number,
+            self.version,
+        ));
+        self
+    }
+
+    /// Returns the events of the script, in order.
+    pub fn events(&self) -> &[StoreEvent<E>] {
+        &self.events
+    }
+
+    /// Feeds the events of the script, in order, to the given event handler.
+    pub async fn feed<A>(&self, event_handler: &(impl EventHandler<A> + ?Sized))
+    where
+        A: Aggregate<Event = E>,
+    {
+        for event in &self.events {
+            event_handler.handle(event).await;
+        }
+    }
+
+    /// Feeds the events of the script, in order, to the given transactional event handler, using
+    /// the given executor (e.g. a Postgres transaction, to be rolled back at the end of the test, or
+    /// a fake).
+    ///
+    /// # Errors
+    ///
+    /// Will return the first `Err` returned by the transactional event handler, skipping the
+    /// remaining events.
+    pub async fn feed_transactional<A, Er, Ex>(
+        &self,
+        transactional_event_handler: &(impl TransactionalEventHandler<A, Er, Ex> + ?Sized),
+        executor: &mut Ex,
+    ) -> Result<(), Er>
+    where
+        A: Aggregate<Event = E>,
+    {
+        for event in &self.events {
+            transactional_event_handler.handle(event, executor).await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Asserts that the given query, typically selecting from the view built by an event handler,
+/// returns exactly the expected rows, in order.
+///
+/// # Panics
+///
+/// Will panic if the query fails, or the rows differ from the expected ones.
+#[cfg(feature = "postgres")]
+pub async fn assert_view_rows<'e, T>(
+    executor: impl sqlx::Executor<'e, Database = sqlx::Postgres>,
+    query: &str,
+    expected: &[T],
+) where
+    T: for<'r> sqlx::FromRow<'r, sqlx::postgres::PgRow> + PartialEq + std::fmt::Debug + Send + Unpin,
+{
+    let actual: Vec<T> = sqlx::query_as(query)
+        .fetch_all(executor)
+        .await
+        .expect("Failed to query view rows");
+
+    assert_eq!(actual, expected, "Unexpected view rows for `{}`", query);
+}
@@ -0,0 +1,171 @@
+//! Synthetic load generation through a real event store, to benchmark the database setup (sizing,
+//! indexes, pool) before going to production.
+//!
+//! ```ignore
+//! let report = LoadGen::new(|aggregate_id, sequence_number| Event::Incremented { by: sequence_number })
+//!     .with_aggregates(1_000)
+//!     .with_events_per_aggregate(50)
+//!     .with_batch_size(5)
+//!     .with_concurrency(16)
+//!     .run(&store)
+//!     .await?;
+//!
+//! println!("{:.0} events/s, p99 {:?}", report.events_per_second(), report.percentile(0.99));
+//! ```
+
+use std::time::{Duration, Instant};
+
+use futures::{StreamExt, TryStreamExt};
+use uuid::Uuid;
+
+use crate::store::EventStore;
+use crate::types::SequenceNumber;
+use crate::{Aggregate, AggregateState};
+
+/// Generates events for brand new aggregate instances and persists them through an
+/// [`EventStore`], measuring the latency of each persist.
+///
+/// Each aggregate instance receives the same number of events, persisted in batches of the given
+/// size, one batch after the other. Aggregate instances are loaded concurrently, up to the given
+/// concurrency. The events are built by the given generator, out of the aggregate id and the
+/// sequence number the event will get, making it possible to shape the distribution of the event
+/// types and the size of the payloads.
+pub struct LoadGen<E> {
+    generator: Box<dyn Fn(Uuid, SequenceNumber) -> E + Send + Sync>,
+    aggregates: usize,
+    events_per_aggregate: usize,
+    batch_size: usize,
+    concurrency: usize,
+}
+
+impl<E> LoadGen<E> {
+    /// Creates a [`LoadGen`] building the events through the given generator. Defaults to 100
+    /// aggregate instances of 10 events each, persisted one at a time, with a concurrency of 1.
+    pub fn new(generator: impl Fn(Uuid, SequenceNumber) -> E + Send + Sync + 'static) -> Self {
+        Self {
+            generator: Box::new(generator),
+            aggregates: 100,
+            events_per_aggregate: 10,
+            batch_size: 1,
+            concurrency: 1,
+        }
+    }
+
+    /// Set the number of aggregate instances to create.
+    pub fn with_aggregates(mut self, aggregates: usize) -> Self {
+        self.aggregates = aggregates;
+        self
+    }
+
+    /// Set the number of events persisted for each aggregate instance.
+    pub fn with_events_per_aggregate(mut self, events_per_aggregate: usize) -> Self {
+        self.events_per_aggregate = events_per_aggregate;
+        self
+    }
+
+    /// Set the number of events persisted at once, i.e. emitted by a single command.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Set the number of aggregate instances being loaded at the same time.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Generates the load through the given store.
+    ///
+    /// # Errors
+    ///
+    /// Will return the first `Err` returned by the store, stopping the load.
+    pub async fn run<S>(&self, store: &S) -> Result<LoadReport, S::Error>
+    where
+        S: EventStore,
+        S::Aggregate: Aggregate<Event = E>,
+        <S::Aggregate as Aggregate>::State: Default,
+    {
+        let started_at: Instant = Instant::now();
+
+        let latencies: Vec<Vec<Duration>> = futures::stream::iter(0..self.aggregates)
+            .map(|_| self.load_aggregate(store))
+            .buffer_unordered(self.concurrency)
+            .try_collect()
+            .await?;
+
+        let mut persist_latencies: Vec<Duration> = latencies.into_iter().flatten().collect();
+        persist_latencies.sort();
+
+        Ok(LoadReport {
+            aggregates: self.aggregates,
+            events: self.aggregates * self.events_per_aggregate,
+            elapsed: started_at.elapsed(),
+            persist_latencies,
+        })
+    }
+
+    /// Persists all the events of a brand new aggregate instance, returning the latency of each
+    /// persist.
+    async fn load_aggregate<S>(&self, store: &S) -> Result<Vec<Duration>, S::Error>
+    where
+        S: EventStore,
+        S::Aggregate: Aggregate<Event = E>,
+        <S::Aggregate as Aggregate>::State: Default,
+    {
+        let mut aggregate_state: AggregateState<<S::Aggregate as Aggregate>::State> = AggregateState::new();
+        let aggregate_id: Uuid = *aggregate_state.id();
+        let mut latencies: Vec<Duration> = vec![];
+        let mut sequence_number: SequenceNumber = *aggregate_state.sequence_number();
+        let mut remaining: usize = self.events_per_aggregate;
+
+        while remaining > 0 {
+            let batch_size: usize = remaining.min(self.batch_size);
+            let events: Vec<E> = (0..batch_size)
+                .map(|_| {
+                    sequence_number += 1;
+                    (self.generator)(aggregate_id, sequence_number)
+                })
+                .collect();
+
+            let persist_started_at: Instant = Instant::now();
+            let _ = store.persist(&mut aggregate_state, events).await?;
+            latencies.push(persist_started_at.elapsed());
+
+            remaining -= batch_size;
+        }
+
+        Ok(latencies)
+    }
+}
+
+/// Outcome of a [`LoadGen`] run.
+#[derive(Debug, Clone)]
+pub struct LoadReport {
+    /// The number of aggregate instances created.
+    pub aggregates: usize,
+    /// The number of events persisted.
+    pub events: usize,
+    /// The overall duration of the load.
+    pub elapsed: Duration,
+    /// The latency of each persist, sorted from the fastest to the slowest.
+    pub persist_latencies: Vec<Duration>,
+}
+
+impl LoadReport {
+    /// The throughput of the load, in events persisted per second.
+    pub fn events_per_second(&self) -> f64 {
+        self.events as f64 / self.elapsed.as_secs_f64()
+    }
+
+    /// The persist latency at the given percentile, between `0.0` and `1.0` (e.g. `0.99` for the
+    /// 99th percentile). Returns [`Duration::ZERO`] if nothing has been persisted.
+    pub fn percentile(&self, percentile: f64) -> Duration {
+        if self.persist_latencies.is_empty() {
+            return Duration::ZERO;
+        }
+
+        let index: usize = (percentile.clamp(0.0, 1.0) * (self.persist_latencies.len() - 1) as f64).round() as usize;
+        self.persist_latencies[index]
+    }
+}
@@ -0,0 +1,227 @@
+//! Deterministic simulation of aggregates, event handlers and sagas, without any IO.
+//!
+//! A [`Simulation`] runs a set of tasks on a single thread, picking the next task to be polled
+//! among the ones able to make progress through a pseudo-random generator seeded by the test. The
+//! [`InMemoryStore`] yields to the simulation before each operation, so that the tasks get
+//! interleaved as they would be when running against a real database, and a [`VirtualClock`]
+//! replaces the wall clock. Running the same tasks with the same seed always produces the same
+//! interleaving, making concurrency bugs (lock misuse, saga races) reproducible.
+//!
+//! ```ignore
+//! for seed in 0..100 {
+//!     let clock = VirtualClock::default();
+//!     let manager = AggregateManager::new(InMemoryStore::<Counter>::new(clock.clone()));
+//!     let mut simulation = Simulation::new(seed).with_clock(clock);
+//!
+//!     for _ in 0..2 {
+//!         simulation.spawn(async {
+//!             let state = manager.lock_and_load(id).await.unwrap().unwrap_or_default();
+//!             manager.handle_command(state, Command::Increment).await.unwrap().unwrap();
+//!         });
+//!     }
+//!
+//!     simulation.run().expect("deadlock");
+//! }
+//! ```
+
+mod clock;
+mod in_memory_store;
+
+pub use clock::{Sleep, VirtualClock};
+pub use in_memory_store::{InMemoryStore, InMemoryStoreError};
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures::task::{waker_ref, ArcWake};
+
+/// Identifies a task spawned in a [`Simulation`], in spawning order.
+pub type TaskId = usize;
+
+/// Single-threaded, deterministic scheduler of the tasks of a simulation. See the
+/// [module documentation](self).
+pub struct Simulation<'a> {
+    rng: u64,
+    clock: VirtualClock,
+    tasks: Vec<Task<'a>>,
+    next_task_id: TaskId,
+    max_steps: usize,
+}
+
+struct Task<'a> {
+    id: TaskId,
+    future: Pin<Box<dyn Future<Output = ()> + 'a>>,
+    woken: Arc<WakeFlag>,
+}
+
+struct WakeFlag(AtomicBool);
+
+impl ArcWake for WakeFlag {
+    fn wake_by_ref(arc_self: &Arc<Self>) {
+        arc_self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Outcome of a successful [`Simulation::run`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SimulationReport {
+    /// The tasks polled, in order. Two runs with the same seed produce the same schedule.
+    pub schedule: Vec<TaskId>,
+}
+
+/// Error returned by [`Simulation::run`] when the tasks can't complete.
+#[derive(thiserror::Error, Debug, Clone, Eq, PartialEq)]
+pub enum SimulationError {
+    /// No task can make progress, and there are no pending sleeps to advance the clock to.
+    #[error("deadlock: tasks {pending:?} can't make progress")]
+    Deadlock {
+        pending: Vec<TaskId>,
+        schedule: Vec<TaskId>,
+    },
+    /// The tasks haven't completed within the maximum number of steps.
+    #[error("tasks {pending:?} haven't completed within {steps} steps")]
+    StepLimit {
+        pending: Vec<TaskId>,
+        steps: usize,
+        schedule: Vec<TaskId>,
+    },
+}
+
+impl<'a> Simulation<'a> {
+    /// Creates a simulation whose interleavings are determined by the given seed.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            // Xorshift can't start from zero.
+            rng: seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1,
+            clock: VirtualClock::default(),
+            tasks: vec![],
+            next_task_id: 0,
+            max_steps: 100_000,
+        }
+    }
+
+    /// Set the clock to be advanced when all the tasks are sleeping. It should be the one given to
+    /// the [`InMemoryStore`] and to the tasks.
+    pub fn with_clock(mut self, clock: VirtualClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Set the maximum number of times the tasks are polled before giving up, to catch livelocks.
+    /// Defaults to 100 000.
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    /// Returns the clock of the simulation.
+    pub fn clock(&self) -> &VirtualClock {
+        &self.clock
+    }
+
+    /// Adds a task to the simulation, returning its id.
+    pub fn spawn(&mut self, future: impl Future<Output = ()> + 'a) -> TaskId {
+        let id: TaskId = self.next_task_id;
+        self.next_task_id += 1;
+
+        self.tasks.push(Task {
+            id,
+            future: Box::pin(future),
+            woken: Arc::new(WakeFlag(AtomicBool::new(true))),
+        });
+
+        id
+    }
+
+    /// Runs the tasks until all of them complete.
+    ///
+    /// # Errors
+    ///
+    /// Will return an `Err` if the tasks deadlock, or don't complete within the maximum number of
+    /// steps.
+    pub fn run(&mut self) -> Result<SimulationReport, SimulationError> {
+        let mut schedule: Vec<TaskId> = vec![];
+
+        while !self.tasks.is_empty() {
+            if schedule.len() >= self.max_steps {
+                return Err(SimulationError::StepLimit {
+                    pending: self.pending(),
+                    steps: schedule.len(),
+                    schedule,
+                });
+            }
+
+            let ready: Vec<usize> = (0..self.tasks.len())
+                .filter(|index| self.tasks[*index].woken.0.load(Ordering::SeqCst))
+                .collect();
+
+            if ready.is_empty() {
+                if self.clock.advance_to_next_deadline() {
+                    continue;
+                }
+
+                return Err(SimulationError::Deadlock {
+                    pending: self.pending(),
+                    schedule,
+                });
+            }
+
+            let index: usize = ready[(self.next_random() % ready.len() as u64) as usize];
+            let completed: bool = {
+                let task: &mut Task<'a> = &mut self.tasks[index];
+                task.woken.0.store(false, Ordering::SeqCst);
+                schedule.push(task.id);
+
+                let waker = waker_ref(&task.woken);
+                let mut context: Context<'_> = Context::from_waker(&waker);
+                task.future.as_mut().poll(&mut context).is_ready()
+            };
+
+            if completed {
+                let _ = self.tasks.remove(index);
+            }
+        }
+
+        Ok(SimulationReport { schedule })
+    }
+
+    fn pending(&self) -> Vec<TaskId> {
+        self.tasks.iter().map(|task| task.id).collect()
+    }
+
+    /// Xorshift64* pseudo-random generator: good enough to pick tasks, and stable across versions.
+    fn next_random(&mut self) -> u64 {
+        self.rng ^= self.rng >> 12;
+        self.rng ^= self.rng << 25;
+        self.rng ^= self.rng >> 27;
+        self.rng.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+}
+
+/// Yields to the [`Simulation`], letting it poll other tasks before resuming the current one.
+/// Insert it in event handlers and sagas to add interleaving points.
+pub fn yield_now() -> YieldNow {
+    YieldNow { yielded: false }
+}
+
+/// Future returned by [`yield_now`].
+pub struct YieldNow {
+    yielded: bool,
+}
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.yielded {
+            Poll::Ready(())
+        } else {
+            self.yielded = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
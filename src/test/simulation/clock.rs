@@ -0,0 +1,114 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+/// A clock whose time only moves forward when told to, shared by all the participants of a
+/// [`super::Simulation`].
+///
+/// The simulation advances it to the earliest pending [`VirtualClock::sleep`] deadline once no
+/// task can make progress, so that time-based logic runs instantly and deterministically.
+#[derive(Clone)]
+pub struct VirtualClock {
+    state: Arc<Mutex<ClockState>>,
+}
+
+struct ClockState {
+    now: DateTime<Utc>,
+    sleepers: Vec<(DateTime<Utc>, Waker)>,
+}
+
+impl VirtualClock {
+    /// Creates a clock starting at the given time.
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(ClockState { now, sleepers: vec![] })),
+        }
+    }
+
+    /// Returns the current virtual time.
+    pub fn now(&self) -> DateTime<Utc> {
+        self.state().now
+    }
+
+    /// Moves the clock forward by the given duration, waking the sleeps elapsed in the meantime.
+    pub fn advance(&self, duration: Duration) {
+        let mut state = self.state();
+        let now: DateTime<Utc> = state.now + to_chrono(duration);
+        state.advance_to(now);
+    }
+
+    /// Returns a future completing once the clock has been advanced by the given duration.
+    pub fn sleep(&self, duration: Duration) -> Sleep {
+        Sleep {
+            clock: self.clone(),
+            deadline: self.now() + to_chrono(duration),
+        }
+    }
+
+    /// Moves the clock forward to the earliest pending sleep deadline, if any, waking the sleeps
+    /// elapsed. Returns whether any sleep has been woken.
+    pub(crate) fn advance_to_next_deadline(&self) -> bool {
+        let mut state = self.state();
+
+        match state.sleepers.iter().map(|(deadline, _)| *deadline).min() {
+            Some(deadline) => {
+                let now: DateTime<Utc> = state.now.max(deadline);
+                state.advance_to(now);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, ClockState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Default for VirtualClock {
+    fn default() -> Self {
+        Self::new(DateTime::<Utc>::default())
+    }
+}
+
+impl ClockState {
+    fn advance_to(&mut self, now: DateTime<Utc>) {
+        self.now = now;
+
+        let (elapsed, pending): (Vec<_>, Vec<_>) = self.sleepers.drain(..).partition(|(deadline, _)| *deadline <= now);
+        self.sleepers = pending;
+
+        for (_, waker) in elapsed {
+            waker.wake();
+        }
+    }
+}
+
+/// Future returned by [`VirtualClock::sleep`].
+pub struct Sleep {
+    clock: VirtualClock,
+    deadline: DateTime<Utc>,
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.clock.state();
+
+        if state.now >= self.deadline {
+            Poll::Ready(())
+        } else {
+            state.sleepers.push((self.deadline, cx.waker().clone()));
+            Poll::Pending
+        }
+    }
+}
+
+fn to_chrono(duration: Duration) -> chrono::Duration {
+    chrono::Duration::from_std(duration).expect("Duration out of range")
+}
@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use async_trait::async_trait;
+use tokio::sync::OwnedMutexGuard;
+use uuid::Uuid;
+
+use crate::handler::EventHandler;
+use crate::manager::ConflictError;
+use crate::store::{EventStore, EventStoreLockGuard, Metadata, Since, StoreEvent, UnlockOnDrop};
+use crate::types::SequenceNumber;
+use crate::{Aggregate, AggregateState};
+
+use super::{yield_now, VirtualClock};
+
+/// An [`EventStore`] keeping the events in memory, timestamped by a [`VirtualClock`], meant to be
+/// run in a [`super::Simulation`].
+///
+/// It yields to the simulation before each operation and before running each event handler, so
+/// that the concurrent tasks get interleaved there. Persisting events for an aggregate state that
+/// isn't up to date fails with [`InMemoryStoreError::SequenceConflict`], as the optimistic locking
+/// of the Postgres store would do.
+pub struct InMemoryStore<A>
+where
+    A: Aggregate,
+{
+    clock: VirtualClock,
+    events: Mutex<HashMap<Uuid, Vec<StoreEvent<A::Event>>>>,
+    locks: Mutex<HashMap<Uuid, Arc<tokio::sync::Mutex<()>>>>,
+    event_handlers: Vec<Box<dyn EventHandler<A> + Send>>,
+}
+
+/// Error returned by the [`InMemoryStore`].
+#[derive(thiserror::Error, Debug)]
+pub enum InMemoryStoreError {
+    /// The events have been persisted from an aggregate state behind the stored events.
+    #[error("aggregate {aggregate_id} is at sequence number {stored}, but the state being persisted is at {expected}")]
+    SequenceConflict {
+        aggregate_id: Uuid,
+        expected: SequenceNumber,
+        stored: SequenceNumber,
+    },
+}
+
+impl ConflictError for InMemoryStoreError {
+    fn is_conflict(&self) -> bool {
+        matches!(self, Self::SequenceConflict { .. })
+    }
+}
+
+struct InMemoryLockGuard(#[allow(dead_code)] OwnedMutexGuard<()>);
+
+impl UnlockOnDrop for InMemoryLockGuard {}
+
+impl<A> InMemoryStore<A>
+where
+    A: Aggregate,
+{
+    /// Creates an empty store, timestamping the events with the given clock.
+    pub fn new(clock: VirtualClock) -> Self {
+        Self {
+            clock,
+            events: Mutex::new(HashMap::new()),
+            locks: Mutex::new(HashMap::new()),
+            event_handlers: vec![],
+        }
+    }
+
+    /// Add an event handler, run after the events are persisted.
+    pub fn add_event_handler(mut self, event_handler: impl EventHandler<A> + Send + 'static) -> Self {
+        self.event_handlers.push(Box::new(event_handler));
+        self
+    }
+
+    fn events(&self) -> MutexGuard<'_, HashMap<Uuid, Vec<StoreEvent<A::Event>>>> {
+        self.events.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[async_trait]
+impl<A> EventStore for InMemoryStore<A>
+where
+    A: Aggregate + Send + Sync,
+    A::State: Send,
+    A::Event: Clone + Send + Sync,
+{
+    type Aggregate = A;
+    type Error = InMemoryStoreError;
+
+    async fn lock(&self, aggregate_id: Uuid) -> Result<EventStoreLockGuard, Self::Error> {
+        let lock: Arc<tokio::sync::Mutex<()>> = self
+            .locks
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .entry(aggregate_id)
+            .or_default()
+            .clone();
+
+        yield_now().await;
+
+        Ok(EventStoreLockGuard::new(InMemoryLockGuard(lock.lock_owned().await)))
+    }
+
+    async fn by_aggregate_id(&self, aggregate_id: Uuid) -> Result<Vec<StoreEvent<A::Event>>, Self::Error> {
+        yield_now().await;
+
+        Ok(self.events().get(&aggregate_id).cloned().unwrap_or_default())
+    }
+
+    async fn events_since(&self, since: Since, limit: usize) -> Result<Vec<StoreEvent<A::Event>>, Self::Error> {
+        yield_now().await;
+
+        let mut store_events: Vec<StoreEvent<A::Event>> = self.events().values().flatten().cloned().collect();
+        store_events.sort_by_key(|store_event| {
+            (
+                store_event.occurred_on,
+                store_event.aggregate_id,
+                store_event.sequence_number,
+            )
+        });
+
+        let start: usize = match since {
+            Since::OccurredOn(occurred_on) => store_events
+                .iter()
+                .position(|store_event| store_event.occurred_on >= occurred_on)
+                .unwrap_or(store_events.len()),
+            Since::EventId(event_id) => store_events
+                .iter()
+                .position(|store_event| store_event.id == event_id)
+                .map_or(store_events.len(), |position| position + 1),
+        };
+
+        Ok(store_events.into_iter().skip(start).take(limit).collect())
+    }
+
+    async fn persist(
+        &self,
+        aggregate_state: &mut AggregateState<A::State>,
+        events: Vec<A::Event>,
+    ) -> Result<Vec<StoreEvent<A::Event>>, Self::Error> {
+        self.persist_with_metadata(aggregate_state, events, Metadata::default())
+            .await
+    }
+
+    async fn persist_with_metadata(
+        &self,
+        aggregate_state: &mut AggregateState<A::State>,
+        events: Vec<A::Event>,
+        metadata: Metadata,
+    ) -> Result<Vec<StoreEvent<A::Event>>, Self::Error> {
+        yield_now().await;
+
+        let aggregate_id: Uuid = *aggregate_state.id();
+        let store_events: Vec<StoreEvent<A::Event>> = {
+            let mut stored_events = self.events();
+            let stored_events: &mut Vec<StoreEvent<A::Event>> = stored_events.entry(aggregate_id).or_default();
+            let stored: SequenceNumber = stored_events.last().map_or(0, |event| event.sequence_number);
+
+            if stored != *aggregate_state.sequence_number() {
+                return Err(InMemoryStoreError::SequenceConflict {
+                    aggregate_id,
+                    expected: *aggregate_state.sequence_number(),
+                    stored,
+                });
+            }
+
+            let store_events: Vec<StoreEvent<A::Event>> = events
+                .into_iter()
+                .map(|payload| {
+                    StoreEvent::new(
+                        Uuid::new_v4(),
+                        aggregate_id,
+                        payload,
+                        self.clock.now(),
+                        aggregate_state.next_sequence_number(),
+                        None,
+                    )
+                    .with_metadata(metadata.clone())
+                })
+                .collect();
+
+            stored_events.extend(store_events.iter().cloned());
+            store_events
+        };
+
+        // As in the Postgres store, the lock is released before running the event handlers.
+        drop(aggregate_state.take_lock());
+
+        for store_event in &store_events {
+            for event_handler in &self.event_handlers {
+                yield_now().await;
+                event_handler.handle(store_event).await;
+            }
+        }
+
+        Ok(store_events)
+    }
+
+    async fn publish(&self, _store_events: &[StoreEvent<A::Event>]) {
+        // There are no event buses in a simulation.
+    }
+
+    async fn delete(&self, aggregate_id: Uuid) -> Result<(), Self::Error> {
+        yield_now().await;
+
+        let _ = self.events().remove(&aggregate_id);
+
+        for event_handler in &self.event_handlers {
+            event_handler.delete(aggregate_id).await;
+        }
+
+        Ok(())
+    }
+}
@@ -1,5 +1,6 @@
 use esrs::Aggregate;
 pub use event_handler::*;
+use serde::{Deserialize, Serialize};
 pub use structs::*;
 #[cfg(feature = "postgres")]
 pub use transactional_event_handler::*;
@@ -11,7 +12,7 @@ mod transactional_event_handler;
 
 pub struct TestAggregate;
 
-#[derive(Clone)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct TestAggregateState {
     pub count: i32,
 }
@@ -42,3 +43,22 @@ impl Aggregate for TestAggregate {
         }
     }
 }
+
+/// Aggregate sharing the types of [`TestAggregate`], with an event store table of its own.
+pub struct OtherAggregate;
+
+impl Aggregate for OtherAggregate {
+    const NAME: &'static str = "other";
+    type State = TestAggregateState;
+    type Command = TestCommand;
+    type Event = TestEvent;
+    type Error = TestError;
+
+    fn handle_command(state: &Self::State, command: Self::Command) -> Result<Vec<Self::Event>, Self::Error> {
+        TestAggregate::handle_command(state, command)
+    }
+
+    fn apply_event(state: Self::State, payload: Self::Event) -> Self::State {
+        TestAggregate::apply_event(state, payload)
+    }
+}
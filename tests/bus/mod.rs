@@ -0,0 +1,37 @@
+use esrs::bus::{FixedTopic, TemplateTopic, TopicStrategy};
+use esrs::Aggregate;
+
+use crate::aggregate::{OtherAggregate, TestAggregate};
+
+#[test]
+fn fixed_topic_test() {
+    let topic_strategy: FixedTopic = FixedTopic::new("events");
+
+    assert_eq!(topic_strategy.topic(TestAggregate::NAME), "events");
+    assert_eq!(topic_strategy.topic(OtherAggregate::NAME), "events");
+}
+
+#[test]
+fn template_topic_test() {
+    let topic_strategy: TemplateTopic = TemplateTopic::new("{aggregate_name}-events");
+
+    assert_eq!(topic_strategy.topic(TestAggregate::NAME), "test-events");
+    assert_eq!(topic_strategy.topic(OtherAggregate::NAME), "other-events");
+
+    // Every occurrence of the placeholder is replaced, and templates without it are left as they are.
+    let topic_strategy: TemplateTopic = TemplateTopic::new("{aggregate_name}.{aggregate_name}");
+    assert_eq!(topic_strategy.topic(TestAggregate::NAME), "test.test");
+
+    let topic_strategy: TemplateTopic = TemplateTopic::new("events");
+    assert_eq!(topic_strategy.topic(TestAggregate::NAME), "events");
+}
+
+#[test]
+fn closure_topic_strategy_test() {
+    let environment: String = "staging".to_string();
+    let topic_strategy: Box<dyn TopicStrategy> =
+        Box::new(move |aggregate_name: &str| format!("{}.{}", environment, aggregate_name.to_uppercase()));
+
+    assert_eq!(topic_strategy.topic(TestAggregate::NAME), "staging.TEST");
+    assert_eq!(topic_strategy.topic(OtherAggregate::NAME), "staging.OTHER");
+}
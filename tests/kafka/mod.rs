@@ -7,7 +7,7 @@ use uuid::Uuid;
 
 use esrs::bus::kafka::{KafkaEventBus, KafkaEventBusConfig};
 use esrs::bus::EventBus;
-use esrs::store::StoreEvent;
+use esrs::store::{Metadata, StoreEvent};
 
 use crate::aggregate::{TestAggregate, TestEvent};
 
@@ -36,6 +36,8 @@ async fn kafka_event_bus_test() {
         occurred_on: Utc::now(),
         sequence_number: 1,
         version: None,
+        metadata: Metadata::default(),
+        raw_payload: None,
     };
 
     bus.publish(&store_event).await;
@@ -1,10 +1,25 @@
 pub mod aggregate;
+mod bus;
+mod reexports;
+mod state_machine;
 
 #[cfg(feature = "postgres")]
 mod postgres;
 
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+#[cfg(feature = "macros")]
+mod macros;
+
 #[cfg(feature = "rabbit")]
 mod rabbit;
 
 #[cfg(feature = "kafka")]
 mod kafka;
+
+#[cfg(feature = "pubsub")]
+mod pubsub;
+
+#[cfg(feature = "test-utils")]
+mod test_utils;
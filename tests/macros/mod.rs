@@ -0,0 +1,48 @@
+#[cfg(feature = "upcasting")]
+#[test]
+fn upcaster_derive_test() {
+    use esrs::event::Upcaster;
+    use serde::{Deserialize, Serialize};
+    use serde_json::{json, Value};
+
+    #[derive(Serialize, Deserialize, Upcaster, Debug, PartialEq)]
+    #[upcast(1 => "rename_i", 2 => "widen_u")]
+    struct Incremented {
+        amount: i64,
+    }
+
+    fn rename_i(value: Value) -> Result<Value, serde_json::Error> {
+        Ok(json!({ "u": value["i"] }))
+    }
+
+    fn widen_u(value: Value) -> Result<Value, serde_json::Error> {
+        Ok(json!({ "amount": value["u"] }))
+    }
+
+    assert_eq!(Incremented::current_version(), Some(3));
+
+    let expected = Incremented { amount: 10 };
+    assert_eq!(Incremented::upcast(json!({ "i": 10 }), Some(1)).unwrap(), expected);
+    assert_eq!(Incremented::upcast(json!({ "i": 10 }), None).unwrap(), expected);
+    assert_eq!(Incremented::upcast(json!({ "u": 10 }), Some(2)).unwrap(), expected);
+    assert_eq!(Incremented::upcast(json!({ "amount": 10 }), Some(3)).unwrap(), expected);
+}
+
+#[cfg(feature = "upcasting")]
+#[test]
+fn upcaster_derive_without_migrations_test() {
+    use esrs::event::Upcaster;
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+
+    #[derive(Serialize, Deserialize, Upcaster, Debug, PartialEq)]
+    struct Incremented {
+        amount: i64,
+    }
+
+    assert_eq!(Incremented::current_version(), None);
+    assert_eq!(
+        Incremented::upcast(json!({ "amount": 10 }), None).unwrap(),
+        Incremented { amount: 10 }
+    );
+}
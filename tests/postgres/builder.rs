@@ -1,9 +1,16 @@
+use chrono::{DateTime, Duration, Utc};
 use sqlx::{Pool, Postgres};
+use uuid::Uuid;
 
-use esrs::store::postgres::{PgStore, PgStoreBuilder};
-use esrs::Aggregate;
+use esrs::sql::migrations::Migrations;
+use esrs::store::postgres::{
+    DeletionStrategy, LockStrategy, OccurredOnStrategy, PgStore, PgStoreBuilder, SchemaDriftError, SchemaDriftPolicy,
+    Visibility,
+};
+use esrs::store::{EventStore, StoreEvent};
+use esrs::{Aggregate, AggregateState};
 
-use crate::aggregate::TestAggregate;
+use crate::aggregate::{OtherAggregate, TestAggregate, TestAggregateState, TestEvent};
 
 #[sqlx::test]
 async fn builder_can_skip_migrations_test(pool: Pool<Postgres>) {
@@ -28,6 +35,385 @@ async fn builder_run_migrations_test(pool: Pool<Postgres>) {
     drop(table_name.as_str(), &pool).await;
 }
 
+#[sqlx::test]
+async fn builder_schema_drift_policy_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .with_schema_drift_policy(SchemaDriftPolicy::Deny)
+        .try_build()
+        .await
+        .unwrap();
+
+    let _ = sqlx::query(format!("DROP INDEX {}_aggregate_id", store.table_name()).as_str())
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let _: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .without_running_migrations()
+        .with_schema_drift_policy(SchemaDriftPolicy::Log)
+        .try_build()
+        .await
+        .unwrap();
+
+    let result = PgStoreBuilder::<TestAggregate>::new(pool.clone())
+        .without_running_migrations()
+        .with_schema_drift_policy(SchemaDriftPolicy::Deny)
+        .try_build()
+        .await;
+
+    match result {
+        Err(sqlx::Error::Configuration(error)) => {
+            let error = error.downcast_ref::<SchemaDriftError>().unwrap();
+            assert_eq!(error.mismatches.len(), 1);
+        }
+        _ => panic!("Expected a schema drift error"),
+    }
+}
+
+#[sqlx::test]
+async fn builder_payload_index_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .with_payload_index()
+        .with_schema_drift_policy(SchemaDriftPolicy::Deny)
+        .try_build()
+        .await
+        .unwrap();
+
+    let rows = sqlx::query("SELECT indexname FROM pg_indexes WHERE indexname = $1")
+        .bind(format!("{}_payload", store.table_name()))
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+
+    assert_eq!(rows.len(), 1);
+}
+
+#[sqlx::test]
+async fn builder_migration_steps_test(pool: Pool<Postgres>) {
+    let builder = PgStoreBuilder::<TestAggregate>::new(pool.clone())
+        .with_deletion_strategy(DeletionStrategy::Soft)
+        .with_idempotency_tokens()
+        .without_running_migrations();
+
+    for step in builder.migration_steps() {
+        let _ = sqlx::raw_sql(step.sql().as_str()).execute(&pool).await.unwrap();
+    }
+
+    let store: PgStore<TestAggregate> = builder.try_build().await.unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let store_events: Vec<StoreEvent<TestEvent>> = store
+        .persist_idempotent(&mut aggregate_state, vec![TestEvent { add: 1 }], Uuid::new_v4())
+        .await
+        .unwrap();
+
+    assert_eq!(store_events.len(), 1);
+}
+
+#[sqlx::test]
+async fn builder_database_occurred_on_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .with_occurred_on_strategy(OccurredOnStrategy::Database)
+        .try_build()
+        .await
+        .unwrap();
+
+    let before: DateTime<Utc> = sqlx::query_scalar("SELECT now()").fetch_one(&pool).await.unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let store_events: Vec<StoreEvent<TestEvent>> = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 1 }])
+        .await
+        .unwrap();
+
+    let after: DateTime<Utc> = sqlx::query_scalar("SELECT now()").fetch_one(&pool).await.unwrap();
+
+    let persisted: Vec<StoreEvent<TestEvent>> = store.by_aggregate_id(*aggregate_state.id()).await.unwrap();
+    assert_eq!(persisted.len(), 1);
+    assert_eq!(persisted[0].occurred_on, store_events[0].occurred_on);
+    assert!(persisted[0].occurred_on >= before);
+    assert!(persisted[0].occurred_on <= after);
+}
+
+#[sqlx::test]
+async fn builder_clamped_occurred_on_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .with_occurred_on_strategy(OccurredOnStrategy::Clamped)
+        .try_build()
+        .await
+        .unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let _ = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 1 }])
+        .await
+        .unwrap();
+
+    // Simulates an event persisted by a replica whose clock is ahead.
+    let future: DateTime<Utc> = sqlx::query_scalar(
+        format!(
+            "UPDATE {} SET occurred_on = $1 RETURNING occurred_on",
+            store.table_name()
+        )
+        .as_str(),
+    )
+    .bind(Utc::now() + Duration::hours(1))
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+
+    let store_events: Vec<StoreEvent<TestEvent>> = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 1 }])
+        .await
+        .unwrap();
+
+    assert!(store_events[0].occurred_on >= future);
+}
+
+#[sqlx::test]
+async fn builder_shared_table_test(pool: Pool<Postgres>) {
+    // Building twice checks that the migrations are idempotent.
+    for _ in 0..2 {
+        let _: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+            .with_shared_table("shared_events")
+            .with_schema_drift_policy(SchemaDriftPolicy::Deny)
+            .try_build()
+            .await
+            .unwrap();
+    }
+
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .with_shared_table("shared_events")
+        .try_build()
+        .await
+        .unwrap();
+
+    // The view exposes the columns of the shared table, listed explicitly.
+    let columns: Vec<String> = sqlx::query_scalar(
+        "SELECT column_name::text FROM information_schema.columns WHERE table_name = $1 ORDER BY ordinal_position",
+    )
+    .bind(store.table_name())
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        columns,
+        vec![
+            "id",
+            "aggregate_type",
+            "aggregate_id",
+            "payload",
+            "occurred_on",
+            "sequence_number",
+            "version",
+            "metadata"
+        ]
+    );
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let aggregate_id: Uuid = *aggregate_state.id();
+    let _ = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 1 }, TestEvent { add: 2 }])
+        .await
+        .unwrap();
+
+    let store_events: Vec<StoreEvent<TestEvent>> = store.by_aggregate_id(aggregate_id).await.unwrap();
+    assert_eq!(store_events.len(), 2);
+
+    let aggregate_types: Vec<String> =
+        sqlx::query_scalar("SELECT aggregate_type FROM shared_events WHERE aggregate_id = $1")
+            .bind(aggregate_id)
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    assert_eq!(aggregate_types, vec![TestAggregate::NAME, TestAggregate::NAME]);
+
+    store.delete(aggregate_id).await.unwrap();
+    assert!(store.by_aggregate_id(aggregate_id).await.unwrap().is_empty());
+
+    // Options adding columns to the event store table are rejected.
+    let result = PgStoreBuilder::<TestAggregate>::new(pool.clone())
+        .with_shared_table("shared_events")
+        .with_idempotency_tokens()
+        .try_build()
+        .await;
+    assert!(matches!(result, Err(sqlx::Error::Configuration(_))));
+
+    // So are the ones adding tables referencing it.
+    let result = PgStoreBuilder::<TestAggregate>::new(pool)
+        .with_shared_table("shared_events")
+        .with_visibility(TestVisibility)
+        .try_build()
+        .await;
+    assert!(matches!(result, Err(sqlx::Error::Configuration(_))));
+}
+
+struct TestVisibility;
+
+impl Visibility<TestEvent> for TestVisibility {
+    fn visible_at(&self, _event: &TestEvent) -> Option<DateTime<Utc>> {
+        None
+    }
+}
+
+#[sqlx::test]
+async fn builder_table_naming_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .with_table_name("legacy_test_log")
+        .try_build()
+        .await
+        .unwrap();
+
+    assert_eq!(store.table_name(), "legacy_test_log");
+    assert!(table_exists("legacy_test_log", &pool).await);
+
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .with_table_naming_strategy(|aggregate_name: &str| format!("es_{}", aggregate_name))
+        .try_build()
+        .await
+        .unwrap();
+
+    assert_eq!(store.table_name(), "es_test");
+    assert!(table_exists("es_test", &pool).await);
+
+    let _ = sqlx::query("CREATE SCHEMA IF NOT EXISTS event_store")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    // Building twice checks that the migrations are idempotent, and the drift check schema aware.
+    for _ in 0..2 {
+        let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+            .with_database_schema("event_store")
+            .with_idempotency_tokens()
+            .with_schema_drift_policy(SchemaDriftPolicy::Deny)
+            .try_build()
+            .await
+            .unwrap();
+
+        assert_eq!(store.table_name(), "event_store.test_events");
+    }
+
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .with_database_schema("event_store")
+        .try_build()
+        .await
+        .unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let aggregate_id: Uuid = *aggregate_state.id();
+    let _ = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 1 }])
+        .await
+        .unwrap();
+
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM event_store.test_events WHERE aggregate_id = $1")
+        .bind(aggregate_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(count, 1);
+
+    let public_tables: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM information_schema.tables WHERE table_schema = 'public' AND table_name = $1",
+    )
+    .bind(format!("{}_events", TestAggregate::NAME))
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(public_tables, 0);
+}
+
+#[sqlx::test]
+async fn builder_rename_outbox_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .with_table_name("old_test_events")
+        .with_outbox()
+        .try_build()
+        .await
+        .unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let _ = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 1 }])
+        .await
+        .unwrap();
+
+    Migrations::run_rename(&pool, "old_test_events", "new_test_events", &[])
+        .await
+        .unwrap();
+
+    // The outbox follows the event store table, along with its pending rows and its indexes.
+    assert!(!table_exists("old_test_events_outbox", &pool).await);
+    for relation in ["new_test_events_outbox_pkey", "new_test_events_outbox_next_attempt_at"] {
+        let exists: bool = sqlx::query_scalar("SELECT to_regclass($1) IS NOT NULL")
+            .bind(relation)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert!(exists, "{} not renamed", relation);
+    }
+
+    let pending: i64 = sqlx::query_scalar("SELECT count(*) FROM new_test_events_outbox")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(pending, 1);
+}
+
+#[sqlx::test]
+async fn builder_renamed_from_test(pool: Pool<Postgres>) {
+    let old_store: PgStore<OtherAggregate> = PgStoreBuilder::new(pool.clone())
+        .with_lock_strategy(LockStrategy::RowLevel)
+        .try_build()
+        .await
+        .unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let aggregate_id: Uuid = *aggregate_state.id();
+    let _ = old_store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 1 }, TestEvent { add: 2 }])
+        .await
+        .unwrap();
+    let old_table_name: String = old_store.table_name().to_string();
+
+    // The populated table of the old aggregate name is renamed after the new one, indexes included,
+    // so that no schema drift is detected.
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .renamed_from(OtherAggregate::NAME)
+        .with_lock_strategy(LockStrategy::RowLevel)
+        .with_schema_drift_policy(SchemaDriftPolicy::Deny)
+        .try_build()
+        .await
+        .unwrap();
+
+    assert!(!table_exists(old_table_name.as_str(), &pool).await);
+    assert!(!table_exists(format!("{}_locks", old_table_name).as_str(), &pool).await);
+
+    let adds: Vec<i32> = store
+        .by_aggregate_id(aggregate_id)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|store_event| store_event.payload.add)
+        .collect();
+    assert_eq!(adds, vec![1, 2]);
+
+    // The renamed store keeps working, and building it again renames nothing.
+    let _ = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 3 }])
+        .await
+        .unwrap();
+
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .renamed_from(OtherAggregate::NAME)
+        .with_lock_strategy(LockStrategy::RowLevel)
+        .try_build()
+        .await
+        .unwrap();
+    assert_eq!(store.by_aggregate_id(aggregate_id).await.unwrap().len(), 3);
+}
+
 async fn table_exists(table_name: &str, pool: &Pool<Postgres>) -> bool {
     !sqlx::query("SELECT table_name FROM information_schema.columns WHERE table_name = $1")
         .bind(table_name)
@@ -0,0 +1,158 @@
+use std::any::Any;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+use esrs::manager::{AggregateManager, BusCommand, CommandBus, CommandBusError, CommandBusMiddleware};
+use esrs::store::postgres::{PgStore, PgStoreBuilder};
+use esrs::Aggregate;
+
+struct CounterAggregate;
+
+#[derive(Default)]
+struct CounterState {
+    total: i32,
+}
+
+enum CounterCommand {
+    Increment { id: Uuid, by: i32 },
+}
+
+impl BusCommand for CounterCommand {
+    fn aggregate_id(&self) -> Uuid {
+        match self {
+            Self::Increment { id, .. } => *id,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct CounterEvent {
+    by: i32,
+}
+
+#[cfg(feature = "upcasting")]
+impl esrs::event::Upcaster for CounterEvent {}
+
+#[derive(Debug, thiserror::Error)]
+enum CounterError {
+    #[error("A counter can only be incremented")]
+    NotPositive,
+}
+
+impl Aggregate for CounterAggregate {
+    const NAME: &'static str = "counter";
+    type State = CounterState;
+    type Command = CounterCommand;
+    type Event = CounterEvent;
+    type Error = CounterError;
+
+    fn handle_command(_state: &Self::State, command: Self::Command) -> Result<Vec<Self::Event>, Self::Error> {
+        match command {
+            CounterCommand::Increment { by, .. } if by <= 0 => Err(CounterError::NotPositive),
+            CounterCommand::Increment { by, .. } => Ok(vec![CounterEvent { by }]),
+        }
+    }
+
+    fn apply_event(state: Self::State, payload: Self::Event) -> Self::State {
+        Self::State {
+            total: state.total + payload.by,
+        }
+    }
+}
+
+/// A command no aggregate manager is registered for.
+struct UnregisteredCommand(Uuid);
+
+impl BusCommand for UnregisteredCommand {
+    fn aggregate_id(&self) -> Uuid {
+        self.0
+    }
+}
+
+/// Records the outcome of every command, rejecting the commands incrementing by more than 100.
+#[derive(Clone, Default)]
+struct RecordingMiddleware {
+    outcomes: Arc<Mutex<Vec<(Uuid, bool)>>>,
+}
+
+#[async_trait]
+impl CommandBusMiddleware for RecordingMiddleware {
+    async fn before(&self, _aggregate_id: Uuid, command: &(dyn Any + Send + Sync)) -> Result<(), CommandBusError> {
+        match command.downcast_ref::<CounterCommand>() {
+            Some(CounterCommand::Increment { by, .. }) if *by > 100 => {
+                Err(CommandBusError::Middleware("increment too large".into()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    async fn after(&self, aggregate_id: Uuid, result: &Result<(), CommandBusError>) {
+        self.outcomes.lock().unwrap().push((aggregate_id, result.is_ok()));
+    }
+}
+
+#[sqlx::test]
+async fn command_bus_test(pool: Pool<Postgres>) {
+    let store: PgStore<CounterAggregate> = PgStoreBuilder::new(pool).try_build().await.unwrap();
+    let manager: AggregateManager<PgStore<CounterAggregate>> = AggregateManager::new(store.clone());
+
+    let middleware: RecordingMiddleware = RecordingMiddleware::default();
+    let command_bus: CommandBus = CommandBus::new()
+        .register(AggregateManager::new(store))
+        .add_middleware(middleware.clone());
+
+    let aggregate_id: Uuid = Uuid::new_v4();
+
+    // The first command creates the addressed aggregate instance, the following ones load it.
+    command_bus
+        .send(CounterCommand::Increment {
+            id: aggregate_id,
+            by: 1,
+        })
+        .await
+        .unwrap();
+    command_bus
+        .send(CounterCommand::Increment {
+            id: aggregate_id,
+            by: 2,
+        })
+        .await
+        .unwrap();
+
+    let aggregate_state = manager.load(aggregate_id).await.unwrap().unwrap();
+    assert_eq!(*aggregate_state.id(), aggregate_id);
+    assert_eq!(aggregate_state.inner().total, 3);
+
+    // Denied and rejected commands persist nothing.
+    let denied = command_bus
+        .send(CounterCommand::Increment {
+            id: aggregate_id,
+            by: 0,
+        })
+        .await;
+    assert!(matches!(denied, Err(CommandBusError::Domain(_))));
+
+    let rejected = command_bus
+        .send(CounterCommand::Increment {
+            id: aggregate_id,
+            by: 1000,
+        })
+        .await;
+    assert!(matches!(rejected, Err(CommandBusError::Middleware(_))));
+
+    let aggregate_state = manager.load(aggregate_id).await.unwrap().unwrap();
+    assert_eq!(aggregate_state.inner().total, 3);
+
+    // Commands rejected before being handled don't reach the `after` hook.
+    assert_eq!(
+        *middleware.outcomes.lock().unwrap(),
+        vec![(aggregate_id, true), (aggregate_id, true), (aggregate_id, false)]
+    );
+
+    let unregistered = command_bus.send(UnregisteredCommand(aggregate_id)).await;
+    assert!(matches!(unregistered, Err(CommandBusError::NotRegistered(_))));
+}
@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Postgres};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+use uuid::Uuid;
+
+use esrs::store::postgres::analysis::EventTypeLocation;
+use esrs::store::postgres::{PgStore, PgStoreBuilder};
+use esrs::store::{EventStore, StoreEvent};
+use esrs::{Aggregate, AggregateState};
+
+use crate::aggregate::{TestAggregate, TestAggregateState, TestEvent};
+
+/// Records the fields of every `tracing` event, the message included.
+#[derive(Clone, Default)]
+struct RecordingSubscriber {
+    events: Arc<Mutex<Vec<HashMap<String, String>>>>,
+}
+
+impl RecordingSubscriber {
+    fn logged_events(&self) -> Vec<HashMap<String, String>> {
+        self.events
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|fields| fields.get("message").map(String::as_str) == Some("event persisted"))
+            .cloned()
+            .collect()
+    }
+}
+
+struct FieldsVisitor<'a>(&'a mut HashMap<String, String>);
+
+impl Visit for FieldsVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        let _ = self.0.insert(field.name().to_string(), value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        let _ = self.0.insert(field.name().to_string(), format!("{:?}", value));
+    }
+}
+
+impl Subscriber for RecordingSubscriber {
+    fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, _span: &Attributes<'_>) -> Id {
+        Id::from_u64(1)
+    }
+
+    fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut fields: HashMap<String, String> = HashMap::new();
+        event.record(&mut FieldsVisitor(&mut fields));
+        self.events.lock().unwrap().push(fields);
+    }
+
+    fn enter(&self, _span: &Id) {}
+
+    fn exit(&self, _span: &Id) {}
+}
+
+struct TaggedAggregate;
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type")]
+enum TaggedEvent {
+    Opened,
+    Deposited { amount: i32 },
+}
+
+#[cfg(feature = "upcasting")]
+impl esrs::event::Upcaster for TaggedEvent {}
+
+impl Aggregate for TaggedAggregate {
+    const NAME: &'static str = "tagged";
+    type State = ();
+    type Command = ();
+    type Event = TaggedEvent;
+    type Error = std::convert::Infallible;
+
+    fn handle_command(_state: &Self::State, _command: Self::Command) -> Result<Vec<Self::Event>, Self::Error> {
+        Ok(vec![])
+    }
+
+    fn apply_event(state: Self::State, _payload: Self::Event) -> Self::State {
+        state
+    }
+}
+
+#[sqlx::test]
+async fn event_log_test(pool: Pool<Postgres>) {
+    let subscriber: RecordingSubscriber = RecordingSubscriber::default();
+    let _guard = tracing::subscriber::set_default(subscriber.clone());
+
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .with_event_log(EventTypeLocation::ExternallyTagged)
+        .try_build()
+        .await
+        .unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let aggregate_id: Uuid = *aggregate_state.id();
+    let store_events: Vec<StoreEvent<TestEvent>> = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 1 }, TestEvent { add: 2 }])
+        .await
+        .unwrap();
+
+    // A structured log line is emitted for each persisted event.
+    let logged_events: Vec<HashMap<String, String>> = subscriber.logged_events();
+    assert_eq!(logged_events.len(), 2);
+
+    for (fields, store_event) in logged_events.iter().zip(&store_events) {
+        assert_eq!(fields["aggregate_name"], TestAggregate::NAME);
+        assert_eq!(fields["aggregate_id"], aggregate_id.to_string());
+        assert_eq!(fields["event_id"], store_event.id.to_string());
+        assert_eq!(fields["event_type"], "add");
+        assert_eq!(fields["sequence_number"], store_event.sequence_number.to_string());
+        assert!(fields["latency_ms"].parse::<i64>().is_ok());
+    }
+}
+
+#[sqlx::test]
+async fn event_log_internally_tagged_test(pool: Pool<Postgres>) {
+    let subscriber: RecordingSubscriber = RecordingSubscriber::default();
+    let _guard = tracing::subscriber::set_default(subscriber.clone());
+
+    let store: PgStore<TaggedAggregate> = PgStoreBuilder::new(pool.clone())
+        .with_event_log(EventTypeLocation::InternallyTagged("type"))
+        .try_build()
+        .await
+        .unwrap();
+
+    let mut aggregate_state: AggregateState<()> = AggregateState::new();
+    let _ = store
+        .persist(
+            &mut aggregate_state,
+            vec![TaggedEvent::Opened, TaggedEvent::Deposited { amount: 10 }],
+        )
+        .await
+        .unwrap();
+
+    let event_types: Vec<String> = subscriber
+        .logged_events()
+        .iter()
+        .map(|fields| fields["event_type"].clone())
+        .collect();
+    assert_eq!(event_types, vec!["Opened", "Deposited"]);
+}
@@ -0,0 +1,102 @@
+use std::sync::{Arc, Mutex};
+
+use chrono::{Duration, Utc};
+use futures::FutureExt;
+use sqlx::{PgConnection, Pool, Postgres};
+use uuid::Uuid;
+
+use esrs::handler::EventHandler;
+use esrs::inbox::{DeduplicatingConsumer, Inbox};
+use esrs::store::postgres::{PgStore, PgStoreBuilder};
+use esrs::store::{EventStore, StoreEvent};
+use esrs::AggregateState;
+
+use crate::aggregate::{TestAggregate, TestAggregateState, TestEvent, TestEventHandler};
+
+#[sqlx::test]
+async fn inbox_test(pool: Pool<Postgres>) {
+    let inbox: Inbox = Inbox::new(pool.clone(), "test");
+    inbox.setup().await.unwrap();
+
+    let _ = sqlx::query("CREATE TABLE side_effects (event_id uuid NOT NULL)")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    // The event is processed once per consumer.
+    let event_id: Uuid = Uuid::new_v4();
+    for (consumer, expected) in [("consumer", Some(())), ("consumer", None), ("other", Some(()))] {
+        let processed: Option<()> = inbox
+            .process(consumer, event_id, |connection| {
+                insert_side_effect(connection, event_id).boxed()
+            })
+            .await
+            .unwrap();
+        assert_eq!(processed, expected);
+    }
+
+    // A failed processing is rolled back, and can be retried.
+    let failed_event_id: Uuid = Uuid::new_v4();
+    let result: Result<Option<()>, sqlx::Error> = inbox
+        .process("consumer", failed_event_id, |_| {
+            async { Err(sqlx::Error::RowNotFound) }.boxed()
+        })
+        .await;
+    assert!(result.is_err());
+
+    let processed: Option<()> = inbox
+        .process("consumer", failed_event_id, |connection| {
+            insert_side_effect(connection, failed_event_id).boxed()
+        })
+        .await
+        .unwrap();
+    assert!(processed.is_some());
+
+    let side_effects: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM side_effects")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(side_effects, 3);
+
+    // Pruning forgets the processed events.
+    assert_eq!(inbox.prune(Utc::now() + Duration::minutes(1)).await.unwrap(), 3);
+
+    let processed: Option<()> = inbox
+        .process("consumer", event_id, |connection| {
+            insert_side_effect(connection, event_id).boxed()
+        })
+        .await
+        .unwrap();
+    assert!(processed.is_some());
+}
+
+async fn insert_side_effect(connection: &mut PgConnection, event_id: Uuid) -> Result<(), sqlx::Error> {
+    let _ = sqlx::query("INSERT INTO side_effects (event_id) VALUES ($1)")
+        .bind(event_id)
+        .execute(connection)
+        .await?;
+    Ok(())
+}
+
+#[sqlx::test]
+async fn deduplicating_consumer_test(pool: Pool<Postgres>) {
+    let inbox: Inbox = Inbox::new(pool.clone(), "test");
+    inbox.setup().await.unwrap();
+
+    let total: Arc<Mutex<i32>> = Arc::new(Mutex::new(0));
+    let consumer = DeduplicatingConsumer::new(inbox, TestEventHandler { total: total.clone() });
+
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone()).try_build().await.unwrap();
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let store_events: Vec<StoreEvent<TestEvent>> = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 1 }, TestEvent { add: 2 }])
+        .await
+        .unwrap();
+
+    // Redelivered events are handled once.
+    for store_event in store_events.iter().chain(store_events.iter()) {
+        EventHandler::<TestAggregate>::handle(&consumer, store_event).await;
+    }
+
+    assert_eq!(*total.lock().unwrap(), 3);
+}
@@ -0,0 +1,100 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use sqlx::{Pool, Postgres};
+use tokio::sync::oneshot;
+
+use esrs::leader::LeaderElector;
+
+#[sqlx::test]
+async fn leader_elector_single_leader_test(pool: Pool<Postgres>) {
+    let leader: Arc<LeaderElector> =
+        Arc::new(LeaderElector::new(pool.clone(), "singleton").with_renew_interval(Duration::from_millis(50)));
+    let follower: Arc<LeaderElector> =
+        Arc::new(LeaderElector::new(pool.clone(), "singleton").with_renew_interval(Duration::from_millis(50)));
+
+    let (elected_sender, elected_receiver) = oneshot::channel::<()>();
+    let (stop_sender, stop_receiver) = oneshot::channel::<()>();
+
+    let cloned = leader.clone();
+    let leading = tokio::spawn(async move {
+        cloned
+            .run_when_leader(async move {
+                elected_sender.send(()).unwrap();
+                stop_receiver.await.unwrap();
+                "leader"
+            })
+            .await
+    });
+    elected_receiver.await.unwrap();
+
+    // The follower waits for the leadership, that is renewed across many intervals.
+    let cloned = follower.clone();
+    let following = tokio::spawn(async move { cloned.run_when_leader(async { "follower" }).await });
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert!(!following.is_finished());
+    assert!(!leading.is_finished());
+
+    // Once the leader is done, the leadership is released and acquired by the follower.
+    stop_sender.send(()).unwrap();
+    assert_eq!(leading.await.unwrap().unwrap(), "leader");
+
+    let followed = tokio::time::timeout(Duration::from_secs(1), following).await;
+    assert_eq!(followed.unwrap().unwrap().unwrap(), "follower");
+}
+
+#[sqlx::test]
+async fn leader_elector_different_names_test(pool: Pool<Postgres>) {
+    let leader: LeaderElector = LeaderElector::new(pool.clone(), "singleton");
+    let other_leader: LeaderElector = LeaderElector::new(pool.clone(), "other_singleton");
+
+    // Electors of different names don't exclude each other.
+    let result = leader
+        .run_when_leader(async {
+            tokio::time::timeout(Duration::from_secs(1), other_leader.run_when_leader(async { 1 })).await
+        })
+        .await;
+
+    assert_eq!(result.unwrap().unwrap().unwrap(), 1);
+}
+
+#[sqlx::test]
+async fn leader_elector_failover_test(pool: Pool<Postgres>) {
+    let leader: Arc<LeaderElector> =
+        Arc::new(LeaderElector::new(pool.clone(), "singleton").with_renew_interval(Duration::from_millis(50)));
+    let follower: Arc<LeaderElector> =
+        Arc::new(LeaderElector::new(pool.clone(), "singleton").with_renew_interval(Duration::from_millis(50)));
+
+    let (elected_sender, elected_receiver) = oneshot::channel::<()>();
+
+    let cloned = leader.clone();
+    let leading = tokio::spawn(async move {
+        cloned
+            .run_when_leader(async move {
+                elected_sender.send(()).unwrap();
+                futures::future::pending::<()>().await
+            })
+            .await
+    });
+    elected_receiver.await.unwrap();
+
+    let cloned = follower.clone();
+    let following = tokio::spawn(async move { cloned.run_when_leader(async { "follower" }).await });
+
+    // The connection holding the leadership is lost.
+    let _ = sqlx::query(
+        "SELECT pg_terminate_backend(pid) FROM pg_locks \
+        WHERE locktype = 'advisory' AND granted \
+        AND database = (SELECT oid FROM pg_database WHERE datname = current_database())",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    // The leader stops its work at the next renewal, and the follower takes over.
+    let lost = tokio::time::timeout(Duration::from_secs(1), leading).await;
+    assert!(lost.unwrap().unwrap().is_err());
+
+    let followed = tokio::time::timeout(Duration::from_secs(1), following).await;
+    assert_eq!(followed.unwrap().unwrap().unwrap(), "follower");
+}
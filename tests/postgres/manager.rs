@@ -1,13 +1,18 @@
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
-use sqlx::{Pool, Postgres};
+use async_trait::async_trait;
+use sqlx::{PgConnection, Pool, Postgres};
+use uuid::Uuid;
 
-use esrs::manager::AggregateManager;
-use esrs::store::postgres::{PgStore, PgStoreBuilder};
-use esrs::AggregateState;
+use esrs::handler::{EventHandler, TransactionalEventHandler};
+use esrs::manager::{AggregateManager, CommandMiddleware, ConflictError, PointInTime, RetryPolicy};
+use esrs::store::postgres::analysis::EventTypeLocation;
+use esrs::store::postgres::{LockStrategy, PgSnapshotStore, PgStore, PgStoreBuilder, PgStoreError};
+use esrs::store::{EventStore, Metadata, Snapshot, SnapshotStore, StoreEvent};
+use esrs::{AggregateState, AsyncAggregate};
 
-use crate::aggregate::{TestAggregate, TestAggregateState, TestCommand};
+use crate::aggregate::{TestAggregate, TestAggregateState, TestCommand, TestError, TestEvent};
 
 #[sqlx::test]
 async fn handle_command_test(pool: Pool<Postgres>) {
@@ -48,6 +53,146 @@ async fn handle_command_test(pool: Pool<Postgres>) {
     assert_eq!(aggregate_state.sequence_number(), &4);
 }
 
+#[sqlx::test]
+async fn handle_commands_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool).try_build().await.unwrap();
+    let manager: AggregateManager<PgStore<TestAggregate>> = AggregateManager::new(store);
+
+    let aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let aggregate_id = *aggregate_state.id();
+
+    let state: TestAggregateState = manager
+        .handle_commands(
+            aggregate_state,
+            vec![TestCommand::Single, TestCommand::Multi, TestCommand::Single],
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(state.count, 5);
+
+    let aggregate_state = manager.load(aggregate_id).await.unwrap().unwrap();
+    assert_eq!(aggregate_state.inner().count, 5);
+    assert_eq!(aggregate_state.sequence_number(), &4);
+}
+
+#[sqlx::test]
+async fn command_middleware_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool).try_build().await.unwrap();
+    let middleware = RecordingMiddleware::default();
+    let manager: AggregateManager<PgStore<TestAggregate>> =
+        AggregateManager::new(store).with_middleware(middleware.clone());
+
+    let aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let aggregate_id = *aggregate_state.id();
+
+    manager
+        .handle_command(aggregate_state, TestCommand::Multi)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let aggregate_state = manager.load(aggregate_id).await.unwrap().unwrap();
+    manager
+        .handle_commands(aggregate_state, vec![TestCommand::Single, TestCommand::Single])
+        .await
+        .unwrap()
+        .unwrap();
+
+    // Each command is seen along with the state it is handled onto.
+    assert_eq!(*middleware.before.lock().unwrap(), vec![1, 3, 4]);
+    // Each persisting is seen once, with all its events.
+    assert_eq!(*middleware.after.lock().unwrap(), vec![vec![1, 2], vec![3, 4]]);
+}
+
+#[derive(Clone, Default)]
+struct RecordingMiddleware {
+    before: Arc<Mutex<Vec<i32>>>,
+    after: Arc<Mutex<Vec<Vec<i32>>>>,
+}
+
+impl CommandMiddleware<TestAggregate> for RecordingMiddleware {
+    fn before(&self, _aggregate_id: Uuid, state: &TestAggregateState, _command: &TestCommand) -> Result<(), TestError> {
+        self.before.lock().unwrap().push(state.count);
+        Ok(())
+    }
+
+    fn after(&self, _aggregate_id: Uuid, store_events: &[StoreEvent<TestEvent>]) {
+        let sequence_numbers = store_events.iter().map(|event| event.sequence_number).collect();
+        self.after.lock().unwrap().push(sequence_numbers);
+    }
+}
+
+#[sqlx::test]
+async fn handle_async_command_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool).try_build().await.unwrap();
+    let manager: AggregateManager<PgStore<TestAggregate>> = AggregateManager::new(store);
+
+    let aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let aggregate_id = *aggregate_state.id();
+
+    let state: TestAggregateState = manager
+        .handle_async_command(aggregate_state, TestCommand::Multi, &10)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(state.count, 21);
+
+    let aggregate_state = manager.load(aggregate_id).await.unwrap().unwrap();
+    assert_eq!(aggregate_state.inner().count, 21);
+    assert_eq!(aggregate_state.sequence_number(), &2);
+}
+
+/// Handles the commands adding the amount provided by the services.
+#[async_trait]
+impl AsyncAggregate for TestAggregate {
+    type Services = i32;
+
+    async fn handle_command_async(
+        _state: &TestAggregateState,
+        command: TestCommand,
+        services: &i32,
+    ) -> Result<Vec<TestEvent>, TestError> {
+        let add: i32 = *services;
+
+        match command {
+            TestCommand::Single => Ok(vec![TestEvent { add }]),
+            TestCommand::Multi => Ok(vec![TestEvent { add }, TestEvent { add }]),
+        }
+    }
+}
+
+#[sqlx::test]
+async fn handle_command_with_retry_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool).try_build().await.unwrap();
+    let manager: AggregateManager<PgStore<TestAggregate>> = AggregateManager::new(store);
+
+    let aggregate_id = Uuid::new_v4();
+
+    // Two states loaded without the lock: persisting from the stale one is a conflict.
+    let first: AggregateState<TestAggregateState> = AggregateState::with_id(aggregate_id);
+    let second: AggregateState<TestAggregateState> = AggregateState::with_id(aggregate_id);
+
+    manager
+        .handle_command(first, TestCommand::Single)
+        .await
+        .unwrap()
+        .unwrap();
+    let error = manager.handle_command(second, TestCommand::Single).await.unwrap_err();
+    assert!(error.is_conflict());
+
+    // Retrying reloads the state, so the command is handled onto the latest one.
+    let state: TestAggregateState = manager
+        .handle_command_with_retry(aggregate_id, TestCommand::Multi, RetryPolicy::new())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(state.count, 4);
+
+    let aggregate_state = manager.load(aggregate_id).await.unwrap().unwrap();
+    assert_eq!(aggregate_state.sequence_number(), &3);
+}
+
 #[sqlx::test]
 async fn load_aggregate_state_test(pool: Pool<Postgres>) {
     let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool).try_build().await.unwrap();
@@ -71,6 +216,191 @@ async fn load_aggregate_state_test(pool: Pool<Postgres>) {
     assert_eq!(initial_count + 2, aggregate_state.inner().count);
 }
 
+#[sqlx::test]
+async fn replay_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool).try_build().await.unwrap();
+    let manager: AggregateManager<PgStore<TestAggregate>> = AggregateManager::new(store.clone());
+
+    let aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let aggregate_id = *aggregate_state.id();
+
+    manager
+        .handle_command(aggregate_state, TestCommand::Multi)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let store_events: Vec<StoreEvent<TestEvent>> = store.by_aggregate_id(aggregate_id).await.unwrap();
+
+    // Replaying the stored events yields the same state as the manager.
+    let loaded = manager.load(aggregate_id).await.unwrap().unwrap();
+    let replayed: AggregateState<TestAggregateState> =
+        AggregateState::with_id(aggregate_id).replay::<TestAggregate>(store_events.iter().cloned());
+    assert_eq!(loaded.inner().count, replayed.inner().count);
+    assert_eq!(loaded.sequence_number(), replayed.sequence_number());
+
+    // Replaying a prefix of the events yields an intermediate state.
+    let replayed: AggregateState<TestAggregateState> =
+        AggregateState::with_id(aggregate_id).replay::<TestAggregate>(store_events[..1].iter().cloned());
+    assert_eq!(replayed.inner().count, loaded.inner().count - 1);
+    assert_eq!(replayed.sequence_number(), &1);
+}
+
+#[sqlx::test]
+async fn snapshot_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone()).try_build().await.unwrap();
+    let snapshot_store: PgSnapshotStore<TestAggregateState> = PgSnapshotStore::new::<TestAggregate>(pool.clone());
+    snapshot_store.setup().await.unwrap();
+
+    let manager: AggregateManager<PgStore<TestAggregate>> = AggregateManager::new(store.clone())
+        .with_snapshots(PgSnapshotStore::new::<TestAggregate>(pool.clone()), 3)
+        .with_snapshot_version(1);
+
+    let aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let aggregate_id = *aggregate_state.id();
+
+    // Two events: no snapshot yet.
+    manager
+        .handle_command(aggregate_state, TestCommand::Multi)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(snapshot_store.latest(aggregate_id).await.unwrap().is_none());
+
+    // Four events: the snapshot frequency is crossed.
+    let aggregate_state = manager.load(aggregate_id).await.unwrap().unwrap();
+    manager
+        .handle_command(aggregate_state, TestCommand::Multi)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let snapshot: Snapshot<TestAggregateState> = snapshot_store.latest(aggregate_id).await.unwrap().unwrap();
+    assert_eq!(snapshot.sequence_number, 4);
+    assert_eq!(snapshot.version, Some(1));
+    assert_eq!(snapshot.state.count, 5);
+
+    // Five events: loading starts from the snapshot, replaying the following event.
+    let aggregate_state = manager.load(aggregate_id).await.unwrap().unwrap();
+    manager
+        .handle_command(aggregate_state, TestCommand::Single)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let store_events: Vec<StoreEvent<TestEvent>> = store.by_aggregate_id_after(aggregate_id, 4).await.unwrap();
+    assert_eq!(store_events.len(), 1);
+
+    let aggregate_state = manager.load(aggregate_id).await.unwrap().unwrap();
+    assert_eq!(aggregate_state.inner().count, 6);
+    assert_eq!(aggregate_state.sequence_number(), &5);
+
+    // A tampered snapshot proves that loading starts from it, unless its version is outdated.
+    snapshot_store
+        .save(&Snapshot {
+            state: TestAggregateState { count: 100 },
+            sequence_number: 5,
+            ..snapshot
+        })
+        .await
+        .unwrap();
+
+    let aggregate_state = manager.load(aggregate_id).await.unwrap().unwrap();
+    assert_eq!(aggregate_state.inner().count, 100);
+
+    let manager: AggregateManager<PgStore<TestAggregate>> = AggregateManager::new(store)
+        .with_snapshots(PgSnapshotStore::new::<TestAggregate>(pool), 3)
+        .with_snapshot_version(2);
+
+    let aggregate_state = manager.load(aggregate_id).await.unwrap().unwrap();
+    assert_eq!(aggregate_state.inner().count, 6);
+    assert_eq!(aggregate_state.sequence_number(), &5);
+
+    // Deleting the aggregate instance deletes its snapshots.
+    manager.delete(aggregate_id).await.unwrap();
+    assert!(snapshot_store.latest(aggregate_id).await.unwrap().is_none());
+    assert!(manager.load(aggregate_id).await.unwrap().is_none());
+}
+
+#[sqlx::test]
+async fn handle_command_with_metadata_test(pool: Pool<Postgres>) {
+    let handled: Arc<Mutex<Vec<Metadata>>> = Arc::new(Mutex::new(vec![]));
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool)
+        .add_event_handler(MetadataEventHandler {
+            handled: handled.clone(),
+        })
+        .try_build()
+        .await
+        .unwrap();
+    let manager: AggregateManager<PgStore<TestAggregate>> = AggregateManager::new(store.clone());
+
+    let aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let aggregate_id = *aggregate_state.id();
+    let metadata: Metadata = Metadata::default()
+        .with_correlation_id(uuid::Uuid::new_v4())
+        .with_user_id("user")
+        .with_extra("tenant", serde_json::json!("prima"));
+
+    manager
+        .handle_command_with_metadata(aggregate_state, TestCommand::Multi, metadata.clone())
+        .await
+        .unwrap()
+        .unwrap();
+
+    // The metadata is persisted along with every event, and received by the event handlers.
+    let store_events: Vec<StoreEvent<TestEvent>> = store.by_aggregate_id(aggregate_id).await.unwrap();
+    assert_eq!(store_events.len(), 2);
+    assert!(store_events.iter().all(|store_event| store_event.metadata == metadata));
+    assert_eq!(*handled.lock().unwrap(), vec![metadata.clone(), metadata.clone()]);
+
+    // The events caused by an event share its correlation id.
+    let caused: Metadata = Metadata::caused_by(&store_events[0]);
+    assert_eq!(caused.correlation_id, metadata.correlation_id);
+    assert_eq!(caused.causation_id, Some(store_events[0].id));
+    assert_eq!(caused.user_id.as_deref(), Some("user"));
+
+    // Events persisted without metadata have an empty one.
+    let aggregate_state = manager.load(aggregate_id).await.unwrap().unwrap();
+    manager
+        .handle_command(aggregate_state, TestCommand::Single)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let store_events: Vec<StoreEvent<TestEvent>> = store.by_aggregate_id(aggregate_id).await.unwrap();
+    assert!(store_events[2].metadata.is_empty());
+}
+
+struct MetadataEventHandler {
+    handled: Arc<Mutex<Vec<Metadata>>>,
+}
+
+#[async_trait]
+impl EventHandler<TestAggregate> for MetadataEventHandler {
+    async fn handle(&self, event: &StoreEvent<TestEvent>) {
+        self.handled.lock().unwrap().push(event.metadata.clone());
+    }
+}
+
+#[sqlx::test]
+async fn exists_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool).try_build().await.unwrap();
+    let manager: AggregateManager<PgStore<TestAggregate>> = AggregateManager::new(store);
+
+    let aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let aggregate_id = *aggregate_state.id();
+
+    assert!(!manager.exists(aggregate_id).await.unwrap());
+
+    manager
+        .handle_command(aggregate_state, TestCommand::Single)
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert!(manager.exists(aggregate_id).await.unwrap());
+}
+
 #[sqlx::test]
 async fn lock_and_load_aggregate_state_test(pool: Pool<Postgres>) {
     let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool).try_build().await.unwrap();
@@ -141,6 +471,264 @@ async fn lock_and_load_aggregate_state_test_2(pool: Pool<Postgres>) {
     assert_eq!(initial_count + 2, aggregate_state_2.inner().count);
 }
 
+#[sqlx::test]
+async fn lock_and_load_row_level_lock_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool)
+        .with_lock_strategy(LockStrategy::RowLevel)
+        .try_build()
+        .await
+        .unwrap();
+    let manager: Arc<AggregateManager<PgStore<TestAggregate>>> = Arc::new(AggregateManager::new(store));
+
+    let aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let aggregate_id = *aggregate_state.id();
+    let initial_count = aggregate_state.inner().count;
+    manager
+        .handle_command(aggregate_state, TestCommand::Single)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let other_aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let other_aggregate_id = *other_aggregate_state.id();
+    manager
+        .handle_command(other_aggregate_state, TestCommand::Single)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let aggregate_state_1 = manager.lock_and_load(aggregate_id).await.unwrap().unwrap();
+
+    // The second lock_and_load waits for the first lock to be released.
+    let cloned = manager.clone();
+    let pending = tokio::spawn(async move { cloned.lock_and_load(aggregate_id).await.unwrap().unwrap() });
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(!pending.is_finished());
+
+    // Other aggregate instances are not locked.
+    let locked = tokio::time::timeout(Duration::from_secs(1), manager.lock_and_load(other_aggregate_id)).await;
+    assert!(locked.unwrap().unwrap().is_some());
+
+    // The first command is persisted before the lock is released, so the second state sees its events.
+    manager
+        .handle_command(aggregate_state_1, TestCommand::Single)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let aggregate_state_2 = tokio::time::timeout(Duration::from_secs(1), pending)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(*aggregate_state_2.sequence_number(), 2);
+    assert_eq!(aggregate_state_2.inner().count, initial_count + 2);
+}
+
+#[sqlx::test]
+async fn load_shared_and_upgrade_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool).try_build().await.unwrap();
+    let manager: AggregateManager<PgStore<TestAggregate>> = AggregateManager::new(store);
+
+    let aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let aggregate_id = *aggregate_state.id();
+
+    manager
+        .handle_command(aggregate_state, TestCommand::Single)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let shared = manager.load_shared(aggregate_id).await.unwrap().unwrap();
+    assert_eq!(shared.sequence_number(), &1);
+
+    // Events persisted after the shared state was loaded are applied when upgrading it.
+    let aggregate_state = manager.load(aggregate_id).await.unwrap().unwrap();
+    manager
+        .handle_command(aggregate_state, TestCommand::Multi)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let aggregate_state = manager.upgrade(shared.clone()).await.unwrap();
+    assert_eq!(aggregate_state.sequence_number(), &3);
+    assert_eq!(aggregate_state.inner().count, 4);
+    // The shared state is left untouched.
+    assert_eq!(shared.sequence_number(), &1);
+    assert_eq!(shared.inner().count, 2);
+
+    // The upgraded state holds the lock, and is ready to handle commands.
+    manager
+        .handle_command(aggregate_state, TestCommand::Single)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let aggregate_state = manager.load(aggregate_id).await.unwrap().unwrap();
+    assert_eq!(aggregate_state.sequence_number(), &4);
+    assert_eq!(aggregate_state.inner().count, 5);
+}
+
+#[sqlx::test]
+async fn load_at_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool).try_build().await.unwrap();
+    let manager: AggregateManager<PgStore<TestAggregate>> = AggregateManager::new(store.clone());
+
+    let aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let aggregate_id = *aggregate_state.id();
+
+    manager
+        .handle_command(aggregate_state, TestCommand::Multi)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let store_events: Vec<StoreEvent<TestEvent>> = store.by_aggregate_id(aggregate_id).await.unwrap();
+    assert_eq!(store_events.len(), 2);
+
+    let aggregate_state = manager
+        .load_at(aggregate_id, PointInTime::SequenceNumber(1))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(aggregate_state.inner().count, 2);
+    assert_eq!(aggregate_state.sequence_number(), &1);
+
+    let aggregate_state = manager
+        .load_at(aggregate_id, PointInTime::OccurredOn(store_events[1].occurred_on))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(aggregate_state.inner().count, 3);
+    assert_eq!(aggregate_state.sequence_number(), &2);
+
+    assert!(manager
+        .load_at(aggregate_id, PointInTime::SequenceNumber(0))
+        .await
+        .unwrap()
+        .is_none());
+    assert!(manager
+        .load_at(
+            aggregate_id,
+            PointInTime::OccurredOn(store_events[0].occurred_on - chrono::Duration::seconds(1))
+        )
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[sqlx::test]
+async fn load_at_compacted_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool).try_build().await.unwrap();
+    let manager: AggregateManager<PgStore<TestAggregate>> = AggregateManager::new(store.clone());
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let aggregate_id = *aggregate_state.id();
+    let initial_count = aggregate_state.inner().count;
+
+    let _ = store
+        .persist(
+            &mut aggregate_state,
+            vec![TestEvent { add: 1 }, TestEvent { add: 2 }, TestEvent { add: 3 }],
+        )
+        .await
+        .unwrap();
+
+    // Only the event with sequence number 3 is kept, leaving a gap before it.
+    let removed: u64 = store
+        .compact(&EventTypeLocation::ExternallyTagged, &["add"])
+        .await
+        .unwrap();
+    assert_eq!(removed, 2);
+
+    let _ = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 10 }])
+        .await
+        .unwrap();
+
+    let aggregate_state = manager
+        .load_at(aggregate_id, PointInTime::SequenceNumber(3))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(aggregate_state.inner().count, initial_count + 3);
+    assert_eq!(aggregate_state.sequence_number(), &3);
+
+    let aggregate_state = manager
+        .load_at(aggregate_id, PointInTime::SequenceNumber(4))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(aggregate_state.inner().count, initial_count + 13);
+
+    assert!(manager
+        .load_at(aggregate_id, PointInTime::SequenceNumber(2))
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[sqlx::test]
+async fn handle_command_idempotent_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool)
+        .with_idempotency_tokens()
+        .try_build()
+        .await
+        .unwrap();
+    let manager: AggregateManager<PgStore<TestAggregate>> = AggregateManager::new(store.clone());
+
+    let aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let aggregate_id = *aggregate_state.id();
+    let idempotency_key: Uuid = Uuid::new_v4();
+
+    let state = manager
+        .handle_command_idempotent(aggregate_state, TestCommand::Multi, idempotency_key)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(state.count, 3);
+
+    // Redelivered with a stale state, the command is not handled again.
+    let state = manager
+        .handle_command_idempotent(
+            AggregateState::with_id(aggregate_id),
+            TestCommand::Multi,
+            idempotency_key,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(state.count, 3);
+
+    let aggregate_state = manager.load(aggregate_id).await.unwrap().unwrap();
+    let state = manager
+        .handle_command_idempotent(aggregate_state, TestCommand::Multi, idempotency_key)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(state.count, 3);
+    assert_eq!(store.by_aggregate_id(aggregate_id).await.unwrap().len(), 2);
+
+    let aggregate_state = manager.load(aggregate_id).await.unwrap().unwrap();
+    let state = manager
+        .handle_command_idempotent(aggregate_state, TestCommand::Single, Uuid::new_v4())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(state.count, 4);
+
+    // Redelivered after further commands, the returned state includes their events too.
+    let state = manager
+        .handle_command_idempotent(
+            AggregateState::with_id(aggregate_id),
+            TestCommand::Multi,
+            idempotency_key,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(state.count, 4);
+}
+
 #[sqlx::test]
 async fn delete_aggregate_test(pool: Pool<Postgres>) {
     let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool).try_build().await.unwrap();
@@ -169,3 +757,63 @@ async fn delete_aggregate_test(pool: Pool<Postgres>) {
     let aggregate_state = manager.load(initial_id).await.unwrap();
     assert!(aggregate_state.is_none());
 }
+
+#[sqlx::test]
+async fn watch_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool).try_build().await.unwrap();
+    let manager: AggregateManager<PgStore<TestAggregate>> = AggregateManager::new(store);
+
+    let aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let aggregate_id = *aggregate_state.id();
+    let initial_count = aggregate_state.inner().count;
+
+    let mut receiver = manager.watch(aggregate_id).await.unwrap();
+    assert_eq!(receiver.borrow().sequence_number(), &0);
+    assert_eq!(receiver.borrow().inner().count, initial_count);
+
+    manager
+        .handle_command(aggregate_state, TestCommand::Multi)
+        .await
+        .unwrap()
+        .unwrap();
+
+    receiver.changed().await.unwrap();
+    assert_eq!(receiver.borrow().id(), &aggregate_id);
+    assert_eq!(receiver.borrow().sequence_number(), &2);
+    assert_eq!(receiver.borrow().inner().count, initial_count + 2);
+
+    // A new subscription starts from the current state.
+    let receiver = manager.watch(aggregate_id).await.unwrap();
+    assert_eq!(receiver.borrow().sequence_number(), &2);
+}
+
+struct SlowTransactionalEventHandler;
+
+#[async_trait]
+impl TransactionalEventHandler<TestAggregate, PgStoreError, PgConnection> for SlowTransactionalEventHandler {
+    async fn handle(&self, _event: &StoreEvent<TestEvent>, _executor: &mut PgConnection) -> Result<(), PgStoreError> {
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        Ok(())
+    }
+}
+
+#[sqlx::test]
+async fn handle_command_timeout_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool)
+        .add_transactional_event_handler(SlowTransactionalEventHandler)
+        .try_build()
+        .await
+        .unwrap();
+    let manager: AggregateManager<PgStore<TestAggregate>> =
+        AggregateManager::new(store).with_timeout(Duration::from_millis(50));
+
+    let aggregate_id = *AggregateState::<TestAggregateState>::new().id();
+    let aggregate_state = manager.lock_and_load(aggregate_id).await.unwrap().unwrap_or_default();
+
+    let result = manager.handle_command(aggregate_state, TestCommand::Single).await;
+    assert!(matches!(result, Err(PgStoreError::Custom(_))));
+
+    // The events have been rolled back, and the lock released.
+    let locked = tokio::time::timeout(Duration::from_secs(1), manager.lock_and_load(aggregate_id)).await;
+    assert!(!locked.unwrap().unwrap().is_some());
+}
@@ -1,3 +1,14 @@
 mod builder;
+mod command_bus;
+mod event_log;
+mod inbox;
+mod leader;
 mod manager;
 mod pg_store;
+mod process;
+mod projection;
+mod query;
+#[cfg(feature = "rebuilder")]
+mod rebuilder;
+mod scheduler;
+mod subscription;
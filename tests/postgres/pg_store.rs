@@ -1,13 +1,30 @@
 use std::sync::{Arc, Mutex, MutexGuard};
 
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
 use sqlx::{Pool, Postgres};
 use uuid::Uuid;
 
-use esrs::store::postgres::{PgStore, PgStoreBuilder, PgStoreError};
-use esrs::store::{EventStore, StoreEvent};
+use esrs::bus::{EventBus, PublishPolicy};
+use esrs::handler::EventHandler;
+use esrs::interceptor::PersistInterceptor;
+use esrs::store::postgres::analysis::{
+    AggregateCount, DailyCount, DeadEventsReport, EventTypeCount, EventTypeLocation, EventTypeUsage, Statistics,
+    VersionCount,
+};
+use esrs::store::postgres::{
+    AuditHook, BackfillConfig, BackfillReport, Column, ColumnType, ColumnValue, Compaction, CorrectionKind,
+    CustomColumns, DebeziumOutbox, DeletionStrategy, DeterministicEventIdGenerator, EventCorrection, EventIdGenerator,
+    GlobalEvent, GlobalEventStream, OutboxRelay, PgDeadLetterTable, PgStore, PgStoreBuilder, PgStoreError,
+    RawStoreEvent, Redactor, RekeyMode, Schema, UnitOfWork, ValidTime, Visibility,
+};
+use esrs::store::{EventStore, Metadata, Since, StoreEvent};
+use esrs::types::SequenceNumber;
 use esrs::{Aggregate, AggregateState};
 
-use crate::aggregate::{TestAggregate, TestAggregateState, TestEvent, TestEventHandler, TestTransactionalEventHandler};
+use crate::aggregate::{
+    OtherAggregate, TestAggregate, TestAggregateState, TestEvent, TestEventHandler, TestTransactionalEventHandler,
+};
 
 #[sqlx::test]
 async fn setup_database_test(pool: Pool<Postgres>) {
@@ -140,6 +157,69 @@ async fn persist_multiple_events_test(pool: Pool<Postgres>) {
     assert_eq!(store_events.len(), 2);
 }
 
+#[sqlx::test]
+async fn persist_returns_inserted_rows_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone()).try_build().await.unwrap();
+    let custom_columns_store: PgStore<OtherAggregate> = PgStoreBuilder::new(pool.clone())
+        .with_custom_columns(TestCustomColumns)
+        .try_build()
+        .await
+        .unwrap();
+
+    let mut aggregate_state = AggregateState::new();
+    let persisted: Vec<StoreEvent<TestEvent>> = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 1 }, TestEvent { add: 2 }])
+        .await
+        .unwrap();
+    let loaded: Vec<StoreEvent<TestEvent>> = store.by_aggregate_id(*aggregate_state.id()).await.unwrap();
+    assert_same_store_events(&persisted, &loaded);
+
+    let mut aggregate_state = AggregateState::new();
+    let persisted: Vec<StoreEvent<TestEvent>> = custom_columns_store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 1 }, TestEvent { add: 2 }])
+        .await
+        .unwrap();
+    let loaded: Vec<StoreEvent<TestEvent>> = custom_columns_store
+        .by_aggregate_id(*aggregate_state.id())
+        .await
+        .unwrap();
+    assert_same_store_events(&persisted, &loaded);
+}
+
+/// The persisted events are built from the inserted rows: they match the loaded ones exactly,
+/// `occurred_on` included, despite the database truncating timestamps to microseconds.
+fn assert_same_store_events(persisted: &[StoreEvent<TestEvent>], loaded: &[StoreEvent<TestEvent>]) {
+    assert_eq!(persisted.len(), loaded.len());
+
+    for (persisted, loaded) in persisted.iter().zip(loaded) {
+        assert_eq!(persisted.id, loaded.id);
+        assert_eq!(persisted.aggregate_id, loaded.aggregate_id);
+        assert_eq!(persisted.payload.add, loaded.payload.add);
+        assert_eq!(persisted.occurred_on, loaded.occurred_on);
+        assert_eq!(persisted.sequence_number, loaded.sequence_number);
+        assert_eq!(persisted.version, loaded.version);
+    }
+}
+
+#[sqlx::test]
+async fn persist_moves_events_into_store_events_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone()).try_build().await.unwrap();
+
+    let mut aggregate_state = AggregateState::new();
+    let events: Vec<TestEvent> = vec![TestEvent { add: 1 }, TestEvent { add: 2 }];
+
+    // The events are needed afterwards: they are given back by `persist`, with no clone beforehand.
+    let events: Vec<TestEvent> = EventStore::persist(&store, &mut aggregate_state, events)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|store_event| store_event.payload)
+        .collect();
+
+    assert_eq!(events.iter().map(|event| event.add).collect::<Vec<i32>>(), vec![1, 2]);
+    assert_eq!(*aggregate_state.sequence_number(), 2);
+}
+
 #[sqlx::test]
 async fn event_handling_test(pool: Pool<Postgres>) {
     let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
@@ -232,6 +312,2106 @@ async fn event_handler_test(pool: Pool<Postgres>) {
     assert_eq!(*guard, 101);
 }
 
+#[sqlx::test]
+async fn custom_columns_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .with_custom_columns(TestCustomColumns)
+        .try_build()
+        .await
+        .unwrap();
+
+    let rows = sqlx::query("SELECT indexname FROM pg_indexes WHERE tablename = $1")
+        .bind(store.table_name())
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+
+    // primary key, aggregate_id, aggregate_id-sequence_number, doubled
+    assert_eq!(rows.len(), 4);
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let aggregate_id: Uuid = *aggregate_state.id();
+
+    let _store_events: Vec<StoreEvent<TestEvent>> = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 21 }])
+        .await
+        .unwrap();
+
+    let query: String = format!("SELECT doubled FROM {} WHERE aggregate_id = $1", store.table_name());
+    let doubled: Option<i64> = sqlx::query_scalar(query.as_str())
+        .bind(aggregate_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+    assert_eq!(doubled, Some(42));
+}
+
+#[sqlx::test]
+async fn backfill_custom_columns_test(pool: Pool<Postgres>) {
+    // The events are written before the custom columns are introduced.
+    let legacy_store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone()).try_build().await.unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let _ = legacy_store
+        .persist(&mut aggregate_state, (1..=5).map(|add| TestEvent { add }).collect())
+        .await
+        .unwrap();
+
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .with_custom_columns(TestCustomColumns)
+        .try_build()
+        .await
+        .unwrap();
+
+    let query: String = format!("SELECT doubled FROM {} ORDER BY sequence_number", store.table_name());
+    let doubled: Vec<Option<i64>> = sqlx::query_scalar(query.as_str()).fetch_all(&pool).await.unwrap();
+    assert_eq!(doubled, vec![None; 5]);
+
+    let report: BackfillReport = store
+        .backfill(&TestCustomColumns, BackfillConfig::builder().batch_size(2).build())
+        .await
+        .unwrap();
+    assert_eq!(report, BackfillReport { rows: 5, batches: 3 });
+
+    let doubled: Vec<Option<i64>> = sqlx::query_scalar(query.as_str()).fetch_all(&pool).await.unwrap();
+    assert_eq!(doubled, vec![Some(2), Some(4), Some(6), Some(8), Some(10)]);
+
+    // Running it again is safe.
+    let report: BackfillReport = store
+        .backfill(&TestCustomColumns, BackfillConfig::builder().build())
+        .await
+        .unwrap();
+    assert_eq!(report, BackfillReport { rows: 5, batches: 1 });
+
+    let doubled: Vec<Option<i64>> = sqlx::query_scalar(query.as_str()).fetch_all(&pool).await.unwrap();
+    assert_eq!(doubled, vec![Some(2), Some(4), Some(6), Some(8), Some(10)]);
+}
+
+struct TestCustomColumns;
+
+impl CustomColumns<TestEvent> for TestCustomColumns {
+    fn columns(&self) -> Vec<Column> {
+        vec![Column::new("doubled", ColumnType::BigInt).indexed()]
+    }
+
+    fn values(&self, event: &TestEvent) -> Vec<ColumnValue> {
+        vec![ColumnValue::BigInt(Some(event.add as i64 * 2))]
+    }
+}
+
+#[sqlx::test]
+async fn search_events_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .with_search_fields(&["add"])
+        .try_build()
+        .await
+        .unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let _ = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 41 }, TestEvent { add: 42 }])
+        .await
+        .unwrap();
+
+    let store_events: Vec<StoreEvent<TestEvent>> = store.search_events("42", 10).await.unwrap();
+
+    assert_eq!(store_events.len(), 1);
+    assert_eq!(store_events[0].payload.add, 42);
+}
+
+struct TestVisibility;
+
+impl Visibility<TestEvent> for TestVisibility {
+    fn visible_at(&self, event: &TestEvent) -> Option<DateTime<Utc>> {
+        (event.add > 10).then(|| Utc::now() + chrono::Duration::hours(1))
+    }
+}
+
+#[sqlx::test]
+async fn deferred_events_test(pool: Pool<Postgres>) {
+    let total: Arc<Mutex<i32>> = Arc::new(Mutex::new(0));
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .add_event_handler(TestEventHandler { total: total.clone() })
+        .with_visibility(TestVisibility)
+        .try_build()
+        .await
+        .unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let store_events: Vec<StoreEvent<TestEvent>> = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 1 }, TestEvent { add: 100 }])
+        .await
+        .unwrap();
+
+    assert_eq!(store_events.len(), 2);
+    assert_eq!(*total.lock().unwrap(), 1);
+    assert_eq!(store.release_deferred_events(10).await.unwrap(), 0);
+
+    let _ = sqlx::query(format!("UPDATE {}_deferred SET visible_at = now()", store.table_name()).as_str())
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    assert_eq!(store.release_deferred_events(10).await.unwrap(), 1);
+    assert_eq!(*total.lock().unwrap(), 101);
+    assert_eq!(store.release_deferred_events(10).await.unwrap(), 0);
+}
+
+/// Schema skipping the events adding 100, as if they were deprecated.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SkippingSchema {
+    add: i32,
+}
+
+#[cfg(feature = "upcasting")]
+impl esrs::event::Upcaster for SkippingSchema {}
+
+impl Schema<TestEvent> for SkippingSchema {
+    fn from_event(event: TestEvent) -> Self {
+        Self { add: event.add }
+    }
+
+    fn to_event(self) -> Option<TestEvent> {
+        (self.add != 100).then_some(TestEvent { add: self.add })
+    }
+}
+
+#[sqlx::test]
+async fn deferred_skipped_events_test(pool: Pool<Postgres>) {
+    // The events are written before their schema deprecates some of them.
+    let legacy_store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .with_visibility(TestVisibility)
+        .try_build()
+        .await
+        .unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let _ = legacy_store
+        .persist(
+            &mut aggregate_state,
+            vec![TestEvent { add: 1 }, TestEvent { add: 100 }, TestEvent { add: 20 }],
+        )
+        .await
+        .unwrap();
+
+    let total: Arc<Mutex<i32>> = Arc::new(Mutex::new(0));
+    let store: PgStore<TestAggregate, SkippingSchema> = PgStoreBuilder::new(pool.clone())
+        .add_event_handler(TestEventHandler { total: total.clone() })
+        .with_visibility(TestVisibility)
+        .with_schema::<SkippingSchema>()
+        .try_build()
+        .await
+        .unwrap();
+
+    let _ = sqlx::query(format!("UPDATE {}_deferred SET visible_at = now()", store.table_name()).as_str())
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    assert_eq!(store.release_deferred_events(10).await.unwrap(), 2);
+    assert_eq!(*total.lock().unwrap(), 20);
+
+    let deferred: Vec<i32> = sqlx::query_scalar(
+        format!(
+            "SELECT (events.payload->>'add')::int FROM {0}_deferred AS deferred JOIN {0} AS events ON events.id = deferred.event_id",
+            store.table_name()
+        )
+        .as_str(),
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+
+    assert_eq!(deferred, vec![20]);
+    assert_eq!(store.release_deferred_events(10).await.unwrap(), 0);
+}
+
+struct TestEventBus {
+    published: Arc<Mutex<Vec<Uuid>>>,
+}
+
+#[async_trait::async_trait]
+impl EventBus<TestAggregate> for TestEventBus {
+    async fn publish(&self, store_event: &StoreEvent<TestEvent>) {
+        self.published.lock().unwrap().push(store_event.id);
+    }
+}
+
+#[sqlx::test]
+async fn add_event_bus_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone()).try_build().await.unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let _ = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 1 }])
+        .await
+        .unwrap();
+
+    let published: Arc<Mutex<Vec<Uuid>>> = Arc::new(Mutex::new(vec![]));
+    store
+        .add_event_bus(TestEventBus {
+            published: published.clone(),
+        })
+        .await;
+
+    let store_events: Vec<StoreEvent<TestEvent>> = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 2 }])
+        .await
+        .unwrap();
+
+    assert_eq!(*published.lock().unwrap(), vec![store_events[0].id]);
+}
+
+/// Event bus failing to publish until told otherwise.
+struct FlakyEventBus {
+    available: Arc<Mutex<bool>>,
+    published: Arc<Mutex<Vec<Uuid>>>,
+}
+
+#[async_trait::async_trait]
+impl EventBus<TestAggregate> for FlakyEventBus {
+    async fn publish(&self, _store_event: &StoreEvent<TestEvent>) {}
+
+    async fn try_publish(
+        &self,
+        store_event: &StoreEvent<TestEvent>,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if !*self.available.lock().unwrap() {
+            return Err("bus unavailable".into());
+        }
+
+        self.published.lock().unwrap().push(store_event.id);
+        Ok(())
+    }
+}
+
+#[sqlx::test]
+async fn outbox_test(pool: Pool<Postgres>) {
+    let available: Arc<Mutex<bool>> = Arc::new(Mutex::new(false));
+    let published: Arc<Mutex<Vec<Uuid>>> = Arc::new(Mutex::new(vec![]));
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .add_event_bus(FlakyEventBus {
+            available: available.clone(),
+            published: published.clone(),
+        })
+        .with_outbox()
+        .try_build()
+        .await
+        .unwrap();
+
+    let relay: OutboxRelay<TestAggregate> =
+        OutboxRelay::new(store.clone()).with_retry_backoff(std::time::Duration::ZERO, std::time::Duration::ZERO);
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let store_events: Vec<StoreEvent<TestEvent>> = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 1 }, TestEvent { add: 2 }])
+        .await
+        .unwrap();
+
+    // Nothing is published while persisting: the events wait in the outbox.
+    assert!(published.lock().unwrap().is_empty());
+    assert_eq!(outbox_attempts(&pool, &store).await, vec![0, 0]);
+
+    // The first event fails, and the second one waits for it.
+    assert_eq!(relay.tick().await.unwrap(), 0);
+    assert_eq!(outbox_attempts(&pool, &store).await, vec![1, 0]);
+
+    *available.lock().unwrap() = true;
+
+    assert_eq!(relay.tick().await.unwrap(), 2);
+    assert_eq!(
+        *published.lock().unwrap(),
+        store_events
+            .iter()
+            .map(|store_event| store_event.id)
+            .collect::<Vec<Uuid>>()
+    );
+    assert!(outbox_attempts(&pool, &store).await.is_empty());
+}
+
+#[sqlx::test]
+async fn publish_policy_test(pool: Pool<Postgres>) {
+    let _ = sqlx::query("CREATE SCHEMA dead_letters_schema")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let builder: PgStoreBuilder<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .with_table_naming_strategy(|aggregate_name: &str| format!("{}_journal", aggregate_name))
+        .with_database_schema("dead_letters_schema");
+    let dead_letter_table: PgDeadLetterTable = PgDeadLetterTable::new(&builder);
+    assert_eq!(
+        dead_letter_table.table_name(),
+        format!("dead_letters_schema.{}_journal_dead_letters", TestAggregate::NAME)
+    );
+
+    dead_letter_table.setup().await.unwrap();
+    let dead_letters_query: String = format!("SELECT event_id FROM {}", dead_letter_table.table_name());
+
+    let available: Arc<Mutex<bool>> = Arc::new(Mutex::new(false));
+    let published: Arc<Mutex<Vec<Uuid>>> = Arc::new(Mutex::new(vec![]));
+    let store: PgStore<TestAggregate> = builder
+        .add_event_bus_with_policy(
+            FlakyEventBus {
+                available: available.clone(),
+                published: published.clone(),
+            },
+            PublishPolicy::new()
+                .with_max_retries(2)
+                .with_backoff(std::time::Duration::ZERO, std::time::Duration::ZERO)
+                .with_dead_letter_handler(dead_letter_table),
+        )
+        .try_build()
+        .await
+        .unwrap();
+
+    // Once the retries are exhausted, the event is dead-lettered.
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let store_events: Vec<StoreEvent<TestEvent>> = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 1 }])
+        .await
+        .unwrap();
+
+    assert!(published.lock().unwrap().is_empty());
+    let dead_letters: Vec<Uuid> = sqlx::query_scalar(dead_letters_query.as_str())
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+    assert_eq!(dead_letters, vec![store_events[0].id]);
+
+    *available.lock().unwrap() = true;
+
+    let store_events: Vec<StoreEvent<TestEvent>> = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 2 }])
+        .await
+        .unwrap();
+
+    assert_eq!(*published.lock().unwrap(), vec![store_events[0].id]);
+    let dead_letters: Vec<Uuid> = sqlx::query_scalar(dead_letters_query.as_str())
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+    assert_eq!(dead_letters.len(), 1);
+}
+
+#[sqlx::test]
+async fn outbox_poison_event_test(pool: Pool<Postgres>) {
+    let published: Arc<Mutex<Vec<Uuid>>> = Arc::new(Mutex::new(vec![]));
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .add_event_bus(FlakyEventBus {
+            available: Arc::new(Mutex::new(true)),
+            published: published.clone(),
+        })
+        .with_outbox()
+        .try_build()
+        .await
+        .unwrap();
+
+    let relay: OutboxRelay<TestAggregate> =
+        OutboxRelay::new(store.clone()).with_retry_backoff(std::time::Duration::ZERO, std::time::Duration::ZERO);
+
+    let mut poisoned_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let poisoned: Vec<StoreEvent<TestEvent>> = store
+        .persist(&mut poisoned_state, vec![TestEvent { add: 1 }])
+        .await
+        .unwrap();
+    let _ = sqlx::query(
+        format!(
+            "UPDATE {} SET payload = '{{\"unknown\": true}}' WHERE id = $1",
+            store.table_name()
+        )
+        .as_str(),
+    )
+    .bind(poisoned[0].id)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let store_events: Vec<StoreEvent<TestEvent>> = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 2 }])
+        .await
+        .unwrap();
+
+    // The event that can't be deserialized is retried later, without failing the tick.
+    assert_eq!(relay.tick().await.unwrap(), 1);
+    assert_eq!(*published.lock().unwrap(), vec![store_events[0].id]);
+    assert_eq!(outbox_attempts(&pool, &store).await, vec![1]);
+}
+
+async fn outbox_attempts(pool: &Pool<Postgres>, store: &PgStore<TestAggregate>) -> Vec<i32> {
+    sqlx::query_scalar::<_, i32>(
+        format!("SELECT attempts FROM {}_outbox ORDER BY created_at", store.table_name()).as_str(),
+    )
+    .fetch_all(pool)
+    .await
+    .unwrap()
+}
+
+#[sqlx::test]
+async fn outbox_relay_without_outbox_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone()).try_build().await.unwrap();
+
+    assert!(OutboxRelay::new(store).tick().await.is_err());
+}
+
+struct FailingEventHandler;
+
+#[async_trait::async_trait]
+impl EventHandler<TestAggregate> for FailingEventHandler {
+    async fn handle(&self, _event: &StoreEvent<TestEvent>) {}
+
+    async fn try_handle(&self, _event: &StoreEvent<TestEvent>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Err("read side unavailable".into())
+    }
+
+    fn name(&self) -> &'static str {
+        "failing"
+    }
+}
+
+#[sqlx::test]
+async fn error_observer_test(pool: Pool<Postgres>) {
+    let failures: Arc<Mutex<Vec<(Uuid, String, String)>>> = Arc::new(Mutex::new(vec![]));
+    let observed_failures: Arc<Mutex<Vec<(Uuid, String, String)>>> = failures.clone();
+    let total: Arc<Mutex<i32>> = Arc::new(Mutex::new(0));
+
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .add_event_handler(FailingEventHandler)
+        .add_event_handler(TestEventHandler { total: total.clone() })
+        .add_error_observer(
+            move |event: &StoreEvent<TestEvent>,
+                  event_handler_name: &str,
+                  error: &(dyn std::error::Error + Send + Sync)| {
+                observed_failures
+                    .lock()
+                    .unwrap()
+                    .push((event.id, event_handler_name.to_string(), error.to_string()));
+            },
+        )
+        .try_build()
+        .await
+        .unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let store_events: Vec<StoreEvent<TestEvent>> = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 1 }])
+        .await
+        .unwrap();
+
+    // The failure is observed, and the other event handlers still run.
+    assert_eq!(
+        *failures.lock().unwrap(),
+        vec![(
+            store_events[0].id,
+            "failing".to_string(),
+            "read side unavailable".to_string()
+        )]
+    );
+    assert_eq!(*total.lock().unwrap(), 1);
+}
+
+#[sqlx::test]
+async fn events_per_version_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone()).try_build().await.unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let store_events: Vec<StoreEvent<TestEvent>> = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 1 }, TestEvent { add: 2 }])
+        .await
+        .unwrap();
+
+    let _ = sqlx::query(format!("UPDATE {} SET version = NULL", store.table_name()).as_str())
+        .execute(&pool)
+        .await
+        .unwrap();
+    let _ = sqlx::query(format!("UPDATE {} SET version = 2 WHERE id = $1", store.table_name()).as_str())
+        .bind(store_events[1].id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        store.events_per_version().await.unwrap(),
+        vec![
+            VersionCount {
+                version: None,
+                count: 1
+            },
+            VersionCount {
+                version: Some(2),
+                count: 1
+            },
+        ]
+    );
+
+    let by_version: Vec<StoreEvent<TestEvent>> = store
+        .stream_events_by_version(Some(2), &pool)
+        .map(Result::unwrap)
+        .collect()
+        .await;
+    assert_eq!(by_version.len(), 1);
+    assert_eq!(by_version[0].id, store_events[1].id);
+
+    let older: Vec<StoreEvent<TestEvent>> = store
+        .stream_events_older_than(2, &pool)
+        .map(Result::unwrap)
+        .collect()
+        .await;
+    assert_eq!(older.len(), 1);
+    assert_eq!(older[0].id, store_events[0].id);
+}
+
+#[sqlx::test]
+async fn dead_events_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone()).try_build().await.unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let store_events: Vec<StoreEvent<TestEvent>> = store
+        .persist(
+            &mut aggregate_state,
+            vec![TestEvent { add: 1 }, TestEvent { add: 2 }, TestEvent { add: 3 }],
+        )
+        .await
+        .unwrap();
+
+    let two_years_ago: DateTime<Utc> = Utc::now() - chrono::Duration::days(730);
+    let one_year_ago: DateTime<Utc> = Utc::now() - chrono::Duration::days(365);
+
+    let set_payloads = |payloads: [serde_json::Value; 3]| {
+        let pool = pool.clone();
+        let table_name: String = store.table_name().to_string();
+        let ids: Vec<Uuid> = store_events.iter().map(|store_event| store_event.id).collect();
+
+        async move {
+            for (id, payload) in ids.into_iter().zip(payloads) {
+                let _ = sqlx::query(format!("UPDATE {} SET payload = $2 WHERE id = $1", table_name).as_str())
+                    .bind(id)
+                    .bind(payload)
+                    .execute(&pool)
+                    .await
+                    .unwrap();
+            }
+        }
+    };
+
+    let _ = sqlx::query(format!("UPDATE {} SET occurred_on = $2 WHERE id = $1", store.table_name()).as_str())
+        .bind(store_events[0].id)
+        .bind(two_years_ago)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    set_payloads([
+        serde_json::json!({ "type": "Opened" }),
+        serde_json::json!({ "type": "Deposited", "add": 2 }),
+        serde_json::json!({ "type": "Deposited", "add": 3 }),
+    ])
+    .await;
+
+    let location: EventTypeLocation = EventTypeLocation::InternallyTagged("type");
+    let usages: Vec<EventTypeUsage> = store.event_type_usages(&location).await.unwrap();
+    assert_eq!(
+        usages
+            .iter()
+            .map(|usage| (usage.event_type.as_str(), usage.count))
+            .collect::<Vec<_>>(),
+        vec![("Deposited", 2), ("Opened", 1)]
+    );
+
+    let report: DeadEventsReport = store
+        .dead_events(&location, &["Opened", "Deposited", "Closed"], one_year_ago)
+        .await
+        .unwrap();
+    assert_eq!(report.unused, vec!["Closed".to_string()]);
+    assert_eq!(report.stale.len(), 1);
+    assert_eq!(report.stale[0].event_type, "Opened");
+    assert_eq!(report.stale[0].count, 1);
+
+    // Unit variants are serialized as plain strings by the externally tagged representation.
+    set_payloads([
+        serde_json::json!("Opened"),
+        serde_json::json!({ "Deposited": { "add": 2 } }),
+        serde_json::json!({ "Deposited": { "add": 3 } }),
+    ])
+    .await;
+
+    let report: DeadEventsReport = store
+        .dead_events(
+            &EventTypeLocation::ExternallyTagged,
+            &["Opened", "Deposited", "Closed"],
+            one_year_ago,
+        )
+        .await
+        .unwrap();
+    assert_eq!(report.unused, vec!["Closed".to_string()]);
+    assert_eq!(
+        report
+            .stale
+            .iter()
+            .map(|usage| usage.event_type.as_str())
+            .collect::<Vec<_>>(),
+        vec!["Opened"]
+    );
+}
+
+#[sqlx::test]
+async fn statistics_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone()).try_build().await.unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let aggregate_id: Uuid = *aggregate_state.id();
+    let store_events: Vec<StoreEvent<TestEvent>> = store
+        .persist(
+            &mut aggregate_state,
+            vec![TestEvent { add: 1 }, TestEvent { add: 2 }, TestEvent { add: 3 }],
+        )
+        .await
+        .unwrap();
+
+    let mut other_aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let other_aggregate_id: Uuid = *other_aggregate_state.id();
+    let other_store_events: Vec<StoreEvent<TestEvent>> = store
+        .persist(
+            &mut other_aggregate_state,
+            vec![TestEvent { add: 4 }, TestEvent { add: 5 }],
+        )
+        .await
+        .unwrap();
+
+    let at = |timestamp: &str| DateTime::parse_from_rfc3339(timestamp).unwrap().with_timezone(&Utc);
+
+    // The last event is out of the range.
+    for (store_event, occurred_on) in store_events.iter().chain(&other_store_events).zip([
+        "2024-01-01T10:00:00Z",
+        "2024-01-01T23:59:59Z",
+        "2024-01-02T00:00:00Z",
+        "2024-01-02T12:00:00Z",
+        "2024-01-03T00:00:00Z",
+    ]) {
+        let _ = sqlx::query(format!("UPDATE {} SET occurred_on = $2 WHERE id = $1", store.table_name()).as_str())
+            .bind(store_event.id)
+            .bind(at(occurred_on))
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+
+    let range = at("2024-01-01T00:00:00Z")..at("2024-01-03T00:00:00Z");
+    let statistics: Statistics = store
+        .statistics(range, &EventTypeLocation::ExternallyTagged, 1)
+        .await
+        .unwrap();
+
+    let day = |day: u32| chrono::NaiveDate::from_ymd_opt(2024, 1, day).unwrap();
+    assert_eq!(
+        statistics.events_per_day,
+        vec![
+            DailyCount { day: day(1), count: 2 },
+            DailyCount { day: day(2), count: 2 },
+        ]
+    );
+    assert_eq!(
+        statistics.events_per_type,
+        vec![EventTypeCount {
+            event_type: "add".to_string(),
+            count: 4,
+        }]
+    );
+    assert!(statistics.average_payload_size.unwrap() > 0.0);
+    assert_eq!(
+        statistics.top_aggregates,
+        vec![AggregateCount { aggregate_id, count: 3 }]
+    );
+
+    let statistics: Statistics = store
+        .statistics(
+            at("2024-01-03T00:00:00Z")..at("2024-01-04T00:00:00Z"),
+            &EventTypeLocation::ExternallyTagged,
+            10,
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        statistics.top_aggregates,
+        vec![AggregateCount {
+            aggregate_id: other_aggregate_id,
+            count: 1,
+        }]
+    );
+
+    // An empty range has no statistics.
+    let statistics: Statistics = store
+        .statistics(
+            at("2023-01-01T00:00:00Z")..at("2024-01-01T00:00:00Z"),
+            &EventTypeLocation::ExternallyTagged,
+            10,
+        )
+        .await
+        .unwrap();
+    assert!(statistics.events_per_day.is_empty());
+    assert!(statistics.events_per_type.is_empty());
+    assert!(statistics.average_payload_size.is_none());
+    assert!(statistics.top_aggregates.is_empty());
+}
+
+#[sqlx::test]
+async fn stream_raw_events_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone()).try_build().await.unwrap();
+
+    let metadata: Metadata = Metadata {
+        user_id: Some("user".to_string()),
+        ..Metadata::default()
+    };
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let store_events: Vec<StoreEvent<TestEvent>> = store
+        .persist_with_metadata(
+            &mut aggregate_state,
+            vec![TestEvent { add: 1 }, TestEvent { add: 2 }],
+            metadata.clone(),
+        )
+        .await
+        .unwrap();
+
+    // An event that can't be deserialized.
+    let _ = sqlx::query(format!("UPDATE {} SET payload = $2 WHERE id = $1", store.table_name()).as_str())
+        .bind(store_events[1].id)
+        .bind(serde_json::json!({ "add": "two" }))
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    // The payloads are deserialized lazily, so that streaming never fails on them.
+    let raw_events: Vec<RawStoreEvent<TestEvent>> = store.stream_raw_events(&pool).map(Result::unwrap).collect().await;
+    assert_eq!(raw_events.len(), 2);
+
+    for (raw_event, store_event) in raw_events.iter().zip(&store_events) {
+        assert_eq!(raw_event.id, store_event.id);
+        assert_eq!(raw_event.aggregate_id, store_event.aggregate_id);
+        assert_eq!(raw_event.sequence_number, store_event.sequence_number);
+        assert_eq!(raw_event.metadata, metadata);
+    }
+
+    let raw_payload: serde_json::Value = serde_json::from_str(raw_events[0].raw_payload().get()).unwrap();
+    assert_eq!(raw_payload, serde_json::json!({ "add": 1 }));
+    assert_eq!(raw_events[0].payload().unwrap().unwrap().add, 1);
+    assert!(raw_events[1].payload().is_err());
+
+    let mut raw_events = raw_events.into_iter();
+    let store_event: StoreEvent<TestEvent> = raw_events.next().unwrap().into_store_event().unwrap().unwrap();
+    assert_eq!(store_event.id, store_events[0].id);
+    assert_eq!(store_event.payload.add, 1);
+    assert_eq!(store_event.metadata, metadata);
+    assert!(store_event.raw_payload().is_some());
+    assert!(raw_events.next().unwrap().into_store_event().is_err());
+}
+
+#[sqlx::test]
+async fn event_id_generator_test(pool: Pool<Postgres>) {
+    /// Generates the ids out of the sequence numbers, the same for every aggregate instance.
+    struct SequentialEventIdGenerator;
+
+    impl EventIdGenerator for SequentialEventIdGenerator {
+        fn generate(&self, _aggregate_id: Uuid, sequence_number: SequenceNumber) -> Uuid {
+            Uuid::from_u128(sequence_number as u128)
+        }
+    }
+
+    let namespace: Uuid = Uuid::new_v4();
+    let generator: DeterministicEventIdGenerator = DeterministicEventIdGenerator::new(namespace);
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .with_event_id_generator(DeterministicEventIdGenerator::new(namespace))
+        .try_build()
+        .await
+        .unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let aggregate_id: Uuid = *aggregate_state.id();
+    let store_events: Vec<StoreEvent<TestEvent>> = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 1 }, TestEvent { add: 2 }])
+        .await
+        .unwrap();
+
+    for store_event in &store_events {
+        assert_eq!(
+            store_event.id,
+            generator.generate(aggregate_id, store_event.sequence_number)
+        );
+    }
+    assert_ne!(store_events[0].id, store_events[1].id);
+    assert_ne!(
+        store_events[0].id,
+        DeterministicEventIdGenerator::new(Uuid::new_v4()).generate(aggregate_id, 1)
+    );
+
+    // Persisting the same events again from a stale state generates the same ids, and is rejected.
+    let mut stale_aggregate_state: AggregateState<TestAggregateState> = AggregateState::with_id(aggregate_id);
+    let result = store
+        .persist(&mut stale_aggregate_state, vec![TestEvent { add: 1 }])
+        .await;
+    assert!(result.is_err());
+    assert_eq!(store.by_aggregate_id(aggregate_id).await.unwrap().len(), 2);
+
+    let other_store: PgStore<OtherAggregate> = PgStoreBuilder::new(pool.clone())
+        .with_event_id_generator(SequentialEventIdGenerator)
+        .try_build()
+        .await
+        .unwrap();
+
+    let mut other_aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let store_events: Vec<StoreEvent<TestEvent>> = other_store
+        .persist(
+            &mut other_aggregate_state,
+            vec![TestEvent { add: 1 }, TestEvent { add: 2 }],
+        )
+        .await
+        .unwrap();
+
+    let ids: Vec<Uuid> = store_events.iter().map(|store_event| store_event.id).collect();
+    assert_eq!(ids, vec![Uuid::from_u128(1), Uuid::from_u128(2)]);
+}
+
+#[sqlx::test]
+async fn error_context_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone()).try_build().await.unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let aggregate_id = *aggregate_state.id();
+    let _ = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 1 }])
+        .await
+        .unwrap();
+
+    // The sequence number 1 is already taken.
+    let mut stale_aggregate_state: AggregateState<TestAggregateState> = AggregateState::with_id(aggregate_id);
+    let error = store
+        .persist(&mut stale_aggregate_state, vec![TestEvent { add: 1 }])
+        .await
+        .unwrap_err();
+
+    match &error {
+        PgStoreError::Context { context, .. } => {
+            assert_eq!(context.operation, "persist");
+            assert_eq!(context.aggregate_id, Some(aggregate_id));
+            assert_eq!(context.sequence_number, Some(1));
+        }
+        error => panic!("unexpected error: {:?}", error),
+    }
+    assert!(matches!(error.root_cause(), PgStoreError::Sqlx(_)));
+    assert!(error.to_string().contains(&aggregate_id.to_string()));
+}
+
+#[sqlx::test]
+async fn event_handlers_concurrency_test(pool: Pool<Postgres>) {
+    #[derive(Clone, Default)]
+    struct ConcurrencyEventHandler {
+        running: Arc<Mutex<(usize, usize)>>,
+    }
+
+    #[async_trait::async_trait]
+    impl esrs::handler::EventHandler<TestAggregate> for ConcurrencyEventHandler {
+        async fn handle(&self, _event: &StoreEvent<TestEvent>) {
+            {
+                let mut guard = self.running.lock().unwrap();
+                guard.0 += 1;
+                guard.1 = guard.1.max(guard.0);
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            self.running.lock().unwrap().0 -= 1;
+        }
+    }
+
+    let event_handler = ConcurrencyEventHandler::default();
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .add_event_handler(event_handler.clone())
+        .with_event_handlers_concurrency(1)
+        .try_build()
+        .await
+        .unwrap();
+
+    let mut aggregate_state_1: AggregateState<TestAggregateState> = AggregateState::new();
+    let mut aggregate_state_2: AggregateState<TestAggregateState> = AggregateState::new();
+    let (result_1, result_2) = futures::join!(
+        store.persist(&mut aggregate_state_1, vec![TestEvent { add: 1 }]),
+        store.persist(&mut aggregate_state_2, vec![TestEvent { add: 1 }]),
+    );
+    result_1.unwrap();
+    result_2.unwrap();
+
+    assert_eq!(*event_handler.running.lock().unwrap(), (0, 1));
+}
+
+#[sqlx::test]
+async fn statement_timeout_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .with_statement_timeout(std::time::Duration::from_millis(200))
+        .try_build()
+        .await
+        .unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let aggregate_id = *aggregate_state.id();
+    let _ = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 1 }])
+        .await
+        .unwrap();
+    assert_eq!(store.by_aggregate_id(aggregate_id).await.unwrap().len(), 1);
+
+    let mut transaction = store.begin().await.unwrap();
+    let statement_timeout: String = sqlx::query_scalar("SHOW statement_timeout")
+        .fetch_one(&mut *transaction)
+        .await
+        .unwrap();
+    assert_eq!(statement_timeout, "200ms");
+
+    let result = sqlx::query("SELECT pg_sleep(1)").execute(&mut *transaction).await;
+    assert!(result.is_err());
+}
+
+#[cfg(feature = "upcasting")]
+#[sqlx::test]
+async fn downcaster_test(pool: Pool<Postgres>) {
+    use esrs::event::Downcaster;
+
+    struct RenameAdd;
+
+    impl Downcaster for RenameAdd {
+        fn version(&self) -> Option<i32> {
+            Some(0)
+        }
+
+        fn downcast(&self, mut value: serde_json::Value) -> serde_json::Value {
+            value["increment"] = value["add"].clone();
+            value
+        }
+    }
+
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .with_downcaster(RenameAdd)
+        .try_build()
+        .await
+        .unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let store_events: Vec<StoreEvent<TestEvent>> = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 1 }])
+        .await
+        .unwrap();
+
+    assert_eq!(store_events[0].version, Some(0));
+    assert_eq!(store_events[0].payload.add, 1);
+
+    let (payload, version): (serde_json::Value, Option<i32>) =
+        sqlx::query_as(format!("SELECT payload, version FROM {} WHERE id = $1", store.table_name()).as_str())
+            .bind(store_events[0].id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+
+    assert_eq!(payload, serde_json::json!({"add": 1, "increment": 1}));
+    assert_eq!(version, Some(0));
+
+    let events: Vec<StoreEvent<TestEvent>> = store.by_aggregate_id(*aggregate_state.id()).await.unwrap();
+    assert_eq!(events[0].payload.add, 1);
+}
+
+#[cfg(feature = "json-schema")]
+#[sqlx::test]
+async fn schema_validator_test(pool: Pool<Postgres>) {
+    use esrs::store::postgres::{JsonSchemaValidator, SchemaViolation};
+
+    let schema_validator: JsonSchemaValidator = JsonSchemaValidator::new()
+        .with_schema(
+            None,
+            &serde_json::json!({
+                "type": "object",
+                "properties": { "add": { "type": "integer", "minimum": 0 } },
+                "required": ["add"]
+            }),
+        )
+        .unwrap();
+
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool)
+        .with_schema_validator(schema_validator)
+        .try_build()
+        .await
+        .unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let aggregate_id: Uuid = *aggregate_state.id();
+
+    let result = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 1 }, TestEvent { add: -1 }])
+        .await;
+
+    let Err(PgStoreError::Context { source, .. }) = result else {
+        panic!("expected the events to be rejected");
+    };
+    let PgStoreError::Custom(error) = *source else {
+        panic!("expected a schema violation");
+    };
+    let violation: &SchemaViolation = error.downcast_ref().unwrap();
+    assert_eq!(violation.version, None);
+    assert_eq!(violation.errors.len(), 1);
+
+    // None of the events is persisted.
+    assert!(store.by_aggregate_id(aggregate_id).await.unwrap().is_empty());
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::with_id(aggregate_id);
+    let _ = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 1 }])
+        .await
+        .unwrap();
+}
+
+#[cfg(feature = "encryption")]
+#[sqlx::test]
+async fn encryption_test(pool: Pool<Postgres>) {
+    use esrs::store::postgres::InMemoryDataKeyProvider;
+
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .with_data_key_provider(InMemoryDataKeyProvider::new())
+        .try_build()
+        .await
+        .unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let aggregate_id: Uuid = *aggregate_state.id();
+    let store_events: Vec<StoreEvent<TestEvent>> = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 1 }])
+        .await
+        .unwrap();
+    assert_eq!(store_events[0].payload.add, 1);
+
+    let payload: serde_json::Value =
+        sqlx::query_scalar(format!("SELECT payload FROM {} WHERE id = $1", store.table_name()).as_str())
+            .bind(store_events[0].id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert!(payload.get("add").is_none());
+    assert!(payload.get("esrs_ciphertext").is_some());
+
+    let store_events: Vec<StoreEvent<TestEvent>> = store.by_aggregate_id(aggregate_id).await.unwrap();
+    assert_eq!(store_events.len(), 1);
+    assert_eq!(store_events[0].payload.add, 1);
+
+    store.shred(aggregate_id).await.unwrap();
+
+    // The events are still there, but they can't be read anymore.
+    assert!(store.exists(aggregate_id).await.unwrap());
+    assert!(store.by_aggregate_id(aggregate_id).await.unwrap().is_empty());
+}
+
+/// Event bus keeping the events it publishes, serialized as the Kafka, RabbitMQ and PubSub buses do.
+#[cfg(feature = "encryption")]
+struct SerializingEventBus {
+    published: Arc<Mutex<Vec<serde_json::Value>>>,
+}
+
+#[cfg(feature = "encryption")]
+#[async_trait::async_trait]
+impl EventBus<TestAggregate> for SerializingEventBus {
+    async fn publish(&self, store_event: &StoreEvent<TestEvent>) {
+        let bytes: Vec<u8> = esrs::bus::serialize_store_event(store_event, true).unwrap();
+        self.published
+            .lock()
+            .unwrap()
+            .push(serde_json::from_slice(&bytes).unwrap());
+    }
+}
+
+#[cfg(feature = "encryption")]
+#[sqlx::test]
+async fn encryption_bus_serialization_test(pool: Pool<Postgres>) {
+    use esrs::store::postgres::InMemoryDataKeyProvider;
+
+    let published: Arc<Mutex<Vec<serde_json::Value>>> = Arc::new(Mutex::new(vec![]));
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .with_data_key_provider(InMemoryDataKeyProvider::new())
+        .add_event_bus(SerializingEventBus {
+            published: published.clone(),
+        })
+        .try_build()
+        .await
+        .unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let aggregate_id: Uuid = *aggregate_state.id();
+    let _ = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 1 }])
+        .await
+        .unwrap();
+
+    // The persisted event is published in clear.
+    let published: Vec<serde_json::Value> = published.lock().unwrap().clone();
+    assert_eq!(published.len(), 1);
+    assert_eq!(published[0]["payload"], serde_json::json!({"add": 1}));
+
+    // And so are the loaded ones.
+    let store_events: Vec<StoreEvent<TestEvent>> = store.by_aggregate_id(aggregate_id).await.unwrap();
+    let serialized: serde_json::Value =
+        serde_json::from_slice(&esrs::bus::serialize_store_event(&store_events[0], true).unwrap()).unwrap();
+    assert_eq!(serialized["payload"], serde_json::json!({"add": 1}));
+}
+
+#[sqlx::test]
+async fn compact_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone()).try_build().await.unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let aggregate_id: Uuid = *aggregate_state.id();
+    let _ = store
+        .persist(
+            &mut aggregate_state,
+            vec![TestEvent { add: 1 }, TestEvent { add: 2 }, TestEvent { add: 3 }],
+        )
+        .await
+        .unwrap();
+
+    // The test event is serialized as `{"add": ..}`, hence its externally tagged type is `add`.
+    let removed: u64 = store
+        .compact(&EventTypeLocation::ExternallyTagged, &["add"])
+        .await
+        .unwrap();
+    assert_eq!(removed, 2);
+
+    let store_events: Vec<StoreEvent<TestEvent>> = store.by_aggregate_id(aggregate_id).await.unwrap();
+    assert_eq!(store_events.len(), 1);
+    assert_eq!(store_events[0].sequence_number, 3);
+    assert_eq!(store_events[0].payload.add, 3);
+
+    let compactions: Vec<Compaction> = store.compactions(aggregate_id).await.unwrap();
+    assert_eq!(compactions.len(), 1);
+    assert_eq!(compactions[0].sequence_number, 3);
+    assert_eq!(compactions[0].removed, 2);
+
+    let store_events: Vec<StoreEvent<TestEvent>> = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 4 }])
+        .await
+        .unwrap();
+    assert_eq!(store_events[0].sequence_number, 4);
+}
+
+#[sqlx::test]
+async fn rekey_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone()).try_build().await.unwrap();
+
+    let mut from_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let from: Uuid = *from_state.id();
+    let _ = store
+        .persist(&mut from_state, vec![TestEvent { add: 1 }, TestEvent { add: 2 }])
+        .await
+        .unwrap();
+
+    let mut to_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let to: Uuid = *to_state.id();
+    let _ = store.persist(&mut to_state, vec![TestEvent { add: 3 }]).await.unwrap();
+
+    assert_eq!(store.rekey(from, to, RekeyMode::Copy).await.unwrap(), 2);
+    assert_eq!(store.by_aggregate_id(from).await.unwrap().len(), 2);
+
+    let store_events: Vec<StoreEvent<TestEvent>> = store.by_aggregate_id(to).await.unwrap();
+    let sequence_numbers: Vec<i32> = store_events.iter().map(|event| event.sequence_number).collect();
+    assert_eq!(sequence_numbers, vec![1, 2, 3]);
+
+    assert_eq!(store.rekey(from, to, RekeyMode::Move).await.unwrap(), 2);
+    assert!(store.by_aggregate_id(from).await.unwrap().is_empty());
+
+    let store_events: Vec<StoreEvent<TestEvent>> = store.by_aggregate_id(to).await.unwrap();
+    let adds: Vec<i32> = store_events.iter().map(|event| event.payload.add).collect();
+    assert_eq!(adds, vec![3, 1, 2, 1, 2]);
+    assert_eq!(store_events.last().unwrap().sequence_number, 5);
+}
+
+struct TestValidTime;
+
+impl ValidTime<TestEvent> for TestValidTime {
+    fn valid_at(&self, event: &TestEvent) -> Option<DateTime<Utc>> {
+        // Big additions are retroactive corrections.
+        (event.add > 10).then(|| Utc::now() - chrono::Duration::days(1))
+    }
+}
+
+#[sqlx::test]
+async fn valid_time_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .with_valid_time(TestValidTime)
+        .try_build()
+        .await
+        .unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let aggregate_id: Uuid = *aggregate_state.id();
+    let _ = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 1 }, TestEvent { add: 100 }])
+        .await
+        .unwrap();
+
+    let query: String = format!(
+        "SELECT valid_at FROM {} WHERE aggregate_id = $1 ORDER BY sequence_number",
+        store.table_name()
+    );
+    let valid_at: Vec<Option<DateTime<Utc>>> = sqlx::query_scalar(query.as_str())
+        .bind(aggregate_id)
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+    assert!(valid_at[0].is_none());
+    assert!(valid_at[1].is_some());
+
+    // Events without a business time are effective from their `occurred_on`.
+    let store_events: Vec<StoreEvent<TestEvent>> = store
+        .by_aggregate_id_valid_until(aggregate_id, Utc::now() - chrono::Duration::hours(12))
+        .await
+        .unwrap();
+    assert_eq!(
+        store_events
+            .iter()
+            .map(|store_event| store_event.payload.add)
+            .collect::<Vec<i32>>(),
+        vec![100]
+    );
+
+    // The events are ordered by business time.
+    let store_events: Vec<StoreEvent<TestEvent>> = store
+        .by_aggregate_id_valid_until(aggregate_id, Utc::now())
+        .await
+        .unwrap();
+    assert_eq!(
+        store_events
+            .iter()
+            .map(|store_event| store_event.payload.add)
+            .collect::<Vec<i32>>(),
+        vec![100, 1]
+    );
+}
+
+#[sqlx::test]
+async fn bitemporal_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .with_valid_time(TestValidTime)
+        .try_build()
+        .await
+        .unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let aggregate_id: Uuid = *aggregate_state.id();
+    let _ = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 1 }])
+        .await
+        .unwrap();
+
+    let known_at: DateTime<Utc> = Utc::now();
+    tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+
+    let _ = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 100 }])
+        .await
+        .unwrap();
+
+    let half_day_ago: DateTime<Utc> = Utc::now() - chrono::Duration::hours(12);
+
+    let state = store.load_known_at(aggregate_id, known_at).await.unwrap().unwrap();
+    assert_eq!(state.inner().count, 2);
+
+    let state = store
+        .load_valid_until(aggregate_id, half_day_ago)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(state.inner().count, 101);
+
+    let state = store
+        .load_bitemporal(aggregate_id, known_at, half_day_ago)
+        .await
+        .unwrap();
+    assert!(state.is_none());
+
+    let state = store
+        .load_bitemporal(aggregate_id, Utc::now(), Utc::now())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(state.inner().count, 102);
+}
+
+struct TestValidInterval;
+
+impl ValidTime<TestEvent> for TestValidInterval {
+    fn valid_at(&self, _event: &TestEvent) -> Option<DateTime<Utc>> {
+        Some(Utc::now() - chrono::Duration::days(2))
+    }
+
+    fn valid_to(&self, event: &TestEvent) -> Option<DateTime<Utc>> {
+        // Big additions are temporary, and already ended.
+        (event.add > 10).then(|| Utc::now() - chrono::Duration::days(1))
+    }
+}
+
+#[sqlx::test]
+async fn valid_interval_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .with_valid_time(TestValidInterval)
+        .try_build()
+        .await
+        .unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let aggregate_id: Uuid = *aggregate_state.id();
+    let _ = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 1 }, TestEvent { add: 100 }])
+        .await
+        .unwrap();
+
+    let state = store
+        .load_valid_until(aggregate_id, Utc::now() - chrono::Duration::hours(36))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(state.inner().count, 102);
+
+    let state = store.load_valid_until(aggregate_id, Utc::now()).await.unwrap().unwrap();
+    assert_eq!(state.inner().count, 2);
+
+    let state = store
+        .load_bitemporal(aggregate_id, Utc::now(), Utc::now() - chrono::Duration::days(3))
+        .await
+        .unwrap();
+    assert!(state.is_none());
+}
+
+#[sqlx::test]
+async fn soft_delete_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .with_deletion_strategy(DeletionStrategy::Soft)
+        .try_build()
+        .await
+        .unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let aggregate_id: Uuid = *aggregate_state.id();
+    let _ = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 1 }, TestEvent { add: 2 }])
+        .await
+        .unwrap();
+
+    store.delete(aggregate_id).await.unwrap();
+
+    assert!(!store.exists(aggregate_id).await.unwrap());
+    assert!(store.by_aggregate_id(aggregate_id).await.unwrap().is_empty());
+    assert_eq!(store.stream_events(&pool).collect::<Vec<_>>().await.len(), 0);
+
+    let store_events = store.by_aggregate_id_including_deleted(aggregate_id).await.unwrap();
+    assert_eq!(store_events.len(), 2);
+    assert_eq!(
+        store
+            .stream_events_including_deleted(&pool)
+            .collect::<Vec<_>>()
+            .await
+            .len(),
+        2
+    );
+}
+
+#[sqlx::test]
+async fn persist_idempotent_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .with_idempotency_tokens()
+        .try_build()
+        .await
+        .unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let aggregate_id: Uuid = *aggregate_state.id();
+    let idempotency_token: Uuid = Uuid::new_v4();
+
+    let store_events = store
+        .persist_idempotent(
+            &mut aggregate_state,
+            vec![TestEvent { add: 1 }, TestEvent { add: 2 }],
+            idempotency_token,
+        )
+        .await
+        .unwrap();
+
+    // Retrying with a stale aggregate state and the same token doesn't write the events twice.
+    let mut stale_aggregate_state: AggregateState<TestAggregateState> = AggregateState::with_id(aggregate_id);
+    let retried_store_events = store
+        .persist_idempotent(
+            &mut stale_aggregate_state,
+            vec![TestEvent { add: 1 }, TestEvent { add: 2 }],
+            idempotency_token,
+        )
+        .await
+        .unwrap();
+
+    let ids: Vec<Uuid> = store_events.iter().map(|store_event| store_event.id).collect();
+    let retried_ids: Vec<Uuid> = retried_store_events.iter().map(|store_event| store_event.id).collect();
+    assert_eq!(ids, retried_ids);
+    assert_eq!(store.by_aggregate_id(aggregate_id).await.unwrap().len(), 2);
+}
+
+#[sqlx::test]
+async fn archive_closed_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .with_archival()
+        .try_build()
+        .await
+        .unwrap();
+
+    let mut closed_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let closed_id: Uuid = *closed_state.id();
+    let _ = store
+        .persist(&mut closed_state, vec![TestEvent { add: 1 }, TestEvent { add: 2 }])
+        .await
+        .unwrap();
+
+    let mut active_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let active_id: Uuid = *active_state.id();
+    let _ = store
+        .persist(&mut active_state, vec![TestEvent { add: 10 }])
+        .await
+        .unwrap();
+
+    store.close(closed_id).await.unwrap();
+    assert_eq!(store.archive_closed(10).await.unwrap(), 1);
+    assert_eq!(store.archive_closed(10).await.unwrap(), 0);
+
+    assert!(store.by_aggregate_id(closed_id).await.unwrap().is_empty());
+    assert_eq!(store.by_aggregate_id(active_id).await.unwrap().len(), 1);
+
+    let state = store.load_archived(closed_id).await.unwrap().unwrap();
+    assert_eq!(state.inner().count, 4);
+    assert!(store.load_archived(active_id).await.unwrap().is_none());
+}
+
+#[cfg(feature = "integrity")]
+struct TestKeyProvider;
+
+#[cfg(feature = "integrity")]
+impl esrs::store::postgres::KeyProvider for TestKeyProvider {
+    fn current_key(&self) -> (String, Vec<u8>) {
+        ("key-2".to_string(), b"second secret".to_vec())
+    }
+
+    fn key(&self, key_id: &str) -> Option<Vec<u8>> {
+        match key_id {
+            "key-2" => Some(b"second secret".to_vec()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "integrity")]
+#[sqlx::test]
+async fn verify_signatures_test(pool: Pool<Postgres>) {
+    use esrs::store::postgres::{TamperReason, TamperedEvent};
+
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .with_key_provider(TestKeyProvider)
+        .try_build()
+        .await
+        .unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let aggregate_id: Uuid = *aggregate_state.id();
+    let store_events = store
+        .persist(
+            &mut aggregate_state,
+            vec![TestEvent { add: 1 }, TestEvent { add: 2 }, TestEvent { add: 3 }],
+        )
+        .await
+        .unwrap();
+
+    assert!(store.verify_signatures(aggregate_id).await.unwrap().is_empty());
+
+    let table_name: &str = store.table_name();
+    let _ = sqlx::query(format!("UPDATE {} SET payload = '{{\"add\": 1000}}' WHERE id = $1", table_name).as_str())
+        .bind(store_events[0].id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let _ = sqlx::query(format!("UPDATE {} SET signature_key_id = 'key-1' WHERE id = $1", table_name).as_str())
+        .bind(store_events[1].id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let _ = sqlx::query(format!("UPDATE {} SET signature = NULL WHERE id = $1", table_name).as_str())
+        .bind(store_events[2].id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let tampered_events: Vec<TamperedEvent> = store.verify_signatures(aggregate_id).await.unwrap();
+    let reasons: Vec<TamperReason> = tampered_events.into_iter().map(|event| event.reason).collect();
+
+    assert_eq!(
+        reasons,
+        vec![
+            TamperReason::InvalidSignature,
+            TamperReason::UnknownKey("key-1".to_string()),
+            TamperReason::MissingSignature,
+        ]
+    );
+}
+
+#[cfg(feature = "integrity")]
+#[sqlx::test]
+async fn verify_chain_test(pool: Pool<Postgres>) {
+    use esrs::store::postgres::{ChainBreak, ChainBreakReason};
+
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .with_hash_chain()
+        .try_build()
+        .await
+        .unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let aggregate_id: Uuid = *aggregate_state.id();
+    let _ = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 1 }, TestEvent { add: 2 }])
+        .await
+        .unwrap();
+    let store_events = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 3 }, TestEvent { add: 4 }])
+        .await
+        .unwrap();
+
+    assert!(store.verify_chain(aggregate_id).await.unwrap().is_none());
+
+    let _ = sqlx::query(format!("DELETE FROM {} WHERE id = $1", store.table_name()).as_str())
+        .bind(store_events[0].id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let chain_break: ChainBreak = store.verify_chain(aggregate_id).await.unwrap().unwrap();
+    assert_eq!(chain_break.id, store_events[1].id);
+    assert_eq!(chain_break.reason, ChainBreakReason::BrokenLink);
+}
+
+#[sqlx::test]
+async fn event_correction_test(pool: Pool<Postgres>) {
+    #[derive(Clone, Default)]
+    struct RecordingAuditHook {
+        corrections: Arc<Mutex<Vec<EventCorrection>>>,
+        veto: bool,
+    }
+
+    #[async_trait::async_trait]
+    impl AuditHook for RecordingAuditHook {
+        async fn audit(
+            &self,
+            correction: &EventCorrection,
+            _executor: &mut sqlx::PgConnection,
+        ) -> Result<(), PgStoreError> {
+            if self.veto {
+                return Err(PgStoreError::Custom("vetoed".into()));
+            }
+            self.corrections.lock().unwrap().push(correction.clone());
+            Ok(())
+        }
+    }
+
+    let audit_hook: RecordingAuditHook = RecordingAuditHook::default();
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .add_audit_hook(audit_hook.clone())
+        .try_build()
+        .await
+        .unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let aggregate_id: Uuid = *aggregate_state.id();
+    let store_events: Vec<StoreEvent<TestEvent>> = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 1 }, TestEvent { add: 2 }])
+        .await
+        .unwrap();
+
+    assert!(store
+        .update_event_payload(store_events[0].id, TestEvent { add: 10 })
+        .await
+        .unwrap());
+    assert!(store.delete_event(store_events[1].id).await.unwrap());
+    assert!(!store.delete_event(Uuid::new_v4()).await.unwrap());
+
+    let payloads: Vec<i32> = store
+        .by_aggregate_id(aggregate_id)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|store_event| store_event.payload.add)
+        .collect();
+    assert_eq!(payloads, vec![10]);
+
+    let corrections = audit_hook.corrections.lock().unwrap().clone();
+    assert_eq!(corrections.len(), 2);
+    assert_eq!(corrections[0].kind, CorrectionKind::PayloadUpdate);
+    assert_eq!(corrections[0].aggregate_id, aggregate_id);
+    assert_eq!(corrections[0].previous_payload, serde_json::json!({ "add": 1 }));
+    assert_eq!(corrections[0].payload, Some(serde_json::json!({ "add": 10 })));
+    assert_eq!(corrections[1].kind, CorrectionKind::Deletion);
+    assert_eq!(corrections[1].sequence_number, 2);
+    assert_eq!(corrections[1].payload, None);
+
+    // A failing audit hook vetoes the correction.
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool)
+        .add_audit_hook(RecordingAuditHook {
+            veto: true,
+            ..Default::default()
+        })
+        .try_build()
+        .await
+        .unwrap();
+
+    assert!(store.delete_event(store_events[0].id).await.is_err());
+    assert_eq!(store.by_aggregate_id(aggregate_id).await.unwrap().len(), 1);
+}
+
+#[sqlx::test]
+async fn debezium_outbox_test(pool: Pool<Postgres>) {
+    let outbox: DebeziumOutbox = DebeziumOutbox::new("test_outbox", EventTypeLocation::ExternallyTagged);
+    outbox.create_table(&pool).await.unwrap();
+
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .with_debezium_outbox(outbox)
+        .try_build()
+        .await
+        .unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let aggregate_id: Uuid = *aggregate_state.id();
+    let store_events: Vec<StoreEvent<TestEvent>> = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 1 }])
+        .await
+        .unwrap();
+
+    let (id, aggregate_type, outbox_aggregate_id, payload): (Uuid, String, String, serde_json::Value) =
+        sqlx::query_as("SELECT id, aggregatetype, aggregateid, payload FROM test_outbox")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+
+    assert_eq!(id, store_events[0].id);
+    assert_eq!(aggregate_type, TestAggregate::NAME);
+    assert_eq!(outbox_aggregate_id, aggregate_id.to_string());
+    assert_eq!(payload, serde_json::json!({ "add": 1 }));
+
+    // Deleting the rows right after inserting them keeps the table empty.
+    let outbox: DebeziumOutbox =
+        DebeziumOutbox::new("test_outbox", EventTypeLocation::ExternallyTagged).with_delete_after_insert();
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .with_debezium_outbox(outbox)
+        .try_build()
+        .await
+        .unwrap();
+
+    let _ = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 2 }])
+        .await
+        .unwrap();
+
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM test_outbox")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(count, 1);
+}
+
+#[cfg(feature = "test-utils")]
+#[sqlx::test]
+async fn seed_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone()).try_build().await.unwrap();
+    let aggregate_id: Uuid = Uuid::new_v4();
+
+    let _ = esrs::test::seed(&store, aggregate_id, vec![TestEvent { add: 1 }, TestEvent { add: 2 }])
+        .await
+        .unwrap();
+    let store_events: Vec<StoreEvent<TestEvent>> = esrs::test::seed(&store, aggregate_id, vec![TestEvent { add: 3 }])
+        .await
+        .unwrap();
+
+    assert_eq!(store_events.len(), 1);
+    assert_eq!(store_events[0].sequence_number, 3);
+
+    let store_events: Vec<StoreEvent<TestEvent>> = store.by_aggregate_id(aggregate_id).await.unwrap();
+    let sequence_numbers: Vec<i32> = store_events.iter().map(|event| event.sequence_number).collect();
+    assert_eq!(sequence_numbers, vec![1, 2, 3]);
+}
+
+#[sqlx::test]
+async fn global_event_stream_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone()).try_build().await.unwrap();
+    let other_store: PgStore<OtherAggregate> = PgStoreBuilder::new(pool.clone()).try_build().await.unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let mut other_aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let mut expected: Vec<(String, Uuid)> = vec![];
+
+    for add in 1..=3 {
+        let store_events: Vec<StoreEvent<TestEvent>> = store
+            .persist(&mut aggregate_state, vec![TestEvent { add }])
+            .await
+            .unwrap();
+        expected.push((TestAggregate::NAME.to_string(), store_events[0].id));
+
+        let store_events: Vec<StoreEvent<TestEvent>> = other_store
+            .persist(&mut other_aggregate_state, vec![TestEvent { add }])
+            .await
+            .unwrap();
+        expected.push((OtherAggregate::NAME.to_string(), store_events[0].id));
+    }
+
+    let global_event_stream: GlobalEventStream = GlobalEventStream::new()
+        .with_aggregate::<TestAggregate>()
+        .with_aggregate::<OtherAggregate>();
+
+    let global_events: Vec<GlobalEvent> = global_event_stream.stream(&pool).map(Result::unwrap).collect().await;
+
+    let actual: Vec<(String, Uuid)> = global_events
+        .iter()
+        .map(|event| (event.aggregate_type.clone(), event.id()))
+        .collect();
+    assert_eq!(actual, expected);
+
+    let global_event: GlobalEvent = global_events.into_iter().last().unwrap();
+    assert!(global_event.is::<OtherAggregate>());
+
+    let store_event: StoreEvent<TestEvent> = global_event
+        .into_raw_store_event::<TestEvent, TestEvent>()
+        .into_store_event()
+        .unwrap()
+        .unwrap();
+    assert_eq!(store_event.payload.add, 3);
+}
+
+#[sqlx::test]
+async fn redactor_test(pool: Pool<Postgres>) {
+    #[derive(Clone, Default)]
+    struct RecordingAuditHook {
+        corrections: Arc<Mutex<Vec<EventCorrection>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl AuditHook for RecordingAuditHook {
+        async fn audit(
+            &self,
+            correction: &EventCorrection,
+            _executor: &mut sqlx::PgConnection,
+        ) -> Result<(), PgStoreError> {
+            self.corrections.lock().unwrap().push(correction.clone());
+            Ok(())
+        }
+    }
+
+    create_test_projection_table(&pool).await;
+
+    let audit_hook: RecordingAuditHook = RecordingAuditHook::default();
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .add_transactional_event_handler(TestTransactionalEventHandler)
+        .add_audit_hook(audit_hook.clone())
+        .try_build()
+        .await
+        .unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let aggregate_id: Uuid = *aggregate_state.id();
+    let store_events: Vec<StoreEvent<TestEvent>> = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 1 }, TestEvent { add: 2 }])
+        .await
+        .unwrap();
+
+    let redactor: Redactor<TestAggregate> = Redactor::new(store.clone());
+
+    assert!(redactor
+        .redact_event(store_events[0].id, |payload| payload["add"] = serde_json::json!(5))
+        .await
+        .unwrap());
+    // A redaction leaving the payload unchanged rewrites nothing.
+    assert!(!redactor
+        .redact_event(store_events[0].id, |payload| payload["add"] = serde_json::json!(5))
+        .await
+        .unwrap());
+    assert!(!redactor.redact_event(Uuid::new_v4(), |_| ()).await.unwrap());
+
+    let payloads: Vec<i32> = store
+        .by_aggregate_id(aggregate_id)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|store_event| store_event.payload.add)
+        .collect();
+    assert_eq!(payloads, vec![5, 2]);
+
+    // The projection has been rebuilt from the redacted events.
+    let projection_row = sqlx::query_as::<_, ProjectionRow>("SELECT * FROM test_projection WHERE id = $1")
+        .bind(aggregate_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(projection_row.total, 6);
+
+    let corrections = audit_hook.corrections.lock().unwrap().clone();
+    assert_eq!(corrections.len(), 1);
+    assert_eq!(corrections[0].kind, CorrectionKind::Redaction);
+    assert_eq!(corrections[0].previous_payload, serde_json::json!({ "add": 1 }));
+    assert_eq!(corrections[0].payload, Some(serde_json::json!({ "add": 5 })));
+
+    // A redaction making a payload unreadable is rolled back.
+    assert!(redactor
+        .redact_aggregate(aggregate_id, |payload| payload["add"] = serde_json::json!("[REDACTED]"))
+        .await
+        .is_err());
+    assert_eq!(
+        redactor
+            .redact_aggregate(aggregate_id, |payload| payload["add"] = serde_json::json!(0))
+            .await
+            .unwrap(),
+        2
+    );
+    assert!(store
+        .by_aggregate_id(aggregate_id)
+        .await
+        .unwrap()
+        .into_iter()
+        .all(|store_event| store_event.payload.add == 0));
+}
+
+#[sqlx::test]
+async fn read_pool_test(pool: Pool<Postgres>) {
+    let _ = sqlx::query("CREATE SCHEMA replica").execute(&pool).await.unwrap();
+
+    // The read pool resolves the event store table in the `replica` schema, standing for a replica.
+    let read_pool: Pool<Postgres> = sqlx::postgres::PgPoolOptions::new()
+        .connect_with(
+            pool.connect_options()
+                .as_ref()
+                .clone()
+                .options([("search_path", "replica")]),
+        )
+        .await
+        .unwrap();
+
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .with_read_pool(read_pool.clone())
+        .try_build()
+        .await
+        .unwrap();
+
+    let _ = sqlx::query("CREATE TABLE replica.test_events (LIKE public.test_events INCLUDING ALL)")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let aggregate_id: Uuid = *aggregate_state.id();
+    let _ = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 1 }])
+        .await
+        .unwrap();
+
+    // Persisted on the primary, not replicated yet.
+    assert!(store.exists(aggregate_id).await.unwrap());
+    assert!(store.by_aggregate_id(aggregate_id).await.unwrap().is_empty());
+
+    let _ = sqlx::query("INSERT INTO replica.test_events SELECT * FROM public.test_events")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    assert_eq!(store.by_aggregate_id(aggregate_id).await.unwrap().len(), 1);
+    assert_eq!(store.stream_events(store.read_pool()).count().await, 1);
+}
+
+#[sqlx::test]
+async fn paginated_and_since_queries_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone()).try_build().await.unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let aggregate_id: Uuid = *aggregate_state.id();
+    let store_events: Vec<StoreEvent<TestEvent>> = store
+        .persist(&mut aggregate_state, (1..=5).map(|add| TestEvent { add }).collect())
+        .await
+        .unwrap();
+
+    let page: Vec<StoreEvent<TestEvent>> = store.by_aggregate_id_paginated(aggregate_id, 2, 2).await.unwrap();
+    assert_eq!(
+        page.iter().map(|event| event.sequence_number).collect::<Vec<_>>(),
+        vec![2, 3]
+    );
+    assert!(store
+        .by_aggregate_id_paginated(aggregate_id, 6, 2)
+        .await
+        .unwrap()
+        .is_empty());
+
+    let mut other_aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let other_store_events: Vec<StoreEvent<TestEvent>> = store
+        .persist(&mut other_aggregate_state, vec![TestEvent { add: 6 }])
+        .await
+        .unwrap();
+
+    let since: Vec<StoreEvent<TestEvent>> = store
+        .events_since(Since::EventId(store_events[3].id), 10)
+        .await
+        .unwrap();
+    assert_eq!(
+        since.iter().map(|event| event.id).collect::<Vec<_>>(),
+        vec![store_events[4].id, other_store_events[0].id]
+    );
+
+    let since: Vec<StoreEvent<TestEvent>> = store
+        .events_since(Since::OccurredOn(other_store_events[0].occurred_on), 10)
+        .await
+        .unwrap();
+    assert_eq!(since.len(), 1);
+    assert_eq!(since[0].id, other_store_events[0].id);
+
+    let since: Vec<StoreEvent<TestEvent>> = store
+        .events_since(Since::OccurredOn(DateTime::<Utc>::UNIX_EPOCH), 3)
+        .await
+        .unwrap();
+    assert_eq!(since.len(), 3);
+
+    assert!(store
+        .events_since(Since::EventId(Uuid::new_v4()), 10)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[sqlx::test]
+async fn unit_of_work_test(pool: Pool<Postgres>) {
+    let total: Arc<Mutex<i32>> = Arc::new(Mutex::new(0));
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .add_event_handler(TestEventHandler { total: total.clone() })
+        .try_build()
+        .await
+        .unwrap();
+    let other_store: PgStore<OtherAggregate> = PgStoreBuilder::new(pool.clone()).try_build().await.unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let mut other_aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+
+    // A dropped unit of work persists nothing.
+    let mut unit_of_work: UnitOfWork = UnitOfWork::begin(&store).await.unwrap();
+    let _ = unit_of_work
+        .persist(&store, &mut aggregate_state, vec![TestEvent { add: 1 }])
+        .await
+        .unwrap();
+    drop(unit_of_work);
+
+    assert!(!store.exists(*aggregate_state.id()).await.unwrap());
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::with_id(*aggregate_state.id());
+    let mut unit_of_work: UnitOfWork = UnitOfWork::begin(&store).await.unwrap();
+    let _ = unit_of_work
+        .persist(&store, &mut aggregate_state, vec![TestEvent { add: 1 }])
+        .await
+        .unwrap();
+    let _ = unit_of_work
+        .persist(&other_store, &mut other_aggregate_state, vec![TestEvent { add: 2 }])
+        .await
+        .unwrap();
+
+    // Nothing is visible, nor handled, until the unit of work is committed.
+    assert!(!other_store.exists(*other_aggregate_state.id()).await.unwrap());
+    assert_eq!(*total.lock().unwrap(), 0);
+
+    unit_of_work.commit().await.unwrap();
+
+    assert!(store.exists(*aggregate_state.id()).await.unwrap());
+    assert!(other_store.exists(*other_aggregate_state.id()).await.unwrap());
+    assert_eq!(*total.lock().unwrap(), 1);
+}
+
+#[sqlx::test]
+async fn persist_interceptor_test(pool: Pool<Postgres>) {
+    /// Doubles every event, records the intercepted aggregate instance inside of the persisting
+    /// transaction and vetoes unlucky events.
+    #[derive(Clone, Default)]
+    struct DoublingPersistInterceptor {
+        committed: Arc<Mutex<Vec<i32>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl PersistInterceptor<TestAggregate, PgStoreError, sqlx::PgConnection> for DoublingPersistInterceptor {
+        async fn before_persist(
+            &self,
+            aggregate_id: Uuid,
+            events: &mut Vec<TestEvent>,
+            executor: &mut sqlx::PgConnection,
+        ) -> Result<(), PgStoreError> {
+            let _ = sqlx::query("INSERT INTO intercepted (aggregate_id) VALUES ($1)")
+                .bind(aggregate_id)
+                .execute(&mut *executor)
+                .await?;
+
+            if events.iter().any(|event| event.add == 13) {
+                return Err(PgStoreError::Custom("unlucky event".into()));
+            }
+
+            events.iter_mut().for_each(|event| event.add *= 2);
+            Ok(())
+        }
+
+        async fn after_commit(&self, store_events: &[StoreEvent<TestEvent>]) {
+            self.committed
+                .lock()
+                .unwrap()
+                .extend(store_events.iter().map(|store_event| store_event.payload.add));
+        }
+    }
+
+    let _ = sqlx::query("CREATE TABLE intercepted (aggregate_id uuid NOT NULL)")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let persist_interceptor: DoublingPersistInterceptor = DoublingPersistInterceptor::default();
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .add_persist_interceptor(persist_interceptor.clone())
+        .try_build()
+        .await
+        .unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let aggregate_id: Uuid = *aggregate_state.id();
+
+    // The events are enriched before being persisted.
+    let store_events: Vec<StoreEvent<TestEvent>> = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 1 }, TestEvent { add: 2 }])
+        .await
+        .unwrap();
+
+    assert_eq!(store_events[0].payload.add, 2);
+    assert_eq!(store_events[1].payload.add, 4);
+
+    let persisted: Vec<i32> = store
+        .by_aggregate_id(aggregate_id)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|store_event| store_event.payload.add)
+        .collect();
+    assert_eq!(persisted, vec![2, 4]);
+    assert_eq!(*persist_interceptor.committed.lock().unwrap(), vec![2, 4]);
+
+    // A vetoed persistence aborts the whole transaction, the writes of the interceptor included.
+    let result = store.persist(&mut aggregate_state, vec![TestEvent { add: 13 }]).await;
+    assert!(matches!(result.map_err(|error| error.to_string()), Err(error) if error.contains("unlucky event")));
+
+    assert_eq!(store.by_aggregate_id(aggregate_id).await.unwrap().len(), 2);
+    assert_eq!(*persist_interceptor.committed.lock().unwrap(), vec![2, 4]);
+
+    let intercepted: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM intercepted")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(intercepted, 1);
+}
+
 async fn create_test_projection_table(pool: &Pool<Postgres>) {
     let _ = sqlx::query("DROP TABLE IF EXISTS test_projection")
         .execute(pool)
@@ -0,0 +1,106 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+use esrs::manager::AggregateManager;
+use esrs::process::{ProcessEventHandler, ProcessManager, ProcessRunner};
+use esrs::store::postgres::{PgStore, PgStoreBuilder};
+use esrs::store::{EventStore, StoreEvent};
+use esrs::AggregateState;
+
+use crate::aggregate::{TestAggregate, TestAggregateState, TestCommand, TestEvent};
+
+#[derive(Default, Serialize, Deserialize)]
+struct TestProcessState {
+    total: i32,
+}
+
+/// Mirrors every event of an aggregate instance on its counterpart, until their total reaches 3.
+struct TestProcessManager {
+    manager: AggregateManager<PgStore<TestAggregate>>,
+}
+
+impl TestProcessManager {
+    fn counterpart_id(process_id: Uuid) -> Uuid {
+        Uuid::new_v5(&process_id, b"counterpart")
+    }
+}
+
+impl ProcessManager for TestProcessManager {
+    type State = TestProcessState;
+
+    fn is_completed(&self, state: &Self::State) -> bool {
+        state.total >= 3
+    }
+}
+
+#[async_trait]
+impl ProcessEventHandler<TestAggregate> for TestProcessManager {
+    fn process_id(&self, event: &StoreEvent<TestEvent>) -> Option<Uuid> {
+        Some(event.aggregate_id)
+    }
+
+    async fn handle(
+        &self,
+        process_id: Uuid,
+        state: &mut Self::State,
+        event: &StoreEvent<TestEvent>,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        state.total += event.payload.add;
+
+        let counterpart: AggregateState<TestAggregateState> = self
+            .manager
+            .load(Self::counterpart_id(process_id))
+            .await?
+            .unwrap_or_else(|| AggregateState::with_id(Self::counterpart_id(process_id)));
+
+        self.manager.handle_command(counterpart, TestCommand::Single).await??;
+        Ok(())
+    }
+}
+
+#[sqlx::test]
+async fn process_manager_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone()).try_build().await.unwrap();
+    let runner: ProcessRunner<TestProcessManager> = ProcessRunner::new(
+        pool.clone(),
+        "test",
+        TestProcessManager {
+            manager: AggregateManager::new(store.clone()),
+        },
+    );
+    runner.setup().await.unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let aggregate_id: Uuid = *aggregate_state.id();
+    let store_events: Vec<StoreEvent<TestEvent>> = store
+        .persist(&mut aggregate_state, (0..4).map(|_| TestEvent { add: 1 }).collect())
+        .await
+        .unwrap();
+
+    assert!(runner.load(aggregate_id).await.unwrap().is_none());
+
+    // Redelivered events are skipped.
+    assert!(runner.handle::<TestAggregate>(&store_events[0]).await.unwrap());
+    assert!(!runner.handle::<TestAggregate>(&store_events[0]).await.unwrap());
+
+    let (state, completed) = runner.load(aggregate_id).await.unwrap().unwrap();
+    assert_eq!(state.total, 1);
+    assert!(!completed);
+
+    // Events of completed processes are skipped.
+    for store_event in &store_events[1..] {
+        let _ = runner.handle::<TestAggregate>(store_event).await.unwrap();
+    }
+
+    let (state, completed) = runner.load(aggregate_id).await.unwrap().unwrap();
+    assert_eq!(state.total, 3);
+    assert!(completed);
+
+    let counterpart: Vec<StoreEvent<TestEvent>> = store
+        .by_aggregate_id(TestProcessManager::counterpart_id(aggregate_id))
+        .await
+        .unwrap();
+    assert_eq!(counterpart.len(), 3);
+}
@@ -0,0 +1,168 @@
+use sqlx::{PgConnection, Pool, Postgres};
+use uuid::Uuid;
+
+use esrs::store::postgres::projection::{delete_by_id, increment_column, upsert_by_id};
+use esrs::store::postgres::ColumnValue;
+
+#[sqlx::test]
+async fn projection_primitives_test(pool: Pool<Postgres>) {
+    let _ = sqlx::query("CREATE TABLE test_projection (id uuid PRIMARY KEY NOT NULL, name TEXT, total BIGINT)")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let id: Uuid = Uuid::new_v4();
+    let mut connection = pool.acquire().await.unwrap();
+    let connection: &mut PgConnection = &mut connection;
+
+    for name in ["first", "second"] {
+        upsert_by_id(
+            connection,
+            "test_projection",
+            id,
+            vec![
+                ("name", ColumnValue::Text(Some(name.to_string()))),
+                ("total", ColumnValue::BigInt(Some(1))),
+            ],
+        )
+        .await
+        .unwrap();
+    }
+
+    increment_column(connection, "test_projection", id, "total", 41)
+        .await
+        .unwrap();
+
+    let row: (String, i64) = sqlx::query_as("SELECT name, total FROM test_projection WHERE id = $1")
+        .bind(id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+    assert_eq!(row, ("second".to_string(), 42));
+
+    delete_by_id(connection, "test_projection", id).await.unwrap();
+
+    let rows: Vec<(Uuid,)> = sqlx::query_as("SELECT id FROM test_projection")
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+
+    assert!(rows.is_empty());
+}
+
+#[cfg(feature = "macros")]
+#[sqlx::test]
+async fn view_event_handler_derive_test(pool: Pool<Postgres>) {
+    use esrs::handler::EventHandler;
+    use esrs::store::StoreEvent;
+    use esrs::ViewEventHandler;
+
+    use crate::aggregate::{TestAggregate, TestEvent};
+
+    #[derive(ViewEventHandler)]
+    #[view(aggregate = TestAggregate, table = "test_view")]
+    #[view(on = TestEvent, columns(last_add = add))]
+    struct TestView {
+        pool: Pool<Postgres>,
+    }
+
+    let _ = sqlx::query("CREATE TABLE test_view (id uuid PRIMARY KEY NOT NULL, last_add INTEGER)")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let view = TestView { pool: pool.clone() };
+    let aggregate_id: Uuid = Uuid::new_v4();
+
+    for (sequence_number, add) in [(1, 1), (2, 41)] {
+        view.handle(&StoreEvent::new(
+            Uuid::new_v4(),
+            aggregate_id,
+            TestEvent { add },
+            chrono::Utc::now(),
+            sequence_number,
+            None,
+        ))
+        .await;
+    }
+
+    let row: (i32,) = sqlx::query_as("SELECT last_add FROM test_view WHERE id = $1")
+        .bind(aggregate_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+    assert_eq!(row, (41,));
+
+    EventHandler::<TestAggregate>::delete(&view, aggregate_id).await;
+
+    let rows: Vec<(Uuid,)> = sqlx::query_as("SELECT id FROM test_view")
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+
+    assert!(rows.is_empty());
+}
+
+#[cfg(feature = "macros")]
+#[sqlx::test]
+async fn view_derive_test(pool: Pool<Postgres>) {
+    use esrs::handler::EventHandler;
+    use esrs::store::StoreEvent;
+    use esrs::View;
+
+    use crate::aggregate::{TestAggregate, TestEvent};
+
+    #[derive(View, sqlx::FromRow, PartialEq, Debug)]
+    #[view(table = "test_derived_view")]
+    #[view(event_handler(name = TestDerivedView, aggregate = TestAggregate, apply = apply))]
+    struct TestViewRow {
+        id: Uuid,
+        total: i32,
+        note: Option<String>,
+    }
+
+    fn apply(row: Option<TestViewRow>, event: &StoreEvent<TestEvent>) -> Option<TestViewRow> {
+        let total: i32 = row.map_or(0, |row| row.total) + event.payload.add;
+        Some(TestViewRow {
+            id: event.aggregate_id,
+            total,
+            note: None,
+        })
+    }
+
+    assert_eq!(
+        TestViewRow::CREATE_TABLE,
+        "CREATE TABLE IF NOT EXISTS test_derived_view (id UUID PRIMARY KEY NOT NULL, total INTEGER NOT NULL, note VARCHAR)"
+    );
+    TestViewRow::create_table(&pool).await.unwrap();
+
+    let view = TestDerivedView::new(pool.clone());
+    let aggregate_id: Uuid = Uuid::new_v4();
+
+    for (sequence_number, add) in [(1, 1), (2, 41)] {
+        view.handle(&StoreEvent::new(
+            Uuid::new_v4(),
+            aggregate_id,
+            TestEvent { add },
+            chrono::Utc::now(),
+            sequence_number,
+            None,
+        ))
+        .await;
+    }
+
+    assert_eq!(
+        TestViewRow::by_id(aggregate_id, &pool).await.unwrap(),
+        Some(TestViewRow {
+            id: aggregate_id,
+            total: 42,
+            note: None,
+        })
+    );
+
+    EventHandler::<TestAggregate>::delete(&view, aggregate_id).await;
+
+    assert_eq!(TestViewRow::by_id(aggregate_id, &pool).await.unwrap(), None);
+}
@@ -0,0 +1,159 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use sqlx::{PgConnection, Pool, Postgres};
+use uuid::Uuid;
+
+use esrs::handler::TransactionalEventHandler;
+use esrs::query::{Query, QueryError, QueryRegistry, ReadModel};
+use esrs::store::postgres::{PgStore, PgStoreBuilder, PgStoreError};
+use esrs::store::{EventStore, StoreEvent};
+use esrs::AggregateState;
+
+use crate::aggregate::{TestAggregate, TestAggregateState, TestEvent};
+
+/// Keeps the total of every aggregate instance in the `totals` table.
+struct TotalsEventHandler;
+
+#[async_trait]
+impl TransactionalEventHandler<TestAggregate, PgStoreError, PgConnection> for TotalsEventHandler {
+    async fn handle(&self, event: &StoreEvent<TestEvent>, connection: &mut PgConnection) -> Result<(), PgStoreError> {
+        let _ = sqlx::query(
+            "INSERT INTO totals (id, total) VALUES ($1, $2) \
+            ON CONFLICT (id) DO UPDATE SET total = totals.total + $2",
+        )
+        .bind(event.aggregate_id)
+        .bind(event.payload.add)
+        .execute(connection)
+        .await?;
+        Ok(())
+    }
+
+    async fn delete(&self, aggregate_id: Uuid, connection: &mut PgConnection) -> Result<(), PgStoreError> {
+        let _ = sqlx::query("DELETE FROM totals WHERE id = $1")
+            .bind(aggregate_id)
+            .execute(connection)
+            .await?;
+        Ok(())
+    }
+}
+
+/// The total of an aggregate instance, if any.
+struct TotalOf(Uuid);
+
+impl Query for TotalOf {
+    type Output = Option<i32>;
+    type Error = sqlx::Error;
+}
+
+/// The ids of the aggregate instances with the highest totals.
+struct TopTotals {
+    limit: i64,
+}
+
+impl Query for TopTotals {
+    type Output = Vec<Uuid>;
+    type Error = sqlx::Error;
+}
+
+/// A query reading a table that doesn't exist.
+struct MissingTable;
+
+impl Query for MissingTable {
+    type Output = i64;
+    type Error = sqlx::Error;
+}
+
+/// A query no read model is registered for.
+struct Unregistered;
+
+impl Query for Unregistered {
+    type Output = ();
+    type Error = sqlx::Error;
+}
+
+/// Answers the queries reading the `totals` table.
+struct TotalsReadModel {
+    pool: Pool<Postgres>,
+}
+
+#[async_trait]
+impl ReadModel<TotalOf> for TotalsReadModel {
+    async fn query(&self, query: TotalOf) -> Result<Option<i32>, sqlx::Error> {
+        sqlx::query_scalar("SELECT total FROM totals WHERE id = $1")
+            .bind(query.0)
+            .fetch_optional(&self.pool)
+            .await
+    }
+}
+
+#[async_trait]
+impl ReadModel<TopTotals> for TotalsReadModel {
+    async fn query(&self, query: TopTotals) -> Result<Vec<Uuid>, sqlx::Error> {
+        sqlx::query_scalar("SELECT id FROM totals ORDER BY total DESC LIMIT $1")
+            .bind(query.limit)
+            .fetch_all(&self.pool)
+            .await
+    }
+}
+
+#[async_trait]
+impl ReadModel<MissingTable> for TotalsReadModel {
+    async fn query(&self, _query: MissingTable) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar("SELECT count(*) FROM missing_table")
+            .fetch_one(&self.pool)
+            .await
+    }
+}
+
+#[sqlx::test]
+async fn query_registry_test(pool: Pool<Postgres>) {
+    let _ = sqlx::query("CREATE TABLE totals (id uuid PRIMARY KEY NOT NULL, total INTEGER NOT NULL)")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .add_transactional_event_handler(TotalsEventHandler)
+        .try_build()
+        .await
+        .unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let aggregate_id: Uuid = *aggregate_state.id();
+    let _ = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 1 }, TestEvent { add: 2 }])
+        .await
+        .unwrap();
+
+    let mut other_aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let other_aggregate_id: Uuid = *other_aggregate_state.id();
+    let _ = store
+        .persist(&mut other_aggregate_state, vec![TestEvent { add: 10 }])
+        .await
+        .unwrap();
+
+    // The same read model answers many query types.
+    let read_model: Arc<TotalsReadModel> = Arc::new(TotalsReadModel { pool });
+    let registry: QueryRegistry = QueryRegistry::new()
+        .register::<TotalOf, _>(read_model.clone())
+        .register::<TopTotals, _>(read_model.clone())
+        .register::<MissingTable, _>(read_model);
+
+    assert_eq!(registry.query(TotalOf(aggregate_id)).await.unwrap(), Some(3));
+    assert_eq!(registry.query(TotalOf(Uuid::new_v4())).await.unwrap(), None);
+    assert_eq!(
+        registry.query(TopTotals { limit: 2 }).await.unwrap(),
+        vec![other_aggregate_id, aggregate_id]
+    );
+    assert_eq!(
+        registry.query(TopTotals { limit: 1 }).await.unwrap(),
+        vec![other_aggregate_id]
+    );
+
+    let failed = registry.query(MissingTable).await;
+    assert!(matches!(failed, Err(QueryError::ReadModel(sqlx::Error::Database(_)))));
+
+    let unregistered = registry.query(Unregistered).await;
+    assert!(matches!(unregistered, Err(QueryError::NotRegistered(_))));
+}
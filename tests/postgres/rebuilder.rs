@@ -0,0 +1,599 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use sqlx::postgres::PgPoolOptions;
+use sqlx::{PgConnection, Pool, Postgres};
+use uuid::Uuid;
+
+use esrs::handler::{EventHandler, ReplayableEventHandler, TransactionalEventHandler};
+use esrs::rebuilder::{MergedPgRebuilder, PgRebuilder, RebuildReport, Rebuilder, ShadowEventHandler};
+use esrs::store::postgres::{PgStore, PgStoreBuilder, PgStoreError};
+use esrs::store::{EventStore, StoreEvent};
+use esrs::{Aggregate, AggregateState};
+
+use crate::aggregate::{OtherAggregate, TestAggregate, TestAggregateState, TestEvent};
+
+/// Event handler recording the events of both [`TestAggregate`] and [`OtherAggregate`], in the
+/// order they are handled.
+#[derive(Clone, Default)]
+struct RecordingEventHandler {
+    events: Arc<Mutex<Vec<(&'static str, i32)>>>,
+}
+
+impl RecordingEventHandler {
+    fn events(&self) -> Vec<(&'static str, i32)> {
+        self.events.lock().unwrap().clone()
+    }
+}
+
+#[async_trait::async_trait]
+impl EventHandler<TestAggregate> for RecordingEventHandler {
+    async fn handle(&self, event: &StoreEvent<TestEvent>) {
+        self.events
+            .lock()
+            .unwrap()
+            .push((TestAggregate::NAME, event.payload.add));
+    }
+}
+
+#[async_trait::async_trait]
+impl ReplayableEventHandler<TestAggregate> for RecordingEventHandler {
+    async fn truncate(&self) {
+        self.events.lock().unwrap().clear();
+    }
+}
+
+#[async_trait::async_trait]
+impl EventHandler<OtherAggregate> for RecordingEventHandler {
+    async fn handle(&self, event: &StoreEvent<TestEvent>) {
+        self.events
+            .lock()
+            .unwrap()
+            .push((OtherAggregate::NAME, event.payload.add));
+    }
+}
+
+#[async_trait::async_trait]
+impl ReplayableEventHandler<OtherAggregate> for RecordingEventHandler {
+    async fn truncate(&self) {
+        self.events.lock().unwrap().clear();
+    }
+}
+
+#[sqlx::test]
+async fn merged_rebuilder_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone()).try_build().await.unwrap();
+    let other_store: PgStore<OtherAggregate> = PgStoreBuilder::new(pool.clone())
+        .with_shared_table("shared_events")
+        .try_build()
+        .await
+        .unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let mut other_aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let mut expected: Vec<(&'static str, i32)> = vec![];
+
+    for add in 1..=3 {
+        let _ = store
+            .persist(&mut aggregate_state, vec![TestEvent { add }])
+            .await
+            .unwrap();
+        expected.push((TestAggregate::NAME, add));
+
+        let _ = other_store
+            .persist(&mut other_aggregate_state, vec![TestEvent { add: add * 10 }])
+            .await
+            .unwrap();
+        expected.push((OtherAggregate::NAME, add * 10));
+    }
+
+    let event_handler: RecordingEventHandler = RecordingEventHandler::default();
+    event_handler.events.lock().unwrap().push(("stale", 0));
+
+    let report: RebuildReport = MergedPgRebuilder::for_stores(vec![
+        (
+            &store,
+            PgRebuilder::new().with_event_handlers(vec![Box::new(event_handler.clone())]),
+        )
+            .into(),
+        (
+            &other_store,
+            PgRebuilder::new().with_event_handlers(vec![Box::new(event_handler.clone())]),
+        )
+            .into(),
+    ])
+    .rebuild(pool.clone())
+    .await
+    .unwrap();
+
+    assert_eq!(report.replayed, 6);
+    assert!(report.poison_events.is_empty());
+    assert_eq!(event_handler.events(), expected);
+}
+
+#[sqlx::test]
+async fn merged_rebuilder_same_aggregate_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone()).try_build().await.unwrap();
+
+    let result = MergedPgRebuilder::for_stores(vec![
+        (&store, PgRebuilder::new()).into(),
+        (&store, PgRebuilder::new()).into(),
+    ])
+    .rebuild(pool.clone())
+    .await;
+
+    assert!(result.is_err());
+}
+
+/// Replayable event handler summing the events of every aggregate instance in memory.
+#[derive(Clone, Default)]
+struct SumEventHandler {
+    totals: Arc<Mutex<HashMap<Uuid, i32>>>,
+}
+
+impl SumEventHandler {
+    fn totals(&self) -> HashMap<Uuid, i32> {
+        self.totals.lock().unwrap().clone()
+    }
+}
+
+#[async_trait::async_trait]
+impl EventHandler<TestAggregate> for SumEventHandler {
+    async fn handle(&self, event: &StoreEvent<TestEvent>) {
+        *self.totals.lock().unwrap().entry(event.aggregate_id).or_default() += event.payload.add;
+    }
+
+    async fn delete(&self, aggregate_id: Uuid) {
+        let _ = self.totals.lock().unwrap().remove(&aggregate_id);
+    }
+}
+
+#[async_trait::async_trait]
+impl ReplayableEventHandler<TestAggregate> for SumEventHandler {
+    async fn truncate(&self) {
+        self.totals.lock().unwrap().clear();
+    }
+}
+
+/// Persists the given events for a new instance of [`TestAggregate`], returning its id.
+async fn persist(store: &PgStore<TestAggregate>, adds: &[i32]) -> Uuid {
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let events: Vec<TestEvent> = adds.iter().map(|add| TestEvent { add: *add }).collect();
+    let _ = store.persist(&mut aggregate_state, events).await.unwrap();
+    *aggregate_state.id()
+}
+
+#[sqlx::test]
+async fn rebuilder_truncate_and_delete_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone()).try_build().await.unwrap();
+    let first_id: Uuid = persist(&store, &[1, 2]).await;
+    let second_id: Uuid = persist(&store, &[10]).await;
+    let stale_id: Uuid = Uuid::new_v4();
+
+    let event_handler: SumEventHandler = SumEventHandler::default();
+    let rebuilder: PgRebuilder<TestAggregate> =
+        PgRebuilder::new().with_event_handlers(vec![Box::new(event_handler.clone())]);
+
+    // Rebuilding all at once truncates the whole read side before replaying the events.
+    *event_handler.totals.lock().unwrap() = HashMap::from([(first_id, 100), (stale_id, 100)]);
+    let _ = rebuilder.all_at_once(pool.clone()).await.unwrap();
+    assert_eq!(event_handler.totals(), HashMap::from([(first_id, 3), (second_id, 10)]));
+
+    // Rebuilding by aggregate id only deletes the read side of the replayed aggregate instances.
+    *event_handler.totals.lock().unwrap() = HashMap::from([(first_id, 100), (stale_id, 100)]);
+    let _ = rebuilder.by_aggregate_id(pool.clone()).await.unwrap();
+    assert_eq!(
+        event_handler.totals(),
+        HashMap::from([(first_id, 3), (second_id, 10), (stale_id, 100)])
+    );
+}
+
+/// Replayable event handler panicking on the events adding 13.
+struct PanickingEventHandler;
+
+#[async_trait::async_trait]
+impl EventHandler<TestAggregate> for PanickingEventHandler {
+    async fn handle(&self, event: &StoreEvent<TestEvent>) {
+        assert_ne!(event.payload.add, 13, "unlucky event");
+    }
+}
+
+impl ReplayableEventHandler<TestAggregate> for PanickingEventHandler {}
+
+#[sqlx::test]
+async fn rebuilder_poison_events_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone()).try_build().await.unwrap();
+    let first_id: Uuid = persist(&store, &[1]).await;
+
+    // An event that can't be deserialized anymore.
+    let (poison_event_id, poison_aggregate_id): (Uuid, Uuid) = (Uuid::new_v4(), Uuid::new_v4());
+    let _ = sqlx::query(
+        format!(
+            "INSERT INTO {} (id, aggregate_id, payload, occurred_on, sequence_number) VALUES ($1, $2, $3, now(), 1)",
+            store.table_name()
+        )
+        .as_str(),
+    )
+    .bind(poison_event_id)
+    .bind(poison_aggregate_id)
+    .bind(serde_json::json!({ "add": "oops" }))
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let second_id: Uuid = persist(&store, &[10]).await;
+    let unlucky_id: Uuid = persist(&store, &[13]).await;
+
+    let event_handler: SumEventHandler = SumEventHandler::default();
+    let rebuilder = || {
+        PgRebuilder::<TestAggregate>::new()
+            .with_event_handlers(vec![Box::new(event_handler.clone()), Box::new(PanickingEventHandler)])
+    };
+
+    // By default, the first poison event aborts the rebuild.
+    assert!(rebuilder().all_at_once(pool.clone()).await.is_err());
+
+    let report: RebuildReport = rebuilder()
+        .with_poison_events_table("rebuild_poison_events")
+        .all_at_once(pool.clone())
+        .await
+        .unwrap();
+
+    assert_eq!(report.replayed, 3);
+    assert_eq!(
+        report
+            .poison_events
+            .iter()
+            .map(|poison_event| (poison_event.event_id, poison_event.aggregate_id))
+            .collect::<Vec<(Uuid, Uuid)>>(),
+        vec![
+            (poison_event_id, poison_aggregate_id),
+            (store.by_aggregate_id(unlucky_id).await.unwrap()[0].id, unlucky_id),
+        ]
+    );
+    assert!(report.poison_events[1].error.contains("unlucky event"));
+
+    // The stream went on past the poison events.
+    assert_eq!(
+        event_handler.totals(),
+        HashMap::from([(first_id, 1), (second_id, 10), (unlucky_id, 13)])
+    );
+
+    let recorded: Vec<Uuid> =
+        sqlx::query_scalar("SELECT event_id FROM rebuild_poison_events ORDER BY recorded_on, error")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    assert_eq!(
+        recorded,
+        report
+            .poison_events
+            .iter()
+            .map(|poison_event| poison_event.event_id)
+            .collect::<Vec<Uuid>>()
+    );
+}
+
+/// Transactional event handler summing the events of every aggregate instance into the
+/// `(id, total)` rows of a table.
+struct SumTransactionalEventHandler {
+    table_name: String,
+}
+
+impl SumTransactionalEventHandler {
+    fn new(table_name: &str) -> Self {
+        Self {
+            table_name: table_name.to_string(),
+        }
+    }
+
+    async fn handle_into(
+        event: &StoreEvent<TestEvent>,
+        table_name: &str,
+        connection: &mut PgConnection,
+    ) -> Result<(), PgStoreError> {
+        let query: String = format!(
+            "INSERT INTO {0} (id, total) VALUES ($1, $2) ON CONFLICT (id) DO UPDATE SET total = {0}.total + $2",
+            table_name
+        );
+
+        let _ = sqlx::query(query.as_str())
+            .bind(event.aggregate_id)
+            .bind(event.payload.add)
+            .execute(connection)
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_from(
+        aggregate_id: Uuid,
+        table_name: &str,
+        connection: &mut PgConnection,
+    ) -> Result<(), PgStoreError> {
+        let _ = sqlx::query(format!("DELETE FROM {} WHERE id = $1", table_name).as_str())
+            .bind(aggregate_id)
+            .execute(connection)
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl TransactionalEventHandler<TestAggregate, PgStoreError, PgConnection> for SumTransactionalEventHandler {
+    async fn handle(&self, event: &StoreEvent<TestEvent>, connection: &mut PgConnection) -> Result<(), PgStoreError> {
+        Self::handle_into(event, &self.table_name, connection).await
+    }
+
+    async fn delete(&self, aggregate_id: Uuid, connection: &mut PgConnection) -> Result<(), PgStoreError> {
+        Self::delete_from(aggregate_id, &self.table_name, connection).await
+    }
+}
+
+#[async_trait::async_trait]
+impl TransactionalEventHandler<OtherAggregate, PgStoreError, PgConnection> for SumTransactionalEventHandler {
+    async fn handle(&self, event: &StoreEvent<TestEvent>, connection: &mut PgConnection) -> Result<(), PgStoreError> {
+        Self::handle_into(event, &self.table_name, connection).await
+    }
+
+    async fn delete(&self, aggregate_id: Uuid, connection: &mut PgConnection) -> Result<(), PgStoreError> {
+        Self::delete_from(aggregate_id, &self.table_name, connection).await
+    }
+}
+
+#[async_trait::async_trait]
+impl ShadowEventHandler<TestAggregate> for SumTransactionalEventHandler {
+    fn table_name(&self) -> &str {
+        &self.table_name
+    }
+
+    async fn handle(
+        &self,
+        event: &StoreEvent<TestEvent>,
+        table_name: &str,
+        connection: &mut PgConnection,
+    ) -> Result<(), PgStoreError> {
+        Self::handle_into(event, table_name, connection).await
+    }
+
+    async fn delete(
+        &self,
+        aggregate_id: Uuid,
+        table_name: &str,
+        connection: &mut PgConnection,
+    ) -> Result<(), PgStoreError> {
+        Self::delete_from(aggregate_id, table_name, connection).await
+    }
+}
+
+/// Creates the table of a [`SumTransactionalEventHandler`], holding the given rows.
+async fn create_sums_table(pool: &Pool<Postgres>, table_name: &str, rows: &[(Uuid, i32)]) {
+    let _ = sqlx::query(
+        format!(
+            "CREATE TABLE {} (id uuid PRIMARY KEY NOT NULL, total INTEGER NOT NULL)",
+            table_name
+        )
+        .as_str(),
+    )
+    .execute(pool)
+    .await
+    .unwrap();
+
+    for (id, total) in rows {
+        let _ = sqlx::query(format!("INSERT INTO {} (id, total) VALUES ($1, $2)", table_name).as_str())
+            .bind(id)
+            .bind(total)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+}
+
+/// Returns the rows of the table of a [`SumTransactionalEventHandler`].
+async fn sums(pool: &Pool<Postgres>, table_name: &str) -> HashMap<Uuid, i32> {
+    sqlx::query_as::<_, (Uuid, i32)>(format!("SELECT id, total FROM {}", table_name).as_str())
+        .fetch_all(pool)
+        .await
+        .unwrap()
+        .into_iter()
+        .collect()
+}
+
+#[sqlx::test]
+async fn rebuilder_all_at_once_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone()).try_build().await.unwrap();
+
+    let mut expected: HashMap<Uuid, i32> = HashMap::new();
+    for add in 1..=20 {
+        let _ = expected.insert(persist(&store, &[add, add, add]).await, add * 3);
+    }
+
+    let outdated_id: Uuid = *expected.keys().next().unwrap();
+    create_sums_table(&pool, "sums", &[(outdated_id, 1000)]).await;
+
+    // The events are streamed from a snapshot while the transaction is open: two connections are enough.
+    let small_pool: Pool<Postgres> = PgPoolOptions::new()
+        .max_connections(2)
+        .connect_with((*pool.connect_options()).clone())
+        .await
+        .unwrap();
+
+    let report: RebuildReport = PgRebuilder::<TestAggregate>::new()
+        .with_transactional_event_handlers(vec![Box::new(SumTransactionalEventHandler::new("sums"))])
+        .all_at_once(small_pool)
+        .await
+        .unwrap();
+
+    assert_eq!(report.replayed, 60);
+    assert_eq!(sums(&pool, "sums").await, expected);
+}
+
+#[sqlx::test]
+async fn rebuilder_target_pool_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone()).try_build().await.unwrap();
+    let aggregate_id: Uuid = persist(&store, &[1, 2, 3]).await;
+
+    // The target database is emulated by a schema the target pool resolves the tables in.
+    let _ = sqlx::query("CREATE SCHEMA analytics").execute(&pool).await.unwrap();
+    create_sums_table(&pool, "sums", &[]).await;
+    create_sums_table(&pool, "analytics.sums", &[]).await;
+
+    let target_pool: Pool<Postgres> = PgPoolOptions::new()
+        .connect_with(
+            (*pool.connect_options())
+                .clone()
+                .options([("search_path", "analytics")]),
+        )
+        .await
+        .unwrap();
+
+    let _ = PgRebuilder::<TestAggregate>::new()
+        .with_transactional_event_handlers(vec![Box::new(SumTransactionalEventHandler::new("sums"))])
+        .with_poison_events_table("rebuild_poison_events")
+        .with_target_pool(target_pool)
+        .all_at_once(pool.clone())
+        .await
+        .unwrap();
+
+    assert_eq!(sums(&pool, "analytics.sums").await, HashMap::from([(aggregate_id, 6)]));
+    assert!(sums(&pool, "sums").await.is_empty());
+
+    let poison_events_tables: Vec<String> = sqlx::query_scalar(
+        "SELECT table_schema::text FROM information_schema.tables WHERE table_name = 'rebuild_poison_events'",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(poison_events_tables, vec!["analytics".to_string()]);
+}
+
+#[sqlx::test]
+async fn merged_rebuilder_transactional_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone()).try_build().await.unwrap();
+    let other_store: PgStore<OtherAggregate> = PgStoreBuilder::new(pool.clone()).try_build().await.unwrap();
+
+    let aggregate_id: Uuid = persist(&store, &[1, 2]).await;
+    let mut other_aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let _ = other_store
+        .persist(&mut other_aggregate_state, vec![TestEvent { add: 10 }])
+        .await
+        .unwrap();
+    let other_aggregate_id: Uuid = *other_aggregate_state.id();
+
+    create_sums_table(&pool, "sums", &[(aggregate_id, 1000)]).await;
+
+    let merged_rebuild = || async {
+        MergedPgRebuilder::for_stores(vec![
+            (
+                &store,
+                PgRebuilder::<TestAggregate>::new()
+                    .with_transactional_event_handlers(vec![Box::new(SumTransactionalEventHandler::new("sums"))]),
+            )
+                .into(),
+            (
+                &other_store,
+                PgRebuilder::<OtherAggregate>::new()
+                    .with_transactional_event_handlers(vec![Box::new(SumTransactionalEventHandler::new("sums"))]),
+            )
+                .into(),
+        ])
+        .rebuild(pool.clone())
+        .await
+    };
+
+    let report: RebuildReport = merged_rebuild().await.unwrap();
+    assert_eq!(report.replayed, 3);
+
+    let expected: HashMap<Uuid, i32> = HashMap::from([(aggregate_id, 3), (other_aggregate_id, 10)]);
+    assert_eq!(sums(&pool, "sums").await, expected);
+
+    // The views of all the stores are rebuilt in a single transaction: a failure leaves them untouched.
+    let _ = sqlx::query(
+        format!(
+            "INSERT INTO {} (id, aggregate_id, payload, occurred_on, sequence_number) VALUES ($1, $2, $3, now(), 2)",
+            other_store.table_name()
+        )
+        .as_str(),
+    )
+    .bind(Uuid::new_v4())
+    .bind(other_aggregate_id)
+    .bind(serde_json::json!({ "add": "oops" }))
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let _ = sqlx::query("UPDATE sums SET total = 0").execute(&pool).await.unwrap();
+
+    assert!(merged_rebuild().await.is_err());
+    assert_eq!(
+        sums(&pool, "sums").await,
+        HashMap::from([(aggregate_id, 0), (other_aggregate_id, 0)])
+    );
+}
+
+/// Returns whether the given table exists.
+async fn table_exists(pool: &Pool<Postgres>, table_name: &str) -> bool {
+    sqlx::query_scalar::<_, bool>("SELECT to_regclass($1) IS NOT NULL")
+        .bind(table_name)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+#[sqlx::test]
+async fn rebuilder_into_shadow_tables_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone()).try_build().await.unwrap();
+
+    let aggregate_id: Uuid = persist(&store, &[1, 2, 3]).await;
+    let other_aggregate_id: Uuid = persist(&store, &[4]).await;
+    let stale_aggregate_id: Uuid = Uuid::new_v4();
+
+    create_sums_table(&pool, "sums", &[(aggregate_id, 1000), (stale_aggregate_id, 7)]).await;
+
+    let report: RebuildReport = PgRebuilder::<TestAggregate>::new()
+        .with_shadow_event_handlers(vec![Box::new(SumTransactionalEventHandler::new("sums"))])
+        .into_shadow_tables(pool.clone())
+        .await
+        .unwrap();
+
+    assert_eq!(report.replayed, 4);
+
+    // The live table holds the rebuilt data only, and the shadow table is gone.
+    let expected: HashMap<Uuid, i32> = HashMap::from([(aggregate_id, 6), (other_aggregate_id, 4)]);
+    assert_eq!(sums(&pool, "sums").await, expected);
+    assert!(!table_exists(&pool, "sums_shadow").await);
+
+    // The swapped table keeps the constraints of the live one.
+    let duplicate = sqlx::query("INSERT INTO sums (id, total) VALUES ($1, 0)")
+        .bind(aggregate_id)
+        .execute(&pool)
+        .await;
+    assert!(duplicate.is_err());
+}
+
+#[sqlx::test]
+async fn rebuilder_into_shadow_tables_failed_swap_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone()).try_build().await.unwrap();
+
+    let aggregate_id: Uuid = persist(&store, &[1, 2, 3]).await;
+    let stale_aggregate_id: Uuid = Uuid::new_v4();
+
+    create_sums_table(&pool, "sums", &[(aggregate_id, 1000), (stale_aggregate_id, 7)]).await;
+
+    // A view depending on the live table prevents it from being dropped, failing the swap.
+    let _ = sqlx::query("CREATE VIEW sums_view AS SELECT id, total FROM sums")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let result = PgRebuilder::<TestAggregate>::new()
+        .with_shadow_event_handlers(vec![Box::new(SumTransactionalEventHandler::new("sums"))])
+        .into_shadow_tables(pool.clone())
+        .await;
+
+    assert!(result.is_err());
+
+    // The swap is atomic: the live table keeps its data and is still the one the view reads.
+    let expected: HashMap<Uuid, i32> = HashMap::from([(aggregate_id, 1000), (stale_aggregate_id, 7)]);
+    assert_eq!(sums(&pool, "sums").await, expected);
+    assert_eq!(sums(&pool, "sums_view").await, expected);
+}
@@ -0,0 +1,235 @@
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+use esrs::manager::AggregateManager;
+use esrs::scheduler::{CommandScheduler, TimeoutExpired, TimeoutHandler, TimeoutScheduler};
+use esrs::store::postgres::{PgStore, PgStoreBuilder};
+
+use crate::aggregate::{TestAggregate, TestCommand};
+
+#[derive(Default)]
+struct TestTimeoutHandler {
+    names: Mutex<Vec<String>>,
+}
+
+#[async_trait]
+impl TimeoutHandler for TestTimeoutHandler {
+    async fn handle(&self, timeout: &TimeoutExpired) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.names.lock().unwrap().push(timeout.name.clone());
+        Ok(())
+    }
+}
+
+#[sqlx::test]
+async fn timeout_scheduler_test(pool: Pool<Postgres>) {
+    let scheduler: TimeoutScheduler = TimeoutScheduler::new(pool.clone(), "test");
+    scheduler.setup().await.unwrap();
+
+    let aggregate_id: Uuid = Uuid::new_v4();
+    let mut connection = pool.acquire().await.unwrap();
+    let past = Utc::now() - Duration::minutes(1);
+
+    let _ = scheduler
+        .schedule(&mut connection, aggregate_id, "expired", past, serde_json::Value::Null)
+        .await
+        .unwrap();
+    let _ = scheduler
+        .schedule(
+            &mut connection,
+            aggregate_id,
+            "cancelled",
+            past,
+            serde_json::Value::Null,
+        )
+        .await
+        .unwrap();
+    let _ = scheduler
+        .schedule(
+            &mut connection,
+            aggregate_id,
+            "pending",
+            Utc::now() + Duration::hours(1),
+            serde_json::Value::Null,
+        )
+        .await
+        .unwrap();
+
+    scheduler
+        .cancel(&mut connection, aggregate_id, "cancelled")
+        .await
+        .unwrap();
+
+    let handler: TestTimeoutHandler = TestTimeoutHandler::default();
+
+    assert_eq!(scheduler.tick(&handler).await.unwrap(), 1);
+    assert_eq!(scheduler.tick(&handler).await.unwrap(), 0);
+    assert_eq!(*handler.names.lock().unwrap(), vec!["expired".to_string()]);
+
+    let remaining: Vec<(String,)> = sqlx::query_as(format!("SELECT name FROM {}", scheduler.table_name()).as_str())
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+
+    assert_eq!(remaining, vec![("pending".to_string(),)]);
+}
+
+/// Timeout handler failing to handle the timeouts named "failing" until their third attempt.
+#[derive(Default)]
+struct FlakyTimeoutHandler {
+    attempts: Mutex<Vec<(String, i32)>>,
+}
+
+#[async_trait]
+impl TimeoutHandler for FlakyTimeoutHandler {
+    async fn handle(&self, timeout: &TimeoutExpired) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.attempts
+            .lock()
+            .unwrap()
+            .push((timeout.name.clone(), timeout.attempts));
+
+        if timeout.name == "failing" && timeout.attempts < 2 {
+            return Err("unavailable".into());
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the attempts of the "failing" timeout and the delay of its next attempt after the given time.
+async fn failing_timeout_backoff(
+    pool: &Pool<Postgres>,
+    scheduler: &TimeoutScheduler,
+    before: chrono::DateTime<Utc>,
+) -> (i32, Duration) {
+    let (attempts, next_attempt_at, last_error): (i32, chrono::DateTime<Utc>, Option<String>) = sqlx::query_as(
+        format!(
+            "SELECT attempts, next_attempt_at, last_error FROM {} WHERE name = 'failing'",
+            scheduler.table_name()
+        )
+        .as_str(),
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap();
+
+    assert_eq!(last_error.as_deref(), Some("unavailable"));
+    (attempts, next_attempt_at - before)
+}
+
+#[sqlx::test]
+async fn timeout_scheduler_retry_test(pool: Pool<Postgres>) {
+    let scheduler: TimeoutScheduler = TimeoutScheduler::new(pool.clone(), "test")
+        .with_retry_backoff(std::time::Duration::from_secs(60), std::time::Duration::from_secs(600));
+    scheduler.setup().await.unwrap();
+
+    let aggregate_id: Uuid = Uuid::new_v4();
+    let mut connection = pool.acquire().await.unwrap();
+    let past = Utc::now() - Duration::minutes(1);
+
+    for name in ["failing", "succeeding"] {
+        let _ = scheduler
+            .schedule(&mut connection, aggregate_id, name, past, serde_json::Value::Null)
+            .await
+            .unwrap();
+    }
+
+    let handler: FlakyTimeoutHandler = FlakyTimeoutHandler::default();
+    // The failing timeout is postponed, without holding back the other one.
+    let before = Utc::now();
+    assert_eq!(scheduler.tick(&handler).await.unwrap(), 1);
+    assert_eq!(scheduler.tick(&handler).await.unwrap(), 0);
+
+    let (attempts, delay) = failing_timeout_backoff(&pool, &scheduler, before).await;
+    assert_eq!(attempts, 1);
+    assert!(delay >= Duration::seconds(60) && delay < Duration::seconds(120));
+
+    // Once due again, the delay doubles at every failure.
+    let _ = sqlx::query(format!("UPDATE {} SET next_attempt_at = now()", scheduler.table_name()).as_str())
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let before = Utc::now();
+    assert_eq!(scheduler.tick(&handler).await.unwrap(), 0);
+
+    let (attempts, delay) = failing_timeout_backoff(&pool, &scheduler, before).await;
+    assert_eq!(attempts, 2);
+    assert!(delay >= Duration::seconds(120) && delay < Duration::seconds(180));
+
+    let _ = sqlx::query(format!("UPDATE {} SET next_attempt_at = now()", scheduler.table_name()).as_str())
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    assert_eq!(scheduler.tick(&handler).await.unwrap(), 1);
+    assert_eq!(
+        *handler.attempts.lock().unwrap(),
+        vec![
+            ("failing".to_string(), 0),
+            ("succeeding".to_string(), 0),
+            ("failing".to_string(), 1),
+            ("failing".to_string(), 2),
+        ]
+    );
+
+    let remaining: i64 = sqlx::query_scalar(format!("SELECT COUNT(*) FROM {}", scheduler.table_name()).as_str())
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(remaining, 0);
+}
+
+#[sqlx::test]
+async fn command_scheduler_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone()).try_build().await.unwrap();
+    let manager: AggregateManager<PgStore<TestAggregate>> = AggregateManager::new(store);
+    let scheduler: CommandScheduler<PgStore<TestAggregate>> = CommandScheduler::new(pool.clone(), "test", manager);
+    scheduler.setup().await.unwrap();
+
+    let aggregate_id: Uuid = Uuid::new_v4();
+    let mut connection = pool.acquire().await.unwrap();
+
+    let _ = scheduler
+        .schedule(
+            &mut connection,
+            aggregate_id,
+            "due",
+            &TestCommand::Multi,
+            Utc::now() - Duration::minutes(1),
+        )
+        .await
+        .unwrap();
+    let _ = scheduler
+        .schedule(
+            &mut connection,
+            aggregate_id,
+            "pending",
+            &TestCommand::Single,
+            Utc::now() + Duration::hours(1),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(scheduler.tick().await.unwrap(), 1);
+    assert_eq!(scheduler.tick().await.unwrap(), 0);
+
+    let manager: AggregateManager<PgStore<TestAggregate>> =
+        AggregateManager::new(PgStoreBuilder::new(pool.clone()).try_build().await.unwrap());
+    let aggregate_state = manager.load(aggregate_id).await.unwrap().unwrap();
+    assert_eq!(aggregate_state.sequence_number(), &2);
+
+    scheduler
+        .cancel(&mut connection, aggregate_id, "pending")
+        .await
+        .unwrap();
+
+    let remaining: i64 = sqlx::query_scalar(format!("SELECT COUNT(*) FROM {}", scheduler.table_name()).as_str())
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(remaining, 0);
+}
@@ -0,0 +1,153 @@
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+use sqlx::{Pool, Postgres};
+
+use esrs::store::postgres::{Checkpoint, PgStore, PgStoreBuilder, Subscription, Visibility};
+use esrs::store::{EventStore, StoreEvent};
+use esrs::AggregateState;
+
+use crate::aggregate::{TestAggregate, TestAggregateState, TestEvent, TestEventHandler};
+
+#[sqlx::test]
+async fn subscription_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone()).try_build().await.unwrap();
+
+    let total: Arc<Mutex<i32>> = Arc::new(Mutex::new(0));
+    let subscription: Subscription<TestAggregate> =
+        Subscription::new(store.clone(), TestEventHandler { total: total.clone() })
+            .with_batch_size(2)
+            .with_settle_delay(std::time::Duration::ZERO);
+    subscription.setup().await.unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let store_events: Vec<StoreEvent<TestEvent>> = store
+        .persist(
+            &mut aggregate_state,
+            vec![TestEvent { add: 1 }, TestEvent { add: 2 }, TestEvent { add: 3 }],
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(subscription.checkpoint().await.unwrap(), None);
+
+    // The events are handled in batches, moving the checkpoint forward.
+    assert_eq!(subscription.tick().await.unwrap(), 2);
+    assert_eq!(*total.lock().unwrap(), 3);
+    assert_eq!(
+        subscription.checkpoint().await.unwrap(),
+        Some(Checkpoint {
+            event_id: store_events[1].id,
+            occurred_on: store_events[1].occurred_on,
+            aggregate_id: store_events[1].aggregate_id,
+            sequence_number: 2,
+        })
+    );
+
+    assert_eq!(subscription.tick().await.unwrap(), 1);
+    assert_eq!(subscription.tick().await.unwrap(), 0);
+    assert_eq!(*total.lock().unwrap(), 6);
+
+    // A new subscription with the same name resumes from the checkpoint.
+    let resumed: Subscription<TestAggregate> =
+        Subscription::new(store.clone(), TestEventHandler { total: total.clone() })
+            .with_settle_delay(std::time::Duration::ZERO);
+
+    let _ = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 4 }])
+        .await
+        .unwrap();
+
+    assert_eq!(resumed.tick().await.unwrap(), 1);
+    assert_eq!(*total.lock().unwrap(), 10);
+
+    // Once reset, the subscription handles all the events again.
+    resumed.reset().await.unwrap();
+    *total.lock().unwrap() = 0;
+
+    assert_eq!(resumed.tick().await.unwrap(), 4);
+    assert_eq!(*total.lock().unwrap(), 10);
+}
+
+struct TestVisibility;
+
+impl Visibility<TestEvent> for TestVisibility {
+    fn visible_at(&self, event: &TestEvent) -> Option<DateTime<Utc>> {
+        (event.add > 10).then(|| Utc::now() + chrono::Duration::hours(1))
+    }
+}
+
+#[sqlx::test]
+async fn subscription_deferred_events_test(pool: Pool<Postgres>) {
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .with_visibility(TestVisibility)
+        .try_build()
+        .await
+        .unwrap();
+
+    let total: Arc<Mutex<i32>> = Arc::new(Mutex::new(0));
+    let subscription: Subscription<TestAggregate> =
+        Subscription::new(store.clone(), TestEventHandler { total: total.clone() })
+            .with_settle_delay(std::time::Duration::ZERO);
+    subscription.setup().await.unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let _ = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 100 }, TestEvent { add: 1 }])
+        .await
+        .unwrap();
+
+    // The embargoed event is not handled, but it doesn't hold back the following ones.
+    assert_eq!(subscription.tick().await.unwrap(), 1);
+    assert_eq!(*total.lock().unwrap(), 1);
+    assert_eq!(subscription.tick().await.unwrap(), 0);
+
+    let _ = sqlx::query(format!("UPDATE {}_deferred SET visible_at = now()", store.table_name()).as_str())
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    // Once visible, it's handled even if the subscription moved past its occurred on, and whether
+    // it has been released or not.
+    assert_eq!(subscription.tick().await.unwrap(), 1);
+    assert_eq!(*total.lock().unwrap(), 101);
+
+    assert_eq!(store.release_deferred_events(10).await.unwrap(), 1);
+    assert_eq!(subscription.tick().await.unwrap(), 0);
+    assert_eq!(*total.lock().unwrap(), 101);
+}
+
+#[sqlx::test]
+async fn subscription_database_schema_test(pool: Pool<Postgres>) {
+    let _ = sqlx::query("CREATE SCHEMA subscriptions").execute(&pool).await.unwrap();
+
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone())
+        .with_database_schema("subscriptions")
+        .try_build()
+        .await
+        .unwrap();
+
+    let total: Arc<Mutex<i32>> = Arc::new(Mutex::new(0));
+    let subscription: Subscription<TestAggregate> =
+        Subscription::new(store.clone(), TestEventHandler { total: total.clone() })
+            .with_settle_delay(std::time::Duration::ZERO);
+    subscription.setup().await.unwrap();
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let _ = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 1 }])
+        .await
+        .unwrap();
+
+    assert_eq!(subscription.tick().await.unwrap(), 1);
+    assert!(subscription.checkpoint().await.unwrap().is_some());
+
+    // The checkpoints are kept in the schema of the store.
+    let schemas: Vec<String> = sqlx::query_scalar(
+        "SELECT table_schema::text FROM information_schema.tables WHERE table_name = 'projection_offsets'",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(schemas, vec!["subscriptions".to_string()]);
+}
@@ -0,0 +1,63 @@
+use chrono::Utc;
+use google_cloud_pubsub::client::{Client, ClientConfig};
+use google_cloud_pubsub::subscriber::ReceivedMessage;
+use google_cloud_pubsub::subscription::SubscriptionConfig;
+use uuid::Uuid;
+
+use esrs::bus::pubsub::{PubSubEventBus, PubSubEventBusConfig};
+use esrs::bus::EventBus;
+use esrs::store::StoreEvent;
+
+use crate::aggregate::{TestAggregate, TestEvent};
+
+#[tokio::test]
+async fn pubsub_event_bus_test() {
+    // Points to the emulator through the `PUBSUB_EMULATOR_HOST` environment variable.
+    let client: Client = Client::new(ClientConfig::default()).await.unwrap();
+    let topic_name: &str = "test";
+    let subscription_name: String = format!("test-{}", Uuid::new_v4());
+
+    let topic = client.topic(topic_name);
+    if !topic.exists(None).await.unwrap() {
+        topic.create(None, None).await.unwrap();
+    }
+
+    let subscription = client.subscription(subscription_name.as_str());
+    let subscription_config: SubscriptionConfig = SubscriptionConfig {
+        enable_message_ordering: true,
+        ..Default::default()
+    };
+    subscription
+        .create(topic.fully_qualified_name(), subscription_config, None)
+        .await
+        .unwrap();
+
+    let config: PubSubEventBusConfig = PubSubEventBusConfig::builder()
+        .topic(topic_name)
+        .client_config(ClientConfig::default())
+        .error_handler(Box::new(|error| panic!("{:?}", error)))
+        .build();
+
+    let bus: PubSubEventBus<TestAggregate> = match PubSubEventBus::new(config).await {
+        Ok(bus) => bus,
+        Err(error) => panic!("{:?}", error),
+    };
+
+    let store_event: StoreEvent<TestEvent> = StoreEvent::new(
+        Uuid::new_v4(),
+        Uuid::new_v4(),
+        TestEvent { add: 1 },
+        Utc::now(),
+        1,
+        None,
+    );
+
+    bus.publish(&store_event).await;
+
+    let messages: Vec<ReceivedMessage> = subscription.pull(1, None).await.unwrap();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].message.ordering_key, store_event.aggregate_id.to_string());
+
+    messages[0].ack().await.unwrap();
+    subscription.delete(None).await.unwrap();
+}
@@ -1,12 +1,12 @@
 use chrono::Utc;
 use futures::TryStreamExt;
-use lapin::options::{BasicAckOptions, BasicConsumeOptions, QueueBindOptions, QueueDeclareOptions};
+use lapin::options::{BasicAckOptions, BasicConsumeOptions, BasicRejectOptions, QueueBindOptions, QueueDeclareOptions};
 use lapin::types::FieldTable;
 use lapin::{Connection, ConnectionProperties, Consumer, ExchangeKind};
 use rand::prelude::IteratorRandom;
 use uuid::Uuid;
 
-use esrs::bus::rabbit::{RabbitEventBus, RabbitEventBusConfig};
+use esrs::bus::rabbit::{RabbitEventBus, RabbitEventBusConfig, RabbitQueueConfig};
 use esrs::bus::EventBus;
 use esrs::store::StoreEvent;
 
@@ -42,14 +42,8 @@ async fn rabbit_event_bus_test() {
     .await;
 
     let event_id: Uuid = Uuid::new_v4();
-    let store_event: StoreEvent<TestEvent> = StoreEvent {
-        id: event_id,
-        aggregate_id: Uuid::new_v4(),
-        payload: TestEvent { add: 1 },
-        occurred_on: Utc::now(),
-        sequence_number: 1,
-        version: None,
-    };
+    let store_event: StoreEvent<TestEvent> =
+        StoreEvent::new(event_id, Uuid::new_v4(), TestEvent { add: 1 }, Utc::now(), 1, None);
 
     bus.publish(&store_event).await;
 
@@ -66,6 +60,71 @@ async fn rabbit_event_bus_test() {
     }
 }
 
+#[tokio::test]
+async fn rabbit_event_bus_topology_test() {
+    let rabbit_url: String = std::env::var("RABBIT_URL").unwrap();
+
+    let exchange: String = format!("{}_test_exchange", random_letters());
+    let queue: String = format!("{}_test_queue", random_letters());
+    let dead_letter_exchange: String = format!("{}_test_dead_letter", random_letters());
+
+    let config: RabbitEventBusConfig = RabbitEventBusConfig::builder()
+        .url(rabbit_url.as_str())
+        .exchange(exchange.as_str())
+        .exchange_kind(ExchangeKind::Fanout)
+        .queues(vec![RabbitQueueConfig::builder().name(queue.as_str()).build()])
+        .dead_letter_exchange(Some(dead_letter_exchange.as_str()))
+        .error_handler(Box::new(|error| panic!("{:?}", error)))
+        .build();
+
+    let bus: RabbitEventBus<TestAggregate> = match RabbitEventBus::new(config).await {
+        Ok(bus) => bus,
+        Err(error) => panic!("{:?}", error),
+    };
+
+    let conn = Connection::connect(rabbit_url.as_str(), ConnectionProperties::default())
+        .await
+        .unwrap();
+    let channel = conn.create_channel().await.unwrap();
+    let mut consumer: Consumer = channel
+        .basic_consume(
+            queue.as_str(),
+            "test_consumer",
+            BasicConsumeOptions::default(),
+            FieldTable::default(),
+        )
+        .await
+        .unwrap();
+
+    let event_id: Uuid = Uuid::new_v4();
+    let store_event: StoreEvent<TestEvent> =
+        StoreEvent::new(event_id, Uuid::new_v4(), TestEvent { add: 1 }, Utc::now(), 1, None);
+
+    bus.publish(&store_event).await;
+
+    let delivery = consumer.try_next().await.unwrap().expect("error in consumer");
+    delivery.reject(BasicRejectOptions::default()).await.unwrap();
+
+    let mut dead_letter_consumer: Consumer = channel
+        .basic_consume(
+            dead_letter_exchange.as_str(),
+            "test_dead_letter_consumer",
+            BasicConsumeOptions::default(),
+            FieldTable::default(),
+        )
+        .await
+        .unwrap();
+
+    let delivery = dead_letter_consumer
+        .try_next()
+        .await
+        .unwrap()
+        .expect("error in consumer");
+    delivery.ack(BasicAckOptions::default()).await.unwrap();
+    let event = serde_json::from_slice::<StoreEvent<TestEvent>>(delivery.data.as_slice()).unwrap();
+    assert_eq!(event.id, event_id);
+}
+
 async fn consumer(url: &str, exchange: &str, queue: &str, routing_key: &str) -> Consumer {
     let conn = Connection::connect(url, ConnectionProperties::default()).await.unwrap();
 
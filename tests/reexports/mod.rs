@@ -0,0 +1,42 @@
+//! The core abstractions live in `esrs-core`: these imports check that they are still reachable
+//! through their `esrs` paths.
+#![allow(unused_imports)]
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use esrs::handler::{ErrorObserver, EventHandler, ReplayableEventHandler, TransactionalEventHandler};
+use esrs::interceptor::PersistInterceptor;
+use esrs::store::{
+    EventStore, EventStoreLockGuard, IdempotentEventStore, Metadata, Since, Snapshot, SnapshotStore, StoreEvent,
+    UnlockOnDrop,
+};
+use esrs::types::SequenceNumber;
+use esrs::{Aggregate, AggregateState, AsyncAggregate, SharedAggregateState};
+
+use crate::aggregate::{TestAggregate, TestAggregateState, TestEvent};
+
+#[test]
+fn reexports_test() {
+    let aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let aggregate_id: Uuid = *aggregate_state.id();
+
+    let store_event: StoreEvent<TestEvent> = StoreEvent {
+        id: Uuid::new_v4(),
+        aggregate_id,
+        payload: TestEvent { add: 1 },
+        occurred_on: Utc::now(),
+        sequence_number: 1,
+        version: None,
+        metadata: Metadata::default(),
+        raw_payload: None,
+    };
+
+    let shared: SharedAggregateState<TestAggregateState> = aggregate_state.into();
+    let shared: SharedAggregateState<TestAggregateState> = shared.replay::<TestAggregate>(vec![store_event]);
+
+    let sequence_number: SequenceNumber = *shared.sequence_number();
+    assert_eq!(sequence_number, 1);
+    assert_eq!(shared.inner().count, 2);
+    assert_eq!(TestAggregate::NAME, "test");
+}
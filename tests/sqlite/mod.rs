@@ -0,0 +1,103 @@
+use std::sync::{Arc, Mutex};
+
+use futures::StreamExt;
+use sqlx::sqlite::SqlitePoolOptions;
+use sqlx::{Pool, Sqlite};
+use uuid::Uuid;
+
+use esrs::manager::AggregateManager;
+use esrs::store::sqlite::{SqliteStore, SqliteStoreBuilder, SqliteStoreError};
+use esrs::store::{EventStore, StoreEvent};
+use esrs::AggregateState;
+
+use crate::aggregate::{TestAggregate, TestAggregateState, TestCommand, TestEvent, TestEventHandler};
+
+async fn in_memory_pool() -> Pool<Sqlite> {
+    // Each connection to an in-memory database opens a distinct database.
+    SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn sqlite_store_test() {
+    let pool: Pool<Sqlite> = in_memory_pool().await;
+    let store: SqliteStore<TestAggregate> = SqliteStoreBuilder::new(pool.clone()).try_build().await.unwrap();
+
+    let aggregate_id: Uuid = Uuid::new_v4();
+    assert!(store.by_aggregate_id(aggregate_id).await.unwrap().is_empty());
+    assert!(!store.exists(aggregate_id).await.unwrap());
+
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::with_id(aggregate_id);
+    let store_events: Vec<StoreEvent<TestEvent>> = store
+        .persist(&mut aggregate_state, vec![TestEvent { add: 1 }, TestEvent { add: 2 }])
+        .await
+        .unwrap();
+
+    assert_eq!(store_events.len(), 2);
+    assert_eq!(store_events[1].sequence_number, 2);
+    assert!(store.exists(aggregate_id).await.unwrap());
+
+    let loaded: Vec<StoreEvent<TestEvent>> = store.by_aggregate_id(aggregate_id).await.unwrap();
+    assert_eq!(loaded.len(), 2);
+    assert_eq!(loaded[0].id, store_events[0].id);
+    assert_eq!(loaded[1].payload.add, 2);
+
+    let loaded: Vec<StoreEvent<TestEvent>> = store.by_aggregate_id_after(aggregate_id, 1).await.unwrap();
+    assert_eq!(loaded.len(), 1);
+    assert_eq!(loaded[0].sequence_number, 2);
+
+    // Violation of aggregate_id - sequence_number unique constraint
+    let mut aggregate_state: AggregateState<TestAggregateState> = AggregateState::with_id(aggregate_id);
+    let result: Result<Vec<StoreEvent<TestEvent>>, SqliteStoreError> =
+        store.persist(&mut aggregate_state, vec![TestEvent { add: 1 }]).await;
+    assert!(result.is_err());
+
+    let mut connection = pool.acquire().await.unwrap();
+    let streamed: Vec<StoreEvent<TestEvent>> = store
+        .stream_events(&mut *connection)
+        .map(Result::unwrap)
+        .collect()
+        .await;
+    assert_eq!(streamed.len(), 2);
+    drop(connection);
+
+    store.delete(aggregate_id).await.unwrap();
+    assert!(store.by_aggregate_id(aggregate_id).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn sqlite_store_manager_test() {
+    let total: Arc<Mutex<i32>> = Arc::new(Mutex::new(0));
+    let store: SqliteStore<TestAggregate> = SqliteStoreBuilder::new(in_memory_pool().await)
+        .add_event_handler(TestEventHandler { total: total.clone() })
+        .try_build()
+        .await
+        .unwrap();
+    let manager: AggregateManager<SqliteStore<TestAggregate>> = AggregateManager::new(store);
+
+    let aggregate_state: AggregateState<TestAggregateState> = AggregateState::new();
+    let aggregate_id = *aggregate_state.id();
+
+    manager
+        .handle_command(aggregate_state, TestCommand::Multi)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let aggregate_state = manager.lock_and_load(aggregate_id).await.unwrap().unwrap();
+    assert_eq!(aggregate_state.inner().count, 3);
+    assert_eq!(aggregate_state.sequence_number(), &2);
+
+    manager
+        .handle_command(aggregate_state, TestCommand::Single)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let aggregate_state = manager.load(aggregate_id).await.unwrap().unwrap();
+    assert_eq!(aggregate_state.inner().count, 4);
+    assert_eq!(*total.lock().unwrap(), 3);
+}
@@ -0,0 +1,135 @@
+use esrs::Aggregate;
+
+pub struct Turnstile;
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub enum TurnstileState {
+    #[default]
+    Locked,
+    Unlocked {
+        coins: u32,
+    },
+    Broken,
+}
+
+pub enum TurnstileCommand {
+    InsertCoin,
+    Push,
+    Break,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TurnstileEvent {
+    CoinInserted,
+    Pushed,
+    Broken,
+    Repaired,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum TurnstileError {
+    #[error("Illegal transition")]
+    IllegalTransition,
+    #[error("Out of order")]
+    OutOfOrder,
+}
+
+esrs::state_machine! {
+    aggregate Turnstile {
+        name: "turnstile",
+        state: TurnstileState,
+        command: TurnstileCommand,
+        event: TurnstileEvent,
+        error: TurnstileError,
+    }
+
+    commands {
+        (TurnstileState::Locked | TurnstileState::Unlocked { .. }, TurnstileCommand::InsertCoin) => {
+            Ok(vec![TurnstileEvent::CoinInserted])
+        },
+        (TurnstileState::Unlocked { coins }, TurnstileCommand::Push) if *coins > 1 => {
+            Ok(vec![TurnstileEvent::Pushed, TurnstileEvent::CoinInserted])
+        },
+        (TurnstileState::Unlocked { .. }, TurnstileCommand::Push) => Ok(vec![TurnstileEvent::Pushed]),
+        (TurnstileState::Locked, TurnstileCommand::Push) => Err(TurnstileError::IllegalTransition),
+        (TurnstileState::Locked | TurnstileState::Unlocked { .. }, TurnstileCommand::Break) => {
+            Ok(vec![TurnstileEvent::Broken])
+        },
+        (TurnstileState::Broken, _) => Err(TurnstileError::OutOfOrder),
+    }
+
+    transitions {
+        (TurnstileState::Locked, TurnstileEvent::CoinInserted) => TurnstileState::Unlocked { coins: 1 },
+        (TurnstileState::Unlocked { coins }, TurnstileEvent::CoinInserted) => TurnstileState::Unlocked { coins: coins + 1 },
+        (TurnstileState::Unlocked { coins }, TurnstileEvent::Pushed) if coins > 1 => TurnstileState::Unlocked { coins: coins - 2 },
+        (TurnstileState::Unlocked { .. }, TurnstileEvent::Pushed) => TurnstileState::Locked,
+        (_, TurnstileEvent::Broken) => TurnstileState::Broken,
+        (TurnstileState::Broken, TurnstileEvent::Repaired) => TurnstileState::Locked,
+    }
+}
+
+fn replay(state: TurnstileState, events: Vec<TurnstileEvent>) -> TurnstileState {
+    events.into_iter().fold(state, Turnstile::apply_event)
+}
+
+#[test]
+fn state_machine_name_test() {
+    assert_eq!(Turnstile::NAME, "turnstile");
+}
+
+#[test]
+fn state_machine_legal_transitions_test() {
+    let events = Turnstile::handle_command(&TurnstileState::Locked, TurnstileCommand::InsertCoin).unwrap();
+    assert_eq!(events, vec![TurnstileEvent::CoinInserted]);
+
+    let state = replay(TurnstileState::Locked, events);
+    assert_eq!(state, TurnstileState::Unlocked { coins: 1 });
+
+    let events = Turnstile::handle_command(&state, TurnstileCommand::Push).unwrap();
+    let state = replay(state, events);
+    assert_eq!(state, TurnstileState::Locked);
+}
+
+#[test]
+fn state_machine_guards_test() {
+    let state = TurnstileState::Unlocked { coins: 3 };
+
+    let events = Turnstile::handle_command(&state, TurnstileCommand::Push).unwrap();
+    assert_eq!(events, vec![TurnstileEvent::Pushed, TurnstileEvent::CoinInserted]);
+
+    let state = replay(state, events);
+    assert_eq!(state, TurnstileState::Unlocked { coins: 2 });
+}
+
+#[test]
+fn state_machine_rejected_commands_test() {
+    let result = Turnstile::handle_command(&TurnstileState::Locked, TurnstileCommand::Push);
+    assert!(matches!(result, Err(TurnstileError::IllegalTransition)));
+
+    for command in [
+        TurnstileCommand::InsertCoin,
+        TurnstileCommand::Push,
+        TurnstileCommand::Break,
+    ] {
+        let result = Turnstile::handle_command(&TurnstileState::Broken, command);
+        assert!(matches!(result, Err(TurnstileError::OutOfOrder)));
+    }
+}
+
+#[test]
+fn state_machine_unlisted_transition_leaves_state_unchanged_test() {
+    let state = Turnstile::apply_event(TurnstileState::Locked, TurnstileEvent::Pushed);
+    assert_eq!(state, TurnstileState::Locked);
+
+    let state = Turnstile::apply_event(TurnstileState::Unlocked { coins: 1 }, TurnstileEvent::Repaired);
+    assert_eq!(state, TurnstileState::Unlocked { coins: 1 });
+
+    let state = Turnstile::apply_event(TurnstileState::Broken, TurnstileEvent::CoinInserted);
+    assert_eq!(state, TurnstileState::Broken);
+
+    let state = replay(
+        TurnstileState::Locked,
+        vec![TurnstileEvent::Broken, TurnstileEvent::Pushed, TurnstileEvent::Repaired],
+    );
+    assert_eq!(state, TurnstileState::Locked);
+}
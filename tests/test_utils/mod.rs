@@ -0,0 +1,209 @@
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use uuid::Uuid;
+
+use esrs::test::{assert_golden_payloads, EventScript};
+
+use crate::aggregate::{TestEvent, TestEventHandler};
+
+#[test]
+fn golden_payloads_test() {
+    let directory: PathBuf = std::env::temp_dir().join(Uuid::new_v4().to_string());
+
+    // Golden files are written on first run, and checked afterwards.
+    assert_golden_payloads(&directory, None, &[("test_event", TestEvent { add: 1 })]);
+    assert_golden_payloads(&directory, None, &[("test_event", TestEvent { add: 1 })]);
+    assert!(directory.join("test_event.v0.json").exists());
+
+    let changed = std::panic::catch_unwind(|| {
+        assert_golden_payloads(&directory, None, &[("test_event", TestEvent { add: 2 })]);
+    });
+    assert!(changed.is_err());
+
+    // A version bump writes new golden files.
+    assert_golden_payloads(&directory, Some(1), &[("test_event", TestEvent { add: 2 })]);
+    assert!(directory.join("test_event.v1.json").exists());
+
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[tokio::test]
+async fn event_script_test() {
+    let aggregate_id: Uuid = Uuid::new_v4();
+    let script: EventScript<TestEvent> = EventScript::new(aggregate_id)
+        .then(TestEvent { add: 1 })
+        .then(TestEvent { add: 2 });
+
+    let sequence_numbers: Vec<i32> = script.events().iter().map(|event| event.sequence_number).collect();
+    assert_eq!(sequence_numbers, vec![1, 2]);
+    assert!(script.events().iter().all(|event| event.aggregate_id == aggregate_id));
+
+    let event_handler = TestEventHandler {
+        total: Arc::new(Mutex::new(0)),
+    };
+    script.feed(&event_handler).await;
+
+    assert_eq!(*event_handler.total.lock().unwrap(), 3);
+}
+
+#[cfg(feature = "postgres")]
+#[sqlx::test]
+async fn event_script_transactional_test(pool: sqlx::Pool<sqlx::Postgres>) {
+    use esrs::test::assert_view_rows;
+
+    use crate::aggregate::TestTransactionalEventHandler;
+
+    let _ = sqlx::query("CREATE TABLE test_projection (id uuid PRIMARY KEY NOT NULL, total INTEGER)")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let aggregate_id: Uuid = Uuid::new_v4();
+    let script: EventScript<TestEvent> = EventScript::new(aggregate_id)
+        .then(TestEvent { add: 1 })
+        .then(TestEvent { add: 2 });
+
+    let mut transaction = pool.begin().await.unwrap();
+    script
+        .feed_transactional(&TestTransactionalEventHandler, &mut *transaction)
+        .await
+        .unwrap();
+
+    assert_view_rows(
+        &mut *transaction,
+        "SELECT id, total FROM test_projection",
+        &[(aggregate_id, 2)],
+    )
+    .await;
+}
+
+#[cfg(feature = "postgres")]
+#[sqlx::test]
+async fn loadgen_test(pool: sqlx::Pool<sqlx::Postgres>) {
+    use esrs::store::postgres::{PgStore, PgStoreBuilder};
+    use esrs::test::loadgen::{LoadGen, LoadReport};
+
+    use crate::aggregate::TestAggregate;
+
+    let store: PgStore<TestAggregate> = PgStoreBuilder::new(pool.clone()).try_build().await.unwrap();
+
+    let report: LoadReport = LoadGen::new(|_, sequence_number| TestEvent { add: sequence_number })
+        .with_aggregates(5)
+        .with_events_per_aggregate(7)
+        .with_batch_size(3)
+        .with_concurrency(2)
+        .run(&store)
+        .await
+        .unwrap();
+
+    assert_eq!(report.events, 35);
+    // Batches of 3, 3 and 1 events for each aggregate.
+    assert_eq!(report.persist_latencies.len(), 15);
+    assert!(report.percentile(0.5) <= report.percentile(0.99));
+
+    let count: i64 = sqlx::query_scalar(format!("SELECT COUNT(*) FROM {}", store.table_name()).as_str())
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(count, 35);
+}
+
+#[test]
+fn simulation_test() {
+    use std::cell::Cell;
+
+    use esrs::manager::AggregateManager;
+    use esrs::test::simulation::{InMemoryStore, InMemoryStoreError, Simulation, VirtualClock};
+    use esrs::AggregateState;
+
+    use crate::aggregate::{TestAggregate, TestCommand};
+
+    let aggregate_id: Uuid = Uuid::new_v4();
+
+    // Locking the aggregate makes every interleaving safe.
+    for seed in 0..20 {
+        let clock: VirtualClock = VirtualClock::default();
+        let manager = AggregateManager::new(InMemoryStore::<TestAggregate>::new(clock.clone()));
+        let mut simulation: Simulation = Simulation::new(seed).with_clock(clock);
+
+        for _ in 0..3 {
+            let _ = simulation.spawn(async {
+                let aggregate_state = manager.lock_and_load(aggregate_id).await.unwrap().unwrap_or_default();
+                manager
+                    .handle_command(aggregate_state, TestCommand::Single)
+                    .await
+                    .unwrap()
+                    .unwrap();
+            });
+        }
+        let _ = simulation.run().unwrap();
+
+        let aggregate_state = futures::executor::block_on(manager.load(aggregate_id))
+            .unwrap()
+            .unwrap();
+        // The state starts from a count of 1.
+        assert_eq!(aggregate_state.inner().count, 4);
+    }
+
+    // Without locking, some interleavings persist from a stale state.
+    let conflicts: Cell<usize> = Cell::new(0);
+    for seed in 0..20 {
+        let manager = AggregateManager::new(InMemoryStore::<TestAggregate>::new(VirtualClock::default()));
+        let mut simulation: Simulation = Simulation::new(seed);
+
+        for _ in 0..2 {
+            let _ = simulation.spawn(async {
+                let aggregate_state = manager
+                    .load(aggregate_id)
+                    .await
+                    .unwrap()
+                    .unwrap_or_else(|| AggregateState::with_id(aggregate_id));
+
+                if let Err(InMemoryStoreError::SequenceConflict { .. }) =
+                    manager.handle_command(aggregate_state, TestCommand::Single).await
+                {
+                    conflicts.set(conflicts.get() + 1);
+                }
+            });
+        }
+        let _ = simulation.run().unwrap();
+    }
+    assert!(conflicts.get() > 0);
+
+    // The same seed always produces the same interleaving.
+    let schedule = |seed: u64| {
+        let manager = AggregateManager::new(InMemoryStore::<TestAggregate>::new(VirtualClock::default()));
+        let mut simulation: Simulation = Simulation::new(seed);
+        for _ in 0..3 {
+            let _ = simulation.spawn(async {
+                let aggregate_state = manager.lock_and_load(aggregate_id).await.unwrap().unwrap_or_default();
+                let _ = manager.handle_command(aggregate_state, TestCommand::Single).await;
+            });
+        }
+        simulation.run().unwrap().schedule
+    };
+    assert_eq!(schedule(42), schedule(42));
+}
+
+#[test]
+fn simulation_clock_test() {
+    use std::time::Duration;
+
+    use esrs::test::simulation::{Simulation, SimulationError, VirtualClock};
+
+    let clock: VirtualClock = VirtualClock::default();
+    let started_at = clock.now();
+    let mut simulation: Simulation = Simulation::new(0).with_clock(clock.clone());
+
+    let sleeping_clock: VirtualClock = clock.clone();
+    let _ = simulation.spawn(async move { sleeping_clock.sleep(Duration::from_secs(3600)).await });
+    let _ = simulation.run().unwrap();
+
+    assert_eq!(clock.now() - started_at, chrono::Duration::hours(1));
+
+    // A task waiting forever is reported as a deadlock.
+    let mut simulation: Simulation = Simulation::new(0);
+    let _ = simulation.spawn(futures::future::pending());
+    assert!(matches!(simulation.run(), Err(SimulationError::Deadlock { .. })));
+}